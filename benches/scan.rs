@@ -0,0 +1,117 @@
+//! Scan throughput over a generated corpus.
+//!
+//! The corpus is [`COPIES`] copies of each Rust fixture in a temporary
+//! directory. Every group reports throughput in files, so criterion prints
+//! files/sec alongside the timings:
+//!
+//! - `parse`: parsing the fixtures, without running detectors;
+//! - `detect`: running every detector over already parsed fixtures;
+//! - `detector`: the same, one detector at a time;
+//! - `scan`: a whole [`scanner::scan_with`] of the corpus, serially and on
+//!   every core, to check the rayon path scales.
+//!
+//! Run with `cargo bench`, or `cargo bench -- detector/` for one group.
+
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anchor_audit::detectors::Registry;
+use anchor_audit::parser::{self, ParsedProgram};
+use anchor_audit::scanner::{self, ScanOptions};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const FIXTURES: &[&str] = &["vulnerable_vault.rs", "insecure_nft_marketplace.rs"];
+
+/// Copies of each fixture in the `scan` corpus.
+const COPIES: usize = 100;
+
+/// Path and contents of every fixture.
+fn sources() -> Vec<(PathBuf, String)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("contracts/sources");
+    FIXTURES
+        .iter()
+        .map(|name| {
+            let path = dir.join(name);
+            let source = fs::read_to_string(&path).expect("fixture should be readable");
+            (path, source)
+        })
+        .collect()
+}
+
+fn parsed(sources: &[(PathBuf, String)]) -> Vec<ParsedProgram> {
+    sources
+        .iter()
+        .map(|(path, source)| {
+            parser::parse_source(path.clone(), source.clone()).expect("fixture should parse")
+        })
+        .collect()
+}
+
+fn parse(c: &mut Criterion) {
+    let sources = sources();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements(sources.len() as u64));
+    group.bench_function("fixtures", |b| b.iter(|| parsed(black_box(&sources))));
+    group.finish();
+}
+
+fn detect(c: &mut Criterion) {
+    let programs = parsed(&sources());
+    let registry = Registry::default();
+    let mut group = c.benchmark_group("detect");
+    group.throughput(Throughput::Elements(programs.len() as u64));
+    group.bench_function("fixtures", |b| {
+        b.iter(|| {
+            for program in &programs {
+                black_box(scanner::analyze(program, &registry));
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("detector");
+    group.throughput(Throughput::Elements(programs.len() as u64));
+    for detector in registry.detectors() {
+        group.bench_function(detector.id(), |b| {
+            b.iter(|| {
+                for program in &programs {
+                    black_box(detector.run(program));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn scan(c: &mut Criterion) {
+    let corpus = tempfile::tempdir().expect("temporary directory");
+    let sources = sources();
+    for copy in 0..COPIES {
+        for (path, source) in &sources {
+            let name = format!("{copy:03}_{}", path.file_name().unwrap().to_string_lossy());
+            fs::write(corpus.path().join(name), source).expect("corpus should be writable");
+        }
+    }
+    let files = (COPIES * sources.len()) as u64;
+
+    let mut group = c.benchmark_group("scan");
+    group.throughput(Throughput::Elements(files));
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+    for jobs in [1, 0] {
+        let options = ScanOptions {
+            jobs,
+            ..ScanOptions::default()
+        };
+        let label = if jobs == 1 { "serial" } else { "parallel" };
+        group.bench_with_input(BenchmarkId::new("corpus", label), &options, |b, options| {
+            b.iter(|| scanner::scan_with(corpus.path(), options).expect("corpus should scan"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, detect, scan);
+criterion_main!(benches);
@@ -0,0 +1,186 @@
+//! Interface for anchor-audit detectors shipped as dynamic libraries.
+//!
+//! A plugin is a `cdylib` that implements [`DetectorPlugin`] for each of
+//! its detectors and exports them with [`export_plugin!`]:
+//!
+//! ```ignore
+//! anchor_audit_plugin::export_plugin!(TodoComment);
+//! ```
+//!
+//! The host only calls the `extern "C"` functions named in [`symbols`],
+//! passing NUL-terminated UTF-8 JSON both ways, so plugin and host need not
+//! be built by the same compiler. [`API_VERSION`] is checked before
+//! anything else and a plugin built against another version is refused.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the interface below. Bumped on every incompatible change.
+pub const API_VERSION: u32 = 1;
+
+/// Names of the functions [`export_plugin!`] defines, NUL-terminated.
+pub mod symbols {
+    /// `extern "C" fn() -> u32`, the plugin's [`API_VERSION`](crate::API_VERSION).
+    pub const API_VERSION: &[u8] = b"anchor_audit_api_version\0";
+    /// `extern "C" fn() -> *mut c_char`, a JSON array of
+    /// [`DetectorMeta`](crate::DetectorMeta).
+    pub const DETECTORS: &[u8] = b"anchor_audit_detectors\0";
+    /// `extern "C" fn(u32, *const c_char, *const c_char) -> *mut c_char`:
+    /// runs the detector at the given index over a file's path and source,
+    /// returning a JSON array of [`PluginFinding`](crate::PluginFinding),
+    /// or null if the detector panicked.
+    pub const RUN: &[u8] = b"anchor_audit_run\0";
+    /// `extern "C" fn(*mut c_char)`, releasing a string returned above.
+    pub const FREE: &[u8] = b"anchor_audit_free\0";
+}
+
+pub type ApiVersionFn = unsafe extern "C" fn() -> u32;
+pub type DetectorsFn = unsafe extern "C" fn() -> *mut c_char;
+pub type RunFn =
+    unsafe extern "C" fn(detector: u32, path: *const c_char, source: *const c_char) -> *mut c_char;
+pub type FreeFn = unsafe extern "C" fn(text: *mut c_char);
+
+/// Catalog entry of a plugin detector, mirroring the host's built-in ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectorMeta {
+    /// Namespaced rule id, e.g. `acme/todo-comment`.
+    pub id: String,
+    /// Short name used in configuration and suppressions.
+    pub name: String,
+    pub title: String,
+    /// `AccessControl`, `Arithmetic`, `MemorySafety` or `Validation`.
+    pub category: String,
+    /// `critical`, `high`, `medium` or `low`.
+    pub severity: String,
+    pub cwe: u32,
+    pub description: String,
+    #[serde(default)]
+    pub example: String,
+    #[serde(default)]
+    pub fixed_example: String,
+    #[serde(default)]
+    pub references: Vec<String>,
+}
+
+/// One issue found by a plugin detector. Lines and columns are 1-based and
+/// `col_end` is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginFinding {
+    pub line_start: usize,
+    pub col_start: usize,
+    pub line_end: usize,
+    pub col_end: usize,
+    pub message: String,
+    /// Overrides the detector's severity for this finding.
+    #[serde(default)]
+    pub severity: Option<String>,
+    /// `high`, `medium` or `low`; medium when absent.
+    #[serde(default)]
+    pub confidence: Option<String>,
+    #[serde(default)]
+    pub suggestion: Option<String>,
+}
+
+/// A detector implemented outside the host.
+///
+/// Plugins receive the raw source rather than the host's syntax tree, which
+/// is not part of the stable interface; parse it with `syn` if needed.
+pub trait DetectorPlugin: Send + Sync {
+    fn meta(&self) -> DetectorMeta;
+
+    fn run(&self, path: &str, source: &str) -> Vec<PluginFinding>;
+}
+
+/// Defines the exported functions for the given [`DetectorPlugin`] values,
+/// which are constructed once, on first use.
+#[macro_export]
+macro_rules! export_plugin {
+    ($($plugin:expr),+ $(,)?) => {
+        fn __anchor_audit_plugins() -> &'static [::std::boxed::Box<dyn $crate::DetectorPlugin>] {
+            static PLUGINS: ::std::sync::OnceLock<
+                ::std::vec::Vec<::std::boxed::Box<dyn $crate::DetectorPlugin>>,
+            > = ::std::sync::OnceLock::new();
+            PLUGINS.get_or_init(|| ::std::vec![$(::std::boxed::Box::new($plugin)),+])
+        }
+
+        #[no_mangle]
+        pub extern "C" fn anchor_audit_api_version() -> u32 {
+            $crate::API_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn anchor_audit_detectors() -> *mut ::std::ffi::c_char {
+            $crate::__private::detectors(__anchor_audit_plugins())
+        }
+
+        /// # Safety
+        ///
+        /// `path` and `source` must be valid NUL-terminated strings.
+        #[no_mangle]
+        pub unsafe extern "C" fn anchor_audit_run(
+            detector: u32,
+            path: *const ::std::ffi::c_char,
+            source: *const ::std::ffi::c_char,
+        ) -> *mut ::std::ffi::c_char {
+            unsafe { $crate::__private::run(__anchor_audit_plugins(), detector, path, source) }
+        }
+
+        /// # Safety
+        ///
+        /// `text` must have been returned by this library and not freed yet.
+        #[no_mangle]
+        pub unsafe extern "C" fn anchor_audit_free(text: *mut ::std::ffi::c_char) {
+            unsafe { $crate::__private::free(text) }
+        }
+    };
+}
+
+/// Implementation of the functions [`export_plugin!`] defines.
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    fn to_c(json: String) -> *mut c_char {
+        // JSON escapes control characters, so there is no interior NUL.
+        CString::new(json).map_or(std::ptr::null_mut(), CString::into_raw)
+    }
+
+    pub fn detectors(plugins: &[Box<dyn DetectorPlugin>]) -> *mut c_char {
+        let metas: Vec<_> = plugins.iter().map(|plugin| plugin.meta()).collect();
+        to_c(serde_json::to_string(&metas).unwrap_or_default())
+    }
+
+    /// # Safety
+    ///
+    /// `path` and `source` must be valid NUL-terminated strings.
+    pub unsafe fn run(
+        plugins: &[Box<dyn DetectorPlugin>],
+        detector: u32,
+        path: *const c_char,
+        source: *const c_char,
+    ) -> *mut c_char {
+        let Some(plugin) = plugins.get(detector as usize) else {
+            return std::ptr::null_mut();
+        };
+        let (path, source) = unsafe { (CStr::from_ptr(path), CStr::from_ptr(source)) };
+        let (Ok(path), Ok(source)) = (path.to_str(), source.to_str()) else {
+            return std::ptr::null_mut();
+        };
+        // Unwinding across `extern "C"` aborts the host.
+        match panic::catch_unwind(AssertUnwindSafe(|| plugin.run(path, source))) {
+            Ok(findings) => to_c(serde_json::to_string(&findings).unwrap_or_default()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `text` must come from [`to_c`] and not have been freed.
+    pub unsafe fn free(text: *mut c_char) {
+        if !text.is_null() {
+            drop(unsafe { CString::from_raw(text) });
+        }
+    }
+}
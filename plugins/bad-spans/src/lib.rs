@@ -0,0 +1,47 @@
+//! Test plugin reporting malformed spans, one finding per kind the host
+//! has to drop or clamp, followed by a well-formed one.
+
+use anchor_audit_plugin::{export_plugin, DetectorMeta, DetectorPlugin, PluginFinding};
+
+struct BadSpans;
+
+impl DetectorPlugin for BadSpans {
+    fn meta(&self) -> DetectorMeta {
+        DetectorMeta {
+            id: "test/bad-spans".into(),
+            name: "bad_spans".into(),
+            title: "Malformed spans".into(),
+            category: "Validation".into(),
+            severity: "low".into(),
+            cwe: 0,
+            description: "Reports spans that do not fit the file.".into(),
+            example: String::new(),
+            fixed_example: String::new(),
+            references: Vec::new(),
+        }
+    }
+
+    fn run(&self, _path: &str, source: &str) -> Vec<PluginFinding> {
+        let lines = source.lines().count();
+        let finding = |message: &str, line_start, col_start, line_end, col_end| PluginFinding {
+            line_start,
+            col_start,
+            line_end,
+            col_end,
+            message: message.into(),
+            severity: None,
+            confidence: None,
+            suggestion: None,
+        };
+        vec![
+            finding("reversed", 5, 1, 2, 1),
+            finding("line zero", 0, 1, 1, 1),
+            finding("past the end", lines + 1, 1, lines + 2, 1),
+            finding("runs past the end", 2, 0, lines + 10, 1),
+            finding("backwards columns", 3, 9, 3, 4),
+            finding("fine", 1, 1, 1, 2),
+        ]
+    }
+}
+
+export_plugin!(BadSpans);
@@ -0,0 +1,48 @@
+//! Example plugin: flags `// TODO` comments left in program code.
+//!
+//! Build with `cargo build -p anchor-audit-example-plugin` and point the
+//! `[plugins]` table of `audit.toml` at the directory holding the library.
+
+use anchor_audit_plugin::{export_plugin, DetectorMeta, DetectorPlugin, PluginFinding};
+
+struct TodoComment;
+
+impl DetectorPlugin for TodoComment {
+    fn meta(&self) -> DetectorMeta {
+        DetectorMeta {
+            id: "example/todo-comment".into(),
+            name: "todo_comment".into(),
+            title: "TODO left in program code".into(),
+            category: "Validation".into(),
+            severity: "low".into(),
+            cwe: 546,
+            description: "A `TODO` comment marks work that was never finished.".into(),
+            example: "// TODO: check the authority".into(),
+            fixed_example: "require_keys_eq!(vault.authority, ctx.accounts.authority.key());"
+                .into(),
+            references: vec!["https://cwe.mitre.org/data/definitions/546.html".into()],
+        }
+    }
+
+    fn run(&self, _path: &str, source: &str) -> Vec<PluginFinding> {
+        source
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let at = line.find("// TODO")?;
+                Some(PluginFinding {
+                    line_start: i + 1,
+                    col_start: at + 1,
+                    line_end: i + 1,
+                    col_end: line.len() + 1,
+                    message: format!("unfinished work: `{}`", line[at + 3..].trim()),
+                    severity: None,
+                    confidence: Some("high".into()),
+                    suggestion: None,
+                })
+            })
+            .collect()
+    }
+}
+
+export_plugin!(TodoComment);
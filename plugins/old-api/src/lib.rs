@@ -0,0 +1,7 @@
+//! Test plugin that reports plugin API version 0 and exports nothing else,
+//! as a library built against an older `anchor-audit-plugin` would look.
+
+#[no_mangle]
+pub extern "C" fn anchor_audit_api_version() -> u32 {
+    0
+}
@@ -0,0 +1,495 @@
+//! Syntax helpers shared by detectors.
+
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprStruct, ItemFn, Local, Macro, Member, Pat};
+
+/// CPI account structs from `anchor_spl::token` that carry an `authority`.
+pub(crate) const CPI_ACCOUNT_STRUCTS: &[&str] = &["Transfer", "MintTo", "Burn"];
+
+/// Methods that borrow the data of an `AccountInfo`: the `try_` methods,
+/// and `borrow`/`borrow_mut` on its `data` `RefCell`.
+pub(crate) const DATA_BORROWS: &[&str] = &[
+    "try_borrow_data",
+    "try_borrow_mut_data",
+    "borrow",
+    "borrow_mut",
+];
+
+/// Method calls that return (a view of) the same account.
+const ACCOUNT_CONVERSIONS: &[&str] = &["to_account_info", "clone", "as_ref"];
+
+/// Strips references, parentheses and account conversions, so that
+/// `&ctx.accounts.vault.to_account_info()` becomes `ctx.accounts.vault`.
+pub(crate) fn peel(mut expr: &Expr) -> &Expr {
+    loop {
+        expr = match expr {
+            Expr::Reference(reference) => &reference.expr,
+            Expr::Paren(paren) => &paren.expr,
+            Expr::MethodCall(call)
+                if ACCOUNT_CONVERSIONS.iter().any(|name| call.method == name) =>
+            {
+                &call.receiver
+            }
+            _ => return expr,
+        };
+    }
+}
+
+/// Strips redundant parentheses.
+pub(crate) fn unparen(mut expr: &Expr) -> &Expr {
+    while let Expr::Paren(paren) = expr {
+        expr = &paren.expr;
+    }
+    expr
+}
+
+/// The field name `x` of a `<ctx>.accounts.x` expression.
+pub(crate) fn accounts_field(expr: &Expr) -> Option<String> {
+    let Expr::Field(field) = peel(expr) else {
+        return None;
+    };
+    let Expr::Field(parent) = &*field.base else {
+        return None;
+    };
+    match (&parent.member, &field.member) {
+        (Member::Named(accounts), Member::Named(name)) if accounts == "accounts" => {
+            Some(name.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Local variables bound to an account of the context, e.g.
+/// `let vault = &mut ctx.accounts.vault;` maps `vault` to `vault`.
+#[derive(Debug, Default)]
+pub(crate) struct Bindings {
+    accounts: HashMap<String, String>,
+    mutable: HashSet<String>,
+}
+
+impl Bindings {
+    pub(crate) fn of(item: &ItemFn) -> Self {
+        let mut bindings = Bindings::default();
+        bindings.visit_item_fn(item);
+        bindings
+    }
+
+    /// The context account `expr` refers to, directly or through a local.
+    pub(crate) fn account(&self, expr: &Expr) -> Option<String> {
+        if let Some(field) = accounts_field(expr) {
+            return Some(field);
+        }
+        let name = ident(peel(expr))?;
+        self.accounts.get(&name).cloned()
+    }
+
+    /// The `(account, field)` pair of a `vault.balance` style access on any
+    /// context account, mutable or not.
+    pub(crate) fn account_field(&self, expr: &Expr) -> Option<(String, String)> {
+        let Expr::Field(access) = expr else {
+            return None;
+        };
+        let Member::Named(field) = &access.member else {
+            return None;
+        };
+        Some((self.account(&access.base)?, field.to_string()))
+    }
+
+    /// The `(account, field)` pair of a `vault.balance` style access whose
+    /// base is a mutable account: a `&mut` local or `ctx.accounts.x` itself.
+    pub(crate) fn mut_account_field(&self, expr: &Expr) -> Option<(String, String)> {
+        let Expr::Field(access) = expr else {
+            return None;
+        };
+        let Member::Named(field) = &access.member else {
+            return None;
+        };
+        let account = match accounts_field(&access.base) {
+            Some(account) => account,
+            None => {
+                let name = ident(&access.base)?;
+                self.mutable.contains(&name).then_some(())?;
+                self.accounts.get(&name)?.clone()
+            }
+        };
+        Some((account, field.to_string()))
+    }
+}
+
+impl Visit<'_> for Bindings {
+    fn visit_local(&mut self, local: &Local) {
+        if let (Some(name), Some(init)) = (pat_ident(&local.pat), &local.init) {
+            if let Some(account) = self.account(&init.expr) {
+                if matches!(&*init.expr, Expr::Reference(r) if r.mutability.is_some()) {
+                    self.mutable.insert(name.clone());
+                }
+                self.accounts.insert(name, account);
+            }
+        }
+        visit::visit_local(self, local);
+    }
+}
+
+/// The name bound by a simple `x` / `mut x` pattern.
+pub(crate) fn pat_ident(pat: &Pat) -> Option<String> {
+    match pat {
+        Pat::Ident(pat) => Some(pat.ident.to_string()),
+        Pat::Type(pat) => pat_ident(&pat.pat),
+        _ => None,
+    }
+}
+
+/// Method names along a receiver chain, outermost first, together with the
+/// innermost receiver: `data.as_ptr().add(64)` yields `["add", "as_ptr"]`
+/// and `data`. `?`, references and parentheses are looked through.
+pub(crate) fn method_chain(mut expr: &Expr) -> (Vec<String>, &Expr) {
+    let mut methods = Vec::new();
+    loop {
+        expr = match expr {
+            Expr::MethodCall(call) => {
+                methods.push(call.method.to_string());
+                &call.receiver
+            }
+            Expr::Try(try_expr) => &try_expr.expr,
+            Expr::Reference(reference) => &reference.expr,
+            Expr::Paren(paren) => &paren.expr,
+            _ => return (methods, expr),
+        };
+    }
+}
+
+/// The last segment of a called function path, e.g. `write_bytes` for
+/// `std::ptr::write_bytes(..)`.
+pub(crate) fn call_name(call: &ExprCall) -> Option<String> {
+    match &*call.func {
+        Expr::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Whether `mac` is one of Anchor's `require*!` guard macros.
+pub(crate) fn is_require(mac: &Macro) -> bool {
+    mac.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident.to_string().starts_with("require"))
+}
+
+/// Whether the identifier `name` occurs anywhere in `tokens`.
+pub(crate) fn mentions(tokens: &TokenStream, name: &str) -> bool {
+    tokens.clone().into_iter().any(|tree| match tree {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions(&group.stream(), name),
+        _ => false,
+    })
+}
+
+/// Whitespace-insensitive text of `node`, for comparing expressions.
+pub(crate) fn normalized(node: &impl ToTokens) -> String {
+    node.to_token_stream()
+        .to_string()
+        .split_whitespace()
+        .collect()
+}
+
+/// The source text of `node`, falling back to its token stream.
+pub(crate) fn source_text(node: &impl ToTokens) -> String {
+    node.span()
+        .source_text()
+        .unwrap_or_else(|| node.to_token_stream().to_string())
+}
+
+/// The source text of `expr` as a method receiver, parenthesised unless it
+/// already binds tighter than `.`: `a + b` becomes `(a + b)` so that
+/// appending `.checked_add(c)` keeps its meaning.
+pub(crate) fn receiver_text(expr: &Expr) -> String {
+    let text = source_text(expr);
+    match expr {
+        Expr::Path(_)
+        | Expr::Field(_)
+        | Expr::MethodCall(_)
+        | Expr::Call(_)
+        | Expr::Paren(_)
+        | Expr::Lit(_)
+        | Expr::Index(_)
+        | Expr::Try(_)
+        | Expr::Macro(_) => text,
+        _ => format!("({text})"),
+    }
+}
+
+/// The identifier of a single-segment path expression.
+pub(crate) fn ident(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => path.path.get_ident().map(ToString::to_string),
+        _ => None,
+    }
+}
+
+/// A CPI accounts struct literal such as `Transfer { from, to, authority }`.
+#[derive(Debug)]
+pub(crate) struct CpiAccounts {
+    /// The struct name, e.g. `MintTo`.
+    pub kind: String,
+    pub fields: Vec<(String, Expr)>,
+}
+
+impl CpiAccounts {
+    pub(crate) fn field(&self, name: &str) -> Option<&Expr> {
+        self.fields
+            .iter()
+            .find_map(|(field, expr)| (field == name).then_some(expr))
+    }
+}
+
+/// `node` as a CPI accounts struct, if it builds one.
+pub(crate) fn cpi_accounts_literal(node: &ExprStruct) -> Option<CpiAccounts> {
+    let kind = node.path.segments.last()?.ident.to_string();
+    if !CPI_ACCOUNT_STRUCTS.contains(&kind.as_str()) {
+        return None;
+    }
+    let fields = node
+        .fields
+        .iter()
+        .filter_map(|field| match &field.member {
+            Member::Named(name) => Some((name.to_string(), field.expr.clone())),
+            Member::Unnamed(_) => None,
+        })
+        .collect();
+    Some(CpiAccounts { kind, fields })
+}
+
+/// Every CPI accounts struct literal built inside `item`.
+pub(crate) fn cpi_accounts(item: &ItemFn) -> Vec<CpiAccounts> {
+    #[derive(Default)]
+    struct Collector(Vec<CpiAccounts>);
+
+    impl Visit<'_> for Collector {
+        fn visit_expr_struct(&mut self, node: &ExprStruct) {
+            self.0.extend(cpi_accounts_literal(node));
+            visit::visit_expr_struct(self, node);
+        }
+    }
+
+    let mut collector = Collector::default();
+    collector.visit_item_fn(item);
+    collector.0
+}
+
+/// An assignment to a field of a mutable context account, such as
+/// `vault.authority = new_authority` or `vault.balance += amount`.
+#[derive(Debug)]
+pub(crate) struct FieldWrite {
+    pub account: String,
+    pub field: String,
+    /// The assignment target as written, e.g. `vault.authority`.
+    pub target: String,
+    pub span: proc_macro2::Span,
+}
+
+/// Every write to a mutable account field inside `item`, in source order.
+pub(crate) fn field_writes(item: &ItemFn, bindings: &Bindings) -> Vec<FieldWrite> {
+    struct Collector<'a> {
+        bindings: &'a Bindings,
+        writes: Vec<FieldWrite>,
+    }
+
+    impl Collector<'_> {
+        fn record(&mut self, left: &Expr, span: proc_macro2::Span) {
+            if let Some((account, field)) = self.bindings.mut_account_field(left) {
+                self.writes.push(FieldWrite {
+                    account,
+                    field,
+                    target: source_text(left),
+                    span,
+                });
+            }
+        }
+    }
+
+    impl Visit<'_> for Collector<'_> {
+        fn visit_expr_assign(&mut self, node: &syn::ExprAssign) {
+            self.record(&node.left, node.span());
+            visit::visit_expr_assign(self, node);
+        }
+
+        fn visit_expr_binary(&mut self, node: &syn::ExprBinary) {
+            use syn::BinOp::*;
+            if matches!(
+                node.op,
+                AddAssign(_) | SubAssign(_) | MulAssign(_) | DivAssign(_)
+            ) {
+                self.record(&node.left, node.span());
+            }
+            visit::visit_expr_binary(self, node);
+        }
+    }
+
+    let mut collector = Collector {
+        bindings,
+        writes: Vec::new(),
+    };
+    collector.visit_item_fn(item);
+    collector.writes
+}
+
+/// A borrow of a context account's raw data, such as
+/// `ctx.accounts.vault.to_account_info().try_borrow_data()`.
+#[derive(Debug)]
+pub(crate) struct DataRead {
+    pub account: String,
+    /// The borrow or `.data` access itself.
+    pub span: proc_macro2::Span,
+    /// Whether the data was borrowed for writing.
+    pub mutable: bool,
+    /// The `to_account_info()` call the data was reached through, inline or
+    /// via a local.
+    pub conversion: Option<proc_macro2::Span>,
+}
+
+/// Every raw data borrow of a context account inside `item`, in source
+/// order: `try_borrow_data()`, `try_borrow_mut_data()` and `.data`.
+pub(crate) fn data_reads(item: &ItemFn, bindings: &Bindings) -> Vec<DataRead> {
+    const BYTE_ACCESS: &[&str] = &["try_borrow_data", "try_borrow_mut_data"];
+
+    struct Collector<'a> {
+        bindings: &'a Bindings,
+        /// Locals bound to `ctx.accounts.x.to_account_info()`.
+        infos: HashMap<String, (String, proc_macro2::Span)>,
+        reads: Vec<DataRead>,
+    }
+
+    impl Collector<'_> {
+        fn converted(&self, expr: &Expr) -> Option<(String, proc_macro2::Span)> {
+            match unparen(expr) {
+                Expr::MethodCall(call) if call.method == "to_account_info" => {
+                    Some((self.bindings.account(&call.receiver)?, call.span()))
+                }
+                expr => self.infos.get(&ident(expr)?).cloned(),
+            }
+        }
+
+        fn record(&mut self, receiver: &Expr, span: proc_macro2::Span, mutable: bool) {
+            let (account, conversion) = match self.converted(receiver) {
+                Some((account, conversion)) => (account, Some(conversion)),
+                None => match self.bindings.account(receiver) {
+                    Some(account) => (account, None),
+                    None => return,
+                },
+            };
+            self.reads.push(DataRead {
+                account,
+                span,
+                mutable,
+                conversion,
+            });
+        }
+    }
+
+    impl Visit<'_> for Collector<'_> {
+        fn visit_local(&mut self, local: &Local) {
+            if let (Some(name), Some(init)) = (pat_ident(&local.pat), &local.init) {
+                if let Some(converted) = self.converted(&init.expr) {
+                    self.infos.insert(name, converted);
+                }
+            }
+            visit::visit_local(self, local);
+        }
+
+        fn visit_expr_method_call(&mut self, node: &syn::ExprMethodCall) {
+            if BYTE_ACCESS.iter().any(|method| node.method == method) {
+                let mutable = node.method == "try_borrow_mut_data";
+                self.record(&node.receiver, node.span(), mutable);
+            }
+            visit::visit_expr_method_call(self, node);
+        }
+
+        /// `info.data.borrow()`
+        fn visit_expr_field(&mut self, node: &syn::ExprField) {
+            if matches!(&node.member, Member::Named(name) if name == "data") {
+                self.record(&node.base, node.span(), false);
+            }
+            visit::visit_expr_field(self, node);
+        }
+    }
+
+    let mut collector = Collector {
+        bindings,
+        infos: HashMap::new(),
+        reads: Vec::new(),
+    };
+    collector.visit_item_fn(item);
+    collector.reads
+}
+
+/// Modules whose functions perform a cross-program invocation.
+const CPI_MODULES: &[&str] = &[
+    "token",
+    "token_interface",
+    "system_program",
+    "associated_token",
+];
+
+/// Functions that perform a CPI regardless of path.
+const CPI_FUNCTIONS: &[&str] = &["invoke", "invoke_signed"];
+
+/// The path of `call` if it performs a CPI, e.g. `token::transfer`.
+pub(crate) fn cpi_path(call: &ExprCall) -> Option<String> {
+    let Expr::Path(path) = &*call.func else {
+        return None;
+    };
+    let segments: Vec<_> = path
+        .path
+        .segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect();
+    let is_cpi = match segments.as_slice() {
+        [.., name] if CPI_FUNCTIONS.contains(&name.as_str()) => true,
+        [.., module, _] => CPI_MODULES.contains(&module.as_str()),
+        _ => false,
+    };
+    is_cpi.then(|| segments.join("::"))
+}
+
+/// Paths of every CPI call inside `item`, in source order, e.g.
+/// `token::transfer`.
+pub(crate) fn cpi_calls(item: &ItemFn) -> Vec<String> {
+    #[derive(Default)]
+    struct Collector(Vec<String>);
+
+    impl Visit<'_> for Collector {
+        fn visit_expr_call(&mut self, node: &ExprCall) {
+            self.0.extend(cpi_path(node));
+            visit::visit_expr_call(self, node);
+        }
+    }
+
+    let mut collector = Collector::default();
+    collector.visit_item_fn(item);
+    collector.0
+}
+
+/// Token bodies of every `require*!` macro inside `item`.
+pub(crate) fn require_guards(item: &ItemFn) -> Vec<TokenStream> {
+    #[derive(Default)]
+    struct Collector(Vec<TokenStream>);
+
+    impl Visit<'_> for Collector {
+        fn visit_macro(&mut self, node: &Macro) {
+            if is_require(node) {
+                self.0.push(node.tokens.clone());
+            }
+            visit::visit_macro(self, node);
+        }
+    }
+
+    let mut collector = Collector::default();
+    collector.visit_item_fn(item);
+    collector.0
+}
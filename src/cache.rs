@@ -0,0 +1,156 @@
+//! Reuse of parsed programs between scans of the same tree.
+//!
+//! Parsing dominates the cost of scanning, and in watch mode most files
+//! have not changed since the last scan. [`ParseCache`] remembers each
+//! file's program together with the blake3 hash of the contents it was
+//! parsed from, and parses again only when the hash differs.
+//!
+//! `syn` trees are not `Send`, so a cache lives on one thread and scans
+//! that use it run serially.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::error::{Error, Result};
+use crate::parser::{self, ParsedProgram};
+
+/// Parsed programs keyed by file, valid while the content hash matches.
+#[derive(Default)]
+pub struct ParseCache {
+    entries: HashMap<PathBuf, Entry>,
+    hits: usize,
+    misses: usize,
+    parsed_bytes: usize,
+}
+
+struct Entry {
+    hash: blake3::Hash,
+    program: Rc<ParsedProgram>,
+}
+
+impl ParseCache {
+    pub fn new() -> Self {
+        ParseCache::default()
+    }
+
+    /// The program in `path`, parsed again only if its contents changed
+    /// since the last call. Files that fail to parse are not cached.
+    pub fn parse_file(&mut self, path: &Path) -> Result<Rc<ParsedProgram>> {
+        let source = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let hash = blake3::hash(source.as_bytes());
+        if let Some(entry) = self.entries.get(path).filter(|entry| entry.hash == hash) {
+            self.hits += 1;
+            return Ok(Rc::clone(&entry.program));
+        }
+        self.misses += 1;
+        self.parsed_bytes += source.len();
+        let program = match parser::parse_source(path, source) {
+            Ok(program) => Rc::new(program),
+            Err(err) => {
+                self.entries.remove(path);
+                return Err(err.into());
+            }
+        };
+        self.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                hash,
+                program: Rc::clone(&program),
+            },
+        );
+        Ok(program)
+    }
+
+    /// Drops the program cached for `path`, such as a deleted file.
+    pub fn forget(&mut self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    /// Drops every cached program, such as before
+    /// [`parser::release_spans`] makes their spans unusable, and starts
+    /// counting [`ParseCache::parsed_bytes`] again.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.parsed_bytes = 0;
+    }
+
+    /// Size of the sources parsed since the cache was created or last
+    /// cleared, all of which stay in memory until the spans are released.
+    pub fn parsed_bytes(&self) -> usize {
+        self.parsed_bytes
+    }
+
+    /// Lookups answered from the cache so far.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Lookups that had to parse so far.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Number of files currently cached.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::scanner::{self, ScanOptions};
+    use crate::test_support::fixture;
+
+    #[test]
+    fn second_scan_reuses_unchanged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["insecure_nft_marketplace.rs", "vulnerable_vault.rs"] {
+            fs::copy(fixture(name), dir.path().join(name)).unwrap();
+        }
+        let options = ScanOptions::default();
+        let mut cache = ParseCache::new();
+
+        let first = scanner::scan_cached(dir.path(), &options, &mut cache).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+
+        // Only the vault changes between the scans.
+        let vault = dir.path().join("vulnerable_vault.rs");
+        let source = fs::read_to_string(&vault).unwrap();
+        fs::write(
+            &vault,
+            source.replace("vault.balance - amount", "vault.balance"),
+        )
+        .unwrap();
+
+        let second = scanner::scan_cached(dir.path(), &options, &mut cache).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+        assert_eq!(cache.len(), 2);
+        assert!(second.findings.len() < first.findings.len());
+        assert_eq!(
+            second.findings,
+            scanner::scan_with(dir.path(), &options).unwrap().findings
+        );
+    }
+
+    #[test]
+    fn parse_errors_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.rs");
+        fs::write(&path, "fn broken( {").unwrap();
+        let mut cache = ParseCache::new();
+        assert!(matches!(cache.parse_file(&path), Err(Error::Parse(_))));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+//! Which external programs each instruction handler calls.
+//!
+//! Every CPI found by [`ast::cpi_path`], `token::transfer`,
+//! `system_program::create_account`, a bare `invoke_signed` and so on, is an
+//! edge from the handler it appears in. The graph is meant for planning an
+//! audit: handlers without edges only touch the program's own accounts.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::ExprCall;
+
+use crate::ast;
+use crate::parser::ParsedProgram;
+
+/// One CPI made by a handler.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CpiTarget {
+    /// Module of the called program, e.g. `token`; `None` for `invoke` and
+    /// `invoke_signed`, whose program is only known at runtime.
+    pub program: Option<String>,
+    /// The call as written, e.g. `token::transfer`.
+    pub call: String,
+    /// Line of the call.
+    pub line: usize,
+}
+
+impl CpiTarget {
+    fn of(call: String, line: usize) -> Self {
+        let program = call.rsplit_once("::").and_then(|(module, function)| {
+            let dynamic = matches!(function, "invoke" | "invoke_signed");
+            (!dynamic).then(|| module.rsplit("::").next().unwrap_or(module).to_string())
+        });
+        CpiTarget {
+            program,
+            call,
+            line,
+        }
+    }
+}
+
+/// The CPIs of every handler of `program`, in source order. Handlers that
+/// make none map to an empty list.
+pub fn call_graph(program: &ParsedProgram) -> HashMap<String, Vec<CpiTarget>> {
+    #[derive(Default)]
+    struct Collector(Vec<CpiTarget>);
+
+    impl Visit<'_> for Collector {
+        fn visit_expr_call(&mut self, node: &ExprCall) {
+            if let Some(call) = ast::cpi_path(node) {
+                self.0.push(CpiTarget::of(call, node.span().start().line));
+            }
+            visit::visit_expr_call(self, node);
+        }
+    }
+
+    program
+        .handlers()
+        .iter()
+        .map(|handler| {
+            let mut collector = Collector::default();
+            collector.visit_item_fn(&handler.item);
+            (handler.name.clone(), collector.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn vault_handlers_edge_to_the_token_program() {
+        let graph = call_graph(&parse_fixture("vulnerable_vault.rs"));
+        for handler in ["withdraw", "deposit"] {
+            let edges = &graph[handler];
+            assert_eq!(edges.len(), 1, "{handler}");
+            assert_eq!(edges[0].program.as_deref(), Some("token"));
+            assert_eq!(edges[0].call, "token::transfer");
+        }
+        assert!(graph["get_user_balance"].is_empty());
+        assert_eq!(graph.len(), 6);
+    }
+
+    #[test]
+    fn invoke_has_no_static_program() {
+        let graph = call_graph(&parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn relay(ctx: Context<Relay>) -> Result<()> {
+                    anchor_spl::token::mint_to(mint_ctx, 1)?;
+                    solana_program::program::invoke(&ix, &infos)?;
+                    Ok(())
+                }
+            }
+            "#,
+        ));
+        let targets: Vec<_> = graph["relay"]
+            .iter()
+            .map(|t| (t.program.as_deref(), t.call.as_str()))
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                (Some("token"), "anchor_spl::token::mint_to"),
+                (None, "solana_program::program::invoke"),
+            ]
+        );
+    }
+}
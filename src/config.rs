@@ -0,0 +1,407 @@
+//! Project configuration from `audit.toml`.
+//!
+//! ```toml
+//! [severity]
+//! unchecked_arithmetic = "critical"
+//!
+//! [detectors]
+//! disabled = ["panic_prone_unwrap"]
+//! warning_only = ["integer_division_truncation"]
+//!
+//! [notify]
+//! webhook = "https://hooks.example.com/audit"
+//!
+//! [plugins]
+//! dir = "audit-plugins"
+//!
+//! [limits]
+//! max_source_bytes = 1048576
+//! max_files = 5000
+//! ```
+//!
+//! Detectors may be named by short name or namespaced id. The file is found
+//! by walking up from the scan root, so a config at the repository root
+//! covers scans of any subdirectory. `anchor-audit init` writes a
+//! [`template`] to start from.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::detectors::{self, Registry};
+use crate::error::{Error, Result};
+use crate::finding::Severity;
+
+/// Name of the configuration file looked for by [`Config::discover`].
+pub const CONFIG_FILE: &str = "audit.toml";
+
+/// Contents of an `audit.toml`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Severity to report each listed detector's findings at.
+    #[serde(default)]
+    pub severity: HashMap<String, Severity>,
+    #[serde(default)]
+    pub detectors: DetectorConfig,
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    #[serde(default)]
+    pub plugins: PluginConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+}
+
+/// The `[detectors]` table.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DetectorConfig {
+    /// Run only these detectors; empty runs all of them.
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Report these detectors' findings, but never fail the scan on them.
+    #[serde(default)]
+    pub warning_only: Vec<String>,
+}
+
+/// The `[notify]` table; see [`crate::notify`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// URL to POST the scan summary to.
+    pub webhook: Option<String>,
+    /// Attempts after the first failed one.
+    pub retries: Option<u32>,
+    /// Per-attempt timeout in seconds.
+    pub timeout_secs: Option<u64>,
+}
+
+/// The `[plugins]` table; see [`crate::plugin`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    /// Directory of detector libraries, relative to the config file.
+    pub dir: Option<PathBuf>,
+}
+
+/// The `[limits]` table, guarding against oversized input.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Largest source `POST /scan` accepts, in bytes; see
+    /// [`crate::server::DEFAULT_MAX_SOURCE_BYTES`].
+    pub max_source_bytes: Option<usize>,
+    /// Most `.rs` files a directory scan reads; unlimited if unset.
+    pub max_files: Option<usize>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut config: Config = toml::from_str(&text).map_err(|source| Error::Config {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if let (Some(dir), Some(base)) = (&mut config.plugins.dir, path.parent()) {
+            *dir = base.join(&*dir);
+        }
+        Ok(config)
+    }
+
+    /// The nearest `audit.toml` in `root` or one of its ancestors.
+    pub fn discover(root: &Path) -> Option<PathBuf> {
+        let root = fs::canonicalize(root).ok()?;
+        let start = if root.is_file() {
+            root.parent()?
+        } else {
+            &root
+        };
+        start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Adds the configured detector selection and overrides to `registry`.
+    pub fn apply(&self, registry: &mut Registry) {
+        registry
+            .enabled
+            .extend(self.detectors.enabled.iter().cloned());
+        registry
+            .disabled
+            .extend(self.detectors.disabled.iter().cloned());
+        registry
+            .warning_only
+            .extend(self.detectors.warning_only.iter().cloned());
+        registry.severity.extend(
+            self.severity
+                .iter()
+                .map(|(id, severity)| (id.clone(), *severity)),
+        );
+    }
+}
+
+/// A commented `audit.toml` listing every built-in detector at its default
+/// severity. Every override is commented out, so the file changes nothing
+/// until it is edited.
+pub fn template() -> String {
+    let mut out = String::from(
+        "# Configuration for anchor-audit, found by walking up from the scan root.\n\
+         # Detectors may be named by short name or namespaced id.\n\
+         \n\
+         # Severity each detector's findings are reported at. The values are\n\
+         # the defaults; uncomment a line and change it to override one.\n\
+         [severity]\n",
+    );
+    let catalog: Vec<_> = detectors::catalog().collect();
+    let width = catalog
+        .iter()
+        .map(|info| info.name.len())
+        .max()
+        .unwrap_or(0);
+    for info in catalog {
+        let value = format!("\"{}\"", info.severity);
+        let _ = writeln!(
+            out,
+            "# {:width$} = {value:<10} # {}: {}",
+            info.name, info.id, info.title
+        );
+    }
+    out.push_str(
+        "\n\
+         [detectors]\n\
+         # Run only these detectors; empty runs all of them.\n\
+         enabled = []\n\
+         # Never run these, e.g. [\"panic_prone_unwrap\"].\n\
+         disabled = []\n\
+         # Report these, but never fail the scan on their findings.\n\
+         warning_only = []\n\
+         \n\
+         # POST the scan summary to a webhook.\n\
+         # [notify]\n\
+         # webhook = \"https://hooks.example.com/audit\"\n\
+         # retries = 2\n\
+         # timeout_secs = 10\n\
+         \n\
+         # Load detector libraries from a directory relative to this file.\n\
+         # [plugins]\n\
+         # dir = \"audit-plugins\"\n\
+         \n\
+         # Reject oversized input: sources posted to `--serve` (default 1 MiB)\n\
+         # and trees with more `.rs` files than this (default unlimited).\n\
+         # [limits]\n\
+         # max_source_bytes = 1048576\n\
+         # max_files = 5000\n",
+    );
+    out
+}
+
+/// Writes [`template`] to `path`, which must not exist unless `force`.
+pub fn write_template(path: &Path, force: bool) -> Result<()> {
+    if !force && path.exists() {
+        return Err(Error::Exists {
+            path: path.to_path_buf(),
+        });
+    }
+    fs::write(path, template()).map_err(|source| Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{self, ScanOptions};
+    use crate::test_support::fixture;
+
+    const CONFIG: &str = r#"
+        [severity]
+        unchecked_arithmetic = "critical"
+
+        [detectors]
+        disabled = ["panic_prone_unwrap"]
+    "#;
+
+    #[test]
+    fn overrides_apply_before_filtering() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("programs/vault/src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::copy(fixture("vulnerable_vault.rs"), nested.join("lib.rs")).unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), CONFIG).unwrap();
+
+        let path = Config::discover(&nested).expect("config above the scan root");
+        assert_eq!(
+            path,
+            fs::canonicalize(dir.path().join(CONFIG_FILE)).unwrap()
+        );
+        let mut options = ScanOptions {
+            min_severity: Severity::Critical,
+            ..ScanOptions::default()
+        };
+        Config::load(&path).unwrap().apply(&mut options.registry);
+        assert!(options.registry.unknown_ids().is_empty());
+
+        let report = scanner::scan_with(&nested, &options).unwrap();
+        let deposit: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.detector == "unchecked_arithmetic" && f.message.contains("`deposit`"))
+            .collect();
+        assert_eq!(deposit.len(), 2);
+        assert!(deposit.iter().all(|f| f.severity == Severity::Critical));
+        assert!(report
+            .findings
+            .iter()
+            .all(|f| f.detector != "panic_prone_unwrap"));
+    }
+
+    #[test]
+    fn warning_only_findings_do_not_fail_the_scan() {
+        let scan = |warning_only: &str| {
+            let config: Config = toml::from_str(&format!(
+                "[severity]\n\
+                 integer_division_truncation = \"critical\"\n\
+                 [detectors]\n\
+                 enabled = [\"integer_division_truncation\"]\n\
+                 warning_only = [{warning_only}]\n"
+            ))
+            .unwrap();
+            let mut options = ScanOptions {
+                min_severity: Severity::Critical,
+                ..ScanOptions::default()
+            };
+            config.apply(&mut options.registry);
+            assert!(options.registry.unknown_ids().is_empty());
+            scanner::scan_with(&fixture(""), &options).unwrap()
+        };
+
+        let failing = scan("");
+        assert_eq!(failing.exit_code(), scanner::EXIT_FINDINGS);
+        let warned = scan("\"rust/integer-division-truncation\"");
+        assert_eq!(warned.findings.len(), failing.findings.len());
+        assert!(warned
+            .findings
+            .iter()
+            .all(|f| f.severity == Severity::Critical && f.warning_only));
+        assert_eq!(warned.exit_code(), 0);
+    }
+
+    #[test]
+    fn reads_notify_table() {
+        let config: Config =
+            toml::from_str("[notify]\nwebhook = \"https://hooks.example.com/a\"\nretries = 5\n")
+                .unwrap();
+        assert_eq!(
+            config.notify.webhook.as_deref(),
+            Some("https://hooks.example.com/a")
+        );
+        assert_eq!(config.notify.retries, Some(5));
+        assert!(toml::from_str::<Config>("[notify]\nurl = \"x\"\n").is_err());
+    }
+
+    #[test]
+    fn limits_cap_the_files_scanned() {
+        let config: Config =
+            toml::from_str("[limits]\nmax_source_bytes = 4096\nmax_files = 1\n").unwrap();
+        assert_eq!(config.limits.max_source_bytes, Some(4096));
+        let options = ScanOptions {
+            max_files: config.limits.max_files,
+            ..ScanOptions::default()
+        };
+        let err = scanner::scan_with(&fixture(""), &options).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TooManyFiles {
+                found: 2,
+                limit: 1,
+                ..
+            }
+        ));
+        assert!(err
+            .to_string()
+            .ends_with("has 2 .rs files, more than the limit of 1"));
+
+        let options = ScanOptions {
+            max_files: Some(2),
+            ..ScanOptions::default()
+        };
+        assert!(scanner::scan_with(&fixture(""), &options).is_ok());
+    }
+
+    #[test]
+    fn plugin_dir_is_relative_to_the_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, "[plugins]\ndir = \"audit-plugins\"\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.plugins.dir, Some(dir.path().join("audit-plugins")));
+    }
+
+    #[test]
+    fn template_lists_every_detector_at_its_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        write_template(&path, false).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+        let text = fs::read_to_string(&path).unwrap();
+        let overrides: String = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| detectors::catalog().any(|info| line.starts_with(info.name)))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let config: Config = toml::from_str(&format!("[severity]\n{overrides}")).unwrap();
+        let mut listed: Vec<_> = config.severity.keys().map(String::as_str).collect();
+        listed.sort_unstable();
+        let mut names: Vec<_> = detectors::catalog().map(|info| info.name).collect();
+        names.sort_unstable();
+        assert_eq!(listed, names);
+        for info in detectors::catalog() {
+            assert_eq!(config.severity[info.name], info.severity);
+            assert!(text.contains(info.id));
+        }
+
+        let mut registry = Registry::default();
+        config.apply(&mut registry);
+        assert!(registry.unknown_ids().is_empty());
+    }
+
+    #[test]
+    fn template_never_overwrites_without_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, "[detectors]\ndisabled = [\"mul_overflow\"]\n").unwrap();
+        let err = write_template(&path, false).unwrap_err();
+        assert!(matches!(err, Error::Exists { .. }));
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("mul_overflow\"]"));
+
+        write_template(&path, true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), template());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_severities() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, "[severity]\nmul_overflow = \"severe\"\n").unwrap();
+        let err = Config::load(&path).unwrap_err().to_string();
+        assert!(err.starts_with("invalid config"), "{err}");
+
+        fs::write(&path, "[detector]\ndisabled = []\n").unwrap();
+        assert!(Config::load(&path).is_err());
+        assert_eq!(Config::discover(&dir.path().join("missing")), None);
+    }
+}
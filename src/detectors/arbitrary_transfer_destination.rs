@@ -0,0 +1,205 @@
+//! Transfers out of program-controlled accounts to wherever the caller says.
+//!
+//! When a handler moves tokens out of the program's own vault, the `to`
+//! account decides who receives them. If that account is just another
+//! field of the Accounts struct, with no `has_one`, `address`,
+//! `token::authority` or `constraint` tying it to a stored or expected
+//! owner, the caller chooses the recipient. In a privileged handler such as
+//! an emergency drain this turns any missing authority check into theft.
+
+use std::collections::HashSet;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::unprotected_privileged_fn::is_privileged;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountKind, AccountsStruct, ParsedProgram};
+
+pub const ID: &str = "arbitrary_transfer_destination";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/arbitrary-transfer-destination",
+    name: ID,
+    title: "Transfer to a caller-chosen destination",
+    category: Category::AccessControl,
+    severity: Severity::High,
+    cwe: 639,
+    description: "A token transfer out of a program-controlled account sends to a destination account that nothing constrains, so the caller picks who receives the funds.",
+    example: "#[account(mut)]\npub destination: Account<'info, TokenAccount>, // `to` of the drain transfer",
+    fixed_example: "#[account(mut, token::authority = vault.authority)]\npub destination: Account<'info, TokenAccount>,",
+    references: &[
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+        "https://github.com/coral-xyz/sealevel-attacks",
+    ],
+};
+
+/// Token instructions with a `from` and a `to`.
+const TRANSFERS: &[&str] = &["Transfer", "TransferChecked"];
+
+/// Constraint keys that pin an account to a known address or owner.
+const PINNING: &[&str] = &[
+    "address",
+    "constraint",
+    "seeds",
+    "token::authority",
+    "associated_token::authority",
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut reported = HashSet::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let bindings = Bindings::of(&handler.item);
+        let guards = ast::require_guards(&handler.item);
+        for cpi in ast::cpi_accounts(&handler.item) {
+            if !TRANSFERS.contains(&cpi.kind.as_str()) {
+                continue;
+            }
+            let account = |name| cpi.field(name).and_then(|expr| bindings.account(expr));
+            let (Some(from), Some(to)) = (account("from"), account("to")) else {
+                continue;
+            };
+            let Some(vault) = controller(accounts, &from) else {
+                continue;
+            };
+            let Some(field) = accounts.field(&to) else {
+                continue;
+            };
+            let pinned = PINNING.iter().any(|key| field.has_constraint(key))
+                || accounts.fields.iter().any(|other| has_one(other, &to))
+                || guards.iter().any(|guard| ast::mentions(guard, &to));
+            if pinned || !reported.insert((accounts.name.clone(), to.clone())) {
+                continue;
+            }
+            let privileged = is_privileged(handler);
+            let message = format!(
+                "`{}` sends tokens from `{from}`, controlled by the program's `{vault}`, to \
+                 `{}::{to}`, which the caller supplies and no constraint ties to a trusted owner",
+                handler.name, accounts.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::High, program, field.span(), message)
+                    .with_confidence(if privileged {
+                        Confidence::High
+                    } else {
+                        Confidence::Medium
+                    })
+                    .with_impact(format!(
+                        "Anyone who can call `{}` names their own token account as `{to}` and \
+                         receives the tokens from `{from}`",
+                        handler.name
+                    ))
+                    .with_suggestion(format!(
+                        "pin `{to}` to its rightful owner, e.g. \
+                         `#[account(mut, token::authority = {vault}.authority)]` or \
+                         `has_one = {to}` on `{vault}`"
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// The program-owned account that controls the token account `from`:
+/// `vault` for `vault_token`, or `from` itself if it is derived from seeds.
+fn controller(accounts: &AccountsStruct, from: &str) -> Option<String> {
+    let field = accounts.field(from)?;
+    if field.has_constraint("seeds") {
+        return Some(from.to_string());
+    }
+    accounts
+        .fields
+        .iter()
+        .find(|owner| {
+            matches!(&owner.kind, AccountKind::Account(state) if !is_token_type(state))
+                && owner.name != from
+                && from.starts_with(&owner.name)
+        })
+        .map(|owner| owner.name.clone())
+}
+
+fn is_token_type(state: &str) -> bool {
+    matches!(state, "TokenAccount" | "Mint")
+}
+
+/// Whether `field` declares `has_one = name`.
+fn has_one(field: &AccountField, name: &str) -> bool {
+    field
+        .constraint_values("has_one")
+        .any(|value| value.to_string().trim() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_drain_destination() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let messages: Vec<_> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("`withdraw` sends tokens from `vault_token`"));
+        let drain = &findings[1];
+        assert_eq!(drain.span.line_start, 170);
+        assert_eq!(drain.confidence, Confidence::High);
+        assert_eq!(
+            drain.message,
+            "`emergency_drain` sends tokens from `vault_token`, controlled by the program's \
+             `vault`, to `Emergency::destination`, which the caller supplies and no constraint \
+             ties to a trusted owner"
+        );
+        // NFT transfers move the caller's own tokens.
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn drain(destination: &str, vault: &str, guard: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn emergency_drain(ctx: Context<Drain>, amount: u64) -> Result<()> {{
+                    {guard}
+                    let cpi_accounts = Transfer {{
+                        from: ctx.accounts.vault_token.to_account_info(),
+                        to: ctx.accounts.destination.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    }};
+                    token::transfer(CpiContext::new(program, cpi_accounts), amount)
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Drain<'info> {{
+                {vault}
+                pub vault: Account<'info, Vault>,
+                #[account(mut)]
+                pub vault_token: Account<'info, TokenAccount>,
+                {destination}
+                pub destination: Account<'info, TokenAccount>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn pinned_destinations_are_fine() {
+        assert_eq!(drain("#[account(mut)]", "", "").len(), 1);
+        assert!(drain(
+            "#[account(mut, token::authority = vault.authority)]",
+            "",
+            ""
+        )
+        .is_empty());
+        assert!(drain("#[account(mut)]", "#[account(has_one = destination)]", "").is_empty());
+        assert!(drain(
+            "#[account(mut)]",
+            "",
+            "require_keys_eq!(ctx.accounts.destination.owner, ctx.accounts.vault.authority);"
+        )
+        .is_empty());
+    }
+}
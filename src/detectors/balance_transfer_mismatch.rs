@@ -0,0 +1,251 @@
+//! Transfers of a balance captured before the stored balance changed.
+//!
+//! `let amount = vault.balance;` followed by a transfer of `amount` is only
+//! sound if the stored balance is updated to match in between, normally
+//! `vault.balance = 0` or `vault.balance -= amount`. When the account is
+//! instead rewritten through its raw bytes, or the field is set to a value
+//! unrelated to `amount`, the tokens that leave and the balance the program
+//! records afterwards disagree.
+
+use std::collections::HashMap;
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprCall, ExprMethodCall, Lit, Local};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "balance_transfer_mismatch";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/balance-transfer-mismatch",
+    name: ID,
+    title: "Transfer of a stale captured balance",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 682,
+    description: "An account balance is copied into a local, the stored balance is then changed through raw bytes or to an unrelated value, and the stale local is transferred.",
+    example: "let amount = vault.balance;\nstd::ptr::write_bytes(data.as_mut_ptr(), 0, 8);\ntoken::transfer(cpi_ctx, amount)?;",
+    fixed_example: "let amount = vault.balance;\nvault.balance = 0;\ntoken::transfer(cpi_ctx, amount)?;",
+    references: &[
+        "https://cwe.mitre.org/data/definitions/682.html",
+        "https://docs.rs/anchor-spl/latest/anchor_spl/token/fn.transfer.html",
+    ],
+};
+
+/// CPI functions whose last argument is the amount moved.
+const TRANSFERS: &[&str] = &["transfer", "transfer_checked"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            captures: HashMap::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// A local copied from an account field.
+struct Capture {
+    account: String,
+    field: String,
+    /// The field access as written, e.g. `vault.balance`.
+    source: String,
+    /// How the stored value stopped matching the local, once it has.
+    diverged: Option<String>,
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    captures: HashMap<String, Capture>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// Records a typed write of `left`, which keeps a capture in step when
+    /// it zeroes the field or is derived from the captured local.
+    fn write(&mut self, left: &Expr, right: &Expr) {
+        let Some((account, field)) = self.bindings.mut_account_field(left) else {
+            return;
+        };
+        let tokens = right.to_token_stream();
+        let zero = is_zero(right);
+        for (local, capture) in &mut self.captures {
+            if capture.account != account || capture.field != field || capture.diverged.is_some() {
+                continue;
+            }
+            if !zero && !ast::mentions(&tokens, local) {
+                capture.diverged = Some(format!(
+                    "`{}` is set to `{}`",
+                    ast::source_text(left),
+                    ast::source_text(right)
+                ));
+            }
+        }
+    }
+}
+
+fn is_zero(expr: &Expr) -> bool {
+    let Expr::Lit(lit) = ast::unparen(expr) else {
+        return false;
+    };
+    matches!(&lit.lit, Lit::Int(int) if int.base10_digits() == "0")
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        visit::visit_local(self, local);
+        let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) else {
+            return;
+        };
+        self.captures.remove(&name);
+        if let Some((account, field)) = self.bindings.account_field(ast::unparen(&init.expr)) {
+            self.captures.insert(
+                name,
+                Capture {
+                    account,
+                    field,
+                    source: ast::source_text(&*init.expr),
+                    diverged: None,
+                },
+            );
+        }
+    }
+
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        visit::visit_expr_assign(self, node);
+        self.write(&node.left, &node.right);
+    }
+
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        visit::visit_expr_binary(self, node);
+        if matches!(
+            node.op,
+            BinOp::AddAssign(_) | BinOp::SubAssign(_) | BinOp::MulAssign(_) | BinOp::DivAssign(_)
+        ) {
+            self.write(&node.left, &node.right);
+        }
+    }
+
+    /// `ctx.accounts.vault.to_account_info().try_borrow_mut_data()`
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        visit::visit_expr_method_call(self, node);
+        if node.method != "try_borrow_mut_data" {
+            return;
+        }
+        let Some(account) = self.bindings.account(&node.receiver) else {
+            return;
+        };
+        for capture in self.captures.values_mut() {
+            if capture.account == account && capture.diverged.is_none() {
+                capture.diverged = Some(format!(
+                    "the raw data of `{account}` is borrowed for writing"
+                ));
+            }
+        }
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        visit::visit_expr_call(self, node);
+        let Some(path) = ast::cpi_path(node) else {
+            return;
+        };
+        if !TRANSFERS
+            .iter()
+            .any(|name| path.ends_with(&format!("::{name}")))
+        {
+            return;
+        }
+        let Some(local) = node
+            .args
+            .last()
+            .and_then(|arg| ast::ident(ast::unparen(arg)))
+        else {
+            return;
+        };
+        let Some(capture) = self.captures.get_mut(&local) else {
+            return;
+        };
+        let Some(diverged) = capture.diverged.take() else {
+            return;
+        };
+        let source = &capture.source;
+        let message = format!(
+            "`{}` transfers `{local}`, copied from `{}`, after {diverged}, so the amount \
+             sent no longer matches the stored balance",
+            self.handler.name, source
+        );
+        let suggestion = format!(
+            "update `{source}` through the typed account right after reading it, e.g. \
+             `{source} = 0;` or `{source} -= {local};`, and transfer the captured amount last"
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::Medium, self.program, node.span(), message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(suggestion),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_emergency_drain_transfer() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].span.line_start, 112);
+        assert_eq!(findings[0].severity, Severity::Medium);
+        assert_eq!(
+            findings[0].message,
+            "`emergency_drain` transfers `amount`, copied from `vault.balance`, after the raw \
+             data of `vault` is borrowed for writing, so the amount sent no longer matches the \
+             stored balance"
+        );
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn drain(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn drain(ctx: Context<Drain>) -> Result<()> {{
+                    let vault = &mut ctx.accounts.vault;
+                    let amount = vault.balance;
+                    {body}
+                    token::transfer(cpi_ctx, amount)?;
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn typed_updates_in_step_with_the_amount_are_fine() {
+        assert!(drain("vault.balance = 0;").is_empty());
+        assert!(drain("vault.balance -= amount;").is_empty());
+        assert!(drain("vault.balance = vault.balance.checked_sub(amount).unwrap();").is_empty());
+        assert!(drain("vault.fees = 1;").is_empty());
+        let findings = drain("vault.balance = vault.reserve;");
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0]
+            .message
+            .contains("after `vault.balance` is set to `vault.reserve`"));
+    }
+}
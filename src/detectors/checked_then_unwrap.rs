@@ -0,0 +1,135 @@
+//! `.checked_*()` arithmetic whose `None` is unwrapped.
+//!
+//! `checked_sub` is the right call for a balance guard, but unwrapping its
+//! result turns "insufficient funds" back into a panic: the transaction
+//! aborts with no program error for the client to decode. The `Option`
+//! should become an error with `ok_or`, not a panic.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprMethodCall};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "checked_then_unwrap";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/checked-then-unwrap",
+    name: ID,
+    title: "Checked arithmetic unwrapped",
+    category: Category::Arithmetic,
+    severity: Severity::Medium,
+    cwe: 248,
+    description: "The `Option` of a `checked_*` operation is unwrapped, so the overflow or insufficient balance it detects panics instead of returning a program error.",
+    example: "let buyer_balance = ctx.accounts.buyer.lamports().checked_sub(price).unwrap();",
+    fixed_example: "let buyer_balance = ctx.accounts.buyer.lamports().checked_sub(price).ok_or(ErrorCode::InsufficientFunds)?;",
+    references: &[
+        "https://doc.rust-lang.org/std/primitive.u64.html#method.checked_sub",
+        "https://doc.rust-lang.org/std/option/enum.Option.html#method.ok_or",
+    ],
+};
+
+const PANICKING: &[&str] = &["unwrap", "expect"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        let method = node.method.to_string();
+        if PANICKING.contains(&method.as_str()) {
+            if let Expr::MethodCall(checked) = ast::unparen(&node.receiver) {
+                let op = checked.method.to_string();
+                if op.starts_with("checked_") {
+                    // Running out of funds is the usual reason a subtraction fails.
+                    let error = if op == "checked_sub" {
+                        "InsufficientFunds"
+                    } else {
+                        "Overflow"
+                    };
+                    let message = format!(
+                        "`.{method}()` on `{op}()` in `{}` panics when the check fails instead \
+                         of returning an error",
+                        self.handler.name
+                    );
+                    let suggestion =
+                        format!("`{}.ok_or(ErrorCode::{error})?`", ast::source_text(checked));
+                    self.findings.push(
+                        Finding::new(ID, Severity::Medium, self.program, node.span(), message)
+                            .with_confidence(Confidence::High)
+                            .with_suggestion(suggestion),
+                    );
+                }
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_buy_nft_balance_guard() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.span.line_start, 70);
+        assert_eq!(
+            finding.message,
+            "`.unwrap()` on `checked_sub()` in `buy_nft` panics when the check fails instead of \
+             returning an error"
+        );
+        assert_eq!(
+            finding.suggestion.as_deref(),
+            Some(
+                "`ctx.accounts.buyer.lamports().checked_sub(price)\
+                 .ok_or(ErrorCode::InsufficientFunds)?`"
+            )
+        );
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    #[test]
+    fn ignores_handled_options_and_other_unwraps() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn run(ctx: Context<Run>, amount: u64) -> Result<()> {
+                    let total = amount.checked_add(1).ok_or(ErrorCode::Overflow)?;
+                    let first = ctx.accounts.list.first().unwrap();
+                    let doubled = amount.checked_mul(2).expect("fits");
+                    Ok(())
+                }
+            }
+            "#,
+        );
+        let findings = run(&program);
+        assert_eq!(findings.len(), 1, "{findings:?}");
+        assert!(findings[0]
+            .message
+            .starts_with("`.expect()` on `checked_mul()`"));
+    }
+}
@@ -0,0 +1,223 @@
+//! Direct edits of an account's lamports.
+//!
+//! `**account.try_borrow_mut_lamports()? -= amount` only works for accounts
+//! owned by the executing program; moving SOL out of user wallets this way
+//! fails at runtime, and unguarded arithmetic can mint or burn lamports.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprMethodCall, Macro, UnOp};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "direct_lamport_mutation";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/direct-lamport-mutation",
+    name: ID,
+    title: "Direct lamport balance mutation",
+    category: Category::AccessControl,
+    severity: Severity::Critical,
+    cwe: 284,
+    description: "Lamports are moved by editing `try_borrow_mut_lamports()` directly instead of through a system program transfer.",
+    example: "**ctx.accounts.vault.try_borrow_mut_lamports()? -= amount;",
+    fixed_example: "system_program::transfer(CpiContext::new(system_program, system_program::Transfer { from, to }), amount)?;",
+    references: &[
+        "https://solana.com/docs/core/accounts",
+        "https://solana.com/docs/core/cpi",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            checked: Vec::new(),
+            required: Vec::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    /// `(method, argument)` of every `checked_*` call seen so far.
+    checked: Vec<(String, String)>,
+    /// Bodies of every `require*!` seen so far.
+    required: Vec<proc_macro2::TokenStream>,
+    findings: &'a mut Vec<Finding>,
+}
+
+/// The account behind `**account.try_borrow_mut_lamports()?`.
+pub(crate) fn lamports_target(bindings: &Bindings, expr: &Expr) -> Option<String> {
+    let Expr::Unary(deref) = expr else {
+        return None;
+    };
+    if !matches!(deref.op, UnOp::Deref(_)) {
+        return None;
+    }
+    let inner = match &*deref.expr {
+        Expr::Unary(inner) if matches!(inner.op, UnOp::Deref(_)) => &*inner.expr,
+        inner => inner,
+    };
+    let (methods, root) = ast::method_chain(inner);
+    if !methods.iter().any(|m| m == "try_borrow_mut_lamports") {
+        return None;
+    }
+    Some(
+        bindings
+            .account(root)
+            .unwrap_or_else(|| ast::source_text(root)),
+    )
+}
+
+impl Visitor<'_> {
+    /// Whether `value` was validated by a matching `checked_*` or `require!`.
+    fn guarded(&self, method: &str, value: &Expr) -> bool {
+        let value_text = ast::normalized(value);
+        let checked = self
+            .checked
+            .iter()
+            .any(|(m, arg)| m == method && *arg == value_text);
+        let required = ast::ident(value).is_some_and(|name| {
+            self.required
+                .iter()
+                .any(|tokens| ast::mentions(tokens, &name))
+        });
+        checked || required
+    }
+
+    /// `op` is the compound operator, `None` for a plain `=`.
+    fn report(&mut self, node: &impl Spanned, account: String, op: Option<&BinOp>, value: &Expr) {
+        let (action, severity) = match op {
+            Some(BinOp::SubAssign(_)) if !self.guarded("checked_sub", value) => {
+                ("debit", Severity::Critical)
+            }
+            Some(BinOp::AddAssign(_)) if !self.guarded("checked_add", value) => {
+                ("credit", Severity::Critical)
+            }
+            Some(BinOp::SubAssign(_)) => ("debit", Severity::High),
+            Some(BinOp::AddAssign(_)) => ("credit", Severity::High),
+            _ => ("overwrite", Severity::High),
+        };
+        let mut finding = Finding::new(
+            ID,
+            severity,
+            self.program,
+            node.span(),
+            format!(
+                "direct lamport {action} of `{account}` in `{}`; lamports can only be \
+                 moved this way out of accounts owned by this program",
+                self.handler.name
+            ),
+        )
+        .with_confidence(Confidence::High)
+        .with_suggestion(
+            "transfer SOL with `system_program::transfer` via CPI, or validate the \
+             amount with `checked_sub`/`require!` first",
+        );
+        if action == "debit" {
+            finding = finding.with_impact(format!(
+                "Whoever calls `{}` takes lamports out of `{account}` without its signature, \
+                 as many as the instruction asks for",
+                self.handler.name
+            ));
+        }
+        self.findings.push(finding);
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        let method = node.method.to_string();
+        if method.starts_with("checked_") {
+            if let Some(arg) = node.args.first() {
+                self.checked.push((method, ast::normalized(arg)));
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &Macro) {
+        if ast::is_require(node) {
+            self.required.push(node.tokens.clone());
+        }
+        visit::visit_macro(self, node);
+    }
+
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        visit::visit_expr_binary(self, node);
+        if matches!(node.op, BinOp::AddAssign(_) | BinOp::SubAssign(_)) {
+            if let Some(account) = lamports_target(&self.bindings, &node.left) {
+                self.report(node, account, Some(&node.op), &node.right);
+            }
+        }
+    }
+
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        visit::visit_expr_assign(self, node);
+        if let Some(account) = lamports_target(&self.bindings, &node.left) {
+            self.report(node, account, None, &node.right);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_buyer_debit_and_seller_credit() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 2);
+        assert!(findings[0]
+            .message
+            .starts_with("direct lamport debit of `buyer`"));
+        assert!(findings[1]
+            .message
+            .starts_with("direct lamport credit of `seller`"));
+        assert!(findings[0].message.contains("owned by this program"));
+        // The debit follows `lamports().checked_sub(price)`, the credit is unguarded.
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(findings[1].severity, Severity::Critical);
+    }
+
+    #[test]
+    fn require_guard_downgrades_severity() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn pay(ctx: Context<Pay>, price: u64) -> Result<()> {
+                    require!(price <= ctx.accounts.from.lamports(), ErrorCode::Insufficient);
+                    **ctx.accounts.from.try_borrow_mut_lamports()? -= price;
+                    **ctx.accounts.to.try_borrow_mut_lamports()? = 0;
+                    Ok(())
+                }
+            }
+            "#,
+        );
+        let findings = run(&program);
+        let severities: Vec<_> = findings.iter().map(|f| f.severity).collect();
+        assert_eq!(severities, [Severity::High, Severity::High]);
+        assert!(findings[1]
+            .message
+            .starts_with("direct lamport overwrite of `to`"));
+    }
+
+    #[test]
+    fn vault_has_no_lamport_mutation() {
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+//! CPIs whose `Result` is thrown away.
+//!
+//! A failed CPI does not abort the calling instruction by itself: the error
+//! comes back as the call's `Result`. `let _ = token::transfer(..)` or a
+//! bare `token::transfer(..);` drops it, so the handler carries on and
+//! records a transfer that never happened. Propagating with `?` makes the
+//! whole instruction fail instead.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, Local, Pat, Stmt};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "ignored_cpi_result";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/ignored-cpi-result",
+    name: ID,
+    title: "CPI result ignored",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 252,
+    description: "The `Result` of a cross-program invocation is discarded with `let _ =`, `.ok()` or a bare expression statement, so the handler continues as if a failed CPI had succeeded.",
+    example: "let _ = token::transfer(cpi_ctx, amount);\nvault.balance -= amount;",
+    fixed_example: "token::transfer(cpi_ctx, amount)?;\nvault.balance -= amount;",
+    references: &[
+        "https://doc.rust-lang.org/std/result/#results-must-be-used",
+        "https://cwe.mitre.org/data/definitions/252.html",
+    ],
+};
+
+/// `Result` adapters that still leave the error unhandled when their own
+/// result is dropped.
+const DISCARDING: &[&str] = &["ok", "map", "map_err", "or_else", "and_then"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// The CPI call whose `Result` `expr` evaluates to, looking through
+/// [`DISCARDING`] adapters but not through `?`.
+fn cpi_result(expr: &Expr) -> Option<(&ExprCall, String)> {
+    match ast::unparen(expr) {
+        Expr::Call(call) => ast::cpi_path(call).map(|path| (call, path)),
+        Expr::MethodCall(method) if DISCARDING.contains(&method.method.to_string().as_str()) => {
+            cpi_result(&method.receiver)
+        }
+        _ => None,
+    }
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    fn check(&mut self, expr: &Expr, how: &str) {
+        let Some((call, path)) = cpi_result(expr) else {
+            return;
+        };
+        let message = format!(
+            "`{}` discards the `Result` of `{path}` {how}, so a failed CPI goes unnoticed",
+            self.handler.name
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::High, self.program, call.span(), message)
+                .with_confidence(Confidence::High)
+                .with_suggestion(format!(
+                    "propagate the error with `{path}(..)?`, or match on the `Result` and \
+                     return an error when it fails"
+                )),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    /// `let _ = token::transfer(..);`
+    fn visit_local(&mut self, local: &Local) {
+        visit::visit_local(self, local);
+        if let (Pat::Wild(_), Some(init)) = (&local.pat, &local.init) {
+            self.check(&init.expr, "with `let _ =`");
+        }
+    }
+
+    /// `token::transfer(..);`
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        visit::visit_stmt(self, stmt);
+        if let Stmt::Expr(expr, Some(_)) = stmt {
+            self.check(expr, "as a statement");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn handler(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn pay(ctx: Context<Pay>, amount: u64) -> Result<()> {{
+                    let cpi_ctx = CpiContext::new(program, accounts);
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_discarded_transfers() {
+        let findings = handler("let _ = token::transfer(cpi_ctx, amount);");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`pay` discards the `Result` of `token::transfer` with `let _ =`, so a failed CPI \
+             goes unnoticed"
+        );
+        assert_eq!(findings[0].severity, Severity::High);
+
+        let findings = handler("token::transfer(cpi_ctx, amount);");
+        assert!(findings[0].message.contains("as a statement"));
+        assert_eq!(handler("invoke_signed(&ix, &infos, seeds).ok();").len(), 1);
+        assert_eq!(
+            handler("let _ = system_program::transfer(cpi_ctx, amount).map_err(log);").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn propagated_results_are_fine() {
+        assert!(handler("token::transfer(cpi_ctx, amount)?;").is_empty());
+        assert!(handler("let result = token::transfer(cpi_ctx, amount);").is_empty());
+        assert!(handler("let _ = msg!(\"paid\");").is_empty());
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+//! Keys compared with `==` after being read out of raw account bytes.
+//!
+//! A key sliced or cast out of `try_borrow_data()` is only as trustworthy
+//! as the offset and layout the handler assumed; compared with `==`, a
+//! wrong offset or a short buffer silently turns into "not equal" or, worse,
+//! into comparing the wrong 32 bytes. Deserializing the account and
+//! comparing keys with `require_keys_eq!` fails loudly and names both keys
+//! in the error.
+
+use std::collections::HashSet;
+
+use proc_macro2::{LineColumn, Span};
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprBinary, Local, Macro, Token};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "improper_key_comparison";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/improper-key-comparison",
+    name: ID,
+    title: "Key compared with `==` on raw account bytes",
+    category: Category::AccessControl,
+    severity: Severity::Medium,
+    cwe: 697,
+    description: "A public key read out of raw account data is compared with `==` or `!=` instead of deserializing the account and using `require_keys_eq!`.",
+    example: "let data = ctx.accounts.vault.try_borrow_data()?;\nrequire!(&data[8..40] == ctx.accounts.authority.key().as_ref(), ErrorCode::Unauthorized);",
+    fixed_example: "require_keys_eq!(ctx.accounts.vault.authority, ctx.accounts.authority.key(), ErrorCode::Unauthorized);",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/macro.require_keys_eq.html",
+        "https://cwe.mitre.org/data/definitions/697.html",
+    ],
+};
+
+/// Identifiers that make a comparison about keys rather than, say, lengths.
+const KEY_WORDS: &[&str] = &["Pubkey", "key", "to_bytes", "owner", "authority"];
+
+/// Macros whose arguments are conditions.
+const ASSERTIONS: &[&str] = &["require", "assert", "assert_eq", "assert_ne"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let bindings = Bindings::of(&handler.item);
+        let reads: Vec<_> = ast::data_reads(&handler.item, &bindings)
+            .into_iter()
+            .map(|read| (read.span.start(), read.span.end(), read.account))
+            .collect();
+        if reads.is_empty() {
+            continue;
+        }
+        let mut visitor = Visitor {
+            program,
+            handler,
+            reads,
+            raw: Vec::new(),
+            reported: HashSet::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// A local holding bytes, or a value decoded from bytes, of an account.
+struct Raw {
+    local: String,
+    account: String,
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    /// Start, end and account of every raw data borrow in the handler.
+    reads: Vec<(LineColumn, LineColumn, String)>,
+    raw: Vec<Raw>,
+    reported: HashSet<(usize, usize)>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// The account whose raw bytes `expr` is derived from, if any.
+    fn raw_account(&self, expr: &Expr) -> Option<String> {
+        let span = expr.span();
+        let (start, end) = (span.start(), span.end());
+        let key = |at: LineColumn| (at.line, at.column);
+        if let Some((_, _, account)) = self
+            .reads
+            .iter()
+            .find(|(from, to, _)| key(start) <= key(*from) && key(*to) <= key(end))
+        {
+            return Some(account.clone());
+        }
+        let tokens = expr.to_token_stream();
+        self.raw
+            .iter()
+            .rev()
+            .find(|raw| ast::mentions(&tokens, &raw.local))
+            .map(|raw| raw.account.clone())
+    }
+
+    /// Reports `left == right` at `at` if it compares a raw key.
+    fn compare(&mut self, at: Span, left: &Expr, right: &Expr) {
+        let Some(account) = self.raw_account(left).or_else(|| self.raw_account(right)) else {
+            return;
+        };
+        let mut tokens = left.to_token_stream();
+        tokens.extend(right.to_token_stream());
+        let keyed = KEY_WORDS.iter().any(|word| ast::mentions(&tokens, word))
+            || self.raw.iter().any(|raw| {
+                ast::mentions(&tokens, &raw.local)
+                    && KEY_WORDS
+                        .iter()
+                        .any(|word| raw.local.contains(&word.to_ascii_lowercase()))
+            });
+        let start = at.start();
+        if !keyed || !self.reported.insert((start.line, start.column)) {
+            return;
+        }
+        let message = format!(
+            "`{}` compares `{}` with `{}` using `==`/`!=` on a key read from the raw data of \
+             `{account}`",
+            self.handler.name,
+            ast::source_text(left),
+            ast::source_text(right)
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::Medium, self.program, at, message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(format!(
+                    "deserialize `{account}` as a typed `Account` and compare with \
+                     `require_keys_eq!(a, b, ErrorCode::Unauthorized)`"
+                )),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        visit::visit_local(self, local);
+        let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) else {
+            return;
+        };
+        self.raw.retain(|raw| raw.local != name);
+        if let Some(account) = self.raw_account(&init.expr) {
+            self.raw.push(Raw {
+                local: name,
+                account,
+            });
+        }
+    }
+
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        visit::visit_expr_binary(self, node);
+        if matches!(node.op, BinOp::Eq(_) | BinOp::Ne(_)) {
+            self.compare(node.span(), &node.left, &node.right);
+        }
+    }
+
+    /// `require!(a == b, ..)`, and `assert_eq!(a, b)` as a comparison itself.
+    fn visit_macro(&mut self, node: &Macro) {
+        visit::visit_macro(self, node);
+        let Some(name) = node.path.segments.last().map(|s| s.ident.to_string()) else {
+            return;
+        };
+        if !ASSERTIONS.contains(&name.as_str()) {
+            return;
+        }
+        let Ok(args) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) else {
+            return;
+        };
+        match (name.as_str(), args.first(), args.get(1)) {
+            ("assert_eq" | "assert_ne", Some(left), Some(right)) => {
+                self.compare(node.span(), left, right);
+            }
+            (_, Some(condition), _) => self.visit_expr(condition),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn check(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn check(ctx: Context<Check>) -> Result<()> {{
+                    let data = ctx.accounts.vault.try_borrow_data()?;
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_keys_compared_from_raw_bytes() {
+        let findings = check(
+            "let stored = Pubkey::try_from(&data[8..40]).unwrap();
+             if stored != ctx.accounts.authority.key() { return err!(E::Unauthorized); }",
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`check` compares `stored` with `ctx.accounts.authority.key()` using `==`/`!=` on \
+             a key read from the raw data of `vault`"
+        );
+        assert_eq!(
+            check("require!(&data[8..40] == ctx.accounts.authority.key().as_ref(), E::Bad);").len(),
+            1
+        );
+        assert_eq!(
+            check("let owner_bytes = &data[8..40]; assert_eq!(owner_bytes, expected);").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn ignores_typed_and_non_key_comparisons() {
+        assert!(check("if data.len() == 40 { msg!(\"sized\"); }").is_empty());
+        assert!(check(
+            "require_keys_eq!(ctx.accounts.vault.authority, ctx.accounts.authority.key());"
+        )
+        .is_empty());
+        assert!(run(&parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn check(ctx: Context<Check>) -> Result<()> {
+                    require!(ctx.accounts.vault.authority == ctx.accounts.authority.key(), E::Bad);
+                    Ok(())
+                }
+            }
+            "#
+        ))
+        .is_empty());
+        for fixture in ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"] {
+            assert!(run(&parse_fixture(fixture)).is_empty(), "{fixture}");
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Account data only partly zeroed before it is abandoned.
+//!
+//! Zeroing the first few bytes with `write_bytes(ptr, 0, 8)` wipes the
+//! discriminator or a balance but leaves the rest of the account readable,
+//! and an account that still holds rent can be revived with its old state.
+//! Anchor's `close = destination` constraint zeroes the data and reassigns
+//! the account in one go.
+
+use std::collections::HashMap;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprMethodCall, Local};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::incorrect_account_space::{data_len, eval};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "incomplete_account_zeroing";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/incomplete-account-zeroing",
+    name: ID,
+    title: "Account data only partly zeroed",
+    category: Category::MemorySafety,
+    severity: Severity::Medium,
+    cwe: 226,
+    description: "`write_bytes(ptr, 0, N)` clears a constant number of bytes that is smaller than the account's data, leaving the rest readable and the account open to revival.",
+    example: "std::ptr::write_bytes(data.as_mut_ptr(), 0, 8); // Vault holds 64 bytes",
+    fixed_example: "#[account(mut, close = destination)]\npub vault: Account<'info, Vault>,",
+    references: &[
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/9-closing-accounts",
+        "https://cwe.mitre.org/data/definitions/226.html",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            data: HashMap::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    /// Locals holding borrowed account data or pointers into it, with the
+    /// context account it belongs to when known.
+    data: HashMap<String, Option<String>>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// `Some(account)` if `expr` is account data or a pointer into it.
+    fn account_data(&self, expr: &Expr) -> Option<Option<String>> {
+        let (methods, root) = ast::method_chain(expr);
+        if methods
+            .iter()
+            .any(|m| ast::DATA_BORROWS.contains(&m.as_str()))
+        {
+            return Some(self.bindings.account(root));
+        }
+        self.data.get(&ast::ident(root)?).cloned()
+    }
+
+    /// Checks a `write_bytes` of `value` repeated `count` times at `ptr`.
+    fn check(&mut self, node: &impl Spanned, ptr: &Expr, value: &Expr, count: &Expr) {
+        let Some(account) = self.account_data(ptr) else {
+            return;
+        };
+        if eval(value) != Some(0) {
+            return;
+        }
+        let Some(count) = eval(count).filter(|&count| count > 0) else {
+            return;
+        };
+        let len = account.as_deref().and_then(|name| {
+            let field = self.program.accounts_for(self.handler)?.field(name)?;
+            data_len(self.program, field)
+        });
+        let (message, confidence) = match (&account, len) {
+            (Some(name), Some(len)) if count < len => (
+                format!(
+                    "`{}` zeroes only {count} of the {len} bytes of `{name}`; the rest stays \
+                     readable after the account is abandoned",
+                    self.handler.name
+                ),
+                Confidence::High,
+            ),
+            (_, Some(_)) => return,
+            (_, None) => (
+                format!(
+                    "`{}` zeroes only the first {count} bytes of account data; the rest stays \
+                     readable after the account is abandoned",
+                    self.handler.name
+                ),
+                Confidence::Low,
+            ),
+        };
+        let target = account.as_deref().unwrap_or("the account");
+        self.findings.push(
+            Finding::new(ID, Severity::Medium, self.program, node.span(), message)
+                .with_confidence(confidence)
+                .with_suggestion(format!(
+                    "close `{target}` with `#[account(mut, close = destination)]`, or zero \
+                     all `data.len()` bytes and reassign it to the system program"
+                )),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            if let Some(account) = self.account_data(&init.expr) {
+                self.data.insert(name, account);
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    /// `std::ptr::write_bytes(ptr, 0, 8)`
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        if ast::call_name(node).as_deref() == Some("write_bytes") {
+            if let [ptr, value, count] = node.args.iter().collect::<Vec<_>>()[..] {
+                self.check(node, ptr, value, count);
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    /// `ptr.write_bytes(0, 8)`
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        if node.method == "write_bytes" {
+            if let [value, count] = node.args.iter().collect::<Vec<_>>()[..] {
+                self.check(node, &node.receiver, value, count);
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_eight_byte_zeroing_in_emergency_drain() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(finding.confidence, Confidence::High);
+        assert_eq!(finding.span.line_start, 100);
+        assert_eq!(
+            finding.message,
+            "`emergency_drain` zeroes only 8 of the 64 bytes of `vault`; the rest stays readable \
+             after the account is abandoned"
+        );
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn wipe(count: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn wipe(ctx: Context<Wipe>) -> Result<()> {{
+                    let data = ctx.accounts.state.try_borrow_mut_data()?;
+                    unsafe {{ data.as_mut_ptr().write_bytes(0, {count}); }}
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Wipe<'info> {{
+                #[account(mut)]
+                pub state: Account<'info, State>,
+            }}
+
+            #[account]
+            pub struct State {{ pub a: u64, pub b: u64 }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn full_or_dynamic_length_is_fine() {
+        assert_eq!(wipe("8").len(), 1);
+        assert!(wipe("24").is_empty());
+        assert!(wipe("data.len()").is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+//! `space = 8 + N` allocations too small for the account they hold.
+//!
+//! Anchor serializes the whole struct into the allocated bytes, so `space`
+//! must cover the 8-byte discriminator, every fixed-size field and, for each
+//! `String` or `Vec`, a 4-byte length prefix plus its longest contents. A
+//! shortfall makes `init` or a later write fail at runtime.
+
+use syn::{BinOp, Expr, Fields, GenericArgument, Lit, PathArguments, Type};
+
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountKind, AccountsStruct, ParsedProgram};
+
+pub const ID: &str = "incorrect_account_space";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/incorrect-account-space",
+    name: ID,
+    title: "Account space smaller than its struct",
+    category: Category::MemorySafety,
+    severity: Severity::Medium,
+    cwe: 131,
+    description: "The `space` given to `init` does not cover the discriminator plus the serialized size of the account struct, so serialization fails once the data grows.",
+    example: "#[account(init, payer = payer, space = 8 + 16)] pub vault: Account<'info, Vault>, // Vault needs 8 + 40",
+    fixed_example: "#[account(init, payer = payer, space = 8 + Vault::INIT_SPACE)]\npub vault: Account<'info, Vault>,",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/derive.InitSpace.html",
+    ],
+};
+
+const DISCRIMINATOR: usize = 8;
+
+/// Borsh length prefix of a `String` or `Vec`.
+const LEN_PREFIX: usize = 4;
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for accounts in &program.accounts {
+        for field in &accounts.fields {
+            if let Some(finding) = check(program, accounts, field) {
+                findings.push(finding);
+            }
+        }
+    }
+    findings
+}
+
+fn check(
+    program: &ParsedProgram,
+    accounts: &AccountsStruct,
+    field: &AccountField,
+) -> Option<Finding> {
+    let AccountKind::Account(state) = &field.kind else {
+        return None;
+    };
+    let space = field.constraint("space")?;
+    let text = space.value_text()?.replace('+', " + ").replace('*', " * ");
+    let declared = eval(&syn::parse2(space.value.clone()?).ok()?)?;
+    let layout = Layout::of(program, state)?;
+    let required = DISCRIMINATOR + layout.minimum;
+
+    let (message, confidence) = if declared < required {
+        (
+            format!(
+                "`space = {text}` for `{}` in `{}` is {declared} bytes, but `{state}` needs at \
+                 least {required} (8-byte discriminator + {} for its fields)",
+                field.name, accounts.name, layout.minimum
+            ),
+            Confidence::High,
+        )
+    } else if !layout.unbounded.is_empty() {
+        // Enough for empty strings, but nothing says how long they may get.
+        let names: Vec<_> = layout
+            .unbounded
+            .iter()
+            .map(|name| format!("`{name}`"))
+            .collect();
+        (
+            format!(
+                "`space = {text}` for `{}` in `{}` covers the {required}-byte minimum of \
+                 `{state}` but leaves only {} bytes for unbounded {}",
+                field.name,
+                accounts.name,
+                declared - required,
+                names.join(", ")
+            ),
+            Confidence::Low,
+        )
+    } else {
+        return None;
+    };
+    Some(
+        Finding::new(ID, Severity::Medium, program, field.span(), message)
+            .with_confidence(confidence)
+            .with_suggestion(format!(
+                "derive `InitSpace` on `{state}`, bound each `String`/`Vec` with \
+                 `#[max_len(..)]` and use `space = 8 + {state}::INIT_SPACE`"
+            )),
+    )
+}
+
+/// Bytes of account data behind `field`: its literal `space`, or else the
+/// discriminator plus the minimum size of its state struct.
+pub(crate) fn data_len(program: &ParsedProgram, field: &AccountField) -> Option<usize> {
+    if let Some(value) = field
+        .constraint("space")
+        .and_then(|space| space.value.clone())
+    {
+        return eval(&syn::parse2(value).ok()?);
+    }
+    let AccountKind::Account(state) = &field.kind else {
+        return None;
+    };
+    Some(DISCRIMINATOR + Layout::of(program, state)?.minimum)
+}
+
+/// Serialized size of a state struct as far as it can be known statically.
+struct Layout {
+    /// Bytes needed with every unbounded `String`/`Vec` empty.
+    minimum: usize,
+    /// Fields whose length has no `#[max_len]` bound.
+    unbounded: Vec<String>,
+}
+
+impl Layout {
+    fn of(program: &ParsedProgram, state: &str) -> Option<Self> {
+        let Fields::Named(fields) = &program.state_struct(state)?.fields else {
+            return None;
+        };
+        let mut layout = Layout {
+            minimum: 0,
+            unbounded: Vec::new(),
+        };
+        for field in &fields.named {
+            let max_len = field
+                .attrs
+                .iter()
+                .find(|attr| attr.path().is_ident("max_len"))
+                .and_then(|attr| attr.parse_args::<syn::LitInt>().ok())
+                .and_then(|len| len.base10_parse::<usize>().ok());
+            let size = match (dynamic_element(&field.ty), max_len) {
+                (Some(element), Some(len)) => LEN_PREFIX + len * element,
+                (Some(_), None) => {
+                    layout
+                        .unbounded
+                        .extend(field.ident.as_ref().map(|i| i.to_string()));
+                    LEN_PREFIX
+                }
+                // Types we cannot size (nested structs, enums) count as zero,
+                // which keeps the minimum a lower bound.
+                (None, _) => fixed_size(&field.ty).unwrap_or(0),
+            };
+            layout.minimum += size;
+        }
+        Some(layout)
+    }
+}
+
+/// For `String` and `Vec<T>`, the size of one element; `None` otherwise.
+fn dynamic_element(ty: &Type) -> Option<usize> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    match segment.ident.to_string().as_str() {
+        "String" => Some(1),
+        "Vec" => first_type_arg(&segment.arguments).map(|inner| fixed_size(inner).unwrap_or(0)),
+        _ => None,
+    }
+}
+
+fn fixed_size(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::Array(array) => Some(fixed_size(&array.elem)? * eval(&array.len)?),
+        Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            Some(match segment.ident.to_string().as_str() {
+                "bool" | "u8" | "i8" => 1,
+                "u16" | "i16" => 2,
+                "u32" | "i32" | "f32" => 4,
+                "u64" | "i64" | "f64" => 8,
+                "u128" | "i128" => 16,
+                "Pubkey" => 32,
+                "Option" => 1 + fixed_size(first_type_arg(&segment.arguments)?)?,
+                _ => return None,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn first_type_arg(arguments: &PathArguments) -> Option<&Type> {
+    let PathArguments::AngleBracketed(args) = arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+/// Evaluates integer literal arithmetic such as `8 + 32 * 2`.
+pub(crate) fn eval(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(int) => int.base10_parse().ok(),
+            _ => None,
+        },
+        Expr::Paren(paren) => eval(&paren.expr),
+        Expr::Binary(binary) => {
+            let (left, right) = (eval(&binary.left)?, eval(&binary.right)?);
+            match binary.op {
+                BinOp::Add(_) => left.checked_add(right),
+                BinOp::Sub(_) => left.checked_sub(right),
+                BinOp::Mul(_) => left.checked_mul(right),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_nft_metadata_allocation() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(
+            finding.message,
+            "`space = 8 + 200` for `nft_account` in `MintNFT` covers the 62-byte minimum of \
+             `NFTMetadata` but leaves only 146 bytes for unbounded `metadata_uri`"
+        );
+    }
+
+    fn allocation(space: &str, fields: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[derive(Accounts)]
+            pub struct Create<'info> {{
+                #[account(init, payer = payer, space = {space})]
+                pub vault: Account<'info, Vault>,
+                #[account(mut)]
+                pub payer: Signer<'info>,
+            }}
+
+            #[account]
+            pub struct Vault {{ {fields} }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn reports_the_computed_minimum() {
+        let fields = "pub authority: Pubkey, pub balance: u64, pub bump: u8, pub tag: [u8; 4]";
+        let findings = allocation("8 + 40", fields);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0]
+            .message
+            .contains("is 48 bytes, but `Vault` needs at least 53 (8-byte discriminator + 45"));
+        assert_eq!(findings[0].confidence, Confidence::High);
+
+        assert!(allocation("8 + 45", fields).is_empty());
+        assert!(allocation("8 + Vault::INIT_SPACE", fields).is_empty());
+    }
+
+    #[test]
+    fn max_len_bounds_dynamic_fields() {
+        let fields = "#[max_len(10)] pub name: String, #[max_len(3)] pub keys: Vec<Pubkey>";
+        assert!(allocation("8 + 4 + 10 + 4 + 3 * 32", fields).is_empty());
+        assert_eq!(allocation("8 + 100", fields).len(), 1);
+    }
+}
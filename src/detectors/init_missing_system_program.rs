@@ -0,0 +1,120 @@
+//! `init` accounts in a struct without the System program.
+//!
+//! Anchor creates an `init` account by invoking the System program, so the
+//! Accounts struct must carry `system_program: Program<'info, System>`.
+//! Without it the code does not compile against current Anchor or, with a
+//! field of the right name but the wrong type, the caller picks the program
+//! Anchor invokes and the instruction fails or misbehaves at runtime.
+
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, ParsedProgram};
+
+pub const ID: &str = "init_missing_system_program";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/init-missing-system-program",
+    name: ID,
+    title: "`init` without `system_program: Program<'info, System>`",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 665,
+    description: "An Accounts struct creates an account with `init` or `init_if_needed` but has no `Program<'info, System>` field, or has a `system_program` field of another type.",
+    example: "#[account(init, payer = payer, space = 8 + 32)]\npub state: Account<'info, State>,\n#[account(mut)]\npub payer: Signer<'info>,",
+    fixed_example: "#[account(init, payer = payer, space = 8 + 32)]\npub state: Account<'info, State>,\n#[account(mut)]\npub payer: Signer<'info>,\npub system_program: Program<'info, System>,",
+    references: &[
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+        "https://www.anchor-lang.com/docs/references/account-types",
+    ],
+};
+
+const INIT: &[&str] = &["init", "init_if_needed"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for accounts in &program.accounts {
+        let Some(init) = accounts
+            .fields
+            .iter()
+            .find(|field| INIT.iter().any(|key| field.has_constraint(key)))
+        else {
+            continue;
+        };
+        let is_system =
+            |kind: &AccountKind| matches!(kind, AccountKind::Program(name) if name == "System");
+        if accounts.fields.iter().any(|field| is_system(&field.kind)) {
+            continue;
+        }
+        let (span, message) = match accounts.field("system_program") {
+            Some(field) => (
+                field.span(),
+                format!(
+                    "`{}::system_program` is not a `Program<'info, System>`, so the program \
+                     Anchor invokes to create `{}` is whatever the caller passes",
+                    accounts.name, init.name
+                ),
+            ),
+            None => (
+                init.span(),
+                format!(
+                    "`{}::{}` is created with `init`, but `{}` has no \
+                     `system_program: Program<'info, System>` field for Anchor to invoke",
+                    accounts.name, init.name, accounts.name
+                ),
+            ),
+        };
+        findings.push(
+            Finding::new(ID, Severity::High, program, span, message)
+                .with_confidence(Confidence::High)
+                .with_suggestion("add `pub system_program: Program<'info, System>,` to the struct"),
+        );
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn create(fields: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[derive(Accounts)]
+            pub struct Create<'info> {{
+                #[account(init, payer = payer, space = 8 + 32)]
+                pub state: Account<'info, State>,
+                #[account(mut)]
+                pub payer: Signer<'info>,
+                {fields}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_init_without_the_system_program() {
+        let findings = create("");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`Create::state` is created with `init`, but `Create` has no \
+             `system_program: Program<'info, System>` field for Anchor to invoke"
+        );
+        assert_eq!(findings[0].severity, Severity::High);
+
+        let shadowed = create("/// CHECK: any\npub system_program: AccountInfo<'info>,");
+        assert_eq!(shadowed.len(), 1);
+        assert!(shadowed[0]
+            .message
+            .starts_with("`Create::system_program` is not a `Program<'info, System>`"));
+    }
+
+    #[test]
+    fn accepts_the_fixture_mint_nft() {
+        assert!(create("pub system_program: Program<'info, System>,").is_empty());
+        let program = parse_fixture("insecure_nft_marketplace.rs");
+        assert!(program.accounts_struct("MintNFT").is_some());
+        assert!(run(&program).is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! `init` accounts paid for by a signer that no authority check involves.
+//!
+//! `#[account(init, payer = payer)]` only proves that someone signed and
+//! paid the rent. When the same handler then mints with an authority taken
+//! from an unchecked account, nothing connects the payer to that authority,
+//! so anyone willing to pay rent can create accounts the program treats as
+//! legitimately minted.
+
+use std::collections::HashSet;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountKind, AccountsStruct, ParsedProgram};
+
+pub const ID: &str = "init_payer_unconstrained";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/init-payer-unconstrained",
+    name: ID,
+    title: "`init` payer unrelated to the mint authority",
+    category: Category::AccessControl,
+    severity: Severity::Medium,
+    cwe: 284,
+    description: "An `init` account's `payer` is a `Signer`, but the handler mints with an authority from an unchecked account that no constraint or guard ties to the payer or a stored authority.",
+    example: "#[account(init, payer = payer, space = 8 + 200)]\npub nft_account: Account<'info, NFTMetadata>,\npub payer: Signer<'info>,\n/// CHECK: not validated\npub authority: AccountInfo<'info>,",
+    fixed_example: "#[account(init, payer = authority, space = 8 + 200)]\npub nft_account: Account<'info, NFTMetadata>,\n#[account(mut, address = config.mint_authority)]\npub authority: Signer<'info>,",
+    references: &[
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/0-signer-authorization",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut reported = HashSet::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let guards = ast::require_guards(&handler.item);
+        let bindings = Bindings::of(&handler.item);
+        let authorities: Vec<&AccountField> = ast::cpi_accounts(&handler.item)
+            .iter()
+            .filter(|cpi| cpi.kind == "MintTo")
+            .filter_map(|cpi| bindings.account(cpi.field("authority")?))
+            .filter_map(|name| accounts.field(&name))
+            .filter(|field| {
+                matches!(
+                    field.kind,
+                    AccountKind::AccountInfo | AccountKind::UncheckedAccount
+                )
+            })
+            .collect();
+
+        for field in accounts.fields.iter().filter(|f| f.has_constraint("init")) {
+            let Some(payer) = field
+                .constraint("payer")
+                .and_then(|payer| payer.value_text())
+                .and_then(|payer| accounts.field(&payer))
+                .filter(|payer| payer.kind == AccountKind::Signer)
+            else {
+                continue;
+            };
+            let checked = |name: &str| {
+                referenced(accounts, name) || guards.iter().any(|g| ast::mentions(g, name))
+            };
+            let Some(authority) = authorities
+                .iter()
+                .find(|authority| !checked(&authority.name) && !checked(&payer.name))
+            else {
+                continue;
+            };
+            if !reported.insert((accounts.name.clone(), field.name.clone())) {
+                continue;
+            }
+            let message = format!(
+                "`{}::{}` is initialized with `{}` paying, but `{}` mints with unchecked \
+                 `{}`, which no constraint or guard ties to the payer or a stored authority",
+                accounts.name, field.name, payer.name, handler.name, authority.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::Medium, program, field.span(), message)
+                    .with_confidence(Confidence::Medium)
+                    .with_suggestion(format!(
+                        "make `{}` a `Signer` checked against a stored authority \
+                         (`address = ...` or `has_one`), or pay with it directly",
+                        authority.name
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// Whether any constraint in `accounts` other than `payer = ...` mentions
+/// `name`, such as `has_one = authority` or `address = payer.key()`.
+fn referenced(accounts: &AccountsStruct, name: &str) -> bool {
+    accounts.fields.iter().any(|field| {
+        field
+            .constraints
+            .iter()
+            .filter(|constraint| constraint.key != "payer")
+            .filter_map(|constraint| constraint.value.as_ref())
+            .any(|value| ast::mentions(value, name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_mint_nft() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(finding.span.line_start, 167);
+        assert!(finding
+            .message
+            .starts_with("`MintNFT::nft_account` is initialized with `payer` paying"));
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    fn mint(authority: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn mint(ctx: Context<Mint>) -> Result<()> {{
+                    let cpi_accounts = MintTo {{
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.to.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    }};
+                    token::mint_to(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), 1)?;
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Mint<'info> {{
+                #[account(init, payer = payer, space = 8 + 64)]
+                pub record: Account<'info, Record>,
+                #[account(mut)]
+                pub payer: Signer<'info>,
+                {authority}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn constrained_or_signing_authorities_are_fine() {
+        assert_eq!(mint("pub authority: UncheckedAccount<'info>,").len(), 1);
+        assert!(
+            mint("#[account(address = payer.key())]\npub authority: AccountInfo<'info>,")
+                .is_empty()
+        );
+        assert!(mint("pub authority: Signer<'info>,").is_empty());
+    }
+}
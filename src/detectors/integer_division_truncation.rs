@@ -0,0 +1,228 @@
+//! Integer division in amounts that are paid out or returned.
+//!
+//! `(sale_price * royalty_percentage) / 100` rounds toward zero, so a small
+//! sale pays no royalty at all and every payout leaks a fraction to one
+//! side. That is rarely a memory-safety bug, but it is an economic one: the
+//! rounding direction should be a decision, not an accident.
+
+use std::collections::{HashMap, HashSet};
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprCall, ExprReturn, Local, UnOp};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "integer_division_truncation";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/integer-division-truncation",
+    name: ID,
+    title: "Truncating division in a transferred amount",
+    category: Category::Arithmetic,
+    severity: Severity::Low,
+    cwe: 682,
+    description: "An integer division feeds a token or lamport transfer or a returned amount, so the result silently rounds toward zero.",
+    example: "let royalty = (sale_price * royalty_percentage) / 100; Ok(royalty)",
+    fixed_example: "// Basis points, rounded up so small sales still pay a royalty.\nlet royalty = sale_price.checked_mul(royalty_bps).ok_or(ErrorCode::Overflow)?.div_ceil(10_000);\nOk(royalty)",
+    references: &[
+        "https://doc.rust-lang.org/std/primitive.u64.html#method.div_ceil",
+        "https://cwe.mitre.org/data/definitions/682.html",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            divided: HashMap::new(),
+            reported: HashSet::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// The first `a / b` inside `expr`.
+fn division(expr: &Expr) -> Option<&ExprBinary> {
+    #[derive(Default)]
+    struct Finder<'a>(Option<&'a ExprBinary>);
+
+    impl<'a> Visit<'a> for Finder<'a> {
+        fn visit_expr_binary(&mut self, node: &'a ExprBinary) {
+            if self.0.is_none() && matches!(node.op, BinOp::Div(_)) {
+                self.0 = Some(node);
+            }
+            visit::visit_expr_binary(self, node);
+        }
+    }
+
+    let mut finder = Finder::default();
+    finder.visit_expr(expr);
+    finder.0
+}
+
+/// Whether `expr` is `**account.try_borrow_mut_lamports()?` or similar.
+fn is_lamports(mut expr: &Expr) -> bool {
+    while let Expr::Unary(unary) = expr {
+        if !matches!(unary.op, UnOp::Deref(_)) {
+            return false;
+        }
+        expr = &unary.expr;
+    }
+    let (methods, _) = ast::method_chain(expr);
+    methods.iter().any(|method| method.ends_with("lamports"))
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    /// Locals computed by a division, with that division's span and text.
+    divided: HashMap<String, (proc_macro2::Span, String)>,
+    /// Divisions already reported.
+    reported: HashSet<(usize, usize)>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// The division `expr` computes, directly or through a local.
+    fn source(&self, expr: &Expr) -> Option<(proc_macro2::Span, String)> {
+        if let Some(div) = division(expr) {
+            return Some((div.span(), ast::source_text(div)));
+        }
+        let tokens = expr.to_token_stream();
+        self.divided
+            .iter()
+            .filter(|(name, _)| ast::mentions(&tokens, name))
+            .map(|(_, source)| source.clone())
+            .min_by_key(|(span, _)| (span.start().line, span.start().column))
+    }
+
+    /// Reports the division behind `expr`, which is `used` as described.
+    fn check(&mut self, expr: &Expr, used: &str) {
+        let Some((span, text)) = self.source(expr) else {
+            return;
+        };
+        if !self
+            .reported
+            .insert((span.start().line, span.start().column))
+        {
+            return;
+        }
+        let message = format!(
+            "`{}` {used} computed as `{text}`; integer division truncates toward zero, so small amounts \
+             round down and value is silently lost",
+            self.handler.name
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::Low, self.program, span, message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(
+                    "choose the rounding explicitly (`div_ceil`, or add the remainder to one \
+                     side) and keep rates in basis points so precision is lost only once",
+                ),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            if let Some(source) = self.source(&init.expr) {
+                self.divided.insert(name, source);
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        let used = match (ast::cpi_path(node), ast::call_name(node)) {
+            (Some(cpi), _) => Some(format!("passes `{cpi}` an amount")),
+            (None, Some(name)) if name == "Ok" => Some("returns an amount".to_string()),
+            _ => None,
+        };
+        if let Some(used) = used {
+            for arg in &node.args {
+                self.check(arg, &used);
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_return(&mut self, node: &ExprReturn) {
+        if let Some(expr) = &node.expr {
+            self.check(expr, "returns an amount");
+        }
+        visit::visit_expr_return(self, node);
+    }
+
+    /// `**account.try_borrow_mut_lamports()? += share`
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        let compound = matches!(node.op, BinOp::AddAssign(_) | BinOp::SubAssign(_));
+        if compound && is_lamports(&node.left) {
+            self.check(&node.right, "moves lamports");
+        }
+        visit::visit_expr_binary(self, node);
+    }
+
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        if is_lamports(&node.left) {
+            self.check(&node.right, "moves lamports");
+        }
+        visit::visit_expr_assign(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_division_in_calculate_royalties() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Low);
+        assert_eq!(finding.span.line_start, 137);
+        assert_eq!(
+            finding.message,
+            "`calculate_royalties` returns an amount computed as `(sale_price * \
+             royalty_percentage) / 100`; integer division truncates toward zero, so small \
+             amounts round down and value is silently lost"
+        );
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    fn split(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn split(ctx: Context<Split>, amount: u64) -> Result<()> {{
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn only_divisions_reaching_a_payout_count() {
+        assert_eq!(split("token::transfer(cpi_ctx, amount / 3)?;").len(), 1);
+        assert_eq!(
+            split("let share = amount / 2; **ctx.accounts.a.try_borrow_mut_lamports()? += share;")
+                .len(),
+            1
+        );
+        assert!(split("let pages = amount / 2; ctx.accounts.log.pages = pages;").is_empty());
+    }
+}
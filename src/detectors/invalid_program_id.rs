@@ -0,0 +1,189 @@
+//! `declare_id!` values that cannot be a deployed program's address.
+//!
+//! `declare_id!("NFT1234...")` compiles, but the string is not a base58
+//! public key, so the program rejects every instruction once deployed; the
+//! `anchor init` template id is valid yet shared by every fresh project.
+//! Two files declaring one id are two programs that will overwrite each
+//! other, which only the scanner can see; see [`redeclaration`].
+
+use syn::visit::{self, Visit};
+use syn::{ItemMacro, LitStr};
+
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::ParsedProgram;
+
+pub const ID: &str = "invalid_program_id";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/invalid-program-id",
+    name: ID,
+    title: "Placeholder or invalid `declare_id!`",
+    category: Category::Validation,
+    severity: Severity::Low,
+    cwe: 1188,
+    description: "The `declare_id!` string is not a base58-encoded 32-byte public key, is a well-known placeholder, or is declared by more than one program in the scan.",
+    example: "declare_id!(\"NFT1234567890123456789012345678901234567890\");",
+    fixed_example: "// The address of target/deploy/<program>-keypair.json, from `anchor keys sync`.\ndeclare_id!(\"9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin\");",
+    references: &[
+        "https://www.anchor-lang.com/docs/references/cli#keys",
+        "https://docs.rs/anchor-lang/latest/anchor_lang/macro.declare_id.html",
+    ],
+};
+
+/// Base58 alphabet used by Solana addresses.
+const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Valid addresses that no program of its own should declare.
+const PLACEHOLDERS: &[(&str, &str)] = &[
+    (
+        "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS",
+        "the id `anchor init` generates",
+    ),
+    (
+        "11111111111111111111111111111111",
+        "the System Program's id",
+    ),
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for lit in declarations(program) {
+        let id = lit.value();
+        let problem = match decoded_len(&id) {
+            Err(c) => format!("`{c}` is not a base58 character"),
+            Ok(32) => match PLACEHOLDERS
+                .iter()
+                .find(|(placeholder, _)| *placeholder == id)
+            {
+                Some((_, what)) => format!("it is {what}"),
+                None => continue,
+            },
+            Ok(len) => format!("it decodes to {len} bytes, not the 32 of a public key"),
+        };
+        findings.push(
+            Finding::new(
+                ID,
+                Severity::Low,
+                program,
+                lit.span(),
+                format!("`declare_id!(\"{id}\")` is not a deployable program id: {problem}"),
+            )
+            .with_confidence(Confidence::High)
+            .with_suggestion(
+                "generate the program keypair and run `anchor keys sync` so `declare_id!` \
+                 matches the address the program is deployed at",
+            ),
+        );
+    }
+    findings
+}
+
+/// A finding for `program`'s `declare_id!`, to be reported when another
+/// file in the same scan has already declared the same id.
+pub fn redeclaration(program: &ParsedProgram) -> Option<Finding> {
+    let lit = declarations(program).into_iter().next()?;
+    let message = format!(
+        "program id `{}` is also declared by another file in the scan; programs deployed \
+         with one id overwrite each other",
+        lit.value()
+    );
+    Some(
+        Finding::new(ID, Severity::Low, program, lit.span(), message)
+            .with_confidence(Confidence::Medium)
+            .with_suggestion("give each program its own keypair and `declare_id!`"),
+    )
+}
+
+/// The string literal of every `declare_id!` in the file.
+fn declarations(program: &ParsedProgram) -> Vec<LitStr> {
+    #[derive(Default)]
+    struct Finder(Vec<LitStr>);
+
+    impl Visit<'_> for Finder {
+        fn visit_item_macro(&mut self, node: &ItemMacro) {
+            if node.mac.path.is_ident("declare_id") {
+                self.0.extend(node.mac.parse_body::<LitStr>());
+            }
+            visit::visit_item_macro(self, node);
+        }
+    }
+
+    let mut finder = Finder::default();
+    finder.visit_file(&program.file);
+    finder.0
+}
+
+/// Number of bytes `text` decodes to as base58, or the first character
+/// outside the alphabet.
+fn decoded_len(text: &str) -> Result<usize, char> {
+    // Little-endian base-256 digits of the value so far.
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in text.chars() {
+        let mut carry = ALPHABET
+            .iter()
+            .position(|&digit| char::from(digit) == c)
+            .ok_or(c)?;
+        for byte in &mut bytes {
+            carry += usize::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let leading_zeros = text.chars().take_while(|&c| c == '1').count();
+    Ok(leading_zeros + bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_both_fixture_ids_as_invalid_base58() {
+        for (fixture, id) in [
+            (
+                "insecure_nft_marketplace.rs",
+                "NFT1234567890123456789012345678901234567890",
+            ),
+            (
+                "vulnerable_vault.rs",
+                "VuLn1234567890123456789012345678901234567890",
+            ),
+        ] {
+            let findings = run(&parse_fixture(fixture));
+            assert_eq!(findings.len(), 1, "{fixture}");
+            assert_eq!(findings[0].severity, Severity::Low);
+            assert_eq!(
+                findings[0].message,
+                format!(
+                    "`declare_id!(\"{id}\")` is not a deployable program id: `0` is not a \
+                     base58 character"
+                )
+            );
+        }
+    }
+
+    fn declare(id: &str) -> Vec<Finding> {
+        run(&parse(&format!("declare_id!(\"{id}\");")))
+    }
+
+    #[test]
+    fn accepts_real_keys_and_rejects_placeholders_and_bad_lengths() {
+        assert!(declare("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin").is_empty());
+        assert!(declare("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").is_empty());
+        assert!(declare("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS")[0]
+            .message
+            .ends_with("it is the id `anchor init` generates"));
+        assert!(declare("11111111111111111111111111111111")[0]
+            .message
+            .ends_with("the System Program's id"));
+        assert!(declare("VuLn")[0]
+            .message
+            .ends_with("it decodes to 3 bytes, not the 32 of a public key"));
+    }
+}
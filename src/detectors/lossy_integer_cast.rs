@@ -0,0 +1,267 @@
+//! `as` casts that narrow an integer and silently drop its high bits.
+//!
+//! `amount as u32` keeps the low 32 bits of a `u64` without complaint, so a
+//! large amount becomes a small one and a length read from account bytes
+//! wraps to whatever fits. `u32::try_from(amount)` fails instead. Only casts
+//! of runtime values whose type is known from a declaration are reported;
+//! literals and widening casts are left alone.
+
+use std::collections::HashMap;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprCast, Lit, Local, Pat, Type, UnOp};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "lossy_integer_cast";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/lossy-integer-cast",
+    name: ID,
+    title: "Narrowing integer `as` cast",
+    category: Category::Arithmetic,
+    severity: Severity::Low,
+    cwe: 197,
+    description: "An integer is cast with `as` to a narrower integer type, which truncates values that do not fit instead of failing.",
+    example: "let fee = amount as u32;",
+    fixed_example: "let fee = u32::try_from(amount).map_err(|_| ErrorCode::Overflow)?;",
+    references: &[
+        "https://doc.rust-lang.org/reference/expressions/operator-expr.html#numeric-cast",
+        "https://doc.rust-lang.org/std/convert/trait.TryFrom.html",
+    ],
+};
+
+/// Methods whose result is a `usize` whatever they are called on.
+const USIZE_METHODS: &[&str] = &["len", "count"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            locals: HashMap::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// Width in bits of an integer type, taking `usize` and `isize` as the 64
+/// bits they are on Solana.
+fn bits(ty: &str) -> Option<u32> {
+    match ty {
+        "u8" | "i8" => Some(8),
+        "u16" | "i16" => Some(16),
+        "u32" | "i32" => Some(32),
+        "u64" | "i64" | "usize" | "isize" => Some(64),
+        "u128" | "i128" => Some(128),
+        _ => None,
+    }
+}
+
+/// The name of `ty` if it is a plain path such as `u32`.
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.get_ident().map(|i| i.to_string()),
+        Type::Paren(inner) => type_name(&inner.elem),
+        _ => None,
+    }
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    /// Integer type of every local whose type is known so far.
+    locals: HashMap<String, String>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// The integer type of `expr`, when a declaration or an explicit cast
+    /// says what it is.
+    fn integer_type(&self, expr: &Expr) -> Option<String> {
+        let ty = match ast::unparen(expr) {
+            Expr::Cast(cast) => type_name(&cast.ty),
+            // `*(ptr as *const u32)`
+            Expr::Unary(deref) if matches!(deref.op, UnOp::Deref(_)) => {
+                match ast::unparen(&deref.expr) {
+                    Expr::Cast(cast) => match &*cast.ty {
+                        Type::Ptr(ptr) => type_name(&ptr.elem),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            Expr::Lit(lit) => match &lit.lit {
+                Lit::Int(int) if !int.suffix().is_empty() => Some(int.suffix().to_string()),
+                _ => None,
+            },
+            Expr::Path(_) => {
+                let name = ast::ident(expr)?;
+                self.locals
+                    .get(&name)
+                    .cloned()
+                    .or_else(|| self.handler.arg_type(&name))
+            }
+            Expr::Field(_) => {
+                let (account, field) = self.bindings.account_field(expr)?;
+                self.program
+                    .state_field_type(self.handler, &account, &field)
+            }
+            Expr::MethodCall(call) if USIZE_METHODS.contains(&call.method.to_string().as_str()) => {
+                Some("usize".to_string())
+            }
+            Expr::MethodCall(call) if call.method == "lamports" => Some("u64".to_string()),
+            // `u64::from_le_bytes(..)`
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(path) if path.path.segments.len() == 2 => {
+                    Some(path.path.segments[0].ident.to_string())
+                }
+                _ => None,
+            },
+            Expr::Binary(binary) if !is_comparison(&binary.op) => self
+                .integer_type(&binary.left)
+                .or_else(|| self.integer_type(&binary.right)),
+            _ => None,
+        }?;
+        bits(&ty).map(|_| ty)
+    }
+
+    fn check(&mut self, cast: &ExprCast) {
+        // A literal is a constant the author chose, not a runtime value.
+        if matches!(ast::unparen(&cast.expr), Expr::Lit(_)) {
+            return;
+        }
+        let (Some(from), Some(to)) = (self.integer_type(&cast.expr), type_name(&cast.ty)) else {
+            return;
+        };
+        let (Some(from_bits), Some(to_bits)) = (bits(&from), bits(&to)) else {
+            return;
+        };
+        if to_bits >= from_bits {
+            return;
+        }
+        let value = ast::source_text(&*cast.expr);
+        let message = format!(
+            "`{}` casts `{value}` from `{from}` to `{to}` with `as`, silently dropping the high \
+             bits of values that do not fit",
+            self.handler.name
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::Low, self.program, cast.span(), message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(format!(
+                    "use `{to}::try_from({value})` or `.try_into()` and return an error when it \
+                     fails, e.g. `.map_err(|_| ErrorCode::Overflow)?`"
+                )),
+        );
+    }
+}
+
+fn is_comparison(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Eq(_) | BinOp::Ne(_) | BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_)
+    )
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        visit::visit_local(self, local);
+        let Some(name) = ast::pat_ident(&local.pat) else {
+            return;
+        };
+        let declared = match &local.pat {
+            Pat::Type(typed) => type_name(&typed.ty).filter(|ty| bits(ty).is_some()),
+            _ => None,
+        };
+        match declared.or_else(|| {
+            let init = local.init.as_ref()?;
+            self.integer_type(&init.expr)
+        }) {
+            Some(ty) => self.locals.insert(name, ty),
+            None => self.locals.remove(&name),
+        };
+    }
+
+    fn visit_expr_cast(&mut self, node: &ExprCast) {
+        visit::visit_expr_cast(self, node);
+        self.check(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture, parse, parse_fixture};
+
+    fn handler(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn pay(ctx: Context<Pay>, amount: u64, bps: u16) -> Result<()> {{
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_narrowing_casts_of_known_types() {
+        let findings = handler("let fee = amount as u32;");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`pay` casts `amount` from `u64` to `u32` with `as`, silently dropping the high bits \
+             of values that do not fit"
+        );
+        assert_eq!(findings[0].severity, Severity::Low);
+        assert_eq!(
+            handler("let total: u128 = amount as u128 * 3; let out = total as u64;").len(),
+            1
+        );
+        assert_eq!(handler("let n = (amount * 2) as u8;").len(), 1);
+        assert_eq!(
+            handler("let n = ctx.remaining_accounts.len() as u32;").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn ignores_widening_literal_and_unknown_casts() {
+        assert!(handler("let wide = amount as u128;").is_empty());
+        assert!(handler("let share = bps as u64;").is_empty());
+        assert!(handler("let max = 300u64 as u8;").is_empty());
+        assert!(handler("let x = unknown() as u8;").is_empty());
+    }
+
+    #[test]
+    fn evaluates_the_metadata_length_read() {
+        // `*(ptr as *const u32) as usize` widens, so the fixture is clean,
+        // but the same read narrowed is caught.
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+        let narrowed = std::fs::read_to_string(fixture("insecure_nft_marketplace.rs"))
+            .unwrap()
+            .replace(
+                "*(ptr as *const u32) as usize",
+                "*(ptr as *const u32) as u16",
+            );
+        let findings = run(&parse(&narrowed));
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0]
+            .message
+            .starts_with("`get_nft_metadata` casts `*(ptr as *const u32)` from `u32` to `u16`"));
+        assert_eq!(findings[0].span.line_start, 97);
+    }
+}
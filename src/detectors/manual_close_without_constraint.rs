@@ -0,0 +1,288 @@
+//! Accounts "closed" by hand instead of with `close = receiver`.
+//!
+//! Zeroing an account's data or draining its lamports looks like closing
+//! it, but the account stays allocated and owned by the program until the
+//! runtime garbage-collects it at the end of the transaction, and only if it
+//! holds no lamports. A zeroed account that keeps its rent can be reused
+//! with blank state; one drained in the same transaction that refunds it can
+//! be revived. Anchor's `#[account(mut, close = receiver)]` moves the
+//! lamports, zeroes the data and hands the account back to the System
+//! program in one step.
+
+use std::collections::{HashMap, HashSet};
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprCall, ExprMethodCall, Local, UnOp};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::incorrect_account_space::eval;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountsStruct, Handler, ParsedProgram};
+
+pub const ID: &str = "manual_close_without_constraint";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/manual-close-without-constraint",
+    name: ID,
+    title: "Account closed by hand without `close = receiver`",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 672,
+    description: "A handler zeroes an account's data or drains its lamports, mimicking closure, but the account has no `#[account(close = ...)]` constraint, so it stays allocated and can be reused or revived.",
+    example: "let data = ctx.accounts.vault.to_account_info().try_borrow_mut_data()?;\nunsafe { std::ptr::write_bytes(data.as_mut_ptr(), 0, 8); }",
+    fixed_example: "#[account(mut, close = destination)]\npub vault: Account<'info, Vault>,",
+    references: &[
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/9-closing-accounts",
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let mut visitor = Visitor {
+            bindings: Bindings::of(&handler.item),
+            data: HashMap::new(),
+            closes: Vec::new(),
+            closed: HashSet::new(),
+        };
+        visitor.visit_item_fn(&handler.item);
+        let mut reported = HashSet::new();
+        for (account, action) in &visitor.closes {
+            if visitor.closed.contains(account) || !reported.insert(account) {
+                continue;
+            }
+            report(program, handler, accounts, account, action, &mut findings);
+        }
+    }
+    findings
+}
+
+/// Reports `account` at its field unless the field already closes it.
+fn report(
+    program: &ParsedProgram,
+    handler: &Handler,
+    accounts: &AccountsStruct,
+    account: &str,
+    action: &str,
+    findings: &mut Vec<Finding>,
+) {
+    let Some(field) = accounts.field(account) else {
+        return;
+    };
+    if field.has_constraint("close") {
+        return;
+    }
+    // At the attribute the `close` belongs in, when there is one.
+    let span = field
+        .field
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("account"))
+        .map_or_else(|| field.span(), Spanned::span);
+    let message = format!(
+        "`{}` {action} `{}::{account}` by hand, but the field has no `close` constraint, so \
+         the account stays allocated and can be reused or revived",
+        handler.name, accounts.name
+    );
+    findings.push(
+        Finding::new(ID, Severity::Medium, program, span, message)
+            .with_confidence(Confidence::Medium)
+            .with_suggestion(format!(
+                "mark the field `#[account(mut, close = receiver)]` and drop the manual \
+                 zeroing and lamport moves on `{account}`"
+            )),
+    );
+}
+
+struct Visitor {
+    bindings: Bindings,
+    /// Locals holding borrowed account data, or pointers into it, with the
+    /// context account it belongs to.
+    data: HashMap<String, String>,
+    /// Each account treated as closed and what was done to it, in source
+    /// order.
+    closes: Vec<(String, &'static str)>,
+    /// Accounts closed with Anchor's `AccountsClose::close`.
+    closed: HashSet<String>,
+}
+
+impl Visitor {
+    /// The context account whose data `expr` is, or points into.
+    fn account_data(&self, expr: &Expr) -> Option<String> {
+        let (methods, root) = ast::method_chain(expr);
+        if methods
+            .iter()
+            .any(|m| ast::DATA_BORROWS.contains(&m.as_str()))
+        {
+            return self.bindings.account(root);
+        }
+        self.data.get(&ast::ident(root)?).cloned()
+    }
+
+    /// The context account behind `**account.try_borrow_mut_lamports()?`.
+    fn lamports(&self, expr: &Expr) -> Option<String> {
+        let Expr::Unary(deref) = expr else {
+            return None;
+        };
+        if !matches!(deref.op, UnOp::Deref(_)) {
+            return None;
+        }
+        let inner = match &*deref.expr {
+            Expr::Unary(inner) if matches!(inner.op, UnOp::Deref(_)) => &*inner.expr,
+            inner => inner,
+        };
+        let (methods, root) = ast::method_chain(inner);
+        if !methods.iter().any(|m| m == "try_borrow_mut_lamports") {
+            return None;
+        }
+        self.bindings.account(root)
+    }
+
+    /// Records zeroing of `ptr` if `value` is a constant `0`.
+    fn zeroed(&mut self, ptr: &Expr, value: &Expr) {
+        if eval(value) != Some(0) {
+            return;
+        }
+        if let Some(account) = self.account_data(ptr) {
+            self.closes.push((account, "zeroes the data of"));
+        }
+    }
+}
+
+impl Visit<'_> for Visitor {
+    fn visit_local(&mut self, local: &Local) {
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            if let Some(account) = self.account_data(&init.expr) {
+                self.data.insert(name, account);
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    /// `std::ptr::write_bytes(ptr, 0, n)` and `sol_memset(data, 0, n)`.
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        let name = ast::call_name(node);
+        if matches!(name.as_deref(), Some("write_bytes" | "sol_memset")) {
+            if let [ptr, value, _] = node.args.iter().collect::<Vec<_>>()[..] {
+                self.zeroed(ptr, value);
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    /// `ptr.write_bytes(0, n)`, `data.fill(0)` and `account.close(receiver)`.
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        let args: Vec<_> = node.args.iter().collect();
+        match (node.method.to_string().as_str(), &args[..]) {
+            ("write_bytes", [value, _]) | ("fill", [value]) => {
+                self.zeroed(&node.receiver, value);
+            }
+            ("close", [_]) => {
+                if let Some(account) = self.bindings.account(&node.receiver) {
+                    self.closed.insert(account);
+                }
+            }
+            _ => {}
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    /// `**lamports = 0`
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        visit::visit_expr_assign(self, node);
+        if eval(&node.right) == Some(0) {
+            if let Some(account) = self.lamports(&node.left) {
+                self.closes.push((account, "drains the lamports of"));
+            }
+        }
+    }
+
+    /// `**lamports -= account.lamports()`
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        visit::visit_expr_binary(self, node);
+        if !matches!(node.op, BinOp::SubAssign(_)) {
+            return;
+        }
+        let Some(account) = self.lamports(&node.left) else {
+            return;
+        };
+        let (methods, root) = ast::method_chain(&node.right);
+        let whole = methods.iter().any(|m| m == "lamports")
+            && self.bindings.account(root).as_deref() == Some(&account);
+        if whole {
+            self.closes.push((account, "drains the lamports of"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_zeroed_vault_in_emergency_drain() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`emergency_drain` zeroes the data of `Emergency::vault` by hand, but the field has \
+             no `close` constraint, so the account stays allocated and can be reused or revived"
+        );
+        assert_eq!(findings[0].severity, Severity::Medium);
+        assert_eq!(findings[0].span.line_start, 163);
+        // Paying for an NFT moves part of the buyer's lamports, not all of them.
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn close(constraint: &str, body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn close(ctx: Context<Close>) -> Result<()> {{
+                    let state = ctx.accounts.state.to_account_info();
+                    {body}
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Close<'info> {{
+                #[account({constraint})]
+                pub state: Account<'info, State>,
+                #[account(mut)]
+                pub receiver: Signer<'info>,
+            }}
+            "#
+        )))
+    }
+
+    const DRAIN: &str = "**ctx.accounts.receiver.try_borrow_mut_lamports()? += state.lamports();
+        **state.try_borrow_mut_lamports()? = 0;";
+
+    #[test]
+    fn flags_lamport_drains_unless_the_account_closes() {
+        let drained = close("mut", DRAIN);
+        assert_eq!(drained.len(), 1);
+        assert!(drained[0]
+            .message
+            .starts_with("`close` drains the lamports of `Close::state`"));
+        assert_eq!(
+            close("mut", "state.try_borrow_mut_data()?.fill(0);").len(),
+            1
+        );
+        assert!(close("mut, close = receiver", DRAIN).is_empty());
+        assert!(close(
+            "mut",
+            "ctx.accounts.state.close(ctx.accounts.receiver.to_account_info())?;"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+//! Raw data writes to accounts Anchor deserializes and writes back.
+//!
+//! A `mut` field typed `Account<'info, T>` is deserialized into `T` before
+//! the handler runs and serialized from `T` again when it returns. Bytes
+//! written through `try_borrow_mut_data()` in between never reach `T`, so
+//! the exit serialization silently overwrites them, or, when the layout
+//! was hand-rolled to match, they race with whatever the handler set on
+//! the typed fields. State belongs in the fields of `T`.
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, ParsedProgram};
+
+pub const ID: &str = "manual_data_write_bypass";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/manual-data-write-bypass",
+    name: ID,
+    title: "Raw data write to a typed account",
+    category: Category::MemorySafety,
+    severity: Severity::Medium,
+    cwe: 664,
+    description: "A handler writes an `Account<'info, T>` through `try_borrow_mut_data()` instead of mutating `T`, so Anchor's serialization on exit overwrites the raw bytes.",
+    example: "let mut data = ctx.accounts.nft_account.try_borrow_mut_data()?;\ndata[8..16].copy_from_slice(&price.to_le_bytes());",
+    fixed_example: "ctx.accounts.nft_account.price = price;",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/account/struct.Account.html",
+        "https://www.anchor-lang.com/docs/references/account-types",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let mut reported = Vec::new();
+        for write in ast::data_reads(&handler.item, &Bindings::of(&handler.item)) {
+            if !write.mutable || reported.contains(&write.account) {
+                continue;
+            }
+            let Some(AccountKind::Account(ty)) =
+                accounts.field(&write.account).map(|field| &field.kind)
+            else {
+                continue;
+            };
+            let account = &write.account;
+            let message = format!(
+                "`{}` writes the raw data of `{account}`, an `Account<'info, {ty}>`, so Anchor \
+                 serializes its `{ty}` over those bytes when the handler returns",
+                handler.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::Medium, program, write.span, message)
+                    .with_confidence(Confidence::Medium)
+                    .with_suggestion(format!(
+                        "set the fields of `ctx.accounts.{account}` instead of its bytes, or \
+                         declare it `AccountLoader<'info, {ty}>` if it is zero-copy"
+                    )),
+            );
+            reported.push(write.account);
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_batch_price_write() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].span.line_start, 147);
+        assert_eq!(
+            findings[0].message,
+            "`batch_update_prices` writes the raw data of `nft_account`, an \
+             `Account<'info, NFTMetadata>`, so Anchor serializes its `NFTMetadata` over those \
+             bytes when the handler returns"
+        );
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.starts_with("`emergency_drain`"));
+    }
+
+    fn update(field: &str, body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn update(ctx: Context<Update>, price: u64) -> Result<()> {{
+                    {body}
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Update<'info> {{
+                #[account(mut)]
+                pub state: {field},
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn only_mutable_borrows_of_typed_accounts_count() {
+        let write = "let mut data = ctx.accounts.state.try_borrow_mut_data()?; data[8] = 1;";
+        assert_eq!(update("Account<'info, State>", write).len(), 1);
+        assert_eq!(
+            update(
+                "Account<'info, State>",
+                &format!("{write} let more = ctx.accounts.state.try_borrow_mut_data()?;")
+            )
+            .len(),
+            1
+        );
+        assert!(update("UncheckedAccount<'info>", write).is_empty());
+        assert!(update("AccountLoader<'info, State>", write).is_empty());
+        assert!(update(
+            "Account<'info, State>",
+            "let data = ctx.accounts.state.try_borrow_data()?;"
+        )
+        .is_empty());
+        assert!(update("Account<'info, State>", "ctx.accounts.state.price = price;").is_empty());
+    }
+}
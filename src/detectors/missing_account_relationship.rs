@@ -0,0 +1,217 @@
+//! Vault token transfers whose accounts are not tied to the vault.
+//!
+//! When a handler moves tokens out of `vault_token`, Anchor must be told
+//! that the token account belongs to `vault` and that `authority` is the
+//! vault's authority. Without `has_one = authority` or a
+//! `constraint = vault_token.owner == vault.key()`, a caller can pass any
+//! vault alongside accounts of their choosing.
+
+use std::collections::HashSet;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::missing_authority_guard::OWNER_FIELDS;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountKind, AccountsStruct, Handler, ParsedProgram};
+
+pub const ID: &str = "missing_account_relationship";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-account-relationship",
+    name: ID,
+    title: "Vault accounts not tied together",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 639,
+    description: "Tokens leave a vault token account without `has_one` or an ownership `constraint` linking the token account, vault and authority.",
+    example: "#[account(mut)] pub vault_token_account: Account<'info, TokenAccount>, // no has_one / token::authority",
+    fixed_example: "#[account(mut, token::authority = vault)]\npub vault_token_account: Account<'info, TokenAccount>,",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/derive.Accounts.html",
+        "https://github.com/coral-xyz/sealevel-attacks",
+    ],
+};
+
+/// Constraints on a token account that can name its owning account.
+const TOKEN_OWNER_CONSTRAINTS: &[&str] = &[
+    "constraint",
+    "token::authority",
+    "associated_token::authority",
+    "seeds",
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut reported = HashSet::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        for source in transfer_sources(handler) {
+            let Some((vault, owner)) = owning_vault(program, handler, accounts, &source) else {
+                continue;
+            };
+            if !reported.insert((accounts.name.clone(), vault.name.clone())) {
+                continue;
+            }
+            if binds_owner(accounts, vault, owner) || binds_token(accounts, &source, &vault.name) {
+                continue;
+            }
+            let message = format!(
+                "`{}` moves tokens out of `{source}` in `{}` without `has_one = {owner}` on \
+                 `{}` or `constraint = {source}.owner == {}.key()`",
+                accounts.name, handler.name, vault.name, vault.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::High, program, vault.span(), message)
+                    .with_confidence(Confidence::Medium)
+                    .with_suggestion(format!(
+                        "declare `#[account(mut, has_one = {owner})]` on `{}` and \
+                         `#[account(mut, constraint = {source}.owner == {}.key())]` on `{source}`",
+                        vault.name, vault.name
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// Context accounts used as the `from` of a token `Transfer` in `handler`.
+fn transfer_sources(handler: &Handler) -> Vec<String> {
+    let bindings = Bindings::of(&handler.item);
+    let mut sources: Vec<_> = ast::cpi_accounts(&handler.item)
+        .iter()
+        .filter_map(|cpi| cpi.field("from"))
+        .filter_map(|from| bindings.account(from))
+        .collect();
+    sources.dedup();
+    sources
+}
+
+/// The state account `source` belongs to by name (`vault` for
+/// `vault_token`) together with the field recording its authority.
+fn owning_vault<'a>(
+    program: &ParsedProgram,
+    handler: &Handler,
+    accounts: &'a AccountsStruct,
+    source: &str,
+) -> Option<(&'a AccountField, &'static str)> {
+    accounts.fields.iter().find_map(|field| {
+        let owns = matches!(field.kind, AccountKind::Account(_))
+            && field.name != source
+            && source.starts_with(&field.name);
+        if !owns {
+            return None;
+        }
+        OWNER_FIELDS
+            .iter()
+            .copied()
+            .find(|owner| {
+                program
+                    .state_field_type(handler, &field.name, owner)
+                    .is_some()
+            })
+            .map(|owner| (field, owner))
+    })
+}
+
+/// Whether `vault` is tied to the signer through `has_one = owner` or a
+/// `constraint` mentioning the owner field.
+fn binds_owner(accounts: &AccountsStruct, vault: &AccountField, owner: &str) -> bool {
+    vault
+        .constraint_values("has_one")
+        .any(|tokens| ast::mentions(tokens, owner))
+        || accounts.fields.iter().any(|field| {
+            field
+                .constraint_values("constraint")
+                .any(|tokens| ast::mentions(tokens, &vault.name) && ast::mentions(tokens, owner))
+        })
+}
+
+/// Whether the token account `source` is constrained to belong to `vault`.
+fn binds_token(accounts: &AccountsStruct, source: &str, vault: &str) -> bool {
+    accounts.field(source).is_some_and(|field| {
+        TOKEN_OWNER_CONSTRAINTS.iter().any(|key| {
+            field
+                .constraint_values(key)
+                .any(|tokens| ast::mentions(tokens, vault))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn source(vault_attr: &str, token_attr: &str) -> String {
+        format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {{
+                    let cpi_accounts = Transfer {{
+                        from: ctx.accounts.vault_token.to_account_info(),
+                        to: ctx.accounts.user_token.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    }};
+                    token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), amount)?;
+                    Ok(())
+                }}
+            }}
+            #[derive(Accounts)]
+            pub struct Withdraw<'info> {{
+                {vault_attr}
+                pub vault: Account<'info, Vault>,
+                {token_attr}
+                pub vault_token: Account<'info, TokenAccount>,
+                #[account(mut)]
+                pub user_token: Account<'info, TokenAccount>,
+                pub authority: Signer<'info>,
+                pub token_program: Program<'info, Token>,
+            }}
+            #[account]
+            pub struct Vault {{
+                pub authority: Pubkey,
+                pub balance: u64,
+            }}
+            "#
+        )
+    }
+
+    #[test]
+    fn flags_vault_withdrawals() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let structs: Vec<_> = findings
+            .iter()
+            .map(|f| f.message.split('`').nth(1).unwrap())
+            .collect();
+        assert_eq!(structs, ["Withdraw", "Emergency"]);
+        assert_eq!(
+            findings[0].message,
+            "`Withdraw` moves tokens out of `vault_token` in `withdraw` without \
+             `has_one = authority` on `vault` or `constraint = vault_token.owner == vault.key()`"
+        );
+        assert!(findings.iter().all(|f| f.severity == Severity::High));
+    }
+
+    #[test]
+    fn declared_relationships_are_respected() {
+        let mut_only = "#[account(mut)]";
+        assert_eq!(run(&parse(&source(mut_only, mut_only))).len(), 1);
+        assert!(run(&parse(&source(
+            "#[account(mut, has_one = authority)]",
+            mut_only
+        )))
+        .is_empty());
+        let token = "#[account(mut, constraint = vault_token.owner == vault.key())]";
+        assert!(run(&parse(&source(mut_only, token))).is_empty());
+    }
+
+    #[test]
+    fn ignores_deposits_into_the_vault() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert!(findings.iter().all(|f| !f.message.contains("`deposit`")));
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+//! State owned by an authority that is changed without checking the caller.
+//!
+//! Overwriting `vault.authority`, or editing an account that records its
+//! `owner`, is only safe after comparing the signer with the stored key via
+//! `require_keys_eq!` or a `has_one` constraint.
+
+use std::collections::HashSet;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountsStruct, Handler, ParsedProgram};
+
+pub const ID: &str = "missing_authority_guard";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-authority-guard",
+    name: ID,
+    title: "Owned state changed without an ownership check",
+    category: Category::AccessControl,
+    severity: Severity::High,
+    cwe: 862,
+    description: "A handler writes `authority`/`owner` fields, or state an owner controls, without comparing the caller against the stored key.",
+    example: "vault.authority = new_authority; // nothing compares the signer to the current authority",
+    fixed_example: "require_keys_eq!(ctx.accounts.authority.key(), vault.authority, ErrorCode::Unauthorized);\nvault.authority = new_authority;",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/derive.Accounts.html",
+        "https://github.com/coral-xyz/sealevel-attacks",
+    ],
+};
+
+/// Fields that record who controls an account.
+pub(crate) const OWNER_FIELDS: &[&str] = &["authority", "owner"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        if guarded_by_require(handler) {
+            continue;
+        }
+        let bindings = Bindings::of(&handler.item);
+        let writes = ast::field_writes(&handler.item, &bindings);
+        let mut reported = HashSet::new();
+
+        // Direct writes to `authority` / `owner` first, so they win over the
+        // generic "owned state changed" finding for the same account.
+        let ordered = writes
+            .iter()
+            .filter(|w| OWNER_FIELDS.contains(&w.field.as_str()))
+            .chain(&writes);
+        let pure_state_change = ast::cpi_calls(&handler.item).is_empty();
+
+        for write in ordered {
+            if reported.contains(&write.account) || !unguarded(accounts, &write.account) {
+                continue;
+            }
+            let (owner, message, impact) = if OWNER_FIELDS.contains(&write.field.as_str()) {
+                (
+                    write.field.as_str(),
+                    format!(
+                        "`{}` overwrites `{}` without checking the caller against the current {}",
+                        handler.name, write.target, write.field
+                    ),
+                    format!(
+                        "Any wallet can call `{}` and make itself the {} of `{}`, taking over \
+                         everything that {} is trusted with",
+                        handler.name, write.field, write.account, write.field
+                    ),
+                )
+            } else if let Some(owner) = owner_field(program, handler, &write.account) {
+                if !pure_state_change {
+                    continue;
+                }
+                (
+                    owner,
+                    format!(
+                        "`{}` modifies `{}` without checking the caller against its `{owner}`",
+                        handler.name, write.account
+                    ),
+                    format!(
+                        "Any wallet can call `{}` and change `{}` without being its `{owner}`",
+                        handler.name, write.account
+                    ),
+                )
+            } else {
+                continue;
+            };
+            reported.insert(write.account.clone());
+            findings.push(
+                Finding::new(ID, Severity::High, program, write.span, message)
+                    .with_confidence(Confidence::Medium)
+                    .with_impact(impact)
+                    .with_suggestion(format!(
+                        "add `has_one = {owner}` to the constraint of `{account}` or \
+                         `require_keys_eq!(signer.key(), {account}.{owner})` before the write",
+                        account = write.account
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// Whether a `require*!` in the handler compares against a stored owner.
+pub(crate) fn guarded_by_require(handler: &Handler) -> bool {
+    ast::require_guards(&handler.item).iter().any(|tokens| {
+        OWNER_FIELDS
+            .iter()
+            .any(|field| ast::mentions(tokens, field))
+    })
+}
+
+/// Whether the account is neither freshly created nor tied to its owner by
+/// a `has_one` / `constraint` in the Accounts struct.
+pub(crate) fn unguarded(accounts: &AccountsStruct, account: &str) -> bool {
+    let Some(field) = accounts.field(account) else {
+        return true;
+    };
+    if field.has_constraint("init") || field.has_constraint("init_if_needed") {
+        return false;
+    }
+    !field
+        .constraint_values("has_one")
+        .chain(field.constraint_values("constraint"))
+        .any(|tokens| {
+            OWNER_FIELDS
+                .iter()
+                .any(|owner| ast::mentions(tokens, owner))
+        })
+}
+
+/// The owner-like field on the state behind `account`, if it has one.
+fn owner_field(program: &ParsedProgram, handler: &Handler, account: &str) -> Option<&'static str> {
+    OWNER_FIELDS
+        .iter()
+        .copied()
+        .find(|field| program.state_field_type(handler, account, field).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn handlers(findings: &[Finding]) -> Vec<&str> {
+        findings
+            .iter()
+            .map(|f| f.message.split('`').nth(1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn flags_vault_update_authority() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(handlers(&findings), ["update_authority"]);
+        assert_eq!(
+            findings[0].message,
+            "`update_authority` overwrites `vault.authority` without checking the caller \
+             against the current authority"
+        );
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some(
+                "add `has_one = authority` to the constraint of `vault` or \
+                 `require_keys_eq!(signer.key(), vault.authority)` before the write"
+            )
+        );
+    }
+
+    #[test]
+    fn flags_nft_ownership_changes() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(handlers(&findings), ["list_nft", "buy_nft", "transfer_nft"]);
+        assert!(findings[0].message.contains("against its `owner`"));
+        assert_eq!(
+            findings[1].suggestion.as_deref(),
+            Some(
+                "add `has_one = owner` to the constraint of `nft_account` or \
+                 `require_keys_eq!(signer.key(), nft_account.owner)` before the write"
+            )
+        );
+    }
+
+    #[test]
+    fn require_keys_eq_guard_is_respected() {
+        let source = |guard: &str| {
+            format!(
+                r#"
+                #[program]
+                pub mod demo {{
+                    pub fn update_authority(ctx: Context<UpdateAuth>, new_authority: Pubkey) -> Result<()> {{
+                        let vault = &mut ctx.accounts.vault;
+                        {guard}
+                        vault.authority = new_authority;
+                        Ok(())
+                    }}
+                }}
+                #[derive(Accounts)]
+                pub struct UpdateAuth<'info> {{
+                    #[account(mut)]
+                    pub vault: Account<'info, Vault>,
+                    pub caller: Signer<'info>,
+                }}
+                "#
+            )
+        };
+        assert_eq!(run(&parse(&source(""))).len(), 1);
+        let guard = "require_keys_eq!(ctx.accounts.caller.key(), vault.authority);";
+        assert!(run(&parse(&source(guard))).is_empty());
+    }
+
+    #[test]
+    fn has_one_constraint_is_respected() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn update_authority(ctx: Context<UpdateAuth>, new_authority: Pubkey) -> Result<()> {
+                    ctx.accounts.vault.authority = new_authority;
+                    Ok(())
+                }
+            }
+            #[derive(Accounts)]
+            pub struct UpdateAuth<'info> {
+                #[account(mut, has_one = authority)]
+                pub vault: Account<'info, Vault>,
+                pub authority: Signer<'info>,
+            }
+            "#,
+        );
+        assert!(run(&program).is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+//! Raw reads of untyped accounts with no discriminator check.
+//!
+//! Anchor prefixes every `#[account]` with an 8-byte discriminator and
+//! `Account<'info, T>` refuses accounts whose prefix is not `T`'s. An
+//! `AccountInfo` or `UncheckedAccount` gets no such check, so a handler
+//! that reads its bytes at fixed offsets will happily decode an account of
+//! a different type, one whose fields line up so that the "balance" it reads
+//! is whatever the attacker chose. This is the type-cosplay half of what
+//! [`raw_account_info_bypass`](super::raw_account_info_bypass) reports, and
+//! is reported on the same read so the two merge.
+
+use std::collections::HashSet;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, ParsedProgram};
+
+pub const ID: &str = "missing_discriminator_check";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-discriminator-check",
+    name: ID,
+    title: "Untyped account read without a discriminator check",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 843,
+    description: "The raw data of an `AccountInfo` or `UncheckedAccount` is read, but the handler neither types the account as `Account<'info, T>` nor compares its first 8 bytes with `T::DISCRIMINATOR`.",
+    example: "let data = ctx.accounts.user_account.try_borrow_data()?;\nlet balance = u64::from_le_bytes(data[8..16].try_into().unwrap());",
+    fixed_example: "let data = ctx.accounts.user_account.try_borrow_data()?;\nrequire!(data[..8] == UserAccount::DISCRIMINATOR, ErrorCode::WrongAccountType);",
+    references: &[
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/3-type-cosplay",
+        "https://www.anchor-lang.com/docs/basics/idl#discriminators",
+    ],
+};
+
+/// Calls that check the discriminator while decoding.
+const CHECKED_DECODERS: &[&str] = &["try_deserialize", "try_from"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut reported = HashSet::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let body = handler.item.block.to_token_stream();
+        if checks_discriminator(&body) {
+            continue;
+        }
+        for read in ast::data_reads(&handler.item, &Bindings::of(&handler.item)) {
+            let Some(field) = accounts.field(&read.account) else {
+                continue;
+            };
+            if !matches!(
+                field.kind,
+                AccountKind::AccountInfo | AccountKind::UncheckedAccount
+            ) || !reported.insert((accounts.name.clone(), field.name.clone()))
+            {
+                continue;
+            }
+            let message = format!(
+                "`{}` reads the raw data of `{}::{}` without checking its 8-byte \
+                 discriminator, so an account of any other type with a matching layout is \
+                 accepted",
+                handler.name, accounts.name, field.name
+            );
+            findings.push(
+                Finding::new(
+                    ID,
+                    Severity::High,
+                    program,
+                    read.conversion.unwrap_or(read.span),
+                    message,
+                )
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(format!(
+                    "declare `{}` as `Account<'info, T>`, or \
+                     `require!(data[..8] == T::DISCRIMINATOR, ..)` before reading it",
+                    field.name
+                )),
+            );
+        }
+    }
+    findings
+}
+
+/// Whether `tokens` mention a discriminator or call a checking decoder.
+fn checks_discriminator(tokens: &TokenStream) -> bool {
+    tokens.clone().into_iter().any(|tree| match tree {
+        TokenTree::Ident(ident) => {
+            let name = ident.to_string();
+            name.to_lowercase().contains("discriminator")
+                || CHECKED_DECODERS.contains(&name.as_str())
+        }
+        TokenTree::Group(group) => checks_discriminator(&group.stream()),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_query_user_account() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`get_user_balance` reads the raw data of `Query::user_account` without checking \
+             its 8-byte discriminator, so an account of any other type with a matching layout \
+             is accepted"
+        );
+        assert_eq!(findings[0].severity, Severity::High);
+        // The typed `Account<NFTMetadata>` read is Anchor-checked.
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    #[test]
+    fn merges_with_the_raw_account_info_bypass_finding() {
+        let program = parse_fixture("vulnerable_vault.rs");
+        let findings = crate::scanner::analyze(&program, &crate::detectors::Registry::default());
+        let read: Vec<_> = findings
+            .iter()
+            .filter(|f| f.handler.as_deref() == Some("get_user_balance"))
+            .filter(|f| {
+                f.detector == ID
+                    || f.also_matched
+                        .contains(&crate::detectors::raw_account_info_bypass::ID)
+            })
+            .collect();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].detector, ID);
+        assert_eq!(read[0].span.line_start, 45);
+    }
+
+    fn balance(check: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn balance(ctx: Context<Query>) -> Result<u64> {{
+                    let data = ctx.accounts.user_account.try_borrow_data()?;
+                    {check}
+                    Ok(u64::from_le_bytes(data[8..16].try_into().unwrap()))
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Query<'info> {{
+                /// CHECK: read by hand
+                pub user_account: AccountInfo<'info>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn manual_discriminator_checks_are_fine() {
+        assert_eq!(balance("").len(), 1);
+        assert!(balance("require!(data[..8] == UserAccount::DISCRIMINATOR, E::Type);").is_empty());
+        assert!(balance("let user = UserAccount::try_deserialize(&mut &data[..])?;").is_empty());
+    }
+}
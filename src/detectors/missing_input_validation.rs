@@ -0,0 +1,263 @@
+//! Numeric instruction arguments used without any bounds check.
+//!
+//! Amounts, prices and percentages arrive straight from the transaction.
+//! A handler that multiplies, transfers or stores one without a single
+//! `require!`, `assert!` or `if` condition on it accepts zero, `u64::MAX`
+//! and a 10 000% royalty alike. This is a broad heuristic: it does not know
+//! which bound is right, only that none is checked, so it reports at low
+//! severity and confidence.
+
+use std::collections::BTreeMap;
+
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprCall, ExprIf, FnArg, Macro, PatType};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "missing_input_validation";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-input-validation",
+    name: ID,
+    title: "Numeric argument used without validation",
+    category: Category::Validation,
+    severity: Severity::Low,
+    cwe: 20,
+    description: "A numeric instruction argument is used in arithmetic, passed to a CPI or stored in an account without any `require!`, assertion or `if` condition bounding it.",
+    example: "pub fn list_nft(ctx: Context<ListNFT>, price: u64) -> Result<()> {\n    ctx.accounts.nft_account.price = price;\n    Ok(())\n}",
+    fixed_example: "require!(price > 0 && price <= MAX_PRICE, ErrorCode::InvalidPrice);\nctx.accounts.nft_account.price = price;",
+    references: &[
+        "https://cwe.mitre.org/data/definitions/20.html",
+        "https://www.anchor-lang.com/docs/features/errors",
+    ],
+};
+
+const INTEGER_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let params = integer_params(handler);
+        if params.is_empty() {
+            continue;
+        }
+        let mut facts = Facts {
+            bindings: Bindings::of(&handler.item),
+            params: params.iter().map(|(name, _)| name.clone()).collect(),
+            uses: BTreeMap::new(),
+            conditions: Vec::new(),
+        };
+        facts.visit_item_fn(&handler.item);
+        for (param, span) in params {
+            let guarded = facts
+                .conditions
+                .iter()
+                .any(|tokens| ast::mentions(tokens, &param));
+            let Some(uses) = facts.uses.get(&param).filter(|_| !guarded) else {
+                continue;
+            };
+            // How the argument is used stays out of the message, so the
+            // fingerprint survives edits that change one use but not the gap.
+            let message = format!(
+                "`{}` never checks the numeric argument `{param}` with a `require!` or \
+                 condition before using it",
+                handler.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::Low, program, span, message)
+                    .with_confidence(Confidence::Low)
+                    .with_suggestion(format!(
+                        "`{param}` is used {}; check it against the range the instruction \
+                         accepts, e.g. `require!({param} > 0 && {param} <= MAX, \
+                         ErrorCode::InvalidArgument)`",
+                        join(uses)
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// `a`, `a and b`, `a, b and c`.
+fn join(items: &[&str]) -> String {
+    match items.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {last}", rest.join(", ")),
+        _ => items.concat(),
+    }
+}
+
+/// `handler`'s integer arguments with the span of their pattern.
+fn integer_params(handler: &Handler) -> Vec<(String, Span)> {
+    handler
+        .item
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(PatType { pat, .. }) => Some((ast::pat_ident(pat)?, pat.span())),
+            FnArg::Receiver(_) => None,
+        })
+        .filter(|(name, _)| {
+            handler
+                .arg_type(name)
+                .is_some_and(|ty| INTEGER_TYPES.contains(&ty.as_str()))
+        })
+        .collect()
+}
+
+struct Facts {
+    bindings: Bindings,
+    params: Vec<String>,
+    /// How each parameter is used, in order of first occurrence.
+    uses: BTreeMap<String, Vec<&'static str>>,
+    /// Tokens of every `if` condition and `require*!`/`assert*!` macro.
+    conditions: Vec<TokenStream>,
+}
+
+impl Facts {
+    fn used(&mut self, expr: &Expr, how: &'static str) {
+        let tokens = expr.to_token_stream();
+        for param in &self.params {
+            if ast::mentions(&tokens, param) {
+                let uses = self.uses.entry(param.clone()).or_default();
+                if !uses.contains(&how) {
+                    uses.push(how);
+                }
+            }
+        }
+    }
+}
+
+impl Visit<'_> for Facts {
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        visit::visit_expr_binary(self, node);
+        if matches!(
+            node.op,
+            BinOp::Add(_)
+                | BinOp::Sub(_)
+                | BinOp::Mul(_)
+                | BinOp::Div(_)
+                | BinOp::Rem(_)
+                | BinOp::AddAssign(_)
+                | BinOp::SubAssign(_)
+                | BinOp::MulAssign(_)
+                | BinOp::DivAssign(_)
+        ) {
+            self.used(&node.left, "in arithmetic");
+            self.used(&node.right, "in arithmetic");
+        }
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        visit::visit_expr_call(self, node);
+        if ast::cpi_path(node).is_some() {
+            for arg in &node.args {
+                self.used(arg, "in a CPI");
+            }
+        }
+    }
+
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        visit::visit_expr_assign(self, node);
+        if self.bindings.mut_account_field(&node.left).is_some() {
+            self.used(&node.right, "as stored account state");
+        }
+    }
+
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        self.conditions.push(node.cond.to_token_stream());
+        visit::visit_expr_if(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &Macro) {
+        let guard = node
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident.to_string().starts_with("assert"));
+        if guard || ast::is_require(node) {
+            self.conditions.push(node.tokens.clone());
+        }
+        visit::visit_macro(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn flagged(findings: &[Finding]) -> Vec<&str> {
+        findings
+            .iter()
+            .map(|f| {
+                let start = f.message.find("argument `").unwrap() + "argument `".len();
+                let end = start + f.message[start..].find('`').unwrap();
+                &f.message[start..end]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flags_the_unvalidated_fixture_arguments() {
+        let nft = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(flagged(&nft), ["price", "sale_price", "royalty_percentage"]);
+        assert_eq!(
+            nft[0].message,
+            "`list_nft` never checks the numeric argument `price` with a `require!` or \
+             condition before using it"
+        );
+        assert!(nft[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .starts_with("`price` is used as stored account state;"));
+        assert!(nft[2].message.starts_with("`calculate_royalties`"));
+
+        let vault = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(flagged(&vault), ["amount", "amount", "multiplier"]);
+        assert!(vault[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .starts_with("`amount` is used in arithmetic, as stored account state and in a CPI;"));
+        assert!(vault[2].message.starts_with("`calculate_rewards`"));
+        assert!(vault
+            .iter()
+            .all(|f| f.severity == Severity::Low && f.confidence == Confidence::Low));
+    }
+
+    fn rewards(guard: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn calculate_rewards(ctx: Context<Query>, multiplier: u64) -> Result<u64> {{
+                    {guard}
+                    Ok(ctx.accounts.vault.balance * multiplier)
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn any_check_on_the_argument_clears_it() {
+        assert_eq!(rewards("").len(), 1);
+        assert!(rewards("require!(multiplier <= 10, ErrorCode::TooLarge);").is_empty());
+        assert!(rewards("assert!(multiplier > 0);").is_empty());
+        assert!(rewards("if multiplier > 10 { return err!(ErrorCode::TooLarge); }").is_empty());
+        assert_eq!(
+            rewards("require!(ctx.accounts.vault.open, E::Closed);").len(),
+            1
+        );
+    }
+}
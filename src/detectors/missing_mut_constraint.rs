@@ -0,0 +1,177 @@
+//! Writes to accounts not marked `#[account(mut)]`.
+//!
+//! The runtime rejects any change to an account the transaction did not
+//! pass as writable, and Anchor only asks for writable accounts where the
+//! Accounts struct says `mut`. Assigning to a field of any other account
+//! compiles, then fails every transaction when Anchor writes it back.
+
+use std::collections::HashSet;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::ExprMethodCall;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, ParsedProgram};
+
+pub const ID: &str = "missing_mut_constraint";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-mut-constraint",
+    name: ID,
+    title: "Account written without `mut`",
+    category: Category::Validation,
+    severity: Severity::Low,
+    cwe: 665,
+    description: "A handler writes a field, the lamports or the data of an account that its Accounts struct does not mark `mut`, `init` or `zero`, so every such transaction fails.",
+    example: "pub nft_account: Account<'info, NFTMetadata>,\n// in the handler\nctx.accounts.nft_account.price = price;",
+    fixed_example: "#[account(mut)]\npub nft_account: Account<'info, NFTMetadata>,",
+    references: &[
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+        "https://solana.com/docs/core/accounts",
+    ],
+};
+
+/// Constraints that make an account writable.
+const WRITABLE: &[&str] = &["mut", "init", "init_if_needed", "zero"];
+
+/// Methods that borrow an account's lamports or data for writing.
+const MUT_BORROWS: &[&str] = &["try_borrow_mut_lamports", "try_borrow_mut_data"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let bindings = Bindings::of(&handler.item);
+        let mut writes: Vec<_> = ast::field_writes(&handler.item, &bindings)
+            .into_iter()
+            .map(|write| (write.account, format!("`{}`", write.target), write.span))
+            .collect();
+        let mut borrows = MutBorrows {
+            bindings: &bindings,
+            found: Vec::new(),
+        };
+        borrows.visit_item_fn(&handler.item);
+        writes.extend(borrows.found);
+        writes.sort_by_key(|(_, _, span)| (span.start().line, span.start().column));
+
+        let mut reported = HashSet::new();
+        for (account, written, span) in writes {
+            let Some(field) = accounts.field(&account) else {
+                continue;
+            };
+            if is_writable(field) || !reported.insert(account.clone()) {
+                continue;
+            }
+            let message = format!(
+                "`{}` writes {written}, but `{}::{account}` is not `#[account(mut)]`, so the \
+                 transaction fails when the change is written back",
+                handler.name, accounts.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::Low, program, span, message)
+                    .with_confidence(Confidence::High)
+                    .with_suggestion(format!("mark `{account}` with `#[account(mut)]`")),
+            );
+        }
+    }
+    findings
+}
+
+fn is_writable(field: &AccountField) -> bool {
+    WRITABLE.iter().any(|key| field.has_constraint(key))
+}
+
+/// `ctx.accounts.x.try_borrow_mut_lamports()` and the like.
+struct MutBorrows<'a> {
+    bindings: &'a Bindings,
+    /// Account, what is written and where, as for field writes.
+    found: Vec<(String, String, proc_macro2::Span)>,
+}
+
+impl Visit<'_> for MutBorrows<'_> {
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        if MUT_BORROWS.iter().any(|method| node.method == method) {
+            if let Some(account) = self.bindings.account(&node.receiver) {
+                let kind = if node.method == "try_borrow_mut_data" {
+                    "data"
+                } else {
+                    "lamports"
+                };
+                self.found.push((
+                    account.clone(),
+                    format!("the {kind} of `{account}`"),
+                    node.span(),
+                ));
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn fixture_writes_are_all_to_mut_accounts() {
+        let program = parse_fixture("insecure_nft_marketplace.rs");
+        assert!(program
+            .accounts_struct("ListNFT")
+            .unwrap()
+            .field("nft_account")
+            .unwrap()
+            .is_mut());
+        assert!(run(&program).is_empty());
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    fn list(attribute: &str, body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn list_nft(ctx: Context<ListNFT>, price: u64) -> Result<()> {{
+                    {body}
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct ListNFT<'info> {{
+                {attribute}
+                pub nft_account: Account<'info, NFTMetadata>,
+                pub lister: Signer<'info>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_writes_to_read_only_accounts() {
+        let body =
+            "let nft = &mut ctx.accounts.nft_account;\nnft.price = price;\nnft.listed = true;";
+        let findings = list("", body);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`list_nft` writes `nft.price`, but `ListNFT::nft_account` is not `#[account(mut)]`, \
+             so the transaction fails when the change is written back"
+        );
+        assert!(list("#[account(mut)]", body).is_empty());
+
+        let findings = list(
+            "",
+            "**ctx.accounts.nft_account.try_borrow_mut_lamports()? -= price;",
+        );
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0]
+            .message
+            .starts_with("`list_nft` writes the lamports of `nft_account`, but"));
+    }
+}
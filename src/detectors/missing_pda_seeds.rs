@@ -0,0 +1,133 @@
+//! CPI authorities that can only be PDAs but are not derived from seeds.
+//!
+//! An account that is not a `Signer` can only authorise a CPI if it is a
+//! program-derived address the program signs for. Anchor checks that an
+//! account is the expected PDA only when its field declares `seeds` and
+//! `bump`; without them any address with the right shape is accepted, and
+//! the program signs for whatever the caller passed.
+
+use std::collections::HashSet;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, ParsedProgram};
+
+pub const ID: &str = "missing_pda_seeds";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-pda-seeds",
+    name: ID,
+    title: "PDA authority without `seeds`/`bump` validation",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 345,
+    description: "An account used as a CPI authority without being a `Signer` must be a program-derived address, but its Accounts field declares no `seeds`/`bump` constraint to prove it.",
+    example: "/// CHECK: vault authority\npub authority: AccountInfo<'info>, // CPI authority of token::transfer",
+    fixed_example: "/// CHECK: derived from the vault below\n#[account(seeds = [b\"authority\", vault.key().as_ref()], bump)]\npub authority: AccountInfo<'info>,",
+    references: &[
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+        "https://solana.com/docs/core/pda",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut reported = HashSet::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let bindings = Bindings::of(&handler.item);
+        for cpi in ast::cpi_accounts(&handler.item) {
+            let Some(name) = cpi
+                .field("authority")
+                .and_then(|expr| bindings.account(expr))
+            else {
+                continue;
+            };
+            let Some(field) = accounts.field(&name) else {
+                continue;
+            };
+            if matches!(field.kind, AccountKind::Signer) || field.has_constraint("seeds") {
+                continue;
+            }
+            if !reported.insert((accounts.name.clone(), name.clone())) {
+                continue;
+            }
+            let message = format!(
+                "`{}::{name}` is the authority of a `{}` CPI in `{}` without being a `Signer`, \
+                 so it has to be a PDA, but it declares no `seeds`/`bump` to check it is one",
+                accounts.name, cpi.kind, handler.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::High, program, field.span(), message)
+                    .with_confidence(Confidence::Medium)
+                    .with_suggestion(format!(
+                        "add `#[account(seeds = [...], bump)]` to `{name}` with the seeds the \
+                         program signs with, or make it a `Signer<'info>` if a wallet signs"
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_vault_authorities() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let messages: Vec<_> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "`Withdraw::authority` is the authority of a `Transfer` CPI in `withdraw` \
+                 without being a `Signer`, so it has to be a PDA, but it declares no \
+                 `seeds`/`bump` to check it is one",
+                "`Emergency::authority` is the authority of a `Transfer` CPI in \
+                 `emergency_drain` without being a `Signer`, so it has to be a PDA, but it \
+                 declares no `seeds`/`bump` to check it is one",
+            ]
+        );
+        assert_eq!(findings[0].span.line_start, 141);
+        assert!(findings.iter().all(|f| f.severity == Severity::High));
+    }
+
+    fn withdraw(field: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {{
+                    let cpi_accounts = Transfer {{
+                        from: ctx.accounts.vault_token.to_account_info(),
+                        to: ctx.accounts.user_token.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    }};
+                    token::transfer(CpiContext::new(program, cpi_accounts), amount)
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Withdraw<'info> {{
+                {field}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn seeds_or_a_signer_clear_the_authority() {
+        assert_eq!(withdraw("pub authority: AccountInfo<'info>,").len(), 1);
+        assert_eq!(withdraw("pub authority: UncheckedAccount<'info>,").len(), 1);
+        assert!(withdraw(
+            "#[account(seeds = [b\"authority\"], bump)] pub authority: AccountInfo<'info>,"
+        )
+        .is_empty());
+        assert!(withdraw("pub authority: Signer<'info>,").is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+//! Token CPIs whose authority is a PDA but which pass no signer seeds.
+//!
+//! A program-derived address has no private key; the runtime only accepts
+//! it as a CPI signer when the program supplies its seeds through
+//! `CpiContext::new_with_signer`. A `CpiContext::new` transfer out of a
+//! program-owned vault therefore either always fails or, worse, is only
+//! working because the authority check is missing altogether.
+
+use std::collections::HashMap;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprMethodCall, Local};
+
+use crate::ast::{self, Bindings, CpiAccounts};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, AccountsStruct, Handler, ParsedProgram};
+
+pub const ID: &str = "missing_pda_signer_seeds";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-pda-signer-seeds",
+    name: ID,
+    title: "PDA authority without signer seeds",
+    category: Category::AccessControl,
+    severity: Severity::High,
+    cwe: 863,
+    description: "A token transfer or mint whose authority is a program-derived account is built with `CpiContext::new` instead of `CpiContext::new_with_signer`.",
+    example: "token::transfer(CpiContext::new(token_program, Transfer { from: vault_token, to, authority: vault }), amount)?;",
+    fixed_example: "let seeds: &[&[u8]] = &[b\"vault\", &[vault.bump]];\ntoken::transfer(CpiContext::new_with_signer(token_program, accounts, &[seeds]), amount)?;",
+    references: &[
+        "https://solana.com/docs/core/pda",
+        "https://docs.rs/anchor-lang/latest/anchor_lang/context/struct.CpiContext.html",
+    ],
+};
+
+/// Token instructions that need the authority's signature.
+const SIGNED_TOKEN_CPIS: &[&str] = &["transfer", "transfer_checked", "mint_to"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let signs = ast::cpi_calls(&handler.item).iter().any(|call| {
+            call.rsplit("::")
+                .next()
+                .is_some_and(|name| SIGNED_TOKEN_CPIS.contains(&name))
+        });
+        if !signs {
+            continue;
+        }
+        let mut visitor = Visitor::default();
+        visitor.visit_item_fn(&handler.item);
+        if visitor.with_signer {
+            continue;
+        }
+        let bindings = Bindings::of(&handler.item);
+        for (span, cpi) in &visitor.contexts {
+            let Some(cpi) = cpi else {
+                continue;
+            };
+            let account = |name| cpi.field(name).and_then(|expr| bindings.account(expr));
+            let (Some(authority), Some(from)) = (account("authority"), account("from")) else {
+                continue;
+            };
+            let Some((reason, confidence)) = pda_authority(accounts, &authority, &from) else {
+                continue;
+            };
+            findings.push(report(
+                program, handler, *span, &authority, &from, reason, confidence,
+            ));
+        }
+    }
+    findings
+}
+
+fn report(
+    program: &ParsedProgram,
+    handler: &Handler,
+    span: proc_macro2::Span,
+    authority: &str,
+    from: &str,
+    reason: String,
+    confidence: Confidence,
+) -> Finding {
+    let message = format!(
+        "`{}` moves tokens from `{from}` with `CpiContext::new`, but authority `{authority}` \
+         {reason} and must sign with PDA seeds",
+        handler.name
+    );
+    Finding::new(ID, Severity::High, program, span, message)
+        .with_confidence(confidence)
+        .with_suggestion(format!(
+            "derive `{authority}` with `seeds`/`bump` and build the context with \
+             `CpiContext::new_with_signer(token_program, cpi_accounts, &[&[SEED, &[bump]]])`"
+        ))
+}
+
+/// Why the CPI authority `authority` looks program-derived, if it does.
+fn pda_authority(
+    accounts: &AccountsStruct,
+    authority: &str,
+    from: &str,
+) -> Option<(String, Confidence)> {
+    let field = accounts.field(authority)?;
+    if field.has_constraint("seeds") {
+        return Some(("is derived from `seeds`".to_string(), Confidence::High));
+    }
+    match &field.kind {
+        AccountKind::Signer => None,
+        AccountKind::Account(state) => Some((
+            format!("is a program-owned `{state}` account"),
+            Confidence::High,
+        )),
+        _ => {
+            // `vault_token` next to `vault: Account<Vault>` is the vault's
+            // own token account, which the program controls through a PDA.
+            let vault = accounts.fields.iter().find(|field| {
+                matches!(field.kind, AccountKind::Account(_))
+                    && field.name != from
+                    && from.starts_with(&field.name)
+            })?;
+            Some((
+                format!(
+                    "controls `{from}`, the token account of program-owned `{}`,",
+                    vault.name
+                ),
+                Confidence::Medium,
+            ))
+        }
+    }
+}
+
+#[derive(Default)]
+struct Visitor {
+    /// CPI account struct literals bound to locals.
+    locals: HashMap<String, CpiAccounts>,
+    /// Every `CpiContext::new` call and the accounts it was given.
+    contexts: Vec<(proc_macro2::Span, Option<CpiAccounts>)>,
+    /// Whether signer seeds are passed anywhere in the handler.
+    with_signer: bool,
+}
+
+impl Visitor {
+    fn resolve(&mut self, expr: &Expr) -> Option<CpiAccounts> {
+        match ast::unparen(expr) {
+            Expr::Struct(literal) => ast::cpi_accounts_literal(literal),
+            expr => self.locals.remove(&ast::ident(expr)?),
+        }
+    }
+}
+
+impl Visit<'_> for Visitor {
+    fn visit_local(&mut self, local: &Local) {
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            if let Expr::Struct(literal) = ast::unparen(&init.expr) {
+                if let Some(cpi) = ast::cpi_accounts_literal(literal) {
+                    self.locals.insert(name, cpi);
+                }
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        if let Expr::Path(path) = &*node.func {
+            let segments: Vec<_> = path
+                .path
+                .segments
+                .iter()
+                .map(|s| s.ident.to_string())
+                .collect();
+            match segments.as_slice() {
+                [.., context, new] if context == "CpiContext" && new == "new" => {
+                    let cpi = node.args.iter().nth(1).and_then(|arg| self.resolve(arg));
+                    self.contexts.push((node.span(), cpi));
+                }
+                [.., context, new] if context == "CpiContext" && new == "new_with_signer" => {
+                    self.with_signer = true;
+                }
+                _ => {}
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        if node.method == "with_signer" {
+            self.with_signer = true;
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_both_vault_transfers() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let handlers: Vec<_> = findings
+            .iter()
+            .map(|f| f.message.split('`').nth(1).unwrap())
+            .collect();
+        assert_eq!(handlers, ["withdraw", "emergency_drain"]);
+        assert!(findings.iter().all(|f| f.severity == Severity::High));
+        assert!(findings[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("CpiContext::new_with_signer"));
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn withdraw(authority: &str, context: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {{
+                    let accounts = Transfer {{
+                        from: ctx.accounts.vault_token.to_account_info(),
+                        to: ctx.accounts.user_token.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    }};
+                    token::transfer({context}, amount)?;
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Withdraw<'info> {{
+                #[account(mut)]
+                pub vault: Account<'info, Vault>,
+                #[account(mut)]
+                pub vault_token: Account<'info, TokenAccount>,
+                #[account(mut)]
+                pub user_token: Account<'info, TokenAccount>,
+                {authority}
+                pub token_program: Program<'info, Token>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn seeds_or_signer_decide() {
+        let plain = "CpiContext::new(ctx.accounts.token_program.to_account_info(), accounts)";
+        let signed = "CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), \
+                      accounts, &[&[b\"vault\", &[bump]]])";
+        let pda = "#[account(seeds = [b\"vault\"], bump)] pub authority: UncheckedAccount<'info>,";
+
+        let findings = withdraw(pda, plain);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].confidence, Confidence::High);
+        assert!(withdraw(pda, signed).is_empty());
+        assert!(withdraw("pub authority: Signer<'info>,", plain).is_empty());
+    }
+}
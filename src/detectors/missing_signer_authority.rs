@@ -0,0 +1,188 @@
+//! Authority accounts that sign CPIs without being a `Signer`.
+//!
+//! An `AccountInfo` or `UncheckedAccount` named like an authority that is
+//! passed as the `authority` of a token CPI lets any caller supply someone
+//! else's account, because Anchor never checks that it signed.
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, ParsedProgram};
+
+pub const ID: &str = "missing_signer_authority";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/missing-signer",
+    name: ID,
+    title: "Authority account is not a signer",
+    category: Category::AccessControl,
+    severity: Severity::Critical,
+    cwe: 287,
+    description: "An `AccountInfo` or `UncheckedAccount` is used as the authority of a CPI without being declared as a `Signer`, so anyone can pass it.",
+    example: "pub authority: AccountInfo<'info>, // used as `authority` in token::transfer",
+    fixed_example: "pub authority: Signer<'info>,",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/signer/struct.Signer.html",
+        "https://github.com/coral-xyz/sealevel-attacks",
+    ],
+};
+
+const AUTHORITY_NAMES: &[&str] = &["authority", "admin", "owner"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for accounts in &program.accounts {
+        for field in &accounts.fields {
+            let unchecked = matches!(
+                field.kind,
+                AccountKind::AccountInfo | AccountKind::UncheckedAccount
+            );
+            if !unchecked || !AUTHORITY_NAMES.contains(&field.name.as_str()) {
+                continue;
+            }
+            let cpi_handler = program
+                .handlers_using(&accounts.name)
+                .find(|handler| signs_cpi(&handler.item, &field.name));
+            let (message, impact, confidence) = match cpi_handler {
+                Some(handler) => (
+                    format!(
+                        "`{}::{}` is used as CPI authority in `{}` but is not a `Signer`",
+                        accounts.name, field.name, handler.name
+                    ),
+                    format!(
+                        "Any wallet can call `{}` and pass any account as `{}`; nothing checks \
+                         that account signed, so the token moves or mints it authorises go \
+                         through without the real authority's approval",
+                        handler.name, field.name
+                    ),
+                    Confidence::High,
+                ),
+                None => (
+                    format!(
+                        "`{}::{}` is named like an authority but is not a `Signer`",
+                        accounts.name, field.name
+                    ),
+                    format!(
+                        "Any wallet can pass someone else's key as `{}` to instructions using \
+                         `{}` and act as that authority, because no signature is checked",
+                        field.name, accounts.name
+                    ),
+                    Confidence::Low,
+                ),
+            };
+            findings.push(
+                Finding::new(ID, Severity::Critical, program, field.span(), message)
+                    .with_confidence(confidence)
+                    .with_impact(impact)
+                    .with_suggestion(format!(
+                        "declare `pub {}: Signer<'info>` so Anchor verifies the signature",
+                        field.name
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// Whether `handler` passes the context account `name` as a CPI `authority`.
+fn signs_cpi(handler: &syn::ItemFn, name: &str) -> bool {
+    let bindings = Bindings::of(handler);
+    ast::cpi_accounts(handler).iter().any(|cpi| {
+        cpi.field("authority")
+            .and_then(|expr| bindings.account(expr))
+            .is_some_and(|account| account == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_vault_authorities() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let messages: Vec<_> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("`Withdraw::authority`"));
+        assert!(messages[1].starts_with("`Emergency::authority`"));
+        assert!(findings.iter().all(|f| f.severity == Severity::Critical));
+        assert!(findings.iter().all(|f| f.confidence == Confidence::High));
+        assert!(!messages.iter().any(|m| m.contains("Deposit")));
+        let impact = findings[0].impact.as_deref().unwrap();
+        assert!(impact
+            .starts_with("Any wallet can call `withdraw` and pass any account as `authority`"));
+    }
+
+    #[test]
+    fn flags_nft_authorities() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        let messages: Vec<_> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("`MintNFT::authority`"));
+        assert!(messages[1].starts_with("`TransferNFT::authority`"));
+        assert_eq!(
+            (findings[0].span.line_start, findings[1].span.line_start),
+            (180, 222)
+        );
+    }
+
+    #[test]
+    fn ignores_signer_authorities() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn pay(ctx: Context<Pay>) -> Result<()> {
+                    let cpi_accounts = Transfer {
+                        from: ctx.accounts.from.to_account_info(),
+                        to: ctx.accounts.to.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    };
+                    Ok(())
+                }
+            }
+            #[derive(Accounts)]
+            pub struct Pay<'info> {
+                pub authority: Signer<'info>,
+            }
+            "#,
+        );
+        assert!(run(&program).is_empty());
+    }
+
+    #[test]
+    fn unused_authority_has_low_confidence() {
+        let source = |authority: &str| {
+            format!(
+                r#"
+                #[program]
+                pub mod demo {{
+                    pub fn pay(ctx: Context<Pay>) -> Result<()> {{
+                        let cpi_accounts = Transfer {{
+                            from: ctx.accounts.from.to_account_info(),
+                            to: ctx.accounts.to.to_account_info(),
+                            authority: ctx.accounts.{authority}.to_account_info(),
+                        }};
+                        Ok(())
+                    }}
+                }}
+                #[derive(Accounts)]
+                pub struct Pay<'info> {{
+                    pub authority: AccountInfo<'info>,
+                    pub payer: Signer<'info>,
+                }}
+                "#
+            )
+        };
+
+        let used = run(&parse(&source("authority")));
+        assert_eq!(used.len(), 1);
+        assert_eq!(used[0].confidence, Confidence::High);
+
+        let unused = run(&parse(&source("payer")));
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].confidence, Confidence::Low);
+        assert!(unused[0].message.contains("named like an authority"));
+    }
+}
@@ -0,0 +1,546 @@
+//! Vulnerability detectors.
+//!
+//! Each detector inspects a [`ParsedProgram`] and returns the findings it
+//! recognises. Detectors only see typed `syn` nodes, never raw source text.
+//! The [`Registry`] holds every detector and decides which ones run.
+
+pub mod arbitrary_transfer_destination;
+pub mod balance_transfer_mismatch;
+pub mod checked_then_unwrap;
+pub mod direct_lamport_mutation;
+pub mod ignored_cpi_result;
+pub mod improper_key_comparison;
+pub mod incomplete_account_zeroing;
+pub mod incorrect_account_space;
+pub mod init_missing_system_program;
+pub mod init_payer_unconstrained;
+pub mod integer_division_truncation;
+pub mod invalid_program_id;
+pub mod lossy_integer_cast;
+pub mod manual_close_without_constraint;
+pub mod manual_data_write_bypass;
+pub mod missing_account_relationship;
+pub mod missing_authority_guard;
+pub mod missing_discriminator_check;
+pub mod missing_input_validation;
+pub mod missing_mut_constraint;
+pub mod missing_pda_seeds;
+pub mod missing_pda_signer_seeds;
+pub mod missing_signer_authority;
+pub mod mul_overflow;
+pub mod offset_overflow;
+pub mod panic_prone_unwrap;
+pub mod potential_account_aliasing;
+pub mod raw_account_info_bypass;
+pub mod stale_account_after_cpi;
+pub mod state_update_after_cpi;
+pub mod tainted_owner_assignment;
+pub mod timestamp_arithmetic;
+pub mod transfer_without_balance_check;
+pub mod unauthorized_flag_toggle;
+pub mod unbounded_data_write;
+pub mod unbounded_string_assignment;
+pub mod unchecked_arithmetic;
+pub mod unchecked_check_comment;
+pub mod unchecked_utf8;
+pub mod unconstrained_cpi_program;
+pub mod undocumented_unsafe;
+pub mod unprotected_privileged_fn;
+pub mod unrestricted_data_getter;
+pub mod unsafe_account_deserialization;
+pub mod untrusted_length_from_bytes;
+pub mod unverified_foreign_account_read;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::UnknownDetector;
+use crate::finding::{Finding, Severity};
+use crate::parser::ParsedProgram;
+
+/// Broad class of weakness a detector looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Category {
+    AccessControl,
+    Arithmetic,
+    MemorySafety,
+    Validation,
+}
+
+impl Category {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::AccessControl => "AccessControl",
+            Category::Arithmetic => "Arithmetic",
+            Category::MemorySafety => "MemorySafety",
+            Category::Validation => "Validation",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses the names [`Category::as_str`] returns, ignoring case.
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            Category::AccessControl,
+            Category::Arithmetic,
+            Category::MemorySafety,
+            Category::Validation,
+        ]
+        .into_iter()
+        .find(|category| category.as_str().eq_ignore_ascii_case(s))
+        .ok_or_else(|| {
+            format!(
+                "unknown category `{s}`; expected AccessControl, Arithmetic, MemorySafety or \
+                 Validation"
+            )
+        })
+    }
+}
+
+/// Catalog entry describing a detector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DetectorInfo {
+    /// Namespaced rule id used in reports, e.g. `rust/unchecked-arithmetic`.
+    pub id: &'static str,
+    /// Short name, which is also [`Finding::detector`], e.g.
+    /// `unchecked_arithmetic`. Configuration and suppressions accept either.
+    pub name: &'static str,
+    pub title: &'static str,
+    pub category: Category,
+    /// Severity of the detector's typical finding. Individual findings may
+    /// be rated differently.
+    pub severity: Severity,
+    /// Closest CWE number.
+    pub cwe: u32,
+    pub description: &'static str,
+    /// A short snippet of code the detector flags.
+    pub example: &'static str,
+    /// The same snippet written so the detector no longer flags it.
+    pub fixed_example: &'static str,
+    /// Documentation explaining the weakness and how to avoid it.
+    pub references: &'static [&'static str],
+}
+
+impl DetectorInfo {
+    /// Whether `id` is either the namespaced id or the short name.
+    pub fn matches(&self, id: &str) -> bool {
+        self.id == id || self.name == id
+    }
+}
+
+/// A check run over every parsed program.
+///
+/// Detectors are shared between scanning threads, so they must not keep
+/// mutable state between runs.
+pub trait Detector: Send + Sync {
+    fn info(&self) -> &DetectorInfo;
+
+    /// Namespaced catalog id, e.g. `rust/unchecked-arithmetic`.
+    fn id(&self) -> &'static str {
+        self.info().id
+    }
+
+    /// Short name recorded in [`Finding::detector`].
+    fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    fn default_severity(&self) -> Severity {
+        self.info().severity
+    }
+
+    fn run(&self, program: &ParsedProgram) -> Vec<Finding>;
+}
+
+/// A built-in detector backed by a module's `INFO` and `run`.
+#[derive(Clone, Copy)]
+struct Builtin {
+    info: &'static DetectorInfo,
+    run: fn(&ParsedProgram) -> Vec<Finding>,
+}
+
+impl Detector for Builtin {
+    fn info(&self) -> &DetectorInfo {
+        self.info
+    }
+
+    fn run(&self, program: &ParsedProgram) -> Vec<Finding> {
+        (self.run)(program)
+    }
+}
+
+macro_rules! builtin {
+    ($module:ident) => {
+        Builtin {
+            info: &$module::INFO,
+            run: $module::run,
+        }
+    };
+}
+
+/// Every built-in detector, in reporting order.
+static BUILTINS: [Builtin; 46] = [
+    builtin!(missing_signer_authority),
+    builtin!(unchecked_arithmetic),
+    builtin!(unsafe_account_deserialization),
+    builtin!(direct_lamport_mutation),
+    builtin!(mul_overflow),
+    builtin!(unbounded_data_write),
+    builtin!(missing_authority_guard),
+    builtin!(unchecked_check_comment),
+    builtin!(panic_prone_unwrap),
+    builtin!(missing_account_relationship),
+    builtin!(tainted_owner_assignment),
+    builtin!(incorrect_account_space),
+    builtin!(unprotected_privileged_fn),
+    builtin!(missing_pda_signer_seeds),
+    builtin!(unbounded_string_assignment),
+    builtin!(state_update_after_cpi),
+    builtin!(incomplete_account_zeroing),
+    builtin!(stale_account_after_cpi),
+    builtin!(integer_division_truncation),
+    builtin!(raw_account_info_bypass),
+    builtin!(unverified_foreign_account_read),
+    builtin!(init_payer_unconstrained),
+    builtin!(unconstrained_cpi_program),
+    builtin!(checked_then_unwrap),
+    builtin!(transfer_without_balance_check),
+    builtin!(missing_mut_constraint),
+    builtin!(invalid_program_id),
+    builtin!(unrestricted_data_getter),
+    builtin!(undocumented_unsafe),
+    builtin!(untrusted_length_from_bytes),
+    builtin!(manual_data_write_bypass),
+    builtin!(offset_overflow),
+    builtin!(balance_transfer_mismatch),
+    builtin!(missing_pda_seeds),
+    builtin!(arbitrary_transfer_destination),
+    builtin!(missing_input_validation),
+    builtin!(improper_key_comparison),
+    builtin!(init_missing_system_program),
+    builtin!(missing_discriminator_check),
+    builtin!(manual_close_without_constraint),
+    builtin!(lossy_integer_cast),
+    builtin!(unauthorized_flag_toggle),
+    builtin!(timestamp_arithmetic),
+    builtin!(potential_account_aliasing),
+    builtin!(unchecked_utf8),
+    builtin!(ignored_cpi_result),
+];
+
+/// Metadata of every built-in detector, in reporting order.
+pub fn catalog() -> impl Iterator<Item = &'static DetectorInfo> {
+    BUILTINS.iter().map(|builtin| builtin.info)
+}
+
+/// The catalog entry for a namespaced id or short name.
+pub fn lookup(id: &str) -> Option<&'static DetectorInfo> {
+    catalog().find(|info| info.matches(id))
+}
+
+/// The set of known detectors and which of them are switched on.
+///
+/// With `enabled` empty every detector runs; otherwise only the listed
+/// ones do. Ids in `disabled` never run, even if also enabled. Findings of
+/// detectors listed in `severity` are re-rated before anything else sees
+/// them, and those of detectors in `warning_only` are marked so they do not
+/// affect the exit code.
+#[derive(Clone)]
+pub struct Registry {
+    detectors: Vec<Arc<dyn Detector>>,
+    pub enabled: HashSet<String>,
+    pub disabled: HashSet<String>,
+    /// Severity overrides keyed by namespaced id or short name.
+    pub severity: HashMap<String, Severity>,
+    /// Detectors whose findings are reported but never fail the scan.
+    pub warning_only: HashSet<String>,
+}
+
+impl Registry {
+    /// A registry with no detectors.
+    pub fn empty() -> Self {
+        Registry {
+            detectors: Vec::new(),
+            enabled: HashSet::new(),
+            disabled: HashSet::new(),
+            severity: HashMap::new(),
+            warning_only: HashSet::new(),
+        }
+    }
+
+    /// Adds `detector` after the ones already registered.
+    pub fn register(&mut self, detector: impl Detector + 'static) {
+        self.detectors.push(Arc::new(detector));
+    }
+
+    /// Every registered detector, enabled or not.
+    pub fn detectors(&self) -> impl Iterator<Item = &dyn Detector> {
+        self.detectors.iter().map(|detector| &**detector)
+    }
+
+    /// The detector with the given namespaced id or short name.
+    pub fn get(&self, id: &str) -> Option<&dyn Detector> {
+        self.detectors()
+            .find(|detector| detector.info().matches(id))
+    }
+
+    /// Whether the detector named by `id` (in either form) would run.
+    pub fn is_enabled(&self, id: &str) -> bool {
+        let Some(info) = self.get(id).map(Detector::info) else {
+            return false;
+        };
+        let listed = |set: &HashSet<String>| set.contains(info.id) || set.contains(info.name);
+        (self.enabled.is_empty() || listed(&self.enabled)) && !listed(&self.disabled)
+    }
+
+    /// Severity findings of `info`'s detector are reported at, if
+    /// overridden.
+    fn severity_override(&self, info: &DetectorInfo) -> Option<Severity> {
+        self.severity
+            .get(info.id)
+            .or_else(|| self.severity.get(info.name))
+            .copied()
+    }
+
+    /// Whether findings of `info`'s detector are only warnings.
+    fn is_warning_only(&self, info: &DetectorInfo) -> bool {
+        self.warning_only.contains(info.id) || self.warning_only.contains(info.name)
+    }
+
+    /// Ids in `enabled`, `disabled`, `severity` or `warning_only` that name
+    /// no registered detector.
+    pub fn unknown_ids(&self) -> Vec<&str> {
+        let mut unknown: Vec<_> = self
+            .enabled
+            .iter()
+            .chain(&self.disabled)
+            .chain(self.severity.keys())
+            .chain(&self.warning_only)
+            .map(String::as_str)
+            .filter(|id| self.get(id).is_none())
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        unknown
+    }
+
+    /// Runs every enabled detector over `program`, applying severity
+    /// overrides and marking warning-only findings. Each detector runs in a
+    /// `run_detector` span; see [`crate::logging`].
+    pub fn run_all(&self, program: &ParsedProgram) -> Vec<Finding> {
+        self.detectors()
+            .filter(|detector| self.is_enabled(detector.id()))
+            .flat_map(|detector| self.run_detector(detector, program))
+            .collect()
+    }
+
+    /// Runs only the detector named by `id`, in either form, the way
+    /// [`Registry::run_all`] would. `enabled` and `disabled` are not
+    /// consulted: asking for a detector by id runs it.
+    pub fn run_one(
+        &self,
+        id: &str,
+        program: &ParsedProgram,
+    ) -> Result<Vec<Finding>, UnknownDetector> {
+        let detector = self
+            .get(id)
+            .ok_or_else(|| UnknownDetector(id.to_string()))?;
+        Ok(self.run_detector(detector, program))
+    }
+
+    fn run_detector(&self, detector: &dyn Detector, program: &ParsedProgram) -> Vec<Finding> {
+        let severity = self.severity_override(detector.info());
+        let warning_only = self.is_warning_only(detector.info());
+        let span = tracing::info_span!(
+            "run_detector",
+            detector = detector.id(),
+            duration = tracing::field::Empty,
+        );
+        let started = Instant::now();
+        let findings = span.in_scope(|| detector.run(program));
+        let micros = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        span.record("duration", micros);
+        findings
+            .into_iter()
+            .map(|mut finding| {
+                if let Some(severity) = severity {
+                    finding.severity = severity;
+                }
+                finding.warning_only = warning_only;
+                finding
+            })
+            .collect()
+    }
+
+    /// `finding` with the severity override and warning-only mark of its
+    /// detector applied, for findings made outside [`Registry::run_all`].
+    pub(crate) fn rerate(&self, mut finding: Finding) -> Finding {
+        if let Some(info) = self.get(finding.detector).map(Detector::info) {
+            if let Some(severity) = self.severity_override(info) {
+                finding.severity = severity;
+            }
+            finding.warning_only = self.is_warning_only(info);
+        }
+        finding
+    }
+}
+
+/// All built-in detectors, all enabled.
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::empty();
+        for builtin in &BUILTINS {
+            registry.register(*builtin);
+        }
+        registry
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<_> = self.detectors().map(|detector| detector.id()).collect();
+        f.debug_struct("Registry")
+            .field("detectors", &ids)
+            .field("enabled", &self.enabled)
+            .field("disabled", &self.disabled)
+            .field("severity", &self.severity)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::parse_fixture;
+
+    fn detectors_hit(registry: &Registry) -> HashSet<&'static str> {
+        ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"]
+            .iter()
+            .flat_map(|name| registry.run_all(&parse_fixture(name)))
+            .map(|finding| finding.detector)
+            .collect()
+    }
+
+    #[test]
+    fn enabled_set_restricts_detectors() {
+        let mut registry = Registry::default();
+        registry
+            .enabled
+            .insert(unchecked_arithmetic::ID.to_string());
+        assert_eq!(
+            detectors_hit(&registry),
+            HashSet::from([unchecked_arithmetic::ID])
+        );
+    }
+
+    #[test]
+    fn run_one_runs_a_single_detector() {
+        let mut registry = Registry::default();
+        registry
+            .disabled
+            .insert(unchecked_arithmetic::ID.to_string());
+        let vault = parse_fixture("vulnerable_vault.rs");
+        let findings = registry.run_one("unchecked_arithmetic", &vault).unwrap();
+        assert!(!findings.is_empty());
+        assert!(findings
+            .iter()
+            .all(|finding| finding.detector == unchecked_arithmetic::ID));
+        assert_eq!(
+            registry.run_one("rust/unchecked-arithmetic", &vault),
+            Ok(findings)
+        );
+
+        let err = registry.run_one("no_such_detector", &vault).unwrap_err();
+        assert_eq!(err, UnknownDetector("no_such_detector".to_string()));
+        assert_eq!(err.to_string(), "unknown detector `no_such_detector`");
+    }
+
+    #[test]
+    fn disabled_set_wins_over_enabled() {
+        let mut registry = Registry::default();
+        registry
+            .disabled
+            .insert(missing_signer_authority::ID.to_string());
+        let hit = detectors_hit(&registry);
+        assert!(!hit.contains(missing_signer_authority::ID));
+        assert!(hit.contains(unsafe_account_deserialization::ID));
+
+        registry
+            .enabled
+            .insert(missing_signer_authority::ID.to_string());
+        assert!(detectors_hit(&registry).is_empty());
+    }
+
+    #[test]
+    fn every_detector_has_references() {
+        for info in catalog() {
+            assert!(!info.references.is_empty(), "{} has no references", info.id);
+            assert!(
+                info.references
+                    .iter()
+                    .all(|url| url.starts_with("https://")),
+                "{} has a non-https reference",
+                info.id
+            );
+        }
+    }
+
+    #[test]
+    fn catalog_ids_are_unique_and_cover_findings() {
+        let ids: HashSet<_> = catalog().map(|info| info.id).collect();
+        let names: HashSet<_> = catalog().map(|info| info.name).collect();
+        assert_eq!(ids.len(), BUILTINS.len());
+        assert_eq!(names.len(), BUILTINS.len());
+        assert!(ids
+            .iter()
+            .all(|id| id.starts_with("solana/") || id.starts_with("rust/")));
+
+        for finding in crate::test_support::scan_fixtures() {
+            let info = lookup(finding.detector).expect("finding from an unknown detector");
+            assert_eq!(finding.rule_id(), info.id);
+        }
+        assert_eq!(
+            lookup("rust/unchecked-arithmetic").unwrap().name,
+            unchecked_arithmetic::ID
+        );
+    }
+
+    #[test]
+    fn registry_accepts_namespaced_ids() {
+        let mut registry = Registry::default();
+        registry
+            .enabled
+            .insert("rust/unchecked-arithmetic".to_string());
+        assert_eq!(
+            detectors_hit(&registry),
+            HashSet::from([unchecked_arithmetic::ID])
+        );
+        assert!(registry.unknown_ids().is_empty());
+    }
+
+    #[test]
+    fn unknown_ids_are_reported() {
+        let mut registry = Registry::default();
+        registry.enabled.insert("no_such_check".to_string());
+        registry.disabled.insert(mul_overflow::ID.to_string());
+        assert_eq!(registry.unknown_ids(), ["no_such_check"]);
+        assert_eq!(
+            registry.get(mul_overflow::ID).unwrap().default_severity(),
+            Severity::Medium
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Multiplication of two wide amounts that can overflow before any division.
+//!
+//! `(sale_price * royalty_percentage) / 100` wraps for large prices even
+//! though the final result would fit, producing a tiny or zero royalty.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprBinary};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "mul_overflow";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/multiplication-overflow",
+    name: ID,
+    title: "Unchecked multiplication of token amounts",
+    category: Category::Arithmetic,
+    severity: Severity::Medium,
+    cwe: 190,
+    description: "Two `u64`/`u128` amounts are multiplied without `checked_mul`, typically in royalty or reward math.",
+    example: "let royalty = (sale_price * royalty_percentage) / 100;",
+    fixed_example: "let royalty = sale_price.checked_mul(royalty_percentage).ok_or(ErrorCode::Overflow)? / 100;",
+    references: &[
+        "https://doc.rust-lang.org/book/ch03-02-data-types.html#integer-overflow",
+        "https://doc.rust-lang.org/std/primitive.u64.html#method.checked_mul",
+    ],
+};
+
+const WIDE_TYPES: &[&str] = &["u64", "u128"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// Whether `expr` is a `u64`/`u128` handler argument or account field.
+    fn is_wide(&self, expr: &Expr) -> bool {
+        let expr = ast::unparen(expr);
+        let ty = match ast::ident(expr) {
+            Some(name) => self.handler.arg_type(&name),
+            None => self
+                .bindings
+                .account_field(expr)
+                .and_then(|(account, field)| {
+                    self.program
+                        .state_field_type(self.handler, &account, &field)
+                }),
+        };
+        ty.is_some_and(|ty| WIDE_TYPES.contains(&ty.as_str()))
+    }
+
+    fn qualifies(&self, node: &ExprBinary) -> bool {
+        matches!(node.op, BinOp::Mul(_)) && self.is_wide(&node.left) && self.is_wide(&node.right)
+    }
+
+    fn report(&mut self, mul: &ExprBinary, divisor: Option<&Expr>) {
+        let (left, right) = (ast::source_text(&*mul.left), ast::source_text(&*mul.right));
+        let (context, suggestion) = match divisor {
+            Some(divisor) => (
+                " before the division",
+                format!(
+                    "use `{left}.checked_mul({right}).ok_or(ErrorCode::Overflow)?\
+                     .checked_div({}).ok_or(ErrorCode::Overflow)?` or do the math in `u128`",
+                    ast::source_text(divisor)
+                ),
+            ),
+            None => (
+                "",
+                format!("use `{left}.checked_mul({right}).ok_or(ErrorCode::Overflow)?`"),
+            ),
+        };
+        let replacement = format!(
+            "{}.checked_mul({right}).ok_or(ErrorCode::Overflow)?",
+            ast::receiver_text(&mul.left)
+        );
+        self.findings.push(
+            Finding::new(
+                ID,
+                Severity::Medium,
+                self.program,
+                mul.span(),
+                format!(
+                    "`{left} * {right}` in `{}` can overflow{context}",
+                    self.handler.name
+                ),
+            )
+            .with_confidence(Confidence::Medium)
+            .with_suggestion(suggestion)
+            .with_fix(self.program, mul.span(), replacement),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        if matches!(node.op, BinOp::Div(_)) {
+            if let Expr::Binary(mul) = ast::unparen(&node.left) {
+                if self.qualifies(mul) {
+                    self.report(mul, Some(&node.right));
+                    visit::visit_expr_binary(self, mul);
+                    self.visit_expr(&node.right);
+                    return;
+                }
+            }
+        }
+        if self.qualifies(node) {
+            self.report(node, None);
+        }
+        visit::visit_expr_binary(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_royalty_math() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(
+            finding.message,
+            "`sale_price * royalty_percentage` in `calculate_royalties` can overflow before the division"
+        );
+        assert!(finding
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("sale_price.checked_mul(royalty_percentage).ok_or(ErrorCode::Overflow)?.checked_div(100)"));
+    }
+
+    #[test]
+    fn flags_reward_math_on_account_field() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`vault.balance * multiplier` in `calculate_rewards` can overflow"
+        );
+    }
+
+    #[test]
+    fn ignores_narrow_and_local_operands() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn run(ctx: Context<Run>, count: u8, amount: u64) -> Result<()> {
+                    let doubled = count * 2;
+                    let scaled = amount * count;
+                    Ok(())
+                }
+            }
+            "#,
+        );
+        assert!(run(&program).is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+//! Loop indices multiplied into byte offsets without `checked_mul`.
+//!
+//! `for (i, price) in prices.iter().enumerate() { let offset = i * 8; .. }`
+//! turns a caller-sized loop into pointer arithmetic. Solana's `usize` is
+//! 64 bits wide, so the product is unlikely to wrap there, but the same
+//! code compiled for a 32-bit host in tests or tooling wraps silently and
+//! writes to a small offset instead of failing. `checked_mul` makes the
+//! assumption explicit and costs nothing.
+
+use std::collections::{HashMap, HashSet};
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprBinary, ExprForLoop, ExprIndex, ExprMethodCall, Local, Pat};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "offset_overflow";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/offset-overflow",
+    name: ID,
+    title: "Loop index multiplied into an offset unchecked",
+    category: Category::Arithmetic,
+    severity: Severity::Low,
+    cwe: 680,
+    description: "A loop index is multiplied into a byte offset with `*` and the result is used for pointer arithmetic or slicing, so an overflow wraps to a small offset instead of failing.",
+    example: "for (i, price) in prices.iter().enumerate() {\n    let offset = i * 8;\n    let ptr = data.as_mut_ptr().add(offset);\n}",
+    fixed_example: "let offset = i.checked_mul(8).ok_or(ErrorCode::Overflow)?;",
+    references: &[
+        "https://cwe.mitre.org/data/definitions/680.html",
+        "https://doc.rust-lang.org/std/primitive.usize.html#method.checked_mul",
+    ],
+};
+
+/// Methods whose argument is a pointer or buffer offset.
+const OFFSET_METHODS: &[&str] = &[
+    "add",
+    "offset",
+    "wrapping_add",
+    "byte_add",
+    "get",
+    "get_mut",
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            indices: HashSet::new(),
+            offsets: HashMap::new(),
+            reported: HashSet::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// The index a `for` loop binds: `i` in `for (i, x) in xs.iter().enumerate()`
+/// and in `for i in 0..n`.
+fn loop_index(node: &ExprForLoop) -> Option<String> {
+    match (&*node.pat, ast::unparen(&node.expr)) {
+        (Pat::Tuple(tuple), Expr::MethodCall(call)) if call.method == "enumerate" => {
+            ast::pat_ident(tuple.elems.first()?)
+        }
+        (pat, Expr::Range(_)) => ast::pat_ident(pat),
+        _ => None,
+    }
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    /// Loop indices in scope.
+    indices: HashSet<String>,
+    /// Locals holding an index product, with the multiplication and index.
+    offsets: HashMap<String, (ExprBinary, String)>,
+    /// Start positions of multiplications already reported.
+    reported: HashSet<(usize, usize)>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// The index multiplication `expr` is, looking through casts.
+    fn index_product(&self, expr: &Expr) -> Option<(ExprBinary, String)> {
+        match ast::unparen(expr) {
+            Expr::Cast(cast) => self.index_product(&cast.expr),
+            Expr::Binary(binary) if matches!(binary.op, BinOp::Mul(_)) => {
+                let index = [&*binary.left, &*binary.right]
+                    .into_iter()
+                    .find_map(|side| ast::ident(ast::unparen(side)))
+                    .filter(|name| self.indices.contains(name))?;
+                Some((binary.clone(), index))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reports index products used as the offset `expr`, directly or
+    /// through a local.
+    fn check(&mut self, expr: &Expr) {
+        if let Some((mul, index)) = self.index_product(expr) {
+            self.report(&mul, &index);
+            return;
+        }
+        let tokens = expr.to_token_stream();
+        let used: Vec<_> = self
+            .offsets
+            .iter()
+            .filter(|(name, _)| ast::mentions(&tokens, name))
+            .map(|(_, product)| product.clone())
+            .collect();
+        for (mul, index) in used {
+            self.report(&mul, &index);
+        }
+    }
+
+    fn report(&mut self, mul: &ExprBinary, index: &str) {
+        let start = mul.span().start();
+        if !self.reported.insert((start.line, start.column)) {
+            return;
+        }
+        let other = if ast::ident(ast::unparen(&mul.left)).as_deref() == Some(index) {
+            &mul.right
+        } else {
+            &mul.left
+        };
+        let factor = ast::source_text(&**other);
+        let message = format!(
+            "`{}` computes the offset `{}` from the loop index `{index}` without \
+             `checked_mul`, so a large enough index wraps instead of failing",
+            self.handler.name,
+            ast::source_text(mul)
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::Low, self.program, mul.span(), message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(format!(
+                    "use `{index}.checked_mul({factor}).ok_or(ErrorCode::Overflow)?`"
+                )),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_for_loop(&mut self, node: &ExprForLoop) {
+        self.visit_expr(&node.expr);
+        let index = loop_index(node);
+        let fresh = index
+            .clone()
+            .is_some_and(|index| self.indices.insert(index));
+        self.visit_block(&node.body);
+        if let (true, Some(index)) = (fresh, index) {
+            self.indices.remove(&index);
+        }
+    }
+
+    fn visit_local(&mut self, local: &Local) {
+        visit::visit_local(self, local);
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            match self.index_product(&init.expr) {
+                Some(product) => {
+                    self.offsets.insert(name, product);
+                }
+                None => {
+                    self.offsets.remove(&name);
+                }
+            }
+        }
+    }
+
+    /// `ptr.add(offset)`, `data.get(offset..offset + 8)`
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        visit::visit_expr_method_call(self, node);
+        if OFFSET_METHODS.iter().any(|method| node.method == method) {
+            if let Some(arg) = node.args.first() {
+                self.check(arg);
+            }
+        }
+    }
+
+    /// `data[offset]`, `data[offset..offset + 8]`
+    fn visit_expr_index(&mut self, node: &ExprIndex) {
+        visit::visit_expr_index(self, node);
+        match ast::unparen(&node.index) {
+            Expr::Range(range) => {
+                for bound in range.start.iter().chain(&range.end) {
+                    self.check(bound);
+                }
+            }
+            index => self.check(index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_batch_update_offset() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].span.line_start, 151);
+        assert_eq!(findings[0].severity, Severity::Low);
+        assert_eq!(
+            findings[0].message,
+            "`batch_update_prices` computes the offset `i * 8` from the loop index `i` without \
+             `checked_mul`, so a large enough index wraps instead of failing"
+        );
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("use `i.checked_mul(8).ok_or(ErrorCode::Overflow)?`")
+        );
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    fn batch(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn batch(ctx: Context<Batch>, prices: Vec<u64>) -> Result<()> {{
+                    let mut data = ctx.accounts.store.try_borrow_mut_data()?;
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn only_unchecked_index_offsets_count() {
+        assert_eq!(
+            batch("for i in 0..prices.len() { data[i * 8] = 1; }").len(),
+            1
+        );
+        assert_eq!(
+            batch(
+                "for (i, p) in prices.iter().enumerate() { \
+                 let start = i * 8; data[start..start + 8].copy_from_slice(&p.to_le_bytes()); }"
+            )
+            .len(),
+            1
+        );
+        assert!(batch(
+            "for (i, p) in prices.iter().enumerate() { \
+             let start = i.checked_mul(8).unwrap(); data[start] = 1; }"
+        )
+        .is_empty());
+        assert!(
+            batch("for (i, p) in prices.iter().enumerate() { msg!(\"{}\", i * 8); }").is_empty()
+        );
+        assert!(batch("for p in prices.iter() { let total = p * 8; data[0] = 1; }").is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! `.unwrap()` / `.expect()` on fallible account APIs.
+//!
+//! A failed borrow or deserialization is an ordinary runtime condition,
+//! for example when the account is already borrowed. Unwrapping it aborts
+//! the transaction with a panic instead of a program error the client can
+//! decode.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprMethodCall};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "panic_prone_unwrap";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/panic-prone-unwrap",
+    name: ID,
+    title: "Unwrap on a fallible account API",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 248,
+    description: "`.unwrap()` or `.expect()` on a borrow or deserialization result panics instead of returning a program error.",
+    example: "let data = ctx.accounts.vault.try_borrow_data().unwrap();",
+    fixed_example: "let data = ctx.accounts.vault.try_borrow_data()?;",
+    references: &[
+        "https://doc.rust-lang.org/book/ch09-03-to-panic-or-not-to-panic.html",
+    ],
+};
+
+/// Solana and Anchor methods returning a `Result` that callers should
+/// propagate.
+const FALLIBLE_APIS: &[&str] = &[
+    "try_borrow_data",
+    "try_borrow_mut_data",
+    "try_borrow_lamports",
+    "try_borrow_mut_lamports",
+    "try_deserialize",
+    "try_deserialize_unchecked",
+    "try_from_slice",
+    "try_to_vec",
+    "try_serialize",
+    "deserialize",
+    "serialize",
+];
+
+const PANICKING: &[&str] = &["unwrap", "expect"];
+
+/// The [`FALLIBLE_APIS`] call `expr` is, as written: `try_borrow_data` for
+/// `data.try_borrow_data()`, `State::try_deserialize` for
+/// `State::try_deserialize(&mut &data[..])`.
+fn fallible_api(expr: &Expr) -> Option<String> {
+    match ast::unparen(expr) {
+        Expr::MethodCall(call) => {
+            let method = call.method.to_string();
+            FALLIBLE_APIS.contains(&method.as_str()).then_some(method)
+        }
+        Expr::Call(call) => {
+            let Expr::Path(func) = &*call.func else {
+                return None;
+            };
+            let last = func.path.segments.last()?.ident.to_string();
+            FALLIBLE_APIS
+                .contains(&last.as_str())
+                .then(|| ast::source_text(&*call.func))
+        }
+        _ => None,
+    }
+}
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        let method = node.method.to_string();
+        if PANICKING.contains(&method.as_str()) {
+            if let Some(api) = fallible_api(&node.receiver) {
+                let message = format!(
+                    "`.{method}()` on `{api}()` in `{}` panics instead of returning an error",
+                    self.handler.name
+                );
+                let suggestion = format!(
+                    "propagate the error with `{}?`",
+                    ast::source_text(ast::unparen(&node.receiver))
+                );
+                self.findings.push(
+                    Finding::new(ID, Severity::Medium, self.program, node.span(), message)
+                        .with_confidence(Confidence::High)
+                        .with_suggestion(suggestion),
+                );
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_every_fixture_borrow_unwrap() {
+        let findings: Vec<_> = ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"]
+            .iter()
+            .flat_map(|name| run(&parse_fixture(name)))
+            .collect();
+        let handlers: Vec<_> = findings
+            .iter()
+            .map(|f| f.message.split('`').nth(5).unwrap())
+            .collect();
+        assert_eq!(
+            handlers,
+            [
+                "get_user_balance",
+                "emergency_drain",
+                "get_nft_metadata",
+                "batch_update_prices"
+            ]
+        );
+        let spans: HashSet<_> = findings.iter().map(|f| &f.span).collect();
+        assert_eq!(spans.len(), 4);
+        assert!(findings.iter().all(|f| f.severity == Severity::Medium));
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("propagate the error with `data.try_borrow_data()?`")
+        );
+    }
+
+    #[test]
+    fn flags_associated_calls_and_ignores_option_unwraps() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn run(ctx: Context<Run>) -> Result<()> {
+                    let data = ctx.accounts.state.try_borrow_data()?;
+                    let first = data.first().unwrap();
+                    let state = State::try_deserialize(&mut &data[..]).expect("valid state");
+                    Ok(())
+                }
+            }
+            "#,
+        );
+        let findings = run(&program);
+        assert_eq!(findings.len(), 1, "{findings:?}");
+        assert_eq!(
+            findings[0].message,
+            "`.expect()` on `State::try_deserialize()` in `run` panics instead of returning an \
+             error"
+        );
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("propagate the error with `State::try_deserialize(&mut &data[..])?`")
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Transfers between two caller-supplied accounts that may be the same one.
+//!
+//! Nothing stops a caller from passing one account as both `from_token`
+//! and `to_token`, or as both `buyer` and `seller`. The runtime hands the
+//! handler two references to the same data, so a debit followed by a
+//! credit cancels out, balances computed from both sides are wrong and
+//! invariants that assume two parties break. Anchor only rejects the
+//! duplicate when the handler asks with `require_keys_neq!` or a
+//! `constraint = a.key() != b.key()`.
+
+use std::collections::HashSet;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, ExprBinary};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::direct_lamport_mutation::lamports_target;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountsStruct, ParsedProgram};
+
+pub const ID: &str = "potential_account_aliasing";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/potential-account-aliasing",
+    name: ID,
+    title: "Transfer between accounts that may alias",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 694,
+    description: "A handler moves tokens or lamports from one caller-supplied account to another without checking that the two are different accounts, so passing the same account twice breaks the handler's accounting.",
+    example: "let cpi_accounts = Transfer {\n    from: ctx.accounts.from_token.to_account_info(),\n    to: ctx.accounts.to_token.to_account_info(),\n    authority: ctx.accounts.authority.to_account_info(),\n};",
+    fixed_example: "require_keys_neq!(\n    ctx.accounts.from_token.key(),\n    ctx.accounts.to_token.key(),\n    ErrorCode::SameAccount\n);",
+    references: &[
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/6-duplicate-mutable-accounts",
+        "https://cwe.mitre.org/data/definitions/694.html",
+    ],
+};
+
+/// Constraint keys that fix an account's address, so two fields that both
+/// carry one cannot be the same account unless they name the same address.
+const PINNING: &[&str] = &["address", "seeds"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let bindings = Bindings::of(&handler.item);
+        let guards = ast::require_guards(&handler.item);
+        let mut reported = HashSet::new();
+        for transfer in transfers(&handler.item, &bindings) {
+            let (from, to) = (&transfer.from, &transfer.to);
+            if from == to
+                || accounts.field(from).is_none()
+                || accounts.field(to).is_none()
+                || distinct(accounts, from, to)
+                || guards
+                    .iter()
+                    .any(|guard| ast::mentions(guard, from) && ast::mentions(guard, to))
+                || !reported.insert((from.clone(), to.clone()))
+            {
+                continue;
+            }
+            let message = format!(
+                "`{}` moves {} from `{from}` to `{to}` without checking that they are \
+                 different accounts",
+                handler.name, transfer.asset
+            );
+            findings.push(
+                Finding::new(ID, Severity::Medium, program, transfer.span, message)
+                    .with_confidence(Confidence::Medium)
+                    .with_impact(format!(
+                        "A caller who passes the same account as `{from}` and `{to}` makes \
+                         `{}` run against one account as if it were two",
+                        handler.name
+                    ))
+                    .with_suggestion(format!(
+                        "add `require_keys_neq!(ctx.accounts.{from}.key(), \
+                         ctx.accounts.{to}.key(), ErrorCode::SameAccount)` or \
+                         `constraint = {from}.key() != {to}.key()` on `{to}`"
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// Whether the accounts struct already keeps `from` and `to` apart: both
+/// are pinned to an address, or a constraint on one names the other.
+fn distinct(accounts: &AccountsStruct, from: &str, to: &str) -> bool {
+    let (Some(from_field), Some(to_field)) = (accounts.field(from), accounts.field(to)) else {
+        return false;
+    };
+    let pinned = |field: &AccountField| PINNING.iter().any(|key| field.has_constraint(key));
+    let names = |field: &AccountField, other: &str| {
+        field
+            .constraint_values("constraint")
+            .any(|value| ast::mentions(value, other))
+    };
+    (pinned(from_field) && pinned(to_field)) || names(from_field, to) || names(to_field, from)
+}
+
+/// A movement of value between two context accounts.
+struct Transfer {
+    from: String,
+    to: String,
+    /// What moves: `tokens` or `lamports`.
+    asset: &'static str,
+    span: proc_macro2::Span,
+}
+
+/// Every CPI transfer with a `from` and a `to`, and every pairing of a
+/// direct lamport debit with a direct lamport credit, inside `item`.
+fn transfers(item: &syn::ItemFn, bindings: &Bindings) -> Vec<Transfer> {
+    struct Collector<'a> {
+        bindings: &'a Bindings,
+        transfers: Vec<Transfer>,
+        debits: Vec<String>,
+        credits: Vec<(String, proc_macro2::Span)>,
+    }
+
+    impl Visit<'_> for Collector<'_> {
+        fn visit_expr_struct(&mut self, node: &syn::ExprStruct) {
+            visit::visit_expr_struct(self, node);
+            let Some(cpi) = ast::cpi_accounts_literal(node) else {
+                return;
+            };
+            let account = |name| cpi.field(name).and_then(|expr| self.bindings.account(expr));
+            if let (Some(from), Some(to), Some(value)) =
+                (account("from"), account("to"), cpi.field("to"))
+            {
+                self.transfers.push(Transfer {
+                    from,
+                    to,
+                    asset: "tokens",
+                    span: value.span(),
+                });
+            }
+        }
+
+        fn visit_expr_binary(&mut self, node: &ExprBinary) {
+            visit::visit_expr_binary(self, node);
+            let Some(account) = lamports_target(self.bindings, &node.left) else {
+                return;
+            };
+            match node.op {
+                BinOp::SubAssign(_) => self.debits.push(account),
+                BinOp::AddAssign(_) => self.credits.push((account, node.left.span())),
+                _ => {}
+            }
+        }
+    }
+
+    let mut collector = Collector {
+        bindings,
+        transfers: Vec::new(),
+        debits: Vec::new(),
+        credits: Vec::new(),
+    };
+    collector.visit_item_fn(item);
+    let Collector {
+        mut transfers,
+        debits,
+        credits,
+        ..
+    } = collector;
+    for from in &debits {
+        for (to, span) in &credits {
+            transfers.push(Transfer {
+                from: from.clone(),
+                to: to.clone(),
+                asset: "lamports",
+                span: *span,
+            });
+        }
+    }
+    transfers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_marketplace_transfers() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        let messages: Vec<_> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "`buy_nft` moves lamports from `buyer` to `seller` without checking that they \
+                 are different accounts",
+                "`transfer_nft` moves tokens from `from_token` to `to_token` without checking \
+                 that they are different accounts",
+            ]
+        );
+        assert_eq!(findings[0].span.line_start, 77);
+        assert_eq!(findings[1].span.line_start, 119);
+        assert!(findings
+            .iter()
+            .all(|f| f.severity == Severity::Medium && f.confidence == Confidence::Medium));
+    }
+
+    fn transfer(constraint: &str, guard: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn send(ctx: Context<Send>) -> Result<()> {{
+                    {guard}
+                    let cpi_accounts = Transfer {{
+                        from: ctx.accounts.source.to_account_info(),
+                        to: ctx.accounts.sink.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    }};
+                    token::transfer(CpiContext::new(program, cpi_accounts), 1)?;
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Send<'info> {{
+                #[account(mut, seeds = [b"source"], bump)]
+                pub source: Account<'info, TokenAccount>,
+                #[account({constraint})]
+                pub sink: Account<'info, TokenAccount>,
+                pub owner: Signer<'info>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn a_distinctness_check_clears_the_pair() {
+        assert_eq!(transfer("mut", "").len(), 1);
+        assert!(transfer("mut, constraint = sink.key() != source.key()", "").is_empty());
+        assert!(transfer("mut, seeds = [b\"sink\"], bump", "").is_empty());
+        assert!(transfer(
+            "mut",
+            "require_keys_neq!(ctx.accounts.source.key(), ctx.accounts.sink.key());"
+        )
+        .is_empty());
+    }
+}
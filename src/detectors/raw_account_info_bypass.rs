@@ -0,0 +1,140 @@
+//! Raw byte reads through `to_account_info()`.
+//!
+//! `Account<'info, T>` has already checked the owner and discriminator and
+//! deserialized `T`. Converting it back to an `AccountInfo` and reading the
+//! bytes by hand throws that away and hard-codes a layout the compiler no
+//! longer checks. For an untyped `AccountInfo` the bytes were never
+//! checked in the first place.
+
+use std::collections::HashSet;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountKind, Handler, ParsedProgram};
+
+pub const ID: &str = "raw_account_info_bypass";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/raw-account-info-bypass",
+    name: ID,
+    title: "Account bytes read through `to_account_info()`",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 20,
+    description: "An account is converted with `to_account_info()` and its data borrowed as raw bytes, bypassing Anchor's owner, discriminator and layout checks.",
+    example: "let info = ctx.accounts.nft_account.to_account_info(); let data = info.try_borrow_data()?;",
+    fixed_example: "let metadata = &ctx.accounts.nft_account.metadata_uri;",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/account/struct.Account.html",
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/3-type-cosplay",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let mut reported = HashSet::new();
+        for read in ast::data_reads(&handler.item, &Bindings::of(&handler.item)) {
+            let (Some(conversion), Some(field)) = (read.conversion, accounts.field(&read.account))
+            else {
+                continue;
+            };
+            if let Some(finding) = report(program, handler, field, conversion) {
+                if reported.insert(read.account) {
+                    findings.push(finding);
+                }
+            }
+        }
+    }
+    findings
+}
+
+fn report(
+    program: &ParsedProgram,
+    handler: &Handler,
+    field: &AccountField,
+    span: proc_macro2::Span,
+) -> Option<Finding> {
+    let (name, account) = (&handler.name, &field.name);
+    let (message, suggestion, confidence) = match &field.kind {
+        AccountKind::Account(state) => (
+            format!(
+                "`{name}` converts `{account}` (`Account<{state}>`) to `AccountInfo` and reads \
+                 its raw bytes, bypassing Anchor's deserialization and discriminator check"
+            ),
+            format!("read `{state}` fields through `ctx.accounts.{account}` directly"),
+            Confidence::High,
+        ),
+        AccountKind::AccountInfo | AccountKind::UncheckedAccount => (
+            format!(
+                "`{name}` reads the raw bytes of untyped `{account}` through \
+                 `to_account_info()`, with no owner, discriminator or layout check"
+            ),
+            format!(
+                "declare `{account}` as `Account<'info, T>` and read the fields of `T` instead \
+                 of raw bytes"
+            ),
+            Confidence::Medium,
+        ),
+        _ => return None,
+    };
+    Some(
+        Finding::new(ID, Severity::Medium, program, span, message)
+            .with_confidence(confidence)
+            .with_suggestion(suggestion),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn handlers(findings: &[Finding]) -> Vec<&str> {
+        findings
+            .iter()
+            .map(|f| f.message.split('`').nth(1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn flags_raw_reads_in_both_fixtures() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(handlers(&findings), ["get_nft_metadata"]);
+        assert_eq!(findings[0].confidence, Confidence::High);
+        assert_eq!(findings[0].span.line_start, 89);
+
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(handlers(&findings), ["get_user_balance", "emergency_drain"]);
+        assert!(findings[0].message.contains("untyped `user_account`"));
+        assert!(findings
+            .iter()
+            .all(|finding| finding.severity == Severity::Medium));
+    }
+
+    #[test]
+    fn conversion_without_byte_access_is_fine() {
+        let findings = run(&parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn pay(ctx: Context<Pay>) -> Result<()> {
+                    let info = ctx.accounts.vault.to_account_info();
+                    let key = info.key();
+                    Ok(())
+                }
+            }
+
+            #[derive(Accounts)]
+            pub struct Pay<'info> {
+                pub vault: Account<'info, Vault>,
+            }
+            "#,
+        ));
+        assert!(findings.is_empty());
+    }
+}
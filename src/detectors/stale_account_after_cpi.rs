@@ -0,0 +1,222 @@
+//! Account fields read after a CPI without reloading the account.
+//!
+//! Anchor deserializes every `Account<'info, T>` once, before the handler
+//! runs. A CPI that receives the account can change its data on chain, but
+//! the handler's copy keeps the old values until `reload()` is called, so
+//! reading `vault.balance` after a transfer sees the balance from before it.
+
+use std::collections::{HashMap, HashSet};
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprAssign, ExprCall, ExprField, ExprMethodCall, ExprStruct};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, AccountsStruct, Handler, ParsedProgram};
+
+pub const ID: &str = "stale_account_after_cpi";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/stale-account-after-cpi",
+    name: ID,
+    title: "Account read after a CPI without reload",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 367,
+    description: "A field of an `Account<'info, T>` is read after the account was passed to a CPI, without an intervening `reload()`, so the value predates the CPI.",
+    example: "token::transfer(cpi_ctx, amount)?; let left = ctx.accounts.vault_token.amount;",
+    fixed_example: "token::transfer(cpi_ctx, amount)?;\nctx.accounts.vault_token.reload()?;\nlet left = ctx.accounts.vault_token.amount;",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/account/struct.Account.html#method.reload",
+        "https://cwe.mitre.org/data/definitions/367.html",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let mut visitor = Visitor {
+            program,
+            handler,
+            accounts,
+            bindings: Bindings::of(&handler.item),
+            pending: HashSet::new(),
+            stale: HashMap::new(),
+            reported: HashSet::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    accounts: &'a AccountsStruct,
+    bindings: Bindings,
+    /// Accounts named in CPI account structs not yet passed to a CPI.
+    pending: HashSet<String>,
+    /// Accounts whose copy predates a CPI, with that CPI and its line.
+    stale: HashMap<String, (String, usize)>,
+    /// Accounts already reported, so each is flagged once.
+    reported: HashSet<String>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// Whether `name` is a deserialized `Account<'info, T>`.
+    fn is_typed_account(&self, name: &str) -> bool {
+        self.accounts
+            .field(name)
+            .is_some_and(|field| matches!(field.kind, AccountKind::Account(_)))
+    }
+
+    fn check_read(&mut self, node: &ExprField) {
+        let Some((account, _)) = self.bindings.account_field(&Expr::Field(node.clone())) else {
+            return;
+        };
+        let Some((cpi, line)) = self.stale.get(&account) else {
+            return;
+        };
+        if !self.reported.insert(account.clone()) {
+            return;
+        }
+        let message = format!(
+            "`{}` reads `{}` after `{account}` was passed to `{cpi}` on line {line}, without \
+             reloading it",
+            self.handler.name,
+            ast::source_text(node)
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::Medium, self.program, node.span(), message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(format!(
+                    "call `ctx.accounts.{account}.reload()?` after the CPI so the read sees the \
+                     updated data"
+                )),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_struct(&mut self, node: &ExprStruct) {
+        if let Some(cpi) = ast::cpi_accounts_literal(node) {
+            for (_, expr) in &cpi.fields {
+                if let Some(account) = self.bindings.account(expr) {
+                    if self.is_typed_account(&account) {
+                        self.pending.insert(account);
+                    }
+                }
+            }
+        }
+        visit::visit_expr_struct(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        // Arguments first: a CPI context may be built inline.
+        visit::visit_expr_call(self, node);
+        if let Some(cpi) = ast::cpi_path(node) {
+            let line = node.span().start().line;
+            for account in self.pending.drain() {
+                self.reported.remove(&account);
+                self.stale.insert(account, (cpi.clone(), line));
+            }
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        if node.method == "reload" {
+            if let Some(account) = self.bindings.account(&node.receiver) {
+                self.stale.remove(&account);
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    /// Plain assignment targets are writes, not reads.
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        if let Expr::Field(target) = &*node.left {
+            self.visit_expr(&target.base);
+        } else {
+            self.visit_expr(&node.left);
+        }
+        self.visit_expr(&node.right);
+    }
+
+    fn visit_expr_field(&mut self, node: &ExprField) {
+        self.check_read(node);
+        visit::visit_expr_field(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn fixtures_never_read_after_a_cpi() {
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn withdraw(after: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {{
+                    let cpi_accounts = Transfer {{
+                        from: ctx.accounts.vault_token.to_account_info(),
+                        to: ctx.accounts.user_token.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    }};
+                    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+                    token::transfer(cpi_ctx, amount)?;
+                    {after}
+                    let vault = &mut ctx.accounts.vault;
+                    vault.last_balance = vault.balance;
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Withdraw<'info> {{
+                #[account(mut)]
+                pub vault: Account<'info, Vault>,
+                #[account(mut)]
+                pub vault_token: Account<'info, TokenAccount>,
+                #[account(mut)]
+                pub user_token: Account<'info, TokenAccount>,
+                pub token_program: Program<'info, Token>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_balance_read_after_transfer() {
+        let findings = withdraw("");
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(
+            finding.message,
+            "`withdraw` reads `vault.balance` after `vault` was passed to `token::transfer` on \
+             line 11, without reloading it"
+        );
+    }
+
+    #[test]
+    fn reload_refreshes_the_account() {
+        assert!(withdraw("ctx.accounts.vault.reload()?;").is_empty());
+        // Reloading a different account does not help.
+        assert_eq!(withdraw("ctx.accounts.user_token.reload()?;").len(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Account state written after funds have already moved.
+//!
+//! Checks-effects-interactions: a handler should validate, update its own
+//! state, and only then transfer tokens or lamports. Writing state after
+//! the transfer leaves the accounts inconsistent if the CPI re-enters the
+//! program or the write later fails, and makes the flow harder to audit.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{ExprCall, ExprMethodCall};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::ParsedProgram;
+
+pub const ID: &str = "state_update_after_cpi";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/state-update-after-cpi",
+    name: ID,
+    title: "State updated after an external transfer",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 841,
+    description: "An account field is written after a token CPI or lamport mutation in the same handler, breaking the checks-effects-interactions order.",
+    example: "token::transfer(cpi_ctx, amount)?; vault.balance -= amount;",
+    fixed_example: "vault.balance -= amount;\ntoken::transfer(cpi_ctx, amount)?;",
+    references: &[
+        "https://docs.soliditylang.org/en/latest/security-considerations.html#use-the-checks-effects-interactions-pattern",
+        "https://solana.com/docs/core/cpi",
+    ],
+};
+
+/// Methods that move lamports without a CPI.
+const LAMPORT_WRITES: &[&str] = &["try_borrow_mut_lamports"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut interactions = Interactions::default();
+        interactions.visit_item_fn(&handler.item);
+        let Some((first, what)) = interactions.0.first() else {
+            continue;
+        };
+        let late: Vec<_> = ast::field_writes(&handler.item, &Bindings::of(&handler.item))
+            .into_iter()
+            .filter(|write| position(write.span.start()) > *first)
+            .collect();
+        let Some(write) = late.first() else {
+            continue;
+        };
+        let mut targets: Vec<_> = late.iter().map(|w| format!("`{}`", w.target)).collect();
+        targets.dedup();
+        let message = format!(
+            "`{}` writes {} after the {what} on line {}",
+            handler.name,
+            targets.join(", "),
+            first.0
+        );
+        findings.push(
+            Finding::new(ID, Severity::Medium, program, write.span, message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(
+                    "follow checks-effects-interactions: update account state before the \
+                     transfer, and transfer last",
+                ),
+        );
+    }
+    findings
+}
+
+fn position(at: proc_macro2::LineColumn) -> (usize, usize) {
+    (at.line, at.column)
+}
+
+/// Start of every CPI or lamport mutation, with a description, in source
+/// order.
+#[derive(Default)]
+struct Interactions(Vec<((usize, usize), String)>);
+
+impl Visit<'_> for Interactions {
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        if let Some(path) = ast::cpi_path(node) {
+            self.0
+                .push((position(node.span().start()), format!("`{path}` CPI")));
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        if LAMPORT_WRITES.iter().any(|method| node.method == method) {
+            self.0.push((
+                position(node.span().start()),
+                "lamport transfer".to_string(),
+            ));
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_buy_nft_ordering() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(finding.span.line_start, 80);
+        assert_eq!(
+            finding.message,
+            "`buy_nft` writes `nft.owner`, `nft.listed`, `nft.price` after the lamport transfer \
+             on line 76"
+        );
+        // `withdraw` updates the balance before its CPI.
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    #[test]
+    fn flags_writes_after_token_cpi() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+                    let vault = &mut ctx.accounts.vault;
+                    token::transfer(cpi_ctx, amount)?;
+                    vault.balance -= amount;
+                    Ok(())
+                }
+            }
+            "#,
+        );
+        let findings = run(&program);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0]
+            .message
+            .starts_with("`withdraw` writes `vault.balance` after the `token::transfer` CPI"));
+    }
+}
@@ -0,0 +1,234 @@
+//! Caller-supplied `Pubkey` arguments stored as an account's owner.
+//!
+//! A simple intraprocedural taint trace: every `Pubkey` parameter is
+//! tainted, locals initialised from tainted values are tainted too, and a
+//! tainted value reaching an `owner` / `authority` field is reported unless
+//! a guard comparing against the owner ran first. Unlike
+//! `missing_authority_guard`, this only fires when the new owner is
+//! attacker-chosen.
+
+use std::collections::HashMap;
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{ExprAssign, ExprIf, FnArg, Local, Macro};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::missing_authority_guard::{unguarded, OWNER_FIELDS};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountsStruct, Handler, ParsedProgram};
+
+pub const ID: &str = "tainted_owner_assignment";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/tainted-owner-assignment",
+    name: ID,
+    title: "Caller-chosen key stored as owner",
+    category: Category::AccessControl,
+    severity: Severity::Critical,
+    cwe: 639,
+    description: "A `Pubkey` instruction argument flows into an `owner` or `authority` field with no guard on the current owner, letting any caller take over the account.",
+    example: "pub fn transfer_nft(ctx: Context<Transfer>, new_owner: Pubkey) -> Result<()> { ctx.accounts.nft.owner = new_owner; Ok(()) }",
+    fixed_example: "require_keys_eq!(ctx.accounts.owner.key(), ctx.accounts.nft.owner, ErrorCode::NotOwner);\nctx.accounts.nft.owner = new_owner;",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/derive.Accounts.html",
+        "https://cwe.mitre.org/data/definitions/639.html",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            accounts: program.accounts_for(handler),
+            bindings: Bindings::of(&handler.item),
+            tainted: pubkey_params(handler),
+            guarded: false,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// `Pubkey` parameters, each tainted by itself.
+fn pubkey_params(handler: &Handler) -> HashMap<String, Vec<String>> {
+    handler
+        .item
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) => ast::pat_ident(&arg.pat),
+            FnArg::Receiver(_) => None,
+        })
+        .filter(|name| handler.arg_type(name).as_deref() == Some("Pubkey"))
+        .map(|name| (name.clone(), vec![name]))
+        .collect()
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    accounts: Option<&'a AccountsStruct>,
+    bindings: Bindings,
+    /// Tainted locals and the path from a parameter to each of them.
+    tainted: HashMap<String, Vec<String>>,
+    /// Whether a guard on the owner or a tainted value has been seen.
+    guarded: bool,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// The taint path of the first tainted variable `tokens` mention.
+    fn taint_of(&self, tokens: &proc_macro2::TokenStream) -> Option<&Vec<String>> {
+        let mut names: Vec<_> = self.tainted.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .find(|name| ast::mentions(tokens, name))
+            .map(|name| &self.tainted[name])
+    }
+
+    fn is_guard(&self, tokens: &proc_macro2::TokenStream) -> bool {
+        OWNER_FIELDS
+            .iter()
+            .any(|field| ast::mentions(tokens, field))
+            || self.taint_of(tokens).is_some()
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            if let Some(path) = self.taint_of(&init.expr.to_token_stream()) {
+                let mut path = path.clone();
+                path.push(name.clone());
+                self.tainted.insert(name, path);
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_macro(&mut self, node: &Macro) {
+        if ast::is_require(node) && self.is_guard(&node.tokens) {
+            self.guarded = true;
+        }
+        visit::visit_macro(self, node);
+    }
+
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        if self.is_guard(&node.cond.to_token_stream()) {
+            self.guarded = true;
+        }
+        visit::visit_expr_if(self, node);
+    }
+
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        if let Some((account, field)) = self.bindings.mut_account_field(&node.left) {
+            let constrained = self
+                .accounts
+                .is_some_and(|accounts| !unguarded(accounts, &account));
+            if OWNER_FIELDS.contains(&field.as_str()) && !self.guarded && !constrained {
+                if let Some(path) = self.taint_of(&node.right.to_token_stream()) {
+                    let target = ast::source_text(&*node.left);
+                    let mut trace: Vec<_> = path.iter().map(|step| format!("`{step}`")).collect();
+                    trace.push(format!("`{target}`"));
+                    let message = format!(
+                        "`{}` stores caller-supplied `{}` into `{target}` without checking the \
+                         current {field} (taint path: {})",
+                        self.handler.name,
+                        path[0],
+                        trace.join(" -> ")
+                    );
+                    self.findings.push(
+                        Finding::new(ID, Severity::Critical, self.program, node.span(), message)
+                            .with_confidence(Confidence::High)
+                            .with_impact(format!(
+                                "Any wallet can call `{}` with its own key as `{}` and become \
+                                 the {field} of `{account}`, locking the real {field} out",
+                                self.handler.name, path[0]
+                            ))
+                            .with_suggestion(format!(
+                                "require the current {field} to sign, e.g. \
+                                 `require_keys_eq!(ctx.accounts.{field}.key(), {}.{field})`, \
+                                 or add `has_one = {field}`",
+                                account
+                            )),
+                    );
+                }
+            }
+        }
+        visit::visit_expr_assign(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn paths(findings: &[Finding]) -> Vec<&str> {
+        findings
+            .iter()
+            .map(|f| {
+                let start = f.message.find("taint path: ").unwrap() + "taint path: ".len();
+                f.message[start..].trim_end_matches(')')
+            })
+            .collect()
+    }
+
+    #[test]
+    fn traces_fixture_ownership_changes() {
+        let vault = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(paths(&vault), ["`new_authority` -> `vault.authority`"]);
+        assert!(vault[0]
+            .message
+            .starts_with("`update_authority` stores caller-supplied"));
+        assert_eq!(vault[0].severity, Severity::Critical);
+
+        let nft = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(paths(&nft), ["`new_owner` -> `nft.owner`"]);
+        assert!(nft[0].message.starts_with("`transfer_nft`"));
+    }
+
+    fn handler(body: &str) -> String {
+        format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn set_owner(ctx: Context<SetOwner>, new_owner: Pubkey, bump: u8) -> Result<()> {{
+                    let item = &mut ctx.accounts.item;
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )
+    }
+
+    #[test]
+    fn follows_locals_and_stops_at_guards() {
+        let local = handler("let next = new_owner; item.owner = next;");
+        assert_eq!(
+            paths(&run(&parse(&local))),
+            ["`new_owner` -> `next` -> `item.owner`"]
+        );
+
+        let guarded = handler(
+            "require_keys_eq!(ctx.accounts.signer.key(), item.owner); item.owner = new_owner;",
+        );
+        assert!(run(&parse(&guarded)).is_empty());
+
+        // A guard after the write does not protect it.
+        let late = handler("item.owner = new_owner; require!(item.owner != Pubkey::default());");
+        assert_eq!(run(&parse(&late)).len(), 1);
+
+        let untainted = handler("item.owner = ctx.accounts.signer.key(); item.bump = bump;");
+        assert!(run(&parse(&untainted)).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+//! Unchecked `+` and `-` on timestamps and `Clock` values.
+//!
+//! Durations such as `now - vault.created_at` or deadlines such as
+//! `now + lock_period` are `i64` arithmetic that wraps in release builds. A
+//! `created_at` that was never set, or set by the caller, turns an elapsed
+//! time negative or a deadline into the distant past. `checked_sub` and
+//! `checked_add` make the bad case an error instead.
+
+use std::collections::HashSet;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprBinary, Local, Member};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "timestamp_arithmetic";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/timestamp-arithmetic",
+    name: ID,
+    title: "Unchecked arithmetic on a timestamp",
+    category: Category::Arithmetic,
+    severity: Severity::Low,
+    cwe: 190,
+    description: "A timestamp, such as a `created_at: i64` field or `Clock::get()?.unix_timestamp`, is added to or subtracted from with plain `+`/`-`, which wraps instead of failing when the stored time is unset or out of range.",
+    example: "let now = Clock::get()?.unix_timestamp;\nlet elapsed = now - vault.created_at;",
+    fixed_example: "let now = Clock::get()?.unix_timestamp;\nlet elapsed = now\n    .checked_sub(vault.created_at)\n    .filter(|elapsed| *elapsed >= 0)\n    .ok_or(ErrorCode::InvalidTimestamp)?;",
+    references: &[
+        "https://docs.rs/solana-program/latest/solana_program/clock/struct.Clock.html",
+        "https://doc.rust-lang.org/std/primitive.i64.html#method.checked_sub",
+    ],
+};
+
+/// Fields of `Clock` that hold a time or a count of slots.
+const CLOCK_FIELDS: &[&str] = &["unix_timestamp", "slot", "epoch", "epoch_start_timestamp"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            locals: HashSet::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// Whether `name` reads like a point in time: `created_at`, `last_ts`,
+/// `deadline`, `expiry`, `start_time`.
+fn is_timestamp_name(name: &str) -> bool {
+    name.ends_with("_at")
+        || name.ends_with("_ts")
+        || name.ends_with("_time")
+        || name.contains("timestamp")
+        || name.contains("deadline")
+        || name.starts_with("expir")
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    /// Locals holding a timestamp or a duration computed from one.
+    locals: HashSet<String>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// Whether `expr` is a timestamp: a `Clock` field, an `i64` state field
+    /// or argument named like one, or a local assigned from either.
+    fn is_timestamp(&self, expr: &Expr) -> bool {
+        match ast::unparen(expr) {
+            Expr::Field(access) => {
+                let Member::Named(field) = &access.member else {
+                    return false;
+                };
+                let field = field.to_string();
+                if CLOCK_FIELDS.contains(&field.as_str()) {
+                    return true;
+                }
+                let Some((account, field)) = self.bindings.account_field(expr) else {
+                    return false;
+                };
+                is_timestamp_name(&field)
+                    && self
+                        .program
+                        .state_field_type(self.handler, &account, &field)
+                        .is_none_or(|ty| ty == "i64")
+            }
+            path @ Expr::Path(_) => ast::ident(path).is_some_and(|name| {
+                self.locals.contains(&name)
+                    || (is_timestamp_name(&name)
+                        && self.handler.arg_type(&name).as_deref() == Some("i64"))
+            }),
+            Expr::Binary(binary) if arithmetic_symbol(&binary.op).is_some() => {
+                self.is_timestamp(&binary.left) || self.is_timestamp(&binary.right)
+            }
+            _ => false,
+        }
+    }
+
+    fn check(&mut self, node: &ExprBinary) {
+        let Some(symbol) = arithmetic_symbol(&node.op) else {
+            return;
+        };
+        // Report the innermost operation only: in `now - start + grace` the
+        // subtraction is the one to rewrite first.
+        let operand = [&*node.left, &*node.right].into_iter().find(|operand| {
+            !matches!(ast::unparen(operand), Expr::Binary(_)) && self.is_timestamp(operand)
+        });
+        let Some(operand) = operand else {
+            return;
+        };
+        let method = if matches!(node.op, BinOp::Add(_) | BinOp::AddAssign(_)) {
+            "checked_add"
+        } else {
+            "checked_sub"
+        };
+        let message = format!(
+            "`{}` uses unchecked `{symbol}` on the timestamp `{}`, which wraps if the stored \
+             time is unset or out of range",
+            self.handler.name,
+            ast::source_text(operand)
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::Low, self.program, node.span(), message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(format!(
+                    "use `{method}` and reject the result when it is `None` or negative, e.g. \
+                     `now.{method}(vault.created_at).ok_or(ErrorCode::InvalidTimestamp)?`"
+                )),
+        );
+    }
+}
+
+/// `+` or `-` for the operators this detector checks, compound or not.
+fn arithmetic_symbol(op: &BinOp) -> Option<&'static str> {
+    match op {
+        BinOp::Add(_) | BinOp::AddAssign(_) => Some("+"),
+        BinOp::Sub(_) | BinOp::SubAssign(_) => Some("-"),
+        _ => None,
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        visit::visit_local(self, local);
+        let Some(name) = ast::pat_ident(&local.pat) else {
+            return;
+        };
+        let timestamp = local
+            .init
+            .as_ref()
+            .is_some_and(|init| self.is_timestamp(&init.expr));
+        if timestamp {
+            self.locals.insert(name);
+        } else {
+            self.locals.remove(&name);
+        }
+    }
+
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        visit::visit_expr_binary(self, node);
+        self.check(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn vault(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn claim(ctx: Context<Claim>, lock_period: i64) -> Result<()> {{
+                    let vault = &mut ctx.accounts.vault;
+                    {body}
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Claim<'info> {{
+                #[account(mut)]
+                pub vault: Account<'info, Vault>,
+            }}
+
+            #[account]
+            pub struct Vault {{
+                pub created_at: i64,
+                pub balance: u64,
+                pub label_at: String,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_a_duration_from_created_at() {
+        let findings = vault(
+            "let now = Clock::get()?.unix_timestamp;
+             let elapsed = now - vault.created_at;
+             let unlocked = elapsed > 86_400;",
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`claim` uses unchecked `-` on the timestamp `now`, which wraps if the stored time \
+             is unset or out of range"
+        );
+        assert_eq!(findings[0].severity, Severity::Low);
+        assert!(findings[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("checked_sub"));
+
+        let deadline =
+            vault("let clock = Clock::get()?; let unlock = clock.unix_timestamp + lock_period;");
+        assert_eq!(deadline.len(), 1);
+        assert!(deadline[0]
+            .message
+            .contains("unchecked `+` on the timestamp `clock.unix_timestamp`"));
+        assert_eq!(vault("vault.created_at += lock_period;").len(), 1);
+    }
+
+    #[test]
+    fn ignores_checked_and_unrelated_arithmetic() {
+        assert!(vault(
+            "let now = Clock::get()?.unix_timestamp;
+             let elapsed = now.checked_sub(vault.created_at).ok_or(ErrorCode::Overflow)?;"
+        )
+        .is_empty());
+        assert!(vault("vault.balance = vault.balance + 1;").is_empty());
+        assert!(vault("let label = vault.label_at + \"!\";").is_empty());
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+//! Token transfers of a caller-chosen amount that is never checked against
+//! the balance it is taken from.
+//!
+//! `vault.balance = vault.balance - amount` followed by a transfer of
+//! `amount` trusts the caller to ask for no more than the vault holds.
+//! Without a `require!(amount <= vault.balance)` or `checked_sub`, the
+//! subtraction wraps in release builds and the books no longer match the
+//! tokens that left.
+
+use std::collections::HashSet;
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprCall, ExprIf, ExprMethodCall, FnArg};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "transfer_without_balance_check";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/transfer-without-balance-check",
+    name: ID,
+    title: "Transferred amount not checked against the balance",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 1284,
+    description: "A handler transfers an amount argument and subtracts it from a tracked balance without a guard comparing the two or a `checked_sub`.",
+    example: "vault.balance = vault.balance - amount;\ntoken::transfer(cpi_ctx, amount)?;",
+    fixed_example: "require!(amount <= vault.balance, ErrorCode::InsufficientFunds);\nvault.balance -= amount;\ntoken::transfer(cpi_ctx, amount)?;",
+    references: &[
+        "https://cwe.mitre.org/data/definitions/1284.html",
+        "https://docs.rs/anchor-lang/latest/anchor_lang/macro.require.html",
+    ],
+};
+
+const INTEGER_TYPES: &[&str] = &["u8", "u16", "u32", "u64", "u128", "usize"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut facts = Facts {
+            bindings: Bindings::of(&handler.item),
+            params: integer_params(handler),
+            ..Facts::default()
+        };
+        facts.visit_item_fn(&handler.item);
+        facts.conditions.extend(ast::require_guards(&handler.item));
+        report(program, handler, &facts, &mut findings);
+    }
+    findings
+}
+
+/// Names of `handler`'s unsigned integer arguments.
+fn integer_params(handler: &Handler) -> Vec<String> {
+    handler
+        .item
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) => ast::pat_ident(&arg.pat),
+            FnArg::Receiver(_) => None,
+        })
+        .filter(|name| {
+            handler
+                .arg_type(name)
+                .is_some_and(|ty| INTEGER_TYPES.contains(&ty.as_str()))
+        })
+        .collect()
+}
+
+fn report(program: &ParsedProgram, handler: &Handler, facts: &Facts, findings: &mut Vec<Finding>) {
+    let mut reported = HashSet::new();
+    for (param, span) in &facts.transfers {
+        let Some(Debit { target, field, .. }) =
+            facts.debits.iter().find(|debit| &debit.param == param)
+        else {
+            continue;
+        };
+        let guarded = facts
+            .conditions
+            .iter()
+            .any(|tokens| ast::mentions(tokens, param) && ast::mentions(tokens, field));
+        if guarded || facts.checked.contains(param) || !reported.insert(param) {
+            continue;
+        }
+        let message = format!(
+            "`{}` transfers caller-supplied `{param}` and subtracts it from `{target}` without \
+             checking that `{param}` is at most `{target}`",
+            handler.name
+        );
+        findings.push(
+            Finding::new(ID, Severity::High, program, *span, message)
+                .with_confidence(Confidence::Medium)
+                .with_suggestion(format!(
+                    "add `require!({param} <= {target}, ErrorCode::InsufficientFunds)` before the \
+                     transfer, or subtract with `checked_sub`"
+                )),
+        );
+    }
+}
+
+/// `target -= param`, where `target` is the account field `field`.
+struct Debit {
+    param: String,
+    target: String,
+    field: String,
+}
+
+#[derive(Default)]
+struct Facts {
+    bindings: Bindings,
+    params: Vec<String>,
+    /// Parameters passed as a transfer amount, with the call's span.
+    transfers: Vec<(String, proc_macro2::Span)>,
+    debits: Vec<Debit>,
+    /// Parameters given to `checked_sub`.
+    checked: HashSet<String>,
+    /// Tokens of every `if` condition; `require*!` guards are added after.
+    conditions: Vec<proc_macro2::TokenStream>,
+}
+
+impl Facts {
+    fn params_in(&self, expr: &Expr) -> Vec<String> {
+        let tokens = expr.to_token_stream();
+        self.params
+            .iter()
+            .filter(|param| ast::mentions(&tokens, param))
+            .cloned()
+            .collect()
+    }
+
+    fn debit(&mut self, target: &Expr, amount: &Expr) {
+        let Some((_, field)) = self.bindings.account_field(target) else {
+            return;
+        };
+        for param in self.params_in(amount) {
+            self.debits.push(Debit {
+                param,
+                target: ast::source_text(target),
+                field: field.clone(),
+            });
+        }
+    }
+}
+
+impl Visit<'_> for Facts {
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        let is_transfer = ast::cpi_path(node).is_some_and(|path| {
+            path.ends_with("::transfer") || path.ends_with("::transfer_checked")
+        });
+        if is_transfer {
+            for arg in node.args.iter().skip(1) {
+                for param in self.params_in(arg) {
+                    self.transfers.push((param, node.span()));
+                }
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    /// `vault.balance = vault.balance - amount`
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        if let Expr::Binary(value) = ast::unparen(&node.right) {
+            if matches!(value.op, BinOp::Sub(_)) {
+                self.debit(&node.left, &value.right);
+            }
+        }
+        visit::visit_expr_assign(self, node);
+    }
+
+    /// `vault.balance -= amount`
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        if matches!(node.op, BinOp::SubAssign(_)) {
+            self.debit(&node.left, &node.right);
+        }
+        visit::visit_expr_binary(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+        if node.method == "checked_sub" {
+            for arg in &node.args {
+                self.checked.extend(self.params_in(arg));
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        self.conditions.push(node.cond.to_token_stream());
+        visit::visit_expr_if(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_withdraw_but_not_deposit() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::High);
+        assert_eq!(finding.span.line_start, 38);
+        assert_eq!(
+            finding.message,
+            "`withdraw` transfers caller-supplied `amount` and subtracts it from `vault.balance` \
+             without checking that `amount` is at most `vault.balance`"
+        );
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn withdraw(guard: &str, debit: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {{
+                    let vault = &mut ctx.accounts.vault;
+                    {guard}
+                    {debit}
+                    token::transfer(cpi_ctx, amount)?;
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn guards_and_checked_sub_count_as_checks() {
+        assert_eq!(withdraw("", "vault.balance -= amount;").len(), 1);
+        assert!(withdraw(
+            "require!(amount <= vault.balance, ErrorCode::InsufficientFunds);",
+            "vault.balance -= amount;"
+        )
+        .is_empty());
+        assert!(withdraw(
+            "if amount > vault.balance { return err!(ErrorCode::InsufficientFunds); }",
+            "vault.balance -= amount;"
+        )
+        .is_empty());
+        assert!(withdraw(
+            "",
+            "vault.balance = vault.balance.checked_sub(amount).ok_or(ErrorCode::Overflow)?;"
+        )
+        .is_empty());
+        // A guard on something else does not help.
+        assert_eq!(
+            withdraw(
+                "require!(amount > 0, ErrorCode::Zero);",
+                "vault.balance -= amount;"
+            )
+            .len(),
+            1
+        );
+    }
+}
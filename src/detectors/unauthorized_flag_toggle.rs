@@ -0,0 +1,187 @@
+//! Boolean state flags that any caller can flip.
+//!
+//! Flags such as `listed`, `paused` or `frozen` gate what other handlers
+//! allow, so flipping one is a state change like any other. A handler that
+//! sets `nft.listed = true` with nothing tying the caller to the account's
+//! owner lets anyone list, delist or freeze accounts that are not theirs.
+
+use std::collections::HashSet;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprAssign, Lit};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::missing_authority_guard::{guarded_by_require, unguarded};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "unauthorized_flag_toggle";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/unauthorized-flag-toggle",
+    name: ID,
+    title: "Boolean flag toggled without authorization",
+    category: Category::AccessControl,
+    severity: Severity::Medium,
+    cwe: 862,
+    description: "A handler writes a boolean field of a context account, such as `listed` or `paused`, without a `has_one`/`constraint` tying the account to its owner or a `require!` comparing the caller with it.",
+    example: "pub fn list_nft(ctx: Context<ListNFT>, price: u64) -> Result<()> {\n    ctx.accounts.nft_account.listed = true;\n    Ok(())\n}",
+    fixed_example: "#[account(mut, has_one = owner)]\npub nft_account: Account<'info, NFTMetadata>,\npub owner: Signer<'info>,",
+    references: &[
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+        "https://cwe.mitre.org/data/definitions/862.html",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        if guarded_by_require(handler) {
+            continue;
+        }
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            toggles: Vec::new(),
+        };
+        visitor.visit_item_fn(&handler.item);
+        let mut reported = HashSet::new();
+        for toggle in visitor.toggles {
+            if !unguarded(accounts, &toggle.account)
+                || !reported.insert((toggle.account.clone(), toggle.field.clone()))
+            {
+                continue;
+            }
+            let message = format!(
+                "`{}` sets the flag `{}` to `{}` without checking the caller against the \
+                 owner of `{}`",
+                handler.name, toggle.target, toggle.value, toggle.account
+            );
+            findings.push(
+                Finding::new(ID, Severity::Medium, program, toggle.span, message)
+                    .with_confidence(Confidence::Medium)
+                    .with_suggestion(format!(
+                        "add `has_one = owner` to `{}` with `owner: Signer<'info>`, or \
+                         `require_keys_eq!(ctx.accounts.signer.key(), {}.owner)` before the write",
+                        toggle.account, toggle.account
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// An assignment to a boolean field of a mutable context account.
+struct Toggle {
+    account: String,
+    field: String,
+    target: String,
+    /// The value as written, e.g. `true` or `!nft.listed`.
+    value: String,
+    span: proc_macro2::Span,
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    toggles: Vec<Toggle>,
+}
+
+impl Visitor<'_> {
+    /// Whether `account.field` is a `bool`, going by the state struct or,
+    /// when that is not in the file, by a `true`/`false` being written.
+    fn is_flag(&self, account: &str, field: &str, value: &Expr) -> bool {
+        match self.program.state_field_type(self.handler, account, field) {
+            Some(ty) => ty == "bool",
+            None => {
+                matches!(ast::unparen(value), Expr::Lit(lit) if matches!(lit.lit, Lit::Bool(_)))
+            }
+        }
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        visit::visit_expr_assign(self, node);
+        let Some((account, field)) = self.bindings.mut_account_field(&node.left) else {
+            return;
+        };
+        if self.is_flag(&account, &field, &node.right) {
+            self.toggles.push(Toggle {
+                account,
+                field,
+                target: ast::source_text(&*node.left),
+                value: ast::source_text(&*node.right),
+                span: node.span(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_listed_toggles_in_the_marketplace() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        let handlers: Vec<_> = findings
+            .iter()
+            .map(|f| f.message.split('`').nth(1).unwrap())
+            .collect();
+        // `mint_nft` creates the account, so its `listed = false` is fine.
+        assert_eq!(handlers, ["list_nft", "buy_nft", "transfer_nft"]);
+        assert_eq!(
+            findings[0].message,
+            "`list_nft` sets the flag `nft.listed` to `true` without checking the caller \
+             against the owner of `nft_account`"
+        );
+        assert_eq!(findings[0].span.line_start, 59);
+        assert!(findings
+            .iter()
+            .all(|f| f.severity == Severity::Medium && f.confidence == Confidence::Medium));
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    fn pause(constraint: &str, guard: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn pause(ctx: Context<Pause>) -> Result<()> {{
+                    {guard}
+                    ctx.accounts.config.paused = true;
+                    ctx.accounts.config.fee = 0;
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Pause<'info> {{
+                #[account({constraint})]
+                pub config: Account<'info, Config>,
+                pub admin: Signer<'info>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn owner_checks_clear_the_toggle() {
+        assert_eq!(pause("mut", "").len(), 1);
+        assert!(pause("mut, has_one = authority", "").is_empty());
+        assert!(pause(
+            "mut",
+            "require_keys_eq!(ctx.accounts.admin.key(), ctx.accounts.config.authority);"
+        )
+        .is_empty());
+    }
+}
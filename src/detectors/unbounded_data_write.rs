@@ -0,0 +1,214 @@
+//! Raw writes inside loops driven by caller-supplied vectors.
+//!
+//! `for (i, price) in prices.iter().enumerate()` writing at `i * 8` walks off
+//! the end of the account buffer as soon as the caller passes enough items.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprAssign, ExprCall, ExprForLoop, ExprIf, ExprUnsafe, Macro, UnOp};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "unbounded_data_write";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/unbounded-write",
+    name: ID,
+    title: "Unbounded write into account data",
+    category: Category::MemorySafety,
+    severity: Severity::High,
+    cwe: 787,
+    description: "A loop over a caller-supplied vector writes into account data without checking the vector against the data length.",
+    example: "for (i, byte) in payload.iter().enumerate() { data[i] = *byte; }",
+    fixed_example: "require!(payload.len() <= data.len(), ErrorCode::InvalidLength);\nfor (i, byte) in payload.iter().enumerate() { data[i] = *byte; }",
+    references: &[
+        "https://cwe.mitre.org/data/definitions/787.html",
+    ],
+};
+
+const RAW_WRITE_FNS: &[&str] = &[
+    "write",
+    "write_unaligned",
+    "write_bytes",
+    "copy",
+    "copy_nonoverlapping",
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            guards: Vec::new(),
+            loop_over: Vec::new(),
+            unsafe_depth: 0,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    /// Conditions of `require!`/`assert!`/`if` seen so far.
+    guards: Vec<proc_macro2::TokenStream>,
+    /// Caller-supplied vectors driving the enclosing loops.
+    loop_over: Vec<String>,
+    unsafe_depth: usize,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// The `Vec` argument a loop iterates over, via `v.iter()`, `&v` or
+    /// `0..v.len()`.
+    fn vec_argument(&self, expr: &Expr) -> Option<String> {
+        let expr = match ast::unparen(expr) {
+            Expr::Range(range) => range.end.as_deref()?,
+            expr => expr,
+        };
+        let (_, root) = ast::method_chain(expr);
+        let name = ast::ident(root)?;
+        (self.handler.arg_type(&name).as_deref() == Some("Vec")).then_some(name)
+    }
+
+    fn bounded(&self, vec: &str) -> bool {
+        self.guards
+            .iter()
+            .any(|tokens| ast::mentions(tokens, vec) && ast::mentions(tokens, "len"))
+    }
+
+    fn check_write(&mut self, node: &impl Spanned) {
+        if self.unsafe_depth == 0 {
+            return;
+        }
+        let Some(vec) = self.loop_over.last().cloned() else {
+            return;
+        };
+        if self.bounded(&vec) {
+            return;
+        }
+        self.findings.push(
+            Finding::new(
+                ID,
+                Severity::High,
+                self.program,
+                node.span(),
+                format!(
+                    "unsafe write in a loop over caller-supplied `{vec}` in `{}` is not \
+                     bounded by the account data length",
+                    self.handler.name
+                ),
+            )
+            .with_confidence(Confidence::Medium)
+            .with_suggestion(format!(
+                "validate `{vec}.len()` against the account size first, e.g. \
+                 `require!({vec}.len() * 8 <= data.len(), ErrorCode::InvalidLength)`"
+            )),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_macro(&mut self, node: &Macro) {
+        let name = node.path.segments.last().map(|s| s.ident.to_string());
+        if ast::is_require(node) || name.is_some_and(|name| name.starts_with("assert")) {
+            self.guards.push(node.tokens.clone());
+        }
+        visit::visit_macro(self, node);
+    }
+
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        self.guards
+            .push(quote::ToTokens::to_token_stream(&*node.cond));
+        visit::visit_expr_if(self, node);
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &ExprForLoop) {
+        match self.vec_argument(&node.expr) {
+            Some(vec) => {
+                self.loop_over.push(vec);
+                visit::visit_expr_for_loop(self, node);
+                self.loop_over.pop();
+            }
+            None => visit::visit_expr_for_loop(self, node),
+        }
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &ExprUnsafe) {
+        self.unsafe_depth += 1;
+        visit::visit_expr_unsafe(self, node);
+        self.unsafe_depth -= 1;
+    }
+
+    /// `*(ptr as *mut u64) = value`
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        if matches!(&*node.left, Expr::Unary(unary) if matches!(unary.op, UnOp::Deref(_))) {
+            self.check_write(node);
+        }
+        visit::visit_expr_assign(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        if ast::call_name(node).is_some_and(|name| RAW_WRITE_FNS.contains(&name.as_str())) {
+            self.check_write(node);
+        }
+        visit::visit_expr_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn handler(body: &str) -> String {
+        format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn run(ctx: Context<Run>, prices: Vec<u64>) -> Result<()> {{
+                    let mut data = ctx.accounts.state.try_borrow_mut_data()?;
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )
+    }
+
+    #[test]
+    fn flags_batch_update_prices() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert!(findings[0]
+            .message
+            .contains("caller-supplied `prices` in `batch_update_prices`"));
+        assert_eq!(findings[0].span.line_start, 155);
+    }
+
+    #[test]
+    fn ignores_reads_from_fixed_size_loops() {
+        let program = parse(&handler(
+            "for i in 0..4 { unsafe { let v = *(data.as_ptr().add(i * 8) as *const u64); } }",
+        ));
+        assert!(run(&program).is_empty());
+    }
+
+    #[test]
+    fn length_check_silences_the_write() {
+        let body = "for (i, price) in prices.iter().enumerate() {
+            unsafe { *(data.as_mut_ptr().add(i * 8) as *mut u64) = *price; }
+        }";
+        assert_eq!(run(&parse(&handler(body))).len(), 1);
+
+        let guarded = format!("require!(prices.len() * 8 <= data.len(), E::Len); {body}");
+        assert!(run(&parse(&handler(&guarded))).is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+//! `String` arguments stored in account state without a length check.
+//!
+//! An account's `space` is fixed when it is created. Storing a
+//! caller-supplied string of any length makes serialization fail once the
+//! string outgrows that budget, so handlers should reject long input up
+//! front with `require!(arg.len() <= MAX, ..)`.
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{ExprAssign, ExprIf, FnArg, Macro};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "unbounded_string_assignment";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/unbounded-string-assignment",
+    name: ID,
+    title: "Unbounded string stored in account",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 1284,
+    description: "A `String` instruction argument is written into account state without checking its length against the space the account was allocated.",
+    example: "nft.metadata_uri = metadata_uri; // no require!(metadata_uri.len() <= MAX_URI_LEN)",
+    fixed_example: "require!(metadata_uri.len() <= MAX_URI_LEN, ErrorCode::UriTooLong);\nnft.metadata_uri = metadata_uri;",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/derive.InitSpace.html",
+        "https://cwe.mitre.org/data/definitions/1284.html",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let strings = string_params(handler);
+        if strings.is_empty() {
+            continue;
+        }
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            unchecked: strings,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+fn string_params(handler: &Handler) -> Vec<String> {
+    handler
+        .item
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) => ast::pat_ident(&arg.pat),
+            FnArg::Receiver(_) => None,
+        })
+        .filter(|name| handler.arg_type(name).as_deref() == Some("String"))
+        .collect()
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    /// `String` parameters whose length has not been checked yet.
+    unchecked: Vec<String>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// Marks parameters whose `.len()` the condition `tokens` looks at.
+    fn checks(&mut self, tokens: &proc_macro2::TokenStream) {
+        if ast::mentions(tokens, "len") {
+            self.unchecked.retain(|param| !ast::mentions(tokens, param));
+        }
+    }
+
+    /// The `space = ...` of the account being written, for the suggestion.
+    fn space_of(&self, account: &str) -> Option<String> {
+        self.program
+            .accounts_for(self.handler)?
+            .field(account)?
+            .constraint("space")?
+            .value
+            .as_ref()
+            .map(ast::source_text)
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_macro(&mut self, node: &Macro) {
+        if ast::is_require(node) {
+            self.checks(&node.tokens);
+        }
+        visit::visit_macro(self, node);
+    }
+
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        self.checks(&node.cond.to_token_stream());
+        visit::visit_expr_if(self, node);
+    }
+
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        if let Some((account, _)) = self.bindings.mut_account_field(&node.left) {
+            let value = node.right.to_token_stream();
+            if let Some(param) = self.unchecked.iter().find(|p| ast::mentions(&value, p)) {
+                let target = ast::source_text(&*node.left);
+                let budget = match self.space_of(&account) {
+                    Some(space) => format!("the `space = {space}` budget of `{account}`"),
+                    None => format!("the space allocated for `{account}`"),
+                };
+                let message = format!(
+                    "`{}` stores `String` argument `{param}` into `{target}` without checking \
+                     its length",
+                    self.handler.name
+                );
+                self.findings.push(
+                    Finding::new(ID, Severity::Medium, self.program, node.span(), message)
+                        .with_confidence(Confidence::Medium)
+                        .with_suggestion(format!(
+                            "add `require!({param}.len() <= MAX_LEN, ErrorCode::InputTooLong)` \
+                             before the write, with `MAX_LEN` fitting {budget}"
+                        )),
+                );
+            }
+        }
+        visit::visit_expr_assign(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_metadata_uri_in_mint_nft() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(finding.span.line_start, 32);
+        assert_eq!(
+            finding.message,
+            "`mint_nft` stores `String` argument `metadata_uri` into `nft.metadata_uri` without \
+             checking its length"
+        );
+        assert!(finding
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("the `space = 8 + 200` budget of `nft_account`"));
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    fn set_name(check: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn set_name(ctx: Context<SetName>, name: String) -> Result<()> {{
+                    let profile = &mut ctx.accounts.profile;
+                    {check}
+                    profile.name = name;
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn length_checks_before_the_write_count() {
+        assert_eq!(set_name("").len(), 1);
+        assert!(set_name("require!(name.len() <= 32, ErrorCode::NameTooLong);").is_empty());
+        assert!(set_name("if name.len() > 32 { return err!(ErrorCode::NameTooLong); }").is_empty());
+        assert_eq!(set_name("require!(!name.is_empty());").len(), 1);
+    }
+}
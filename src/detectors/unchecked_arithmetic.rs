@@ -0,0 +1,313 @@
+//! Unchecked `+`, `-` and `*` whose result is stored in account state.
+//!
+//! Release builds of Solana programs wrap on overflow, so a raw
+//! `vault.balance - amount` silently underflows into a huge balance.
+//!
+//! Counters bumped by a constant get a suggestion that depends on what the
+//! counter is for: one that only tallies events, like `total_deposits`, can
+//! saturate, while one that hands out ids or nonces must fail rather than
+//! repeat a value.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprLit, Lit};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "unchecked_arithmetic";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/unchecked-arithmetic",
+    name: ID,
+    title: "Unchecked arithmetic on account state",
+    category: Category::Arithmetic,
+    severity: Severity::High,
+    cwe: 190,
+    description:
+        "Plain `+`, `-` or `*` on a value stored in account state wraps silently in release builds.",
+    example: "vault.balance = vault.balance + amount;",
+    fixed_example: "vault.balance = vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;",
+    references: &[
+        "https://doc.rust-lang.org/book/ch03-02-data-types.html#integer-overflow",
+        "https://doc.rust-lang.org/std/primitive.u64.html#method.checked_add",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            bindings: Bindings::of(&handler.item),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    bindings: Bindings,
+    findings: &'a mut Vec<Finding>,
+}
+
+/// Names of counters that only tally events, so saturating at the maximum
+/// loses nothing that matters: `total_deposits`, `trade_count`.
+const TALLY_PREFIXES: &[&str] = &["total_", "num_", "times_"];
+const TALLY_SUFFIXES: &[&str] = &["_count", "_total", "_counter"];
+
+/// What a counter incremented by a constant is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Counter {
+    /// Statistics; wrapping is the bug, saturating is fine.
+    Tally,
+    /// Ids, nonces, sequence numbers, anything else: a repeated value is
+    /// the bug, so the increment must fail instead.
+    Identifier,
+}
+
+impl Counter {
+    fn of(field: &str) -> Self {
+        if TALLY_PREFIXES
+            .iter()
+            .any(|prefix| field.starts_with(prefix))
+            || TALLY_SUFFIXES.iter().any(|suffix| field.ends_with(suffix))
+        {
+            Counter::Tally
+        } else {
+            Counter::Identifier
+        }
+    }
+}
+
+impl Visitor<'_> {
+    /// Reports `node`, the arithmetic stored into `target`; `operand` is the
+    /// right-hand side the fix passes to the checked method.
+    fn report(&mut self, target: &Expr, op: &BinOp, node: &ExprBinary, operand: &Expr) {
+        let compound = compound_op(&node.op).is_some();
+        let receiver = if compound { target } else { &*node.left };
+        // A read-modify-write of the same field is the classic overflow; other
+        // values stored into state may already be bounded.
+        let read_modify_write = compound || ast::normalized(&*node.left) == ast::normalized(target);
+        let confidence = if read_modify_write {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        };
+        let counter = self
+            .counter(target, op, operand)
+            .filter(|_| read_modify_write);
+        let (method, tail) = match counter {
+            Some(Counter::Tally) => ("saturating_add", ""),
+            _ => (
+                checked_method(op).unwrap_or_default(),
+                ".ok_or(ErrorCode::Overflow)?",
+            ),
+        };
+        let fixed = format!(
+            "{}.{method}({}){tail}",
+            ast::receiver_text(receiver),
+            ast::source_text(operand),
+        );
+        let target_text = ast::source_text(target);
+        // The suggestion always shows the whole assignment, and the fix
+        // replaces either it or just its right-hand side.
+        let assignment = format!("{target_text} = {fixed}");
+        let replacement = if compound { assignment.clone() } else { fixed };
+        let field = self
+            .bindings
+            .mut_account_field(target)
+            .map(|(_, field)| field)
+            .unwrap_or_default();
+        let suggestion = match counter {
+            Some(Counter::Tally) => format!(
+                "`{field}` only counts events, so let it saturate instead of wrapping: \
+                 `{assignment}`"
+            ),
+            Some(Counter::Identifier) => format!(
+                "`{field}` is a counter whose values must never repeat, so fail on overflow \
+                 instead of wrapping: `{assignment}`"
+            ),
+            None => assignment,
+        };
+        let message = format!(
+            "unchecked `{}` stored into `{target_text}` in `{}` can overflow",
+            op_symbol(op),
+            self.handler.name
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::High, self.program, node.span(), message)
+                .with_confidence(confidence)
+                .with_suggestion(suggestion)
+                .with_fix(self.program, node.span(), replacement),
+        );
+    }
+
+    /// The kind of counter `target` is, if `op` and `step` bump it by a
+    /// constant, as in `vault.total_deposits += 1`.
+    fn counter(&self, target: &Expr, op: &BinOp, step: &Expr) -> Option<Counter> {
+        let is_constant = matches!(
+            ast::unparen(step),
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(_),
+                ..
+            })
+        );
+        if !matches!(op, BinOp::Add(_)) || !is_constant {
+            return None;
+        }
+        let (_, field) = self.bindings.mut_account_field(target)?;
+        Some(Counter::of(&field))
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    /// `vault.balance = vault.balance + amount`
+    fn visit_expr_assign(&mut self, node: &ExprAssign) {
+        if self.bindings.mut_account_field(&node.left).is_some() {
+            if let Expr::Binary(binary) = ast::unparen(&node.right) {
+                if checked_method(&binary.op).is_some() {
+                    self.report(&node.left, &binary.op, binary, &binary.right);
+                }
+            }
+        }
+        visit::visit_expr_assign(self, node);
+    }
+
+    /// `vault.balance += amount`
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        if let Some(op) = compound_op(&node.op) {
+            if self.bindings.mut_account_field(&node.left).is_some() {
+                self.report(&node.left, &op, node, &node.right);
+            }
+        }
+        visit::visit_expr_binary(self, node);
+    }
+}
+
+fn checked_method(op: &BinOp) -> Option<&'static str> {
+    match op {
+        BinOp::Add(_) => Some("checked_add"),
+        BinOp::Sub(_) => Some("checked_sub"),
+        BinOp::Mul(_) => Some("checked_mul"),
+        _ => None,
+    }
+}
+
+/// The plain operator behind a compound assignment like `+=`.
+fn compound_op(op: &BinOp) -> Option<BinOp> {
+    match op {
+        BinOp::AddAssign(t) => Some(BinOp::Add(syn::Token![+](t.spans[0]))),
+        BinOp::SubAssign(t) => Some(BinOp::Sub(syn::Token![-](t.spans[0]))),
+        BinOp::MulAssign(t) => Some(BinOp::Mul(syn::Token![*](t.spans[0]))),
+        _ => None,
+    }
+}
+
+fn op_symbol(op: &BinOp) -> &'static str {
+    match op {
+        BinOp::Add(_) => "+",
+        BinOp::Sub(_) => "-",
+        _ => "*",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_vault_balance_updates() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let lines: Vec<_> = findings.iter().map(|f| f.span.line_start).collect();
+        assert_eq!(lines, [27, 73, 74]);
+        assert!(findings.iter().all(|f| f.severity == Severity::High));
+        assert!(findings.iter().all(|f| f.confidence == Confidence::High));
+        assert_eq!(
+            findings[1].suggestion.as_deref(),
+            Some("vault.balance = vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?")
+        );
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("vault.balance = vault.balance.checked_sub(amount).ok_or(ErrorCode::Overflow)?")
+        );
+    }
+
+    #[test]
+    fn flags_nft_token_id_increment() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("`nft.token_id` in `mint_nft`"));
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some(
+                "`token_id` is a counter whose values must never repeat, so fail on overflow \
+                 instead of wrapping: \
+                 `nft.token_id = nft.token_id.checked_add(1).ok_or(ErrorCode::Overflow)?`"
+            )
+        );
+    }
+
+    #[test]
+    fn tally_counters_may_saturate() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let deposits = &findings[2];
+        assert!(deposits
+            .message
+            .contains("`vault.total_deposits` in `deposit`"));
+        assert_eq!(
+            deposits.suggestion.as_deref(),
+            Some(
+                "`total_deposits` only counts events, so let it saturate instead of wrapping: \
+                 `vault.total_deposits = vault.total_deposits.saturating_add(1)`"
+            )
+        );
+        assert_eq!(
+            deposits.fix.as_ref().unwrap().replacement,
+            "vault.total_deposits.saturating_add(1)"
+        );
+
+        assert_eq!(Counter::of("trade_count"), Counter::Tally);
+        assert_eq!(Counter::of("nonce"), Counter::Identifier);
+    }
+
+    #[test]
+    fn ignores_local_index_math_unless_stored() {
+        let source = |body: &str| {
+            format!(
+                r#"
+                #[program]
+                pub mod demo {{
+                    pub fn run(ctx: Context<Run>, count: u64) -> Result<()> {{
+                        let state = &mut ctx.accounts.state;
+                        for i in 0..count {{
+                            let offset = i * 8;
+                            {body}
+                        }}
+                        Ok(())
+                    }}
+                }}
+                "#
+            )
+        };
+        assert!(run(&parse(&source("msg!(\"{}\", offset);"))).is_empty());
+
+        let findings = run(&parse(&source("state.offset = i * 8;")));
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.starts_with("unchecked `*`"));
+        assert_eq!(findings[0].confidence, Confidence::Medium);
+
+        let findings = run(&parse(&source("state.total += count;")));
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("state.total = state.total.checked_add(count).ok_or(ErrorCode::Overflow)?")
+        );
+    }
+}
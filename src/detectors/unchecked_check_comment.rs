@@ -0,0 +1,197 @@
+//! `/// CHECK:` comments that document validation nobody performs.
+//!
+//! Anchor requires a `/// CHECK:` doc on every `AccountInfo` and
+//! `UncheckedAccount` field so the author explains why it is safe. A comment
+//! with no matching constraint or key/owner comparison is just a note that
+//! the account is unchecked.
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+use syn::{BinOp, ExprBinary, ExprIf, ItemFn};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountKind, AccountsStruct, ParsedProgram};
+
+pub const ID: &str = "unchecked_check_comment";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/unchecked-check-comment",
+    name: ID,
+    title: "`CHECK:` comment without validation",
+    category: Category::Validation,
+    severity: Severity::Medium,
+    cwe: 20,
+    description: "An unchecked account is documented with `/// CHECK:` but neither its constraints nor its handlers validate it.",
+    example: "/// CHECK: safe\npub user_account: UncheckedAccount<'info>,",
+    fixed_example: "/// CHECK: only used as the lamport destination; its key is compared to `vault.owner` by `has_one`.\npub user_account: UncheckedAccount<'info>,",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/unchecked_account/struct.UncheckedAccount.html",
+    ],
+};
+
+/// Field constraints that validate the account they are attached to.
+const VALIDATING_CONSTRAINTS: &[&str] = &["address", "owner", "constraint", "seeds"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for accounts in &program.accounts {
+        for field in &accounts.fields {
+            let unchecked = matches!(
+                field.kind,
+                AccountKind::AccountInfo | AccountKind::UncheckedAccount
+            );
+            let Some(check) = check_comment(field) else {
+                continue;
+            };
+            if !unchecked || constrained(accounts, field) {
+                continue;
+            }
+            let mut handlers = program.handlers_using(&accounts.name).peekable();
+            if handlers.peek().is_none() {
+                continue;
+            }
+            if handlers.any(|handler| validates(&handler.item, &field.name)) {
+                continue;
+            }
+            let message = format!(
+                "`{}::{}` is documented as `CHECK: {check}` but is never validated",
+                accounts.name, field.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::Medium, program, field.span(), message)
+                    .with_confidence(Confidence::Medium)
+                    .with_suggestion(format!(
+                        "add an `address`, `owner` or `constraint` check for `{}`, or compare \
+                         its key in the handler",
+                        field.name
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// The text after `CHECK:` in the field's doc comment.
+fn check_comment(field: &AccountField) -> Option<String> {
+    field.docs().iter().find_map(|line| {
+        line.strip_prefix("CHECK:")
+            .map(|rest| rest.trim().to_string())
+    })
+}
+
+/// Whether the Accounts struct itself validates `field`, either on the field
+/// or through another field's `has_one` / `constraint`.
+fn constrained(accounts: &AccountsStruct, field: &AccountField) -> bool {
+    if VALIDATING_CONSTRAINTS
+        .iter()
+        .any(|key| field.has_constraint(key))
+    {
+        return true;
+    }
+    accounts.fields.iter().any(|other| {
+        other
+            .constraint_values("has_one")
+            .chain(other.constraint_values("constraint"))
+            .any(|tokens| ast::mentions(tokens, &field.name))
+    })
+}
+
+/// Whether `handler` compares the account against something, requires
+/// something about it, or hands it to a CPI as the signing authority (the
+/// callee then checks the signature).
+fn validates(handler: &ItemFn, name: &str) -> bool {
+    let bindings = Bindings::of(handler);
+    let signs_cpi = ast::cpi_accounts(handler).iter().any(|cpi| {
+        cpi.field("authority")
+            .and_then(|expr| bindings.account(expr))
+            .is_some_and(|account| account == name)
+    });
+    if signs_cpi {
+        return true;
+    }
+
+    let mut checks = Checks::default();
+    checks.visit_item_fn(handler);
+    checks.0.extend(ast::require_guards(handler));
+    checks.0.iter().any(|tokens| ast::mentions(tokens, name))
+}
+
+/// Token streams of every comparison and `if` condition.
+#[derive(Default)]
+struct Checks(Vec<TokenStream>);
+
+impl Visit<'_> for Checks {
+    fn visit_expr_binary(&mut self, node: &ExprBinary) {
+        if matches!(node.op, BinOp::Eq(_) | BinOp::Ne(_)) {
+            self.0.push(node.to_token_stream());
+        }
+        visit::visit_expr_binary(self, node);
+    }
+
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        self.0.push(node.cond.to_token_stream());
+        visit::visit_expr_if(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn fields(findings: &[Finding]) -> Vec<&str> {
+        findings
+            .iter()
+            .map(|f| f.message.split('`').nth(1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn flags_unvalidated_fixture_accounts() {
+        let vault = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(fields(&vault), ["Query::user_account"]);
+        assert_eq!(
+            vault[0].message,
+            "`Query::user_account` is documented as `CHECK: This account is not validated` \
+             but is never validated"
+        );
+        assert_eq!(vault[0].severity, Severity::Medium);
+
+        let nft = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(fields(&nft), ["BuyNFT::seller"]);
+    }
+
+    #[test]
+    fn key_comparison_or_constraint_counts_as_validation() {
+        let source = |attr: &str, check: &str| {
+            format!(
+                r#"
+                #[program]
+                pub mod demo {{
+                    pub fn pay(ctx: Context<Pay>) -> Result<()> {{
+                        {check}
+                        Ok(())
+                    }}
+                }}
+                #[derive(Accounts)]
+                pub struct Pay<'info> {{
+                    pub listing: Account<'info, Listing>,
+                    /// CHECK: must be the listing's seller
+                    {attr}
+                    pub seller: AccountInfo<'info>,
+                }}
+                "#
+            )
+        };
+        assert_eq!(run(&parse(&source("", ""))).len(), 1);
+        let compare = "require_keys_eq!(ctx.accounts.seller.key(), ctx.accounts.listing.seller);";
+        assert!(run(&parse(&source("", compare))).is_empty());
+        let compare =
+            "if ctx.accounts.seller.key() != ctx.accounts.listing.seller { return err!(E::Bad); }";
+        assert!(run(&parse(&source("", compare))).is_empty());
+        assert!(run(&parse(&source("#[account(address = listing.seller)]", ""))).is_empty());
+    }
+}
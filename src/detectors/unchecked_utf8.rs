@@ -0,0 +1,151 @@
+//! Strings built from bytes without checking that they are UTF-8.
+//!
+//! `String::from_utf8_unchecked` and `str::from_utf8_unchecked` promise the
+//! compiler that the bytes are valid UTF-8. Account data is whatever the
+//! last writer put there, so breaking the promise is undefined behaviour,
+//! not just a garbled string: later `str` operations may read past a
+//! character boundary. The checked versions cost one pass over the bytes.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "unchecked_utf8";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/unchecked-utf8",
+    name: ID,
+    title: "UTF-8 conversion without validation",
+    category: Category::MemorySafety,
+    severity: Severity::High,
+    cwe: 172,
+    description: "Bytes are turned into a `String` or `&str` with `from_utf8_unchecked`, which is undefined behaviour when they are not valid UTF-8, as account data supplied by callers need not be.",
+    example: "let metadata = unsafe { String::from_utf8_unchecked(slice.to_vec()) };",
+    fixed_example: "let metadata = String::from_utf8(slice.to_vec()).map_err(|_| ErrorCode::InvalidMetadata)?;",
+    references: &[
+        "https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf8_unchecked",
+        "https://cwe.mitre.org/data/definitions/172.html",
+    ],
+};
+
+/// Unchecked constructors and the checked function to use instead.
+const UNCHECKED: &[(&str, &str)] = &[
+    ("from_utf8_unchecked", "from_utf8"),
+    ("from_utf8_unchecked_mut", "from_utf8_mut"),
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        visit::visit_expr_call(self, node);
+        let Expr::Path(func) = &*node.func else {
+            return;
+        };
+        let segments: Vec<_> = func
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        let Some((unchecked, checked)) = segments
+            .last()
+            .and_then(|last| UNCHECKED.iter().find(|(unchecked, _)| unchecked == last))
+        else {
+            return;
+        };
+        // `String::from_utf8_unchecked` or `std::str::from_utf8_unchecked`.
+        let owner = segments
+            .len()
+            .checked_sub(2)
+            .map_or("str", |at| segments[at].as_str());
+        let call = format!("{owner}::{unchecked}");
+        let bytes = node.args.first().map(ast::source_text).unwrap_or_default();
+        let message = format!(
+            "`{}` builds text from `{bytes}` with `{call}`, which is undefined behaviour if \
+             the bytes are not valid UTF-8",
+            self.handler.name
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::High, self.program, node.span(), message)
+                .with_confidence(Confidence::High)
+                .with_suggestion(format!(
+                    "use `{owner}::{checked}({bytes})` and return an error when it fails, e.g. \
+                     `.map_err(|_| ErrorCode::InvalidUtf8)?`"
+                )),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_metadata_string() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "`get_nft_metadata` builds text from `slice.to_vec()` with \
+             `String::from_utf8_unchecked`, which is undefined behaviour if the bytes are not \
+             valid UTF-8"
+        );
+        assert_eq!(findings[0].span.line_start, 100);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert!(findings[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .starts_with("use `String::from_utf8(slice.to_vec())`"));
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    #[test]
+    fn flags_str_conversions_and_ignores_checked_ones() {
+        let handler = |body: &str| {
+            run(&parse(&format!(
+                r#"
+                #[program]
+                pub mod demo {{
+                    pub fn name(ctx: Context<Name>) -> Result<()> {{
+                        let data = ctx.accounts.profile.try_borrow_data()?;
+                        {body}
+                        Ok(())
+                    }}
+                }}
+                "#
+            )))
+        };
+        let findings =
+            handler("let name = unsafe { std::str::from_utf8_unchecked(&data[8..40]) };");
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0]
+            .message
+            .contains("with `str::from_utf8_unchecked`"));
+        assert!(handler("let name = std::str::from_utf8(&data[8..40]).unwrap();").is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+//! CPIs to a program account the caller chooses.
+//!
+//! `Program<'info, Token>` checks that the account passed is the token
+//! program. An `AccountInfo` or `UncheckedAccount` checks nothing, so a
+//! `CpiContext` built from one invokes whatever program the caller
+//! supplies, with this program's signers and accounts.
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, AccountsStruct, Handler, ParsedProgram};
+
+pub const ID: &str = "unconstrained_cpi_program";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/unconstrained-cpi-program",
+    name: ID,
+    title: "CPI to an unchecked program account",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 829,
+    description: "A `CpiContext` is built from an `AccountInfo` or `UncheckedAccount` program field rather than a typed `Program<'info, T>`, so the caller picks the program that is invoked.",
+    example: "/// CHECK: token program\npub token_program: AccountInfo<'info>,",
+    fixed_example: "pub token_program: Program<'info, Token>,",
+    references: &[
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/5-arbitrary-cpi",
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/program/struct.Program.html",
+    ],
+};
+
+/// Constructors whose first argument is the program to invoke.
+const CONSTRUCTORS: &[&str] = &["new", "new_with_signer"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let mut visitor = Visitor {
+            program,
+            handler,
+            accounts,
+            bindings: Bindings::of(&handler.item),
+            guards: ast::require_guards(&handler.item),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// `node` as `CpiContext::new(program, ..)`, returning `program`.
+fn cpi_context_program(node: &ExprCall) -> Option<&Expr> {
+    let Expr::Path(path) = &*node.func else {
+        return None;
+    };
+    let segments: Vec<_> = path.path.segments.iter().map(|s| &s.ident).collect();
+    match segments.as_slice() {
+        [.., ty, constructor]
+            if *ty == "CpiContext" && CONSTRUCTORS.iter().any(|name| *constructor == name) =>
+        {
+            node.args.first()
+        }
+        _ => None,
+    }
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    accounts: &'a AccountsStruct,
+    bindings: Bindings,
+    guards: Vec<proc_macro2::TokenStream>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    /// Whether `account` is pinned by `address = ...`, a `constraint` or a
+    /// `require*!` guard naming it.
+    fn is_checked(&self, account: &str) -> bool {
+        let field = self.accounts.field(account);
+        field.is_some_and(|field| {
+            field.has_constraint("address")
+                || field
+                    .constraint_values("constraint")
+                    .any(|value| ast::mentions(value, account))
+        }) || self
+            .guards
+            .iter()
+            .any(|guard| ast::mentions(guard, account))
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        if let Some(account) = cpi_context_program(node).and_then(|arg| self.bindings.account(arg))
+        {
+            let untyped = self.accounts.field(&account).is_some_and(|field| {
+                matches!(
+                    field.kind,
+                    AccountKind::AccountInfo | AccountKind::UncheckedAccount
+                )
+            });
+            if untyped && !self.is_checked(&account) {
+                let message = format!(
+                    "`{}` invokes the program in unchecked `{account}`, so the caller chooses \
+                     which program receives the CPI",
+                    self.handler.name
+                );
+                self.findings.push(
+                    Finding::new(ID, Severity::High, self.program, node.span(), message)
+                        .with_confidence(Confidence::High)
+                        .with_suggestion(format!(
+                            "declare `{account}` as `Program<'info, T>` so Anchor checks the \
+                             program id"
+                        )),
+                );
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn fixtures_use_typed_programs() {
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn transfer(token_program: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn pay(ctx: Context<Pay>, amount: u64) -> Result<()> {{
+                    let cpi_program = ctx.accounts.token_program.to_account_info();
+                    let cpi_accounts = Transfer {{
+                        from: ctx.accounts.from.to_account_info(),
+                        to: ctx.accounts.to.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    }};
+                    token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Pay<'info> {{
+                {token_program}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn flags_only_unchecked_program_accounts() {
+        let findings = transfer("/// CHECK: trusted\npub token_program: AccountInfo<'info>,");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(
+            findings[0].message,
+            "`pay` invokes the program in unchecked `token_program`, so the caller chooses which \
+             program receives the CPI"
+        );
+
+        assert!(transfer("pub token_program: Program<'info, Token>,").is_empty());
+        assert!(transfer(
+            "#[account(address = token::ID)]\npub token_program: UncheckedAccount<'info>,"
+        )
+        .is_empty());
+    }
+}
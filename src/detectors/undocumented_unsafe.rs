@@ -0,0 +1,266 @@
+//! `unsafe` blocks doing raw memory access with nothing to justify it.
+//!
+//! An `unsafe` block is a promise that the code inside upholds invariants
+//! the compiler cannot check. The convention is to write that promise down
+//! in a `// SAFETY:` comment, and for account data the invariant is nearly
+//! always a length: a bounds check before the block is what makes the
+//! promise true. A block with neither is a guess.
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprIf, ExprUnsafe, ImplItemFn, ItemFn, Macro, UnOp};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::ParsedProgram;
+
+pub const ID: &str = "undocumented_unsafe";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/undocumented-unsafe",
+    name: ID,
+    title: "Unjustified `unsafe` block",
+    category: Category::MemorySafety,
+    severity: Severity::Medium,
+    cwe: 119,
+    description: "An `unsafe` block dereferences or writes through raw pointers with no `// SAFETY:` comment and no `require!` or bounds check before it.",
+    example: "unsafe {\n    let balance = *(data.as_ptr() as *const u64);\n}",
+    fixed_example: "require!(data.len() >= 8, ErrorCode::AccountTooSmall);\n// SAFETY: the length was checked above and the field is read unaligned.\nlet balance = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const u64) };",
+    references: &[
+        "https://doc.rust-lang.org/nomicon/safe-unsafe-meaning.html",
+        "https://rust-lang.github.io/rust-clippy/master/index.html#undocumented_unsafe_blocks",
+    ],
+};
+
+/// `std::ptr` / `std::slice` functions operating on raw pointers.
+const RAW_POINTER_FNS: &[&str] = &[
+    "read",
+    "read_unaligned",
+    "write",
+    "write_unaligned",
+    "write_bytes",
+    "copy",
+    "copy_nonoverlapping",
+    "from_raw_parts",
+    "from_raw_parts_mut",
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut visitor = Visitor {
+        program,
+        checked: false,
+        findings: Vec::new(),
+    };
+    visitor.visit_file(&program.file);
+    visitor.findings
+}
+
+/// Whether a comment starting with `SAFETY:` sits inside the block, on its
+/// first line, or in the run of comment lines right above it.
+fn has_safety_comment(program: &ParsedProgram, block: &ExprUnsafe) -> bool {
+    let (start, end) = (block.span().start().line, block.span().end().line);
+    let is_safety = |text: &str| text.trim_start().starts_with("SAFETY:");
+    if program
+        .comments
+        .iter()
+        .any(|c| (start..=end).contains(&c.line) && is_safety(&c.text))
+    {
+        return true;
+    }
+    let mut line = start;
+    while let Some(comment) = line.checked_sub(1).and_then(|above| {
+        program
+            .comments
+            .iter()
+            .find(|c| c.line == above && !c.trailing)
+    }) {
+        if is_safety(&comment.text) {
+            return true;
+        }
+        line = comment.line;
+    }
+    false
+}
+
+/// The first raw memory access inside `block`: a dereference or a call to
+/// a raw pointer function.
+fn raw_access(block: &ExprUnsafe) -> Option<&Expr> {
+    #[derive(Default)]
+    struct Finder<'a>(Option<&'a Expr>);
+
+    impl<'a> Visit<'a> for Finder<'a> {
+        fn visit_expr(&mut self, node: &'a Expr) {
+            if self.0.is_some() {
+                return;
+            }
+            let raw = match node {
+                Expr::Unary(unary) => matches!(unary.op, UnOp::Deref(_)),
+                Expr::Call(call) => ast::call_name(call).is_some_and(|name| {
+                    RAW_POINTER_FNS.contains(&name.as_str()) && qualified(call)
+                }),
+                _ => false,
+            };
+            if raw {
+                self.0 = Some(node);
+            } else {
+                visit::visit_expr(self, node);
+            }
+        }
+    }
+
+    let mut finder = Finder::default();
+    finder.visit_block(&block.block);
+    finder.0
+}
+
+/// Whether `call` is `ptr::f(..)` or `slice::f(..)` rather than a local
+/// function that happens to share a name.
+fn qualified(call: &ExprCall) -> bool {
+    let Expr::Path(path) = &*call.func else {
+        return false;
+    };
+    let segments = &path.path.segments;
+    segments.len() >= 2
+        && matches!(
+            segments[segments.len() - 2].ident.to_string().as_str(),
+            "ptr" | "slice"
+        )
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    /// Whether the current function has a `require!` or a length check
+    /// before the point being visited.
+    checked: bool,
+    findings: Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    fn in_fn(&mut self, visit: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.checked, false);
+        visit(self);
+        self.checked = outer;
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_item_fn(&mut self, node: &ItemFn) {
+        self.in_fn(|v| visit::visit_item_fn(v, node));
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &ImplItemFn) {
+        self.in_fn(|v| visit::visit_impl_item_fn(v, node));
+    }
+
+    fn visit_macro(&mut self, node: &Macro) {
+        let asserts_len = node.path.segments.last().is_some_and(|s| {
+            s.ident.to_string().starts_with("assert") && ast::mentions(&node.tokens, "len")
+        });
+        if ast::is_require(node) || asserts_len {
+            self.checked = true;
+        }
+        visit::visit_macro(self, node);
+    }
+
+    /// `if offset + 8 > data.len() { return err!(..) }`
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        self.visit_expr(&node.cond);
+        if ast::mentions(&node.cond.to_token_stream(), "len") {
+            self.checked = true;
+        }
+        self.visit_block(&node.then_branch);
+        if let Some((_, else_branch)) = &node.else_branch {
+            self.visit_expr(else_branch);
+        }
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &ExprUnsafe) {
+        // Nested blocks are covered by the outermost one.
+        if self.checked || has_safety_comment(self.program, node) {
+            return;
+        }
+        let Some(access) = raw_access(node) else {
+            return;
+        };
+        let handler = self
+            .program
+            .handler_at(node.span().start().line)
+            .map_or_else(String::new, |handler| format!(" in `{}`", handler.name));
+        let message = format!(
+            "`unsafe` block{handler} performs raw memory access (`{}`) with no `// SAFETY:` \
+             comment and no bounds check before it",
+            ast::source_text(access)
+        );
+        self.findings.push(
+            Finding::new(
+                ID,
+                Severity::Medium,
+                self.program,
+                node.unsafe_token.span(),
+                message,
+            )
+            .with_confidence(Confidence::High)
+            .with_suggestion(
+                "check the buffer length with `require!` before the block and state why the \
+                 access is sound in a `// SAFETY:` comment, or read the account through its \
+                 typed `Account<'info, T>` instead",
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    fn handlers(program: &ParsedProgram) -> Vec<String> {
+        run(program)
+            .iter()
+            .map(|f| f.message.split('`').nth(3).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn flags_every_fixture_unsafe_block() {
+        assert_eq!(
+            handlers(&parse_fixture("insecure_nft_marketplace.rs")),
+            ["get_nft_metadata", "batch_update_prices"]
+        );
+        let vault = parse_fixture("vulnerable_vault.rs");
+        assert_eq!(handlers(&vault), ["get_user_balance", "emergency_drain"]);
+        let findings = run(&vault);
+        assert_eq!(findings[1].span.line_start, 98);
+        assert_eq!(
+            findings[1].message,
+            "`unsafe` block in `emergency_drain` performs raw memory access \
+             (`std::ptr::write_bytes(ptr, 0, 8)`) with no `// SAFETY:` comment and no bounds \
+             check before it"
+        );
+    }
+
+    fn block(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            "fn read(data: &[u8]) -> u64 {{\n{body}\n}}"
+        )))
+    }
+
+    #[test]
+    fn safety_comments_and_bounds_checks_justify_the_block() {
+        let unsafe_read = "unsafe { *(data.as_ptr() as *const u64) }";
+        assert_eq!(block(unsafe_read).len(), 1);
+        assert!(block(&format!("// SAFETY: callers pass 8 bytes.\n{unsafe_read}")).is_empty());
+        assert!(block(&format!(
+            "// SAFETY: callers pass at least\n// eight bytes.\n{unsafe_read}"
+        ))
+        .is_empty());
+        assert!(block(&format!(
+            "require!(data.len() >= 8, ErrorCode::TooSmall);\n{unsafe_read}"
+        ))
+        .is_empty());
+        assert!(block(&format!("if data.len() < 8 {{ return 0; }}\n{unsafe_read}")).is_empty());
+        assert!(block("unsafe { core::hint::spin_loop() }").is_empty());
+    }
+}
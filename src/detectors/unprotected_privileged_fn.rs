@@ -0,0 +1,177 @@
+//! Emergency and admin handlers with no authorization at all.
+//!
+//! Handlers named `emergency_*`, `*_drain`, `admin_*`, `migrate` or
+//! `upgrade` exist to do what ordinary users must not. When such a handler
+//! moves funds or rewrites state while its context has no `Signer` and its
+//! body never compares a key against the stored authority, anyone can call
+//! it.
+
+use quote::ToTokens;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::missing_authority_guard::OWNER_FIELDS;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, Handler, ParsedProgram};
+
+pub const ID: &str = "unprotected_privileged_fn";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/unprotected-privileged-fn",
+    name: ID,
+    title: "Privileged handler without authorization",
+    category: Category::AccessControl,
+    severity: Severity::Critical,
+    cwe: 306,
+    description: "An emergency, admin, migration or upgrade handler moves funds or mutates state without any `Signer` account or key comparison against the stored authority.",
+    example: "pub fn emergency_drain(ctx: Context<Emergency>) -> Result<()> { token::transfer(cpi_ctx, vault.balance) } // Emergency has no Signer",
+    fixed_example: "#[derive(Accounts)]\npub struct Emergency<'info> {\n    #[account(mut, has_one = authority)]\n    pub vault: Account<'info, Vault>,\n    pub authority: Signer<'info>,\n}",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/signer/struct.Signer.html",
+        "https://cwe.mitre.org/data/definitions/306.html",
+    ],
+};
+
+/// Name fragments of handlers meant for privileged callers only.
+const PRIVILEGED: &[&str] = &["emergency", "drain", "admin", "migrate", "upgrade"];
+
+/// Calls that write account data or lamports directly.
+const RAW_WRITES: &[&str] = &["try_borrow_mut_data", "try_borrow_mut_lamports"];
+
+/// Whether `handler` is named like one meant for privileged callers only.
+pub(crate) fn is_privileged(handler: &Handler) -> bool {
+    let name = handler.name.to_lowercase();
+    PRIVILEGED.iter().any(|fragment| name.contains(fragment))
+}
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        if !is_privileged(handler) {
+            continue;
+        }
+        let effects = effects(handler);
+        if effects.is_empty() || authorized(program, handler) {
+            continue;
+        }
+        let message = format!(
+            "privileged handler `{}` {} without a `Signer` or a check against the stored authority",
+            handler.name,
+            effects.join(" and ")
+        );
+        let impact = format!(
+            "Any wallet can call `{}`, which {}, because nothing checks who signed the \
+             transaction",
+            handler.name,
+            effects.join(" and ")
+        );
+        findings.push(
+            Finding::new(
+                ID,
+                Severity::Critical,
+                program,
+                handler.item.sig.ident.span(),
+                message,
+            )
+            .with_confidence(Confidence::High)
+            .with_impact(impact)
+            .with_suggestion(format!(
+                "add `pub authority: Signer<'info>` to `{}` and `has_one = authority` on the \
+                 state account so only the stored authority can call it",
+                handler.context.as_deref().unwrap_or("the accounts struct")
+            )),
+        );
+    }
+    findings
+}
+
+/// What `handler` does that needs authority, phrased for the message, e.g.
+/// "moves funds via `token::transfer`".
+fn effects(handler: &Handler) -> Vec<String> {
+    let mut effects = Vec::new();
+    let calls = ast::cpi_calls(&handler.item);
+    if !calls.is_empty() {
+        let calls: Vec<_> = calls.iter().map(|call| format!("`{call}`")).collect();
+        effects.push(format!("moves funds via {}", calls.join(", ")));
+    }
+    let writes = ast::field_writes(&handler.item, &Bindings::of(&handler.item));
+    if let Some(write) = writes.first() {
+        effects.push(format!("writes `{}`", write.target));
+    }
+    let body = handler.item.block.to_token_stream();
+    if RAW_WRITES.iter().any(|call| ast::mentions(&body, call)) {
+        effects.push("rewrites raw account data".to_string());
+    }
+    effects
+}
+
+/// Whether any account must sign or the body compares against a stored
+/// authority with `require_keys_eq!` and friends.
+fn authorized(program: &ParsedProgram, handler: &Handler) -> bool {
+    let signer = program.accounts_for(handler).is_some_and(|accounts| {
+        accounts
+            .fields
+            .iter()
+            .any(|field| field.kind == AccountKind::Signer || field.has_constraint("signer"))
+    });
+    signer
+        || ast::require_guards(&handler.item)
+            .iter()
+            .any(|guard| OWNER_FIELDS.iter().any(|field| ast::mentions(guard, field)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_emergency_drain() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::Critical);
+        assert_eq!(finding.confidence, Confidence::High);
+        assert_eq!(finding.span.line_start, 90);
+        assert_eq!(
+            finding.message,
+            "privileged handler `emergency_drain` moves funds via `token::transfer` and rewrites \
+             raw account data without a `Signer` or a check against the stored authority"
+        );
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn admin(signer: &str, body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn admin_set_fee(ctx: Context<Admin>, fee: u64) -> Result<()> {{
+                    let config = &mut ctx.accounts.config;
+                    {body}
+                    config.fee = fee;
+                    Ok(())
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Admin<'info> {{
+                #[account(mut)]
+                pub config: Account<'info, Config>,
+                pub admin: {signer}<'info>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn signer_or_authority_check_protects() {
+        assert_eq!(admin("AccountInfo", "").len(), 1);
+        assert!(admin("Signer", "").is_empty());
+        assert!(admin(
+            "AccountInfo",
+            "require_keys_eq!(ctx.accounts.admin.key(), config.authority);"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+//! Getter handlers that hand raw account bytes to any caller.
+//!
+//! Account data is public on-chain, so a getter is not a leak in itself.
+//! But a `get_*` handler that decodes the value it returns from raw bytes,
+//! with no signer to restrict who calls it, gives every client whatever the
+//! hand-rolled layout happens to read, and other programs may compose it
+//! through CPI as if it were checked. Reading the typed field keeps the
+//! answer tied to the account's real layout.
+
+use syn::{GenericArgument, PathArguments, ReturnType, Type};
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountKind, Handler, ParsedProgram};
+
+pub const ID: &str = "unrestricted_data_getter";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/unrestricted-data-getter",
+    name: ID,
+    title: "Getter returns raw account data to any caller",
+    category: Category::AccessControl,
+    severity: Severity::Low,
+    cwe: 200,
+    description: "A view-style handler with no signer returns a value decoded from an account's raw bytes instead of a typed field.",
+    example: "pub fn get_user_balance(ctx: Context<Query>) -> Result<u64> { let data = ctx.accounts.user_account.try_borrow_data()?; /* decode */ }",
+    fixed_example: "pub fn get_user_balance(ctx: Context<Query>) -> Result<u64> {\n    Ok(ctx.accounts.user_account.balance) // user_account: Account<'info, UserAccount>\n}",
+    references: &[
+        "https://cwe.mitre.org/data/definitions/200.html",
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/account/struct.Account.html",
+    ],
+};
+
+/// Name prefixes of view-style handlers.
+const GETTER_PREFIXES: &[&str] = &["get_", "read_", "view_", "query_", "fetch_"];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        if !is_getter(handler) {
+            continue;
+        }
+        let signed = program.accounts_for(handler).is_some_and(|accounts| {
+            accounts
+                .fields
+                .iter()
+                .any(|field| field.kind == AccountKind::Signer)
+        });
+        if signed {
+            continue;
+        }
+        let reads = ast::data_reads(&handler.item, &Bindings::of(&handler.item));
+        let Some(read) = reads.first() else {
+            continue;
+        };
+        let (name, account) = (&handler.name, &read.account);
+        let message = format!(
+            "getter `{name}` returns a value decoded from the raw bytes of `{account}` to any \
+             caller, with no signer and no typed read"
+        );
+        findings.push(
+            Finding::new(
+                ID,
+                Severity::Low,
+                program,
+                handler.item.sig.ident.span(),
+                message,
+            )
+            .with_confidence(Confidence::Medium)
+            .with_suggestion(format!(
+                "declare `{account}` as `Account<'info, T>` and return the field of `T`; add a \
+                 `Signer` if only some callers should see it"
+            )),
+        );
+    }
+    findings
+}
+
+/// Whether `handler` is named like a getter and returns a value.
+fn is_getter(handler: &Handler) -> bool {
+    GETTER_PREFIXES
+        .iter()
+        .any(|prefix| handler.name.starts_with(prefix))
+        && returns_value(&handler.item.sig.output)
+}
+
+/// Whether `output` is `Result<T>` or `T` with `T` other than `()`.
+fn returns_value(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    let Type::Path(path) = &**ty else {
+        return !matches!(&**ty, Type::Tuple(tuple) if tuple.elems.is_empty());
+    };
+    let Some(last) = path.path.segments.last() else {
+        return false;
+    };
+    if last.ident != "Result" {
+        return true;
+    }
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) => !matches!(
+            args.args.first(),
+            Some(GenericArgument::Type(Type::Tuple(tuple))) if tuple.elems.is_empty()
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_both_fixture_getters() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].span.line_start, 88);
+        assert_eq!(
+            findings[0].message,
+            "getter `get_nft_metadata` returns a value decoded from the raw bytes of \
+             `nft_account` to any caller, with no signer and no typed read"
+        );
+
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.starts_with("getter `get_user_balance`"));
+        assert_eq!(findings[0].severity, Severity::Low);
+    }
+
+    fn getter(signature: &str, body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn {signature} {{
+                    {body}
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Query<'info> {{
+                pub vault: AccountInfo<'info>,
+            }}
+
+            #[derive(Accounts)]
+            pub struct Signed<'info> {{
+                pub vault: AccountInfo<'info>,
+                pub owner: Signer<'info>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn typed_unit_and_signed_getters_are_fine() {
+        let raw = "let data = ctx.accounts.vault.try_borrow_data()?; Ok(data[0] as u64)";
+        assert_eq!(
+            getter("get_balance(ctx: Context<Query>) -> Result<u64>", raw).len(),
+            1
+        );
+        assert!(getter("get_balance(ctx: Context<Signed>) -> Result<u64>", raw).is_empty());
+        assert!(getter("get_balance(ctx: Context<Query>) -> Result<()>", raw).is_empty());
+        assert!(getter("refresh(ctx: Context<Query>) -> Result<u64>", raw).is_empty());
+        assert!(getter(
+            "get_balance(ctx: Context<Query>) -> Result<u64>",
+            "Ok(ctx.accounts.vault.lamports())"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+//! Raw pointer reinterpretation of borrowed account data.
+//!
+//! Casting `data.as_ptr()` to `*const T` skips every length, alignment and
+//! discriminator check that Anchor's typed deserialization performs.
+
+use std::collections::HashSet;
+
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprCast, ExprUnsafe, Local, Type};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "unsafe_account_deserialization";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/unsafe-deref",
+    name: ID,
+    title: "Raw pointer access to account data",
+    category: Category::MemorySafety,
+    severity: Severity::High,
+    cwe: 843,
+    description: "Borrowed account data is reinterpreted through raw pointers inside `unsafe`, skipping length, alignment and discriminator checks.",
+    example: "let vault = unsafe { &*(data.as_ptr() as *const Vault) };",
+    fixed_example: "let vault = Vault::try_deserialize(&mut &data[..])?;",
+    references: &[
+        "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/account/struct.Account.html",
+        "https://doc.rust-lang.org/nomicon/",
+    ],
+};
+
+/// Pointer arithmetic that keeps pointing into the same buffer.
+const POINTER_OFFSETS: &[&str] = &["add", "offset", "sub", "wrapping_add", "wrapping_offset"];
+
+/// `std::ptr` / `std::slice` functions operating on raw pointers.
+const RAW_POINTER_FNS: &[&str] = &[
+    "read",
+    "read_unaligned",
+    "write",
+    "write_unaligned",
+    "write_bytes",
+    "copy",
+    "copy_nonoverlapping",
+    "from_raw_parts",
+    "from_raw_parts_mut",
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            borrowed: HashSet::new(),
+            pointers: HashSet::new(),
+            unsafe_depth: 0,
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    /// Locals holding borrowed account data.
+    borrowed: HashSet<String>,
+    /// Locals holding raw pointers into borrowed account data.
+    pointers: HashSet<String>,
+    unsafe_depth: usize,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    fn is_borrowed(&self, expr: &Expr) -> bool {
+        let (methods, root) = ast::method_chain(expr);
+        methods
+            .iter()
+            .any(|m| ast::DATA_BORROWS.contains(&m.as_str()))
+            || ast::ident(root).is_some_and(|name| self.borrowed.contains(&name))
+    }
+
+    /// Whether `expr` is a raw pointer into borrowed account data.
+    fn is_account_pointer(&self, expr: &Expr) -> bool {
+        match ast::unparen(expr) {
+            Expr::MethodCall(call) if call.method == "as_ptr" || call.method == "as_mut_ptr" => {
+                self.is_borrowed(&call.receiver)
+            }
+            Expr::MethodCall(call) if POINTER_OFFSETS.iter().any(|m| call.method == m) => {
+                self.is_account_pointer(&call.receiver)
+            }
+            expr => ast::ident(expr).is_some_and(|name| self.pointers.contains(&name)),
+        }
+    }
+
+    fn report(&mut self, node: &impl Spanned, what: &str) {
+        self.findings.push(
+            Finding::new(
+                ID,
+                Severity::High,
+                self.program,
+                node.span(),
+                format!(
+                    "{what} on borrowed account data in `{}` bypasses bounds and type checks",
+                    self.handler.name
+                ),
+            )
+            .with_confidence(Confidence::High)
+            .with_suggestion(
+                "use Anchor's typed deserialization (`Account<'info, T>` or \
+                 `T::try_deserialize`) instead of raw pointers",
+            ),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            if self.is_account_pointer(&init.expr) {
+                self.pointers.insert(name);
+            } else if self.is_borrowed(&init.expr) {
+                self.borrowed.insert(name);
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &ExprUnsafe) {
+        self.unsafe_depth += 1;
+        visit::visit_expr_unsafe(self, node);
+        self.unsafe_depth -= 1;
+    }
+
+    fn visit_expr_cast(&mut self, node: &ExprCast) {
+        if self.unsafe_depth > 0
+            && matches!(*node.ty, Type::Ptr(_))
+            && self.is_account_pointer(&node.expr)
+        {
+            self.report(node, "raw pointer cast");
+        }
+        visit::visit_expr_cast(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        if self.unsafe_depth > 0 {
+            if let Some(name) = ast::call_name(node) {
+                let raw = RAW_POINTER_FNS.contains(&name.as_str());
+                if raw
+                    && node
+                        .args
+                        .first()
+                        .is_some_and(|arg| self.is_account_pointer(arg))
+                {
+                    self.report(node, &format!("`{name}`"));
+                }
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_vault_pointer_read_and_zeroing() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        let messages: Vec<_> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("raw pointer cast"));
+        assert!(messages[0].contains("`get_user_balance`"));
+        assert!(messages[1].starts_with("`write_bytes`"));
+        assert!(messages[1].contains("`emergency_drain`"));
+        assert!(findings.iter().all(|f| f.severity == Severity::High));
+    }
+
+    #[test]
+    fn flags_nft_pointer_reads_and_writes() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        let sites: Vec<_> = findings
+            .iter()
+            .map(|f| (f.span.line_start, f.message.split(" on ").next().unwrap()))
+            .collect();
+        assert_eq!(
+            sites,
+            [
+                (97, "raw pointer cast"),
+                (99, "`from_raw_parts`"),
+                (155, "raw pointer cast")
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_pointers_outside_account_data() {
+        let program = parse(
+            r#"
+            #[program]
+            pub mod demo {
+                pub fn run(ctx: Context<Run>) -> Result<()> {
+                    let buf = [0u8; 8];
+                    unsafe {
+                        let ptr = buf.as_ptr();
+                        let value = *(ptr as *const u64);
+                    }
+                    Ok(())
+                }
+            }
+            "#,
+        );
+        assert!(run(&program).is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+//! Lengths read out of account bytes and trusted as slice sizes.
+//!
+//! `let len = *(ptr as *const u32) as usize;` takes a length from data the
+//! caller controls. Passed straight to `from_raw_parts` or used to slice
+//! the buffer, it lets an attacker choose how far past the account data the
+//! program reads or how much it allocates. The length has to be compared
+//! with what is left of the buffer first.
+
+use std::collections::{HashMap, HashSet};
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprIf, ExprIndex, Local, Macro, Type, UnOp};
+
+use crate::ast;
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{Handler, ParsedProgram};
+
+pub const ID: &str = "untrusted_length_from_bytes";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "rust/untrusted-length-from-bytes",
+    name: ID,
+    title: "Length read from account bytes used unchecked",
+    category: Category::MemorySafety,
+    severity: Severity::High,
+    cwe: 130,
+    description: "An integer decoded from raw account bytes is used as a slice length, range bound or allocation size without first being checked against the remaining buffer.",
+    example: "let len = *(ptr as *const u32) as usize;\nlet slice = std::slice::from_raw_parts(ptr.add(4), len);",
+    fixed_example: "let len = u32::from_le_bytes(data[64..68].try_into().unwrap()) as usize;\nlet bytes = data.get(68..68 + len).ok_or(ErrorCode::InvalidLength)?;",
+    references: &[
+        "https://cwe.mitre.org/data/definitions/130.html",
+        "https://doc.rust-lang.org/std/primitive.slice.html#method.get",
+    ],
+};
+
+const INTEGERS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// Calls whose argument at the given index is a length or count.
+const LENGTH_SINKS: &[(&str, usize)] = &[
+    ("from_raw_parts", 1),
+    ("from_raw_parts_mut", 1),
+    ("with_capacity", 0),
+];
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in program.handlers() {
+        let mut visitor = Visitor {
+            program,
+            handler,
+            lengths: HashMap::new(),
+            checked: HashSet::new(),
+            findings: &mut findings,
+        };
+        visitor.visit_item_fn(&handler.item);
+    }
+    findings
+}
+
+/// Whether `expr` decodes an integer from memory: a dereference of a cast
+/// to an integer pointer, `ptr::read*`, or `from_*_bytes`, with any casts
+/// of the result.
+fn reads_integer(expr: &Expr) -> bool {
+    match ast::unparen(expr) {
+        Expr::Cast(cast) => reads_integer(&cast.expr),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Deref(_)) => {
+            let Expr::Cast(cast) = ast::unparen(&unary.expr) else {
+                return false;
+            };
+            matches!(&*cast.ty, Type::Ptr(ptr) if is_integer(&ptr.elem))
+        }
+        Expr::Call(call) => ast::call_name(call).is_some_and(|name| {
+            matches!(
+                name.as_str(),
+                "read" | "read_unaligned" | "from_le_bytes" | "from_be_bytes" | "from_ne_bytes"
+            )
+        }),
+        _ => false,
+    }
+}
+
+fn is_integer(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.get_ident().is_some_and(|ident| {
+        INTEGERS.iter().any(|int| ident == int)
+    }))
+}
+
+struct Visitor<'a> {
+    program: &'a ParsedProgram,
+    handler: &'a Handler,
+    /// Locals decoded from bytes, with the span and text of the read.
+    lengths: HashMap<String, (proc_macro2::Span, String)>,
+    /// Locals a condition has compared before the point being visited.
+    checked: HashSet<String>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Visitor<'_> {
+    fn mark_checked(&mut self, condition: &proc_macro2::TokenStream) {
+        for name in self.lengths.keys() {
+            if ast::mentions(condition, name) {
+                self.checked.insert(name.clone());
+            }
+        }
+    }
+
+    /// Reports the first unchecked length `expr` mentions, used as `sink`.
+    fn check(&mut self, expr: &Expr, sink: &str) {
+        let tokens = expr.to_token_stream();
+        let Some(name) = self
+            .lengths
+            .keys()
+            .find(|name| !self.checked.contains(*name) && ast::mentions(&tokens, name))
+            .cloned()
+        else {
+            return;
+        };
+        let (span, read) = self.lengths.remove(&name).expect("name is a key");
+        let message = format!(
+            "`{}` reads `{name}` from raw account bytes (`{read}`) and uses it as {sink} \
+             without checking it against the buffer",
+            self.handler.name
+        );
+        self.findings.push(
+            Finding::new(ID, Severity::High, self.program, span, message)
+                .with_confidence(Confidence::High)
+                .with_suggestion(format!(
+                    "check `{name}` against the bytes left after its offset, or take the \
+                     slice with `data.get(start..start + {name})` and return an error on `None`"
+                )),
+        );
+    }
+}
+
+impl Visit<'_> for Visitor<'_> {
+    fn visit_local(&mut self, local: &Local) {
+        visit::visit_local(self, local);
+        if let (Some(name), Some(init)) = (ast::pat_ident(&local.pat), &local.init) {
+            if reads_integer(&init.expr) {
+                let text = ast::source_text(&init.expr);
+                self.lengths.insert(name, (init.expr.span(), text));
+            }
+        }
+    }
+
+    fn visit_macro(&mut self, node: &Macro) {
+        let name = node.path.segments.last().map(|s| s.ident.to_string());
+        if name.is_some_and(|name| name.starts_with("require") || name.starts_with("assert")) {
+            self.mark_checked(&node.tokens);
+        }
+        visit::visit_macro(self, node);
+    }
+
+    fn visit_expr_if(&mut self, node: &ExprIf) {
+        self.visit_expr(&node.cond);
+        self.mark_checked(&node.cond.to_token_stream());
+        self.visit_block(&node.then_branch);
+        if let Some((_, else_branch)) = &node.else_branch {
+            self.visit_expr(else_branch);
+        }
+    }
+
+    fn visit_expr_call(&mut self, node: &ExprCall) {
+        visit::visit_expr_call(self, node);
+        let Some(name) = ast::call_name(node) else {
+            return;
+        };
+        if let Some(&(sink, index)) = LENGTH_SINKS.iter().find(|(sink, _)| *sink == name) {
+            if let Some(arg) = node.args.iter().nth(index) {
+                self.check(arg, &format!("the length of `{sink}`"));
+            }
+        }
+    }
+
+    /// `data[start..start + len]`
+    fn visit_expr_index(&mut self, node: &ExprIndex) {
+        visit::visit_expr_index(self, node);
+        if let Expr::Range(range) = ast::unparen(&node.index) {
+            for bound in range.start.iter().chain(&range.end) {
+                self.check(bound, "a slice bound");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_the_metadata_length_read() {
+        let findings = run(&parse_fixture("insecure_nft_marketplace.rs"));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].span.line_start, 97);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(
+            findings[0].message,
+            "`get_nft_metadata` reads `len` from raw account bytes (`*(ptr as *const u32) as \
+             usize`) and uses it as the length of `from_raw_parts` without checking it against \
+             the buffer"
+        );
+        assert!(run(&parse_fixture("vulnerable_vault.rs")).is_empty());
+    }
+
+    fn read(body: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn read(ctx: Context<Read>) -> Result<()> {{
+                    let data = ctx.accounts.store.try_borrow_data()?;
+                    let len = u32::from_le_bytes(data[0..4].try_into().unwrap()) as usize;
+                    {body}
+                    Ok(())
+                }}
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn bounds_checks_clear_the_length() {
+        assert_eq!(read("let name = &data[4..4 + len];").len(), 1);
+        assert_eq!(read("let names = Vec::<u8>::with_capacity(len);").len(), 1);
+        assert!(
+            read("require!(4 + len <= data.len(), E::Short); let name = &data[4..4 + len];")
+                .is_empty()
+        );
+        assert!(read(
+            "if len > data.len() - 4 { return err!(E::Short); } let name = &data[4..4 + len];"
+        )
+        .is_empty());
+        assert!(read("let name = data.get(4..4 + len);").is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+//! Raw data read from an account whose owner is never checked.
+//!
+//! An `AccountInfo` or `UncheckedAccount` can be any account at all. If the
+//! handler reads its bytes without first checking that this program owns
+//! it, an attacker can pass an account they created elsewhere and fill
+//! with whatever balance or authority they like.
+
+use std::collections::HashSet;
+
+use crate::ast::{self, Bindings};
+use crate::detectors::{Category, DetectorInfo};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::parser::{AccountField, AccountKind, ParsedProgram};
+
+pub const ID: &str = "unverified_foreign_account_read";
+
+pub const INFO: DetectorInfo = DetectorInfo {
+    id: "solana/unverified-foreign-account-read",
+    name: ID,
+    title: "Account data read without an owner check",
+    category: Category::Validation,
+    severity: Severity::High,
+    cwe: 345,
+    description: "The data of an `AccountInfo` or `UncheckedAccount` is read although neither an `owner` constraint nor a `require_keys_eq!` on its owner ties the account to this program.",
+    example: "/// CHECK: not validated\npub user_account: AccountInfo<'info>,",
+    fixed_example: "/// CHECK: owned by this program; layout checked on read\n#[account(owner = crate::ID)]\npub user_account: AccountInfo<'info>,",
+    references: &[
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/2-owner-checks",
+        "https://www.anchor-lang.com/docs/references/account-constraints",
+    ],
+};
+
+pub fn run(program: &ParsedProgram) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut reported = HashSet::new();
+    for handler in program.handlers() {
+        let Some(accounts) = program.accounts_for(handler) else {
+            continue;
+        };
+        let guards = ast::require_guards(&handler.item);
+        for read in ast::data_reads(&handler.item, &Bindings::of(&handler.item)) {
+            let Some(field) = accounts.field(&read.account) else {
+                continue;
+            };
+            if !matches!(
+                field.kind,
+                AccountKind::AccountInfo | AccountKind::UncheckedAccount
+            ) || owner_constrained(field)
+                || guards
+                    .iter()
+                    .any(|guard| ast::mentions(guard, "owner") && ast::mentions(guard, &field.name))
+            {
+                continue;
+            }
+            if !reported.insert((accounts.name.clone(), field.name.clone())) {
+                continue;
+            }
+            let message = format!(
+                "`{}::{}` is an unchecked account whose data `{}` reads without verifying \
+                 its owner",
+                accounts.name, field.name, handler.name
+            );
+            findings.push(
+                Finding::new(ID, Severity::High, program, field.span(), message)
+                    .with_confidence(Confidence::High)
+                    .with_suggestion(format!(
+                        "add `#[account(owner = crate::ID)]` to `{}`, or declare it as \
+                         `Account<'info, T>`",
+                        field.name
+                    )),
+            );
+        }
+    }
+    findings
+}
+
+/// `owner = crate::ID`, or a `constraint` comparing the account's owner.
+fn owner_constrained(field: &AccountField) -> bool {
+    field.has_constraint("owner")
+        || field
+            .constraint_values("constraint")
+            .any(|value| ast::mentions(value, "owner") && ast::mentions(value, &field.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{parse, parse_fixture};
+
+    #[test]
+    fn flags_query_user_account() {
+        let findings = run(&parse_fixture("vulnerable_vault.rs"));
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.severity, Severity::High);
+        assert_eq!(finding.span.line_start, 193);
+        assert_eq!(
+            finding.message,
+            "`Query::user_account` is an unchecked account whose data `get_user_balance` reads \
+             without verifying its owner"
+        );
+        assert!(run(&parse_fixture("insecure_nft_marketplace.rs")).is_empty());
+    }
+
+    fn query(attribute: &str, guard: &str) -> Vec<Finding> {
+        run(&parse(&format!(
+            r#"
+            #[program]
+            pub mod demo {{
+                pub fn balance(ctx: Context<Query>) -> Result<u64> {{
+                    {guard}
+                    let data = ctx.accounts.user_account.try_borrow_data()?;
+                    Ok(u64::from_le_bytes(data[..8].try_into().unwrap()))
+                }}
+            }}
+
+            #[derive(Accounts)]
+            pub struct Query<'info> {{
+                {attribute}
+                pub user_account: AccountInfo<'info>,
+            }}
+            "#
+        )))
+    }
+
+    #[test]
+    fn owner_checks_silence_the_read() {
+        assert_eq!(query("", "").len(), 1);
+        assert!(query("#[account(owner = crate::ID)]", "").is_empty());
+        assert!(query(
+            "#[account(constraint = user_account.owner == &crate::ID)]",
+            ""
+        )
+        .is_empty());
+        assert!(query(
+            "",
+            "require_keys_eq!(*ctx.accounts.user_account.owner, crate::ID);"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! Changed line ranges from a unified diff.
+//!
+//! Only the new side of each hunk matters: added lines, plus the line
+//! following a pure deletion, since code there may now behave differently.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::finding::Span;
+
+/// Lines touched by a diff, per file path as it appears in the diff.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangedLines {
+    /// Inclusive 1-based line ranges in the new version of each file.
+    files: HashMap<PathBuf, Vec<(usize, usize)>>,
+}
+
+impl ChangedLines {
+    /// Parses `git diff` output. Anything that is not part of a file header
+    /// or hunk is ignored.
+    pub fn parse(diff: &str) -> Self {
+        let mut changed = ChangedLines::default();
+        let mut file: Option<PathBuf> = None;
+        let mut line = 0;
+        for text in diff.lines() {
+            if let Some(path) = text.strip_prefix("+++ ") {
+                file = new_path(path);
+            } else if text.starts_with("--- ") {
+                // The old path; `+++` follows.
+            } else if let Some(header) = text.strip_prefix("@@ ") {
+                line = hunk_start(header).unwrap_or(0);
+            } else if let Some(file) = file.as_ref().filter(|_| line > 0) {
+                match text.as_bytes().first() {
+                    Some(b'+') => {
+                        changed.add(file, line);
+                        line += 1;
+                    }
+                    Some(b'-') => changed.add(file, line),
+                    Some(b'\\') => {}
+                    _ => line += 1,
+                }
+            }
+        }
+        changed
+    }
+
+    fn add(&mut self, file: &Path, line: usize) {
+        let ranges = self.files.entry(file.to_path_buf()).or_default();
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 >= line => *end = (*end).max(line),
+            _ => ranges.push((line, line)),
+        }
+    }
+
+    /// Whether `span`, in a file under `root`, overlaps a changed line.
+    pub fn touches(&self, root: &Path, span: &Span) -> bool {
+        let file = span.file.strip_prefix(root).unwrap_or(&span.file);
+        self.files.get(file).is_some_and(|ranges| {
+            ranges
+                .iter()
+                .any(|&(start, end)| span.line_start <= end && start <= span.line_end)
+        })
+    }
+
+    /// Changed ranges of `file`, a path relative to the diff root.
+    pub fn ranges(&self, file: &Path) -> &[(usize, usize)] {
+        self.files.get(file).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// The path after `+++ `, without git's `b/` prefix; `None` for deletions.
+fn new_path(path: &str) -> Option<PathBuf> {
+    let path = path.split('\t').next()?.trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(PathBuf::from(path.strip_prefix("b/").unwrap_or(path)))
+}
+
+/// First new-side line of a hunk header such as `-12,7 +13,8 @@`.
+fn hunk_start(header: &str) -> Option<usize> {
+    let new = header
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('+'))?;
+    new.split(',').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3,6 +3,7 @@ fn a() {
+ context
+-old
++new
++added
+ context
+ context
+@@ -20,4 +21,3 @@
+ context
+-removed
+ context
+diff --git a/gone.rs b/gone.rs
+--- a/gone.rs
++++ /dev/null
+@@ -1,2 +0,0 @@
+-x
+-y
+";
+
+    #[test]
+    fn collects_new_side_ranges() {
+        let changed = ChangedLines::parse(DIFF);
+        assert_eq!(changed.ranges(Path::new("src/lib.rs")), [(4, 5), (22, 22)]);
+        assert!(changed.ranges(Path::new("gone.rs")).is_empty());
+    }
+
+    #[test]
+    fn spans_touching_a_range_count() {
+        let changed = ChangedLines::parse(DIFF);
+        let span = |start, end| Span {
+            file: PathBuf::from("/repo/src/lib.rs"),
+            line_start: start,
+            col_start: 1,
+            line_end: end,
+            col_end: 1,
+        };
+        let root = Path::new("/repo");
+        assert!(changed.touches(root, &span(5, 5)));
+        // Starts on an unchanged line but reaches into the hunk.
+        assert!(changed.touches(root, &span(1, 4)));
+        assert!(!changed.touches(root, &span(6, 21)));
+        assert!(!changed.touches(Path::new("/elsewhere"), &span(5, 5)));
+    }
+}
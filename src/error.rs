@@ -0,0 +1,89 @@
+//! Error types shared across the scanner.
+
+use std::io;
+use std::path::PathBuf;
+
+/// Convenience alias used throughout the crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Top-level error returned by the scanning entry points.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to read {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to write {}: {source}", .path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{} has {found} .rs files, more than the limit of {limit}", .path.display())]
+    TooManyFiles {
+        path: PathBuf,
+        found: usize,
+        limit: usize,
+    },
+    #[error("{} already exists", .path.display())]
+    Exists { path: PathBuf },
+    #[error("invalid baseline {}: {source}", .path.display())]
+    Baseline {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("invalid config {}: {source}", .path.display())]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error(transparent)]
+    UnknownDetector(#[from] UnknownDetector),
+    #[error("plugin {}: {message}", .path.display())]
+    Plugin { path: PathBuf, message: String },
+    #[error("starting {jobs} scan threads: {source}")]
+    Threads {
+        jobs: usize,
+        #[source]
+        source: rayon::ThreadPoolBuildError,
+    },
+    #[error("watching {}: {message}", .path.display())]
+    Watch { path: PathBuf, message: String },
+    #[error("webhook {url}: {message}")]
+    Notify { url: String, message: String },
+    #[error("scan history: {0}")]
+    Storage(#[from] rusqlite::Error),
+}
+
+/// A source file that `syn` could not parse.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{}:{line}:{column}: {message}", .file.display())]
+pub struct ParseError {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// A detector id that no registered detector answers to.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown detector `{0}`")]
+pub struct UnknownDetector(pub String);
+
+impl ParseError {
+    pub(crate) fn from_syn(file: PathBuf, err: &syn::Error) -> Self {
+        let start = err.span().start();
+        ParseError {
+            file,
+            line: start.line,
+            column: start.column + 1,
+            message: err.to_string(),
+        }
+    }
+}
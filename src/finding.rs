@@ -0,0 +1,627 @@
+//! Findings emitted by detectors.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fix::Fix;
+use crate::parser::ParsedProgram;
+
+/// How bad a finding is if it turns out to be real.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// All severities, most severe first.
+    pub const ALL: [Severity; 4] = [
+        Severity::Critical,
+        Severity::High,
+        Severity::Medium,
+        Severity::Low,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses the lowercase names used in reports, ignoring case.
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::ALL
+            .into_iter()
+            .find(|severity| severity.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!("unknown severity `{s}`; expected critical, high, medium or low")
+            })
+    }
+}
+
+/// How likely a finding is to be a true positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses the lowercase names used in reports, ignoring case.
+impl FromStr for Confidence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Confidence::High, Confidence::Medium, Confidence::Low]
+            .into_iter()
+            .find(|confidence| confidence.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown confidence `{s}`; expected high, medium or low"))
+    }
+}
+
+/// Source range of a finding. Lines and columns are 1-based and `col_end`
+/// is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Span {
+    pub file: PathBuf,
+    pub line_start: usize,
+    pub col_start: usize,
+    pub line_end: usize,
+    pub col_end: usize,
+}
+
+impl Span {
+    /// Converts a `syn` span (0-based columns) into a report span.
+    pub fn new(file: &Path, span: proc_macro2::Span) -> Self {
+        let (start, end) = (span.start(), span.end());
+        Span {
+            file: file.to_path_buf(),
+            line_start: start.line,
+            col_start: start.column + 1,
+            line_end: end.line,
+            col_end: end.column + 1,
+        }
+    }
+}
+
+/// Renders as `file:line:col`, which editors and terminals can jump to.
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.file.display(),
+            self.line_start,
+            self.col_start
+        )
+    }
+}
+
+/// A single issue reported by a detector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Id of the detector that produced the finding.
+    pub detector: &'static str,
+    pub severity: Severity,
+    pub confidence: Confidence,
+    pub span: Span,
+    pub message: String,
+    /// How to fix the issue, when the detector knows.
+    pub suggestion: Option<String>,
+    /// What an attacker can do with the issue, in plain words for readers
+    /// who do not read code. Access-control detectors set it on critical
+    /// and high findings.
+    pub impact: Option<String>,
+    /// See [`Finding::fingerprint`].
+    pub(crate) fingerprint: String,
+    /// Machine-applicable version of the suggestion, when there is one.
+    pub fix: Option<Fix>,
+    /// Set for findings of detectors the registry treats as warnings: they
+    /// are reported but never make the scan fail. See
+    /// [`Registry::warning_only`](crate::detectors::Registry::warning_only).
+    pub warning_only: bool,
+    /// Other detectors that reported the same span; see [`dedupe`].
+    pub also_matched: Vec<&'static str>,
+    /// The instruction handler the finding belongs to; see
+    /// [`ParsedProgram::handler_at`].
+    pub handler: Option<String>,
+    /// Text of the file the finding is in; see [`Finding::snippet`].
+    pub source: SourceText,
+}
+
+/// Source text shared between a [`ParsedProgram`] and its findings. Its
+/// `Debug` output is only the length, to keep findings readable.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SourceText(pub Arc<str>);
+
+impl fmt::Debug for SourceText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SourceText({} bytes)", self.0.len())
+    }
+}
+
+impl Finding {
+    /// Creates a finding located at `span` inside `program`, with medium
+    /// confidence until the detector says otherwise.
+    pub fn new(
+        detector: &'static str,
+        severity: Severity,
+        program: &ParsedProgram,
+        span: proc_macro2::Span,
+        message: impl Into<String>,
+    ) -> Self {
+        Finding::at(
+            detector,
+            severity,
+            program,
+            Span::new(&program.path, span),
+            message,
+        )
+    }
+
+    /// Like [`Finding::new`], for a location that is already a report
+    /// [`Span`], such as one computed by a plugin.
+    pub fn at(
+        detector: &'static str,
+        severity: Severity,
+        program: &ParsedProgram,
+        span: Span,
+        message: impl Into<String>,
+    ) -> Self {
+        let message = message.into();
+        let handler = program
+            .handler_at(span.line_start)
+            .map(|handler| handler.name.clone());
+        let fingerprint = fingerprint(detector, handler.as_deref(), &program.source, &span);
+        Finding {
+            detector,
+            severity,
+            confidence: Confidence::Medium,
+            span,
+            message,
+            suggestion: None,
+            impact: None,
+            fingerprint,
+            fix: None,
+            warning_only: false,
+            also_matched: Vec::new(),
+            handler,
+            source: SourceText(Arc::clone(&program.source)),
+        }
+    }
+
+    /// The lines of the span with `context` lines either side, numbered,
+    /// with the span's lines marked `>` and a single-line span underlined:
+    ///
+    /// ```text
+    ///    99 |             let ptr = data.as_mut_ptr();
+    /// > 100 |             std::ptr::write_bytes(ptr, 0, 8);
+    ///       |             ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+    ///   101 |         }
+    /// ```
+    pub fn snippet(&self, context: usize) -> String {
+        let lines: Vec<&str> = self.source.0.lines().collect();
+        let span = &self.span;
+        let first = span.line_start.saturating_sub(context).max(1);
+        let last = (span.line_end + context).min(lines.len());
+        let width = last.to_string().len();
+        let mut out = String::new();
+        for number in first..=last {
+            let marked = (span.line_start..=span.line_end).contains(&number);
+            let marker = if marked { '>' } else { ' ' };
+            let row = format!("{marker} {number:>width$} | {}", lines[number - 1]);
+            out += row.trim_end();
+            out.push('\n');
+            if marked && span.line_start == span.line_end {
+                let carets = span.col_end.saturating_sub(span.col_start).max(1);
+                out += &format!(
+                    "  {:width$} | {}{}\n",
+                    "",
+                    " ".repeat(span.col_start.saturating_sub(1)),
+                    "^".repeat(carets)
+                );
+            }
+        }
+        out
+    }
+
+    pub fn with_confidence(mut self, confidence: Confidence) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn with_impact(mut self, impact: impl Into<String>) -> Self {
+        self.impact = Some(impact.into());
+        self
+    }
+
+    /// Attaches a fix replacing the source at `span` with `replacement`.
+    pub fn with_fix(
+        mut self,
+        program: &ParsedProgram,
+        span: proc_macro2::Span,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.fix = Some(Fix {
+            span: Span::new(&program.path, span),
+            replacement: replacement.into(),
+        });
+        self
+    }
+
+    /// 16 hex digits identifying the finding across runs, machines and
+    /// edits elsewhere in the file; see [`fingerprint`].
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Recomputes [`Finding::fingerprint`] with the file relative to
+    /// `root`, the directory a scan started from, so it is the same for
+    /// every checkout of the tree and every working directory the scan ran
+    /// in.
+    pub(crate) fn rebase_fingerprint(&mut self, root: &Path) {
+        let span = Span {
+            file: crate::normalize::relative_to(&self.span.file, root),
+            ..self.span.clone()
+        };
+        self.fingerprint = fingerprint(
+            self.detector,
+            self.handler.as_deref(),
+            &self.source.0,
+            &span,
+        );
+    }
+
+    /// The namespaced catalog id of the detector, e.g.
+    /// `rust/unchecked-arithmetic`, or the detector name for detectors
+    /// outside the built-in catalog.
+    pub fn rule_id(&self) -> &'static str {
+        crate::detectors::lookup(self.detector).map_or(self.detector, |info| info.id)
+    }
+
+    /// Hex of the instruction discriminator of [`Finding::handler`], which
+    /// is how transactions calling it can be recognised on-chain.
+    pub fn discriminator(&self) -> Option<String> {
+        let bytes = crate::parser::discriminator(self.handler.as_deref()?);
+        Some(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
+    /// Documentation links for the detector's weakness class; empty for
+    /// detectors outside the built-in catalog.
+    pub fn references(&self) -> &'static [&'static str] {
+        crate::detectors::lookup(self.detector).map_or(&[], |info| info.references)
+    }
+}
+
+/// Collapses findings that share a span into the most severe one, listing
+/// the other detectors in [`Finding::also_matched`].
+///
+/// Ties go to the higher confidence, then the detector name, so the result
+/// does not depend on the order detectors ran in. Survivors keep the order
+/// of their first appearance.
+pub fn dedupe(findings: Vec<Finding>) -> Vec<Finding> {
+    let mut kept: Vec<Finding> = Vec::with_capacity(findings.len());
+    let mut index: HashMap<Span, usize> = HashMap::new();
+    let rank = |f: &Finding| (f.severity, f.confidence, Reverse(f.detector));
+    for mut finding in findings {
+        let Some(&at) = index.get(&finding.span) else {
+            index.insert(finding.span.clone(), kept.len());
+            kept.push(finding);
+            continue;
+        };
+        let existing = &mut kept[at];
+        if rank(&finding) > rank(existing) {
+            mem::swap(existing, &mut finding);
+        }
+        // `finding` is now the one being dropped. A blocking finding on the
+        // span keeps the merged one blocking.
+        existing.warning_only &= finding.warning_only;
+        existing.also_matched.push(finding.detector);
+        existing.also_matched.append(&mut finding.also_matched);
+        existing.also_matched.sort_unstable();
+        existing.also_matched.dedup();
+    }
+    kept
+}
+
+/// Hash of the detector id, the [handler](Finding::handler), the
+/// whitespace-stripped source lines the finding covers, where in them it
+/// starts and ends, and the file's path with `/` separators, as 16 hex
+/// digits.
+///
+/// Line numbers and messages, which can quote line numbers, are
+/// deliberately left out so that editing code above a finding does not
+/// change its fingerprint. Findings of one detector on identical lines are
+/// told apart by the handler, such as one `authority` field in the
+/// accounts of two instructions, and on the same lines by their columns,
+/// such as two arguments of one signature. Columns are counted in
+/// non-whitespace characters so that reindenting does not matter either. The path is hashed as `span` names it; scans
+/// [rebase](Finding::rebase_fingerprint) it onto the scanned root. The
+/// hash is FNV-1a, which is stable across Rust versions and platforms.
+pub fn fingerprint(detector: &str, handler: Option<&str>, source: &str, span: &Span) -> String {
+    let lines: Vec<&str> = source
+        .lines()
+        .skip(span.line_start.saturating_sub(1))
+        .take((span.line_end + 1).saturating_sub(span.line_start))
+        .collect();
+    let snippet: String = lines
+        .iter()
+        .flat_map(|line| line.split_whitespace())
+        .collect();
+    let offset = |line: Option<&&str>, col: usize| {
+        line.map_or(0, |line| {
+            line.chars()
+                .take(col.saturating_sub(1))
+                .filter(|c| !c.is_whitespace())
+                .count()
+        })
+    };
+    let columns = format!(
+        "{}:{}",
+        offset(lines.first(), span.col_start),
+        offset(lines.last(), span.col_end)
+    );
+
+    let path = slashed(&span.file);
+
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in [detector, handler.unwrap_or(""), &snippet, &columns, &path] {
+        for byte in part.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+/// `path` relative to the working directory when it is inside it, with `/`
+/// separators and no `.` components, so the same file hashes the same on
+/// every platform however it was named on the command line.
+pub fn portable_path(path: &Path) -> String {
+    let cwd = std::env::current_dir().ok();
+    let relative = cwd
+        .as_deref()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path);
+    slashed(relative)
+}
+
+/// `path` with `/` separators and no `.` components.
+fn slashed(path: &Path) -> String {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use syn::visit::Visit;
+    use syn::ExprMethodCall;
+
+    use super::*;
+    use crate::parser;
+    use crate::test_support::parse_fixture;
+
+    #[test]
+    fn span_points_at_unwrap_call() {
+        struct Unwraps(Vec<proc_macro2::Span>);
+        impl Visit<'_> for Unwraps {
+            fn visit_expr_method_call(&mut self, node: &ExprMethodCall) {
+                if node.method == "unwrap" {
+                    self.0.push(syn::spanned::Spanned::span(node));
+                }
+                syn::visit::visit_expr_method_call(self, node);
+            }
+        }
+
+        let program = parse_fixture("vulnerable_vault.rs");
+        let handler = &program.handlers()[1];
+        assert_eq!(handler.name, "get_user_balance");
+        let mut unwraps = Unwraps(Vec::new());
+        unwraps.visit_item_fn(&handler.item);
+
+        // `        let account_data = data.try_borrow_data().unwrap();`
+        let span = Span::new(&program.path, unwraps.0[0]);
+        assert_eq!((span.line_start, span.col_start), (48, 28));
+        assert_eq!((span.line_end, span.col_end), (48, 59));
+        assert!(span.to_string().ends_with("vulnerable_vault.rs:48:28"));
+    }
+
+    #[test]
+    fn snippet_marks_the_write_bytes_line() {
+        let findings =
+            crate::scan_file(&crate::test_support::fixture("vulnerable_vault.rs")).unwrap();
+        let finding = findings
+            .iter()
+            .find(|f| {
+                f.message.starts_with("`write_bytes`")
+                    && f.handler.as_deref() == Some("emergency_drain")
+            })
+            .unwrap();
+        assert_eq!(
+            finding.snippet(2),
+            concat!(
+                "   98 |         unsafe {\n",
+                "   99 |             let ptr = data.as_mut_ptr();\n",
+                "> 100 |             std::ptr::write_bytes(ptr, 0, 8); // Zero out balance\n",
+                "      |             ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^\n",
+                "  101 |         }\n",
+                "  102 |\n",
+            )
+        );
+        assert!(finding.snippet(0).starts_with("> 100 |"));
+    }
+
+    #[test]
+    fn fingerprint_ignores_line_shifts_and_indentation() {
+        let span = |line| Span {
+            file: PathBuf::from("lib.rs"),
+            line_start: line,
+            col_start: 5,
+            line_end: line,
+            col_end: 29,
+        };
+        let original = "fn a() {}\n    vault.balance += amount;\n";
+        let shifted = "fn a() {}\n\n// note\nvault.balance  +=  amount;\n";
+        let print = |source, line| fingerprint("id", None, source, &span(line));
+
+        let moved = Span {
+            col_start: 1,
+            col_end: 27,
+            ..span(4)
+        };
+        assert_eq!(print(original, 2), fingerprint("id", None, shifted, &moved));
+        assert_ne!(print(original, 2), print(original, 1));
+        assert_ne!(
+            print(original, 2),
+            fingerprint("other", None, original, &span(2))
+        );
+        assert_eq!(print(original, 2).len(), 16);
+        let reversed = Span {
+            line_end: 1,
+            ..span(2)
+        };
+        assert_eq!(fingerprint("id", None, original, &reversed).len(), 16);
+        assert_ne!(
+            print(original, 2),
+            fingerprint(
+                "id",
+                None,
+                original,
+                &Span {
+                    file: PathBuf::from("main.rs"),
+                    ..span(2)
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn fingerprint_tells_same_line_findings_apart_by_column() {
+        let source = "fn pay(a: u64, b: u64) {}\n";
+        let at = |col_start, col_end| Span {
+            file: PathBuf::from("lib.rs"),
+            line_start: 1,
+            col_start,
+            line_end: 1,
+            col_end,
+        };
+        let first = fingerprint("id", None, source, &at(8, 14));
+        assert_ne!(first, fingerprint("id", None, source, &at(16, 22)));
+        assert_eq!(
+            first,
+            fingerprint("id", None, &format!("    {source}"), &at(12, 18)),
+            "columns should not count indentation"
+        );
+    }
+
+    #[test]
+    fn fixture_findings_have_distinct_fingerprints() {
+        let findings = crate::test_support::scan_fixtures();
+        let distinct: std::collections::HashSet<_> =
+            findings.iter().map(Finding::fingerprint).collect();
+        assert_eq!(distinct.len(), findings.len());
+    }
+
+    #[test]
+    fn fingerprint_survives_a_blank_line_above_the_finding() {
+        for name in ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"] {
+            let source = fs::read_to_string(crate::test_support::fixture(name)).unwrap();
+            let shifted = format!("\n{source}");
+            let scan = |source: &str| {
+                let program = parser::parse_source(name, source.to_string()).unwrap();
+                crate::scanner::analyze(&program, &crate::detectors::Registry::default())
+            };
+            let (before, after) = (scan(&source), scan(&shifted));
+            assert_eq!(before.len(), after.len());
+            for (before, after) in before.iter().zip(&after) {
+                assert_eq!(after.span.line_start, before.span.line_start + 1);
+                assert_eq!(
+                    after.fingerprint(),
+                    before.fingerprint(),
+                    "{}",
+                    before.message
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fingerprint_ignores_line_numbers_in_the_message() {
+        use crate::detectors::state_update_after_cpi;
+
+        let source =
+            fs::read_to_string(crate::test_support::fixture("insecure_nft_marketplace.rs"))
+                .unwrap();
+        let run = |source: String| {
+            let program = parser::parse_source("lib.rs", source).unwrap();
+            state_update_after_cpi::run(&program).remove(0)
+        };
+        let (before, after) = (run(source.clone()), run(format!("\n{source}")));
+        assert_ne!(after.message, before.message);
+        assert_eq!(after.fingerprint(), before.fingerprint());
+    }
+
+    #[test]
+    fn portable_paths_are_relative_with_forward_slashes() {
+        let cwd = std::env::current_dir().unwrap();
+        let file = Path::new("contracts").join("sources").join("vault.rs");
+        assert_eq!(
+            portable_path(&cwd.join(&file)),
+            "contracts/sources/vault.rs"
+        );
+        assert_eq!(
+            portable_path(&Path::new(".").join(&file)),
+            "contracts/sources/vault.rs"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Machine-applicable fixes and their rendering as unified diffs.
+//!
+//! A [`Fix`] replaces the source covered by its span. Fixes for one file
+//! must not overlap; [`apply`] and [`to_unified_diff`] drop any fix that
+//! overlaps an earlier one and apply the rest from the end of the file
+//! backwards, so earlier offsets stay valid.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::finding::{Finding, Span};
+
+/// Replacement text for a span of one source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub span: Span,
+    pub replacement: String,
+}
+
+/// Lines of context around each diff hunk.
+const CONTEXT: usize = 3;
+
+/// `fixes` sorted by position with overlapping ones removed.
+pub fn non_overlapping<'a>(fixes: impl IntoIterator<Item = &'a Fix>) -> Vec<&'a Fix> {
+    let mut fixes: Vec<_> = fixes.into_iter().collect();
+    fixes.sort_by_key(|fix| start(&fix.span));
+    let mut kept: Vec<&Fix> = Vec::new();
+    for fix in fixes {
+        if kept
+            .last()
+            .is_none_or(|last| end(&last.span) <= start(&fix.span))
+        {
+            kept.push(fix);
+        }
+    }
+    kept
+}
+
+/// `source` with every non-overlapping fix applied.
+pub fn apply<'a>(source: &str, fixes: impl IntoIterator<Item = &'a Fix>) -> String {
+    let mut fixed = source.to_string();
+    for fix in non_overlapping(fixes).into_iter().rev() {
+        let (Some(from), Some(to)) = (
+            offset(source, start(&fix.span)),
+            offset(source, end(&fix.span)),
+        ) else {
+            continue;
+        };
+        fixed.replace_range(from..to, &fix.replacement);
+    }
+    fixed
+}
+
+/// One patch with the fixes of every finding that has one, file by file.
+/// Source files are read from disk.
+pub fn to_patch(findings: &[Finding]) -> Result<String> {
+    let mut by_file: BTreeMap<&Path, Vec<&Fix>> = BTreeMap::new();
+    for fix in findings.iter().filter_map(|finding| finding.fix.as_ref()) {
+        by_file.entry(&fix.span.file).or_default().push(fix);
+    }
+    let mut patch = String::new();
+    for (path, fixes) in by_file {
+        let source = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        patch.push_str(&to_unified_diff(path, &source, fixes));
+    }
+    Ok(patch)
+}
+
+/// A unified diff (`git apply` / `patch -p1` compatible) turning `source`,
+/// the contents of `path`, into the fixed file. Empty when nothing changes.
+pub fn to_unified_diff<'a>(
+    path: &Path,
+    source: &str,
+    fixes: impl IntoIterator<Item = &'a Fix>,
+) -> String {
+    let fixes = non_overlapping(fixes);
+    let fixed = apply(source, fixes.iter().copied());
+    if fixed == source {
+        return String::new();
+    }
+    let old: Vec<_> = source.lines().collect();
+    let new: Vec<_> = fixed.lines().collect();
+
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+    let regions = regions(source, &fixes);
+    let mut index = 0;
+    while index < regions.len() {
+        // Merge regions whose context would touch into one hunk.
+        let mut last = index;
+        while last + 1 < regions.len()
+            && regions[last + 1].old_start <= regions[last].old_end + 2 * CONTEXT
+        {
+            last += 1;
+        }
+        let shift = regions[index].new_start as isize - regions[index].old_start as isize;
+        let old_from = regions[index].old_start.saturating_sub(CONTEXT);
+        let old_to = (regions[last].old_end + CONTEXT).min(old.len());
+        let new_from = old_from.saturating_add_signed(shift);
+        let new_to = (regions[last].new_end + old_to - regions[last].old_end).min(new.len());
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            range(old_from, old_to),
+            range(new_from, new_to)
+        );
+        let mut old_line = old_from;
+        for region in &regions[index..=last] {
+            for line in &old[old_line..region.old_start] {
+                let _ = writeln!(out, " {line}");
+            }
+            for line in &old[region.old_start..region.old_end] {
+                let _ = writeln!(out, "-{line}");
+            }
+            for line in &new[region.new_start..region.new_end] {
+                let _ = writeln!(out, "+{line}");
+            }
+            old_line = region.old_end;
+        }
+        for line in &old[old_line..old_to] {
+            let _ = writeln!(out, " {line}");
+        }
+        index = last + 1;
+    }
+    out
+}
+
+/// A run of whole lines rewritten by one or more fixes, as half-open
+/// 0-based line ranges in the original and the fixed file.
+#[derive(Debug)]
+struct Region {
+    old_start: usize,
+    old_end: usize,
+    new_start: usize,
+    new_end: usize,
+}
+
+/// The line regions touched by `fixes`, which must be sorted and
+/// non-overlapping. Fixes sharing a line fall into the same region.
+fn regions(source: &str, fixes: &[&Fix]) -> Vec<Region> {
+    let mut regions: Vec<Region> = Vec::new();
+    let mut shift: isize = 0;
+    for fix in fixes {
+        let (Some(from), Some(to)) = (
+            offset(source, start(&fix.span)),
+            offset(source, end(&fix.span)),
+        ) else {
+            continue;
+        };
+        let added = fix.replacement.matches('\n').count() as isize
+            - source[from..to].matches('\n').count() as isize;
+        let (old_start, old_end) = (fix.span.line_start - 1, fix.span.line_end);
+        match regions.last_mut() {
+            Some(region) if region.old_end > old_start => {
+                region.old_end = region.old_end.max(old_end);
+                region.new_end = region.old_end.saturating_add_signed(shift + added);
+            }
+            _ => regions.push(Region {
+                old_start,
+                old_end,
+                new_start: old_start.saturating_add_signed(shift),
+                new_end: old_end.saturating_add_signed(shift + added),
+            }),
+        }
+        shift += added;
+    }
+    regions
+}
+
+/// `start,len` with 1-based start, as unified diff headers expect.
+fn range(from: usize, to: usize) -> String {
+    let len = to - from;
+    let start = if len == 0 { from } else { from + 1 };
+    format!("{start},{len}")
+}
+
+fn start(span: &Span) -> (usize, usize) {
+    (span.line_start, span.col_start)
+}
+
+fn end(span: &Span) -> (usize, usize) {
+    (span.line_end, span.col_end)
+}
+
+/// Byte offset of a 1-based `(line, column)` position counting columns in
+/// characters, as `proc_macro2` does.
+fn offset(source: &str, (line, column): (usize, usize)) -> Option<usize> {
+    let line_start = if line == 1 {
+        0
+    } else {
+        source.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let text = &source[line_start..];
+    let within = text
+        .char_indices()
+        .map(|(index, _)| index)
+        .chain([text.len()])
+        .nth(column - 1)?;
+    Some(line_start + within)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::detectors::unchecked_arithmetic;
+    use crate::test_support::fixture;
+
+    fn fix(line: usize, cols: (usize, usize), replacement: &str) -> Fix {
+        Fix {
+            span: Span {
+                file: PathBuf::from("lib.rs"),
+                line_start: line,
+                col_start: cols.0,
+                line_end: line,
+                col_end: cols.1,
+            },
+            replacement: replacement.to_string(),
+        }
+    }
+
+    /// Applies a unified diff produced by [`to_unified_diff`], checking
+    /// every context and removed line.
+    fn patch(source: &str, diff: &str) -> String {
+        let old: Vec<_> = source.lines().collect();
+        let mut out = Vec::new();
+        let mut next = 0;
+        for line in diff.lines().skip(2) {
+            if let Some(header) = line.strip_prefix("@@ -") {
+                let (start, _) = header.split_once(',').unwrap();
+                let start: usize = start.parse().unwrap();
+                out.extend_from_slice(&old[next..start - 1]);
+                next = start - 1;
+            } else if let Some(added) = line.strip_prefix('+') {
+                out.push(added);
+            } else {
+                assert_eq!(&line[1..], old[next], "diff does not match source");
+                if line.starts_with(' ') {
+                    out.push(old[next]);
+                }
+                next += 1;
+            }
+        }
+        out.extend_from_slice(&old[next..]);
+        out.join("\n") + "\n"
+    }
+
+    #[test]
+    fn applying_the_diff_removes_the_deposit_finding() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.rs");
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        fs::write(&path, &source).unwrap();
+
+        let findings: Vec<_> = crate::scan_file(&path)
+            .unwrap()
+            .into_iter()
+            .filter(|f| f.detector == unchecked_arithmetic::ID)
+            .collect();
+        assert!(findings.iter().any(|f| f.message.contains("`deposit`")));
+        let diff = to_unified_diff(
+            &path,
+            &source,
+            findings.iter().filter_map(|f| f.fix.as_ref()),
+        );
+        assert!(diff.contains(
+            "\n-        vault.balance = vault.balance + amount;\n\
+             +        vault.balance = vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;\n"
+        ));
+
+        let patched = patch(&source, &diff);
+        assert_eq!(
+            patched,
+            apply(&source, findings.iter().filter_map(|f| f.fix.as_ref()))
+        );
+        fs::write(&path, patched).unwrap();
+        let rescanned = crate::scan_file(&path).unwrap();
+        assert!(!rescanned
+            .iter()
+            .any(|f| f.detector == unchecked_arithmetic::ID && f.message.contains("`deposit`")));
+    }
+
+    #[test]
+    fn overlapping_fixes_are_dropped_and_rest_applied_back_to_front() {
+        let source = "let a = x + y; let b = x * y;\n";
+        let fixes = [
+            fix(1, (24, 29), "x.checked_mul(y)?"),
+            fix(1, (9, 14), "x.checked_add(y)?"),
+            fix(1, (13, 20), "overlaps the second"),
+        ];
+        assert_eq!(non_overlapping(&fixes).len(), 2);
+        assert_eq!(
+            apply(source, &fixes),
+            "let a = x.checked_add(y)?; let b = x.checked_mul(y)?;\n"
+        );
+    }
+
+    #[test]
+    fn hunks_carry_context_and_line_counts() {
+        let source: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+        let fixes = [fix(2, (1, 7), "first\nsecond"), fix(15, (6, 8), "XV")];
+        let diff = to_unified_diff(Path::new("lib.rs"), &source, &fixes);
+        let headers: Vec<_> = diff.lines().filter(|l| l.starts_with("@@")).collect();
+        assert_eq!(headers, ["@@ -1,5 +1,6 @@", "@@ -12,7 +13,7 @@"]);
+        assert_eq!(patch(&source, &diff), apply(&source, &fixes));
+        assert!(to_unified_diff(Path::new("lib.rs"), &source, &[]).is_empty());
+    }
+}
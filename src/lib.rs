@@ -0,0 +1,60 @@
+//! Static vulnerability scanner for Anchor (Solana) programs.
+//!
+//! Source files are parsed into a [`ParsedProgram`] by the [`parser`] module
+//! and handed to the [`detectors`], which report [`Finding`]s.
+
+mod ast;
+pub mod cache;
+pub mod call_graph;
+pub mod config;
+pub mod detectors;
+pub mod diff;
+pub mod error;
+pub mod finding;
+pub mod fix;
+pub mod logging;
+pub mod normalize;
+pub mod notify;
+pub mod parser;
+pub mod plugin;
+pub mod progress;
+pub mod report;
+pub mod scanner;
+pub mod server;
+pub mod storage;
+pub mod suppress;
+pub mod watch;
+
+#[cfg(test)]
+mod test_support;
+
+use std::fs;
+use std::path::Path;
+
+pub use error::{Error, ParseError, Result, UnknownDetector};
+pub use finding::{Confidence, Finding, Severity, Span};
+pub use parser::ParsedProgram;
+pub use scanner::ScanReport;
+
+/// Parses the file at `path` and runs every detector over it.
+pub fn scan_file(path: &Path) -> Result<Vec<Finding>> {
+    let source = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let scan = scanner::scan_source_with(path, &source, &detectors::Registry::default())?;
+    Ok(scan.findings)
+}
+
+/// Parses in-memory `source` as if it were the file `name` and runs every
+/// detector over it, without touching the filesystem.
+pub fn scan_source(name: &str, source: &str) -> std::result::Result<Vec<Finding>, ParseError> {
+    let scan = scanner::scan_source_with(name, source, &detectors::Registry::default())?;
+    Ok(scan.findings)
+}
+
+/// Scans every `.rs` file under `root`, skipping files that fail to parse.
+/// Use [`scanner::scan`] to also get the list of parse errors.
+pub fn scan_path(root: &Path) -> Result<Vec<Finding>> {
+    Ok(scanner::scan(root)?.findings)
+}
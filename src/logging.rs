@@ -0,0 +1,189 @@
+//! Structured logging through `tracing`.
+//!
+//! The scanner opens a `scan_file` span around the analysis of each file,
+//! with the `file` path and, once known, its `finding_count`. Inside it each
+//! detector runs in a `run_detector` span carrying the `detector` id and
+//! its `duration` in microseconds. Nothing is logged unless a subscriber is
+//! installed; [`init_json`] installs one that writes a JSON line to stderr
+//! whenever a span closes, which is enough to see where the time goes.
+//!
+//! The level filter comes from `RUST_LOG` and defaults to
+//! `anchor_audit=info`.
+
+use std::io;
+
+use tracing::Subscriber;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// Filter used when `RUST_LOG` is unset or invalid.
+const DEFAULT_FILTER: &str = "anchor_audit=info";
+
+/// A subscriber writing one JSON object per event and per closed span to
+/// `writer`.
+pub fn json_subscriber<W>(writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    tracing_subscriber::fmt()
+        .json()
+        .with_span_events(FmtSpan::CLOSE)
+        .with_current_span(true)
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .finish()
+}
+
+/// Installs [`json_subscriber`] on stderr for the whole process. Returns
+/// `false` if a global subscriber was already installed.
+pub fn init_json() -> bool {
+    tracing::subscriber::set_global_default(json_subscriber(io::stderr)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    use super::*;
+    use crate::scanner::{self, ScanOptions};
+    use crate::test_support::fixture;
+
+    type Fields = BTreeMap<String, String>;
+
+    /// Records every span with its fields, in the order spans close.
+    #[derive(Clone, Default)]
+    struct Capture {
+        open: Arc<Mutex<HashMap<u64, (String, Fields)>>>,
+        closed: Arc<Mutex<Vec<(String, Fields)>>>,
+    }
+
+    struct Collect<'a>(&'a mut Fields);
+
+    impl Visit for Collect<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Capture {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _: Context<'_, S>) {
+            let mut fields = Fields::new();
+            attrs.record(&mut Collect(&mut fields));
+            let name = attrs.metadata().name().to_string();
+            self.open
+                .lock()
+                .unwrap()
+                .insert(id.into_u64(), (name, fields));
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            if let Some((_, fields)) = self.open.lock().unwrap().get_mut(&id.into_u64()) {
+                values.record(&mut Collect(fields));
+            }
+        }
+
+        fn on_close(&self, id: Id, _: Context<'_, S>) {
+            if let Some(span) = self.open.lock().unwrap().remove(&id.into_u64()) {
+                self.closed.lock().unwrap().push(span);
+            }
+        }
+    }
+
+    fn scan_fixtures_serially() -> usize {
+        let options = ScanOptions {
+            jobs: 1,
+            ..ScanOptions::default()
+        };
+        scanner::scan_with(&fixture(""), &options)
+            .unwrap()
+            .findings
+            .len()
+    }
+
+    #[test]
+    fn emits_a_scan_file_span_per_fixture() {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        let total = tracing::subscriber::with_default(subscriber, scan_fixtures_serially);
+
+        let closed = capture.closed.lock().unwrap();
+        let files: Vec<_> = closed
+            .iter()
+            .filter(|(name, _)| name == "scan_file")
+            .map(|(_, fields)| fields)
+            .collect();
+        let names: Vec<_> = files
+            .iter()
+            .map(|fields| fields["file"].rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["insecure_nft_marketplace.rs", "vulnerable_vault.rs"]
+        );
+        let counted: usize = files
+            .iter()
+            .map(|fields| fields["finding_count"].parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(counted, total);
+
+        let detectors: Vec<_> = closed
+            .iter()
+            .filter(|(name, _)| name == "run_detector")
+            .collect();
+        let registered = crate::detectors::Registry::default().detectors().count();
+        assert_eq!(detectors.len(), 2 * registered);
+        assert!(detectors.iter().all(|(_, fields)| {
+            fields.contains_key("detector") && fields["duration"].parse::<u64>().is_ok()
+        }));
+    }
+
+    #[test]
+    fn json_subscriber_writes_closed_spans() {
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Buffer {
+            fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(bytes)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        tracing::subscriber::with_default(
+            json_subscriber(move || writer.clone()),
+            scan_fixtures_serially,
+        );
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let scans: Vec<_> = lines
+            .iter()
+            .filter(|line| line["span"]["name"] == "scan_file")
+            .collect();
+        assert_eq!(scans.len(), 2);
+        assert!(scans[0]["span"]["finding_count"].is_u64());
+    }
+}
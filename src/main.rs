@@ -0,0 +1,582 @@
+use std::io::{self, IsTerminal, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use anchor_audit::config::{self, Config, LimitsConfig, NotifyConfig};
+use anchor_audit::detectors::{self, Registry};
+use anchor_audit::notify::{Payload, Webhook};
+use anchor_audit::progress::Progress;
+use anchor_audit::report;
+use anchor_audit::report::artifact::{self, Artifact};
+use anchor_audit::report::baseline::{self, BaselineDiff, Gate};
+use anchor_audit::report::workspace::WorkspaceReport;
+use anchor_audit::scanner::{self, ScanOptions, ScanReport, EXIT_ERROR};
+use anchor_audit::server::{self, ServerOptions};
+use anchor_audit::storage::Storage;
+use anchor_audit::{fix, logging, plugin, Severity};
+
+/// Scan Anchor programs for common Solana vulnerabilities.
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Files or directories to scan.
+    #[arg(default_value = "contracts/sources")]
+    paths: Vec<PathBuf>,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Only report findings at or above this severity (critical, high,
+    /// medium, low). The exit code is 1 when any remain.
+    #[arg(long, alias = "severity-threshold", default_value_t = Severity::Low)]
+    min_severity: Severity,
+
+    /// Files to analyse at once; 0 uses every core.
+    #[arg(long, default_value_t = 0)]
+    jobs: usize,
+
+    /// Configuration file; by default the nearest `audit.toml` above the
+    /// first path is used.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Run only these detector ids (comma-separated or repeated).
+    #[arg(long, value_delimiter = ',')]
+    enable: Vec<String>,
+
+    /// Never run these detector ids.
+    #[arg(long, value_delimiter = ',')]
+    disable: Vec<String>,
+
+    /// Report only findings missing from this baseline file.
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Record the current findings as the baseline in this file.
+    #[arg(long, value_name = "FILE")]
+    write_baseline: Option<PathBuf>,
+
+    /// CI mode: report only findings missing from this baseline and exit 1
+    /// if there are any. On the first run the file is created from the
+    /// current findings and the exit code is 0.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["baseline", "write_baseline"])]
+    fail_on_new: Option<PathBuf>,
+
+    /// Also write the SARIF report to this file.
+    #[arg(long, value_name = "FILE")]
+    sarif: Option<PathBuf>,
+
+    /// Also write the JSON report to this file.
+    #[arg(long, value_name = "FILE")]
+    json: Option<PathBuf>,
+
+    /// Also write the Markdown report to this file.
+    #[arg(long, value_name = "FILE")]
+    markdown: Option<PathBuf>,
+
+    /// Also write the JUnit XML report to this file.
+    #[arg(long, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// Print the available autofixes as a unified diff instead of a report.
+    #[arg(long)]
+    diff: bool,
+
+    /// Serve the HTTP API on this address (e.g. 127.0.0.1:8080) instead of
+    /// scanning paths.
+    #[arg(long, value_name = "ADDR")]
+    serve: Option<SocketAddr>,
+
+    /// With `--serve`, save scans posted with a `repo` to this SQLite
+    /// database and expose their finding counts at `GET /metrics`.
+    #[arg(long, value_name = "DB", requires = "serve")]
+    history: Option<PathBuf>,
+
+    /// Keep running, printing new and fixed findings whenever a file under
+    /// the first path changes.
+    #[arg(long)]
+    watch: bool,
+
+    /// Log a JSON line to stderr for every file scanned and detector run,
+    /// filtered by `RUST_LOG`.
+    #[arg(long)]
+    log_json: bool,
+
+    /// Never color text output. Color is otherwise used when stdout is a
+    /// terminal and `NO_COLOR` is unset.
+    #[arg(long)]
+    no_color: bool,
+
+    /// Never show the progress bar. It is otherwise drawn on stderr for
+    /// human-readable formats when stdout and stderr are terminals.
+    #[arg(long)]
+    no_progress: bool,
+
+    /// Group text output under the instruction handler of each finding,
+    /// with per-handler subtotals.
+    #[arg(long, value_enum, value_name = "KEY")]
+    group_by: Option<GroupBy>,
+
+    /// Repository name reported to the webhook; defaults to the first path.
+    #[arg(long)]
+    repo: Option<String>,
+
+    /// Commit reported to the webhook.
+    #[arg(long)]
+    commit: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the documentation of a detector.
+    Explain {
+        /// Namespaced id or short name, e.g. `unchecked_arithmetic`.
+        detector: String,
+    },
+    /// Write a commented `audit.toml` listing every detector.
+    Init {
+        /// Where to write the file.
+        #[arg(default_value = config::CONFIG_FILE)]
+        path: PathBuf,
+        /// Replace the file if it already exists.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    Markdown,
+    Sarif,
+    /// One JSON object per line, streamed as files are analysed.
+    Ndjson,
+    /// GitHub Actions annotations.
+    Github,
+    /// JUnit XML with a test suite per detector.
+    Junit,
+    /// Only the per-severity counts, total and risk score.
+    Summary,
+    /// Findings nested under the `declare_id!` program they belong to.
+    Workspace,
+    /// One row per finding, sized to the terminal.
+    Table,
+}
+
+impl Format {
+    /// Whether the output is meant for people rather than tools.
+    fn is_human(self) -> bool {
+        matches!(
+            self,
+            Format::Text | Format::Markdown | Format::Summary | Format::Workspace | Format::Table
+        )
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GroupBy {
+    /// The enclosing `pub fn` handler.
+    Handler,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if cli.log_json {
+        logging::init_json();
+    }
+    match &cli.command {
+        Some(Command::Explain { detector }) => {
+            return match explain(detector) {
+                Ok(text) => {
+                    print!("{text}");
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    ExitCode::from(EXIT_ERROR)
+                }
+            };
+        }
+        Some(Command::Init { path, force }) => {
+            return match config::write_template(path, *force) {
+                Ok(()) => {
+                    println!("wrote {}", path.display());
+                    ExitCode::SUCCESS
+                }
+                Err(err @ anchor_audit::Error::Exists { .. }) => {
+                    eprintln!("error: {err}; pass --force to overwrite it");
+                    ExitCode::from(EXIT_ERROR)
+                }
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    ExitCode::from(EXIT_ERROR)
+                }
+            };
+        }
+        None => {}
+    }
+
+    let mut registry = Registry::default();
+    let mut notify = NotifyConfig::default();
+    let mut limits = LimitsConfig::default();
+    let config = cli
+        .config
+        .clone()
+        .or_else(|| cli.paths.first().and_then(|path| Config::discover(path)));
+    if let Some(path) = config {
+        match Config::load(&path) {
+            Ok(config) => {
+                config.apply(&mut registry);
+                if let Some(dir) = &config.plugins.dir {
+                    if let Err(err) = plugin::load_dir(dir, &mut registry) {
+                        eprintln!("error: {err}");
+                        return ExitCode::from(EXIT_ERROR);
+                    }
+                }
+                notify = config.notify;
+                limits = config.limits;
+            }
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+    }
+    registry.enabled.extend(cli.enable.iter().cloned());
+    registry.disabled.extend(cli.disable.iter().cloned());
+    let unknown = registry.unknown_ids();
+    if !unknown.is_empty() {
+        eprintln!("error: unknown detector id(s): {}", unknown.join(", "));
+        return ExitCode::from(EXIT_ERROR);
+    }
+    if let Some(addr) = cli.serve {
+        let history = match cli.history.as_deref().map(Storage::open).transpose() {
+            Ok(history) => history,
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::from(EXIT_ERROR);
+            }
+        };
+        let options = ServerOptions {
+            registry,
+            history,
+            max_source_bytes: limits
+                .max_source_bytes
+                .unwrap_or(server::DEFAULT_MAX_SOURCE_BYTES),
+        };
+        return serve(addr, options);
+    }
+    let options = ScanOptions {
+        min_severity: cli.min_severity,
+        jobs: cli.jobs,
+        registry,
+        progress: Progress::stderr(
+            !cli.no_progress && !cli.watch && !cli.diff && cli.format.is_human(),
+        ),
+        max_files: limits.max_files,
+    };
+    let color =
+        !cli.no_color && std::env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+    if cli.watch {
+        return watch(&cli.paths[0], options, color);
+    }
+
+    let artifacts = artifacts(&cli);
+    let stream = streams(&cli, &artifacts);
+    let mut stdout = io::stdout().lock();
+    let mut scan = ScanReport::default();
+    let mut failed = false;
+    for path in &cli.paths {
+        let result = if stream {
+            scanner::scan_streaming(path, &options, |finding| {
+                let _ = writeln!(stdout, "{}", report::ndjson::finding_line(finding));
+            })
+        } else {
+            scanner::scan_with(path, &options)
+        };
+        match result {
+            Ok(report) => {
+                for err in &report.parse_errors {
+                    eprintln!("error: {err}");
+                }
+                scan.merge(report);
+            }
+            Err(err) => {
+                eprintln!("error: {err}");
+                failed = true;
+            }
+        }
+    }
+
+    if let Some(path) = &cli.write_baseline {
+        if let Err(err) = baseline::write_baseline(&scan.findings, path) {
+            eprintln!("error: {err}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    }
+    if let Some(path) = &cli.baseline {
+        match baseline::diff_against_baseline(std::mem::take(&mut scan.findings), path) {
+            Ok(diff) => scan.keep_only(report_baseline_diff(diff)),
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+    }
+    if let Some(path) = &cli.fail_on_new {
+        match baseline::gate(std::mem::take(&mut scan.findings), path) {
+            Ok(Gate::Compared(diff)) => scan.keep_only(report_baseline_diff(diff)),
+            Ok(Gate::Created { accepted }) => {
+                eprintln!(
+                    "baseline: recorded {accepted} finding(s) in {}",
+                    path.display()
+                );
+                let parse_errors = failed || !scan.parse_errors.is_empty();
+                return if parse_errors {
+                    ExitCode::from(EXIT_ERROR)
+                } else {
+                    ExitCode::SUCCESS
+                };
+            }
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+    }
+
+    if cli.diff {
+        return match fix::to_patch(&scan.findings) {
+            Ok(patch) => {
+                print!("{patch}");
+                ExitCode::from(scan.exit_code())
+            }
+            Err(err) => {
+                eprintln!("error: {err}");
+                ExitCode::from(EXIT_ERROR)
+            }
+        };
+    }
+
+    let findings = &scan.findings;
+    if let Err(err) = artifact::write_all(&artifacts, findings, &scan.summary, &scan.programs) {
+        eprintln!("error: {err}");
+        return ExitCode::from(EXIT_ERROR);
+    }
+    match cli.format {
+        Format::Text => match cli.group_by {
+            Some(GroupBy::Handler) => {
+                print!(
+                    "{}",
+                    report::text::render_by_handler(findings, &scan.summary, color)
+                )
+            }
+            None => print!("{}", report::text::render(findings, &scan.summary, color)),
+        },
+        Format::Json => {
+            let json = report::json::to_json(findings, &scan.summary, &scan.programs);
+            println!("{json:#}");
+        }
+        Format::Markdown => print!("{}", report::markdown::to_markdown(findings)),
+        Format::Sarif => println!("{:#}", report::sarif::to_sarif(findings)),
+        Format::Ndjson => {
+            for finding in findings {
+                let _ = writeln!(stdout, "{}", report::ndjson::finding_line(finding));
+            }
+            let _ = writeln!(stdout, "{}", report::ndjson::summary_line(&scan.summary));
+        }
+        Format::Github => print!("{}", report::github::to_annotations(findings)),
+        Format::Junit => print!("{}", report::junit::to_junit(findings)),
+        Format::Summary => print!("{}", report::text::summary(&scan.summary)),
+        Format::Workspace => {
+            let workspace = WorkspaceReport::new(&scan.programs, findings);
+            print!(
+                "{}",
+                report::text::render_workspace(&workspace, &scan.summary, color)
+            );
+        }
+        Format::Table => print!(
+            "{}",
+            report::table::to_table(findings, &scan.summary, terminal_width())
+        ),
+    }
+    if let Some(webhook) = Webhook::configured(&notify) {
+        let repo = cli.repo.clone().unwrap_or_else(|| {
+            cli.paths
+                .first()
+                .map_or_else(String::new, |path| path.display().to_string())
+        });
+        let payload = Payload::new(&repo, cli.commit.as_deref(), &scan.summary);
+        if let Err(err) = webhook.send(&payload) {
+            eprintln!("warning: {err}");
+        }
+    }
+    if failed {
+        ExitCode::from(EXIT_ERROR)
+    } else {
+        ExitCode::from(scan.exit_code())
+    }
+}
+
+/// Width of the terminal on stdout, else `$COLUMNS`, else the table default.
+fn terminal_width() -> usize {
+    console::Term::stdout()
+        .size_checked()
+        .map(|(_, columns)| usize::from(columns))
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(report::table::DEFAULT_WIDTH)
+}
+
+/// Prints the baseline counts and returns the new findings, the only ones
+/// reported.
+fn report_baseline_diff(diff: BaselineDiff) -> Vec<anchor_audit::Finding> {
+    eprintln!(
+        "baseline: {} new, {} fixed, {} unchanged",
+        diff.new.len(),
+        diff.fixed.len(),
+        diff.unchanged.len()
+    );
+    diff.new
+}
+
+/// The report files requested with `--sarif`, `--json`, `--markdown` and
+/// `--junit`.
+fn artifacts(cli: &Cli) -> Vec<Artifact> {
+    [
+        (artifact::Format::Sarif, &cli.sarif),
+        (artifact::Format::Json, &cli.json),
+        (artifact::Format::Markdown, &cli.markdown),
+        (artifact::Format::Junit, &cli.junit),
+    ]
+    .into_iter()
+    .filter_map(|(format, path)| Some(Artifact::new(format, path.clone()?)))
+    .collect()
+}
+
+/// Whether ndjson findings can be printed as they are found. Streaming
+/// only works when nothing needs the full list afterwards: a streamed scan
+/// keeps no findings.
+fn streams(cli: &Cli, artifacts: &[Artifact]) -> bool {
+    matches!(cli.format, Format::Ndjson)
+        && artifacts.is_empty()
+        && cli.baseline.is_none()
+        && cli.write_baseline.is_none()
+        && cli.fail_on_new.is_none()
+        && !cli.diff
+}
+
+fn explain(id: &str) -> Result<String, String> {
+    match detectors::lookup(id) {
+        Some(info) => Ok(report::text::explain(info)),
+        None => Err(format!(
+            "unknown detector id `{id}`; known ids: {}",
+            detectors::catalog()
+                .map(|info| info.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+fn watch(root: &Path, options: ScanOptions, color: bool) -> ExitCode {
+    eprintln!("watching {} (Ctrl-C to stop)", root.display());
+    let watched = anchor_audit::watch::watch(root, options, |delta| {
+        for err in &delta.parse_errors {
+            eprintln!("error: {err}");
+        }
+        print!("{}", report::text::render_delta(delta, color));
+    });
+    match watched {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn serve(addr: SocketAddr, options: ServerOptions) -> ExitCode {
+    let served = tokio::runtime::Runtime::new().and_then(|runtime| {
+        eprintln!("listening on http://{addr}");
+        runtime.block_on(server::serve(addr, options))
+    });
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explain_args(args: &[&str]) -> Result<String, String> {
+        let cli = Cli::try_parse_from(args).unwrap();
+        let Some(Command::Explain { detector }) = cli.command else {
+            panic!("`{}` is not the explain command", args.join(" "));
+        };
+        explain(&detector)
+    }
+
+    #[test]
+    fn explains_unchecked_arithmetic() {
+        let text = explain_args(&["anchor-audit", "explain", "unchecked_arithmetic"]).unwrap();
+        assert!(text.starts_with("rust/unchecked-arithmetic (unchecked_arithmetic)"));
+        assert!(text.contains("category: Arithmetic"));
+        assert!(text.contains("    vault.balance = vault.balance + amount;"));
+        assert!(text.contains(
+            "    vault.balance = vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;"
+        ));
+        assert!(text.contains("References:\n    https://"));
+    }
+
+    #[test]
+    fn unknown_detector_is_an_error() {
+        let err = explain_args(&["anchor-audit", "explain", "no_such_detector"]).unwrap_err();
+        assert!(err.starts_with("unknown detector id `no_such_detector`"));
+    }
+
+    #[test]
+    fn ndjson_streams_only_without_artifacts() {
+        let streams_with = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).unwrap();
+            streams(&cli, &artifacts(&cli))
+        };
+        assert!(streams_with(&[
+            "anchor-audit",
+            "--format",
+            "ndjson",
+            "contracts"
+        ]));
+        for artifact in ["--json", "--sarif", "--markdown", "--junit"] {
+            let args = [
+                "anchor-audit",
+                "--format",
+                "ndjson",
+                artifact,
+                "out",
+                "contracts",
+            ];
+            assert!(!streams_with(&args), "{artifact}");
+        }
+        assert!(!streams_with(&["anchor-audit", "contracts"]));
+    }
+
+    #[test]
+    fn paths_still_parse_without_a_command() {
+        let cli = Cli::try_parse_from(["anchor-audit", "contracts"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.paths, [PathBuf::from("contracts")]);
+    }
+}
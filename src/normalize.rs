@@ -0,0 +1,240 @@
+//! A canonical view of findings for comparing scans across runs and
+//! machines.
+//!
+//! Two scans of the same code can differ in ways that have nothing to do
+//! with the code: one checkout lives in `/home/ci/work`, another in
+//! `/tmp/build`, and detectors run in parallel so findings arrive in any
+//! order. [`normalize`] rewrites paths relative to the scanned root, drops
+//! what only matters for display, such as the file text each finding
+//! carries for its snippet, recomputes the [`fingerprint`] from the
+//! relative path and sorts the result. [Baselines](crate::report::baseline),
+//! and with them `--fail-on-new`, match findings this way, and any external
+//! diffing of scan output can compare entries as plain values.
+//!
+//! Finding paths are whatever the scan was given, often relative to the
+//! working directory. They are resolved against it before being made
+//! relative to the root, so scanning `contracts` from the repository and
+//! `.` from inside `contracts` normalize alike.
+
+use std::path::{self, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::finding::{fingerprint, portable_path, Confidence, Finding, Severity, Span};
+
+/// The comparable part of a [`Finding`]. Fields are declared in sort
+/// order: file, position, then detector and message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct NormalizedFinding {
+    /// Path relative to the scanned root, with `/` separators.
+    pub file: String,
+    pub line_start: usize,
+    pub col_start: usize,
+    pub line_end: usize,
+    pub col_end: usize,
+    pub detector: &'static str,
+    pub message: String,
+    pub severity: Severity,
+    pub confidence: Confidence,
+    pub handler: Option<String>,
+    pub suggestion: Option<String>,
+    /// [`Finding::fingerprint`] computed from the relative path, so it is
+    /// the same wherever the root was checked out.
+    pub fingerprint: String,
+}
+
+impl NormalizedFinding {
+    /// `finding` with its file made [relative](relative_to) to `root`.
+    /// Files outside `root` keep their path, made [portable](portable_path).
+    pub fn new(finding: &Finding, root: &Path) -> Self {
+        let relative = relative_to(&finding.span.file, root);
+        let span = Span {
+            file: relative.clone(),
+            ..finding.span.clone()
+        };
+        NormalizedFinding {
+            file: portable_path(&relative),
+            line_start: span.line_start,
+            col_start: span.col_start,
+            line_end: span.line_end,
+            col_end: span.col_end,
+            detector: finding.detector,
+            message: finding.message.clone(),
+            severity: finding.severity,
+            confidence: finding.confidence,
+            handler: finding.handler.clone(),
+            suggestion: finding.suggestion.clone(),
+            fingerprint: fingerprint(
+                finding.detector,
+                finding.handler.as_deref(),
+                &finding.source.0,
+                &span,
+            ),
+        }
+    }
+}
+
+/// `file` relative to `root` when it is inside it, and `file` unchanged
+/// otherwise. Relative paths on either side are taken to be relative to
+/// the working directory.
+pub fn relative_to(file: &Path, root: &Path) -> PathBuf {
+    if let Ok(relative) = file.strip_prefix(root) {
+        return relative.to_path_buf();
+    }
+    let (Ok(absolute_file), Ok(absolute_root)) = (path::absolute(file), path::absolute(root))
+    else {
+        return file.to_path_buf();
+    };
+    absolute_file
+        .strip_prefix(&absolute_root)
+        .map_or_else(|_| file.to_path_buf(), Path::to_path_buf)
+}
+
+/// `findings` of a scan of `root` in canonical form and order.
+pub fn normalize(findings: &[Finding], root: &Path) -> Vec<NormalizedFinding> {
+    let mut normalized: Vec<_> = findings
+        .iter()
+        .map(|finding| NormalizedFinding::new(finding, root))
+        .collect();
+    normalized.sort();
+    normalized
+}
+
+/// [`normalize`]d findings as pretty-printed JSON, one stable document per
+/// scanned tree.
+pub fn to_json(findings: &[Finding], root: &Path) -> String {
+    serde_json::to_string_pretty(&normalize(findings, root))
+        .expect("normalized findings are always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::report::baseline::{self, Gate};
+    use crate::scanner;
+    use crate::test_support::fixture;
+
+    /// A checkout of both fixtures under a fresh temporary directory.
+    fn checkout() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("programs")).unwrap();
+        for name in ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"] {
+            fs::copy(fixture(name), dir.path().join("programs").join(name)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn scans_from_different_directories_normalize_identically() {
+        let (first, second) = (checkout(), checkout());
+        let mut a = scanner::scan(first.path()).unwrap().findings;
+        let b = scanner::scan(second.path()).unwrap().findings;
+        assert!(!a.is_empty());
+        assert_ne!(a[0].span.file, b[0].span.file);
+        let fingerprints = |findings: &[Finding]| {
+            findings
+                .iter()
+                .map(|finding| finding.fingerprint().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            fingerprints(&a),
+            fingerprints(&b),
+            "fingerprints should not depend on the checkout location"
+        );
+
+        a.reverse();
+        assert_eq!(
+            to_json(&a, first.path()),
+            to_json(&b, second.path()),
+            "normalized output should not depend on the checkout location or finding order"
+        );
+        let normalized = normalize(&b, second.path());
+        assert!(normalized
+            .iter()
+            .all(|finding| finding.file.starts_with("programs/")));
+        assert!(normalized.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    /// Environment of [`scan_as_child`]: the tree root, the path to scan
+    /// from the child's working directory, and where to write the result.
+    const CHILD_ROOT: &str = "ANCHOR_AUDIT_TEST_ROOT";
+    const CHILD_SCAN: &str = "ANCHOR_AUDIT_TEST_SCAN";
+    const CHILD_OUT: &str = "ANCHOR_AUDIT_TEST_OUT";
+
+    /// Does nothing unless run by [`scans_from_different_working_directories`]
+    /// in a child process, since the working directory is process-wide.
+    /// Writes the normalized scan, then gates it against `baseline.json` in
+    /// the root, creating it on the first run.
+    #[test]
+    fn scan_as_child() {
+        let Some(root) = std::env::var_os(CHILD_ROOT) else {
+            return;
+        };
+        let root = Path::new(&root);
+        let scan = std::env::var_os(CHILD_SCAN).unwrap();
+        let findings = scanner::scan(Path::new(&scan)).unwrap().findings;
+        let mut out = to_json(&findings, root);
+        for finding in &findings {
+            out += &format!("\n{}", finding.fingerprint());
+        }
+        match baseline::gate(findings, &root.join("baseline.json")).unwrap() {
+            Gate::Created { .. } => out += "\ncreated",
+            Gate::Compared(diff) => {
+                out += &format!("\nnew: {}, fixed: {}", diff.new.len(), diff.fixed.len())
+            }
+        }
+        fs::write(std::env::var_os(CHILD_OUT).unwrap(), out).unwrap();
+    }
+
+    #[test]
+    fn scans_from_different_working_directories() {
+        let dir = checkout();
+        let root = dir.path().canonicalize().unwrap();
+        let run = |cwd: &Path, scan: &str| {
+            let out = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+            let status = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", "normalize::tests::scan_as_child", "--quiet"])
+                .current_dir(cwd)
+                .env(CHILD_ROOT, &root)
+                .env(CHILD_SCAN, scan)
+                .env(CHILD_OUT, &out)
+                .stdout(std::process::Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success());
+            fs::read_to_string(&out).unwrap()
+        };
+
+        let from_root = run(&root, "programs");
+        let from_programs = run(&root.join("programs"), ".");
+        let (json, gated) = from_root.rsplit_once('\n').unwrap();
+        assert_eq!(gated, "created");
+        assert!(json.contains("\"file\": \"programs/vulnerable_vault.rs\""));
+        assert_eq!(
+            from_programs,
+            format!("{json}\nnew: 0, fixed: 0"),
+            "normalized findings and baseline matches should not depend on the working directory"
+        );
+    }
+
+    #[test]
+    fn files_outside_the_root_keep_their_path() {
+        let findings = crate::scan_file(&fixture("vulnerable_vault.rs")).unwrap();
+        let contracts = Path::new(env!("CARGO_MANIFEST_DIR")).join("contracts");
+        let normalized = normalize(&findings, &contracts);
+        assert_eq!(normalized.len(), findings.len());
+        assert!(normalized
+            .iter()
+            .all(|finding| finding.file == "sources/vulnerable_vault.rs"));
+        let outside = normalize(&findings, Path::new("elsewhere"));
+        assert_eq!(outside[0].file, "contracts/sources/vulnerable_vault.rs");
+        // The scan's own fingerprints are relative to the file's directory.
+        let sources = normalize(&findings, &contracts.join("sources"));
+        assert!(sources.iter().all(|normalized| findings
+            .iter()
+            .any(|finding| finding.fingerprint() == normalized.fingerprint)));
+    }
+}
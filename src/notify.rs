@@ -0,0 +1,213 @@
+//! Webhook notification after a scan.
+//!
+//! The summary is POSTed as JSON to a URL taken from `$ANCHOR_AUDIT_WEBHOOK`
+//! or the `[notify]` table of `audit.toml`:
+//!
+//! ```json
+//! { "repo": "vault", "commit": "4f2c1e0", "summary": { ... }, "risk_score": 83 }
+//! ```
+//!
+//! Server errors and timeouts are retried with exponential backoff. A
+//! notification that still fails is the caller's to log; it never changes
+//! the outcome of the scan.
+
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::config::NotifyConfig;
+use crate::error::{Error, Result};
+use crate::report::Summary;
+
+/// Environment variable overriding the configured webhook URL.
+pub const WEBHOOK_ENV: &str = "ANCHOR_AUDIT_WEBHOOK";
+
+/// Body of the webhook request.
+#[derive(Debug, Serialize)]
+pub struct Payload<'a> {
+    pub repo: &'a str,
+    pub commit: Option<&'a str>,
+    pub summary: &'a Summary,
+    pub risk_score: u32,
+}
+
+impl<'a> Payload<'a> {
+    pub fn new(repo: &'a str, commit: Option<&'a str>, summary: &'a Summary) -> Self {
+        Payload {
+            repo,
+            commit,
+            summary,
+            risk_score: summary.risk_score,
+        }
+    }
+}
+
+/// Where and how persistently to deliver a [`Payload`].
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub url: String,
+    /// Limit for each attempt, connecting included.
+    pub timeout: Duration,
+    /// Attempts after the first failed one.
+    pub retries: u32,
+    /// Wait before the first retry; doubled for each one after.
+    pub backoff: Duration,
+}
+
+impl Webhook {
+    pub fn new(url: impl Into<String>) -> Self {
+        Webhook {
+            url: url.into(),
+            timeout: Duration::from_secs(10),
+            retries: 2,
+            backoff: Duration::from_millis(500),
+        }
+    }
+
+    /// The webhook from [`WEBHOOK_ENV`] or else `config`, with the
+    /// configured retry and timeout settings; `None` when neither sets a
+    /// URL.
+    pub fn configured(config: &NotifyConfig) -> Option<Self> {
+        let url = std::env::var(WEBHOOK_ENV)
+            .ok()
+            .filter(|url| !url.is_empty())
+            .or_else(|| config.webhook.clone())?;
+        let mut webhook = Webhook::new(url);
+        if let Some(retries) = config.retries {
+            webhook.retries = retries;
+        }
+        if let Some(secs) = config.timeout_secs {
+            webhook.timeout = Duration::from_secs(secs);
+        }
+        Some(webhook)
+    }
+
+    /// POSTs `payload`, retrying transport failures and 5xx responses.
+    pub fn send(&self, payload: &Payload<'_>) -> Result<()> {
+        let body = serde_json::to_string(payload).expect("payload is always serializable");
+        let agent = ureq::AgentBuilder::new().timeout(self.timeout).build();
+        let mut backoff = self.backoff;
+        let mut attempt = 0;
+        loop {
+            let result = agent
+                .post(&self.url)
+                .set("Content-Type", "application/json")
+                .send_string(&body);
+            let (message, retryable) = match result {
+                Ok(_) => return Ok(()),
+                Err(ureq::Error::Status(status, _)) => {
+                    (format!("server answered {status}"), status >= 500)
+                }
+                Err(ureq::Error::Transport(transport)) => {
+                    let kind = transport.kind();
+                    match std::error::Error::source(&transport) {
+                        Some(cause) => (format!("{kind}: {cause}"), true),
+                        None => (kind.to_string(), true),
+                    }
+                }
+            };
+            if !retryable || attempt == self.retries {
+                return Err(Error::Notify {
+                    url: self.url.clone(),
+                    message,
+                });
+            }
+            attempt += 1;
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_support::scan_fixtures;
+
+    /// Serves `POST /hook` answering with `statuses` in turn, then 200,
+    /// and records every body received.
+    fn mock_server(
+        statuses: Vec<StatusCode>,
+    ) -> (tokio::runtime::Runtime, String, Arc<Mutex<Vec<Value>>>) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&received);
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<Value>| {
+                let log = Arc::clone(&log);
+                let statuses = statuses.clone();
+                async move {
+                    let mut log = log.lock().unwrap();
+                    log.push(body);
+                    statuses
+                        .get(log.len() - 1)
+                        .copied()
+                        .unwrap_or(StatusCode::OK)
+                }
+            }),
+        );
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        runtime.spawn(async move { axum::serve(listener, app).await });
+        (runtime, url, received)
+    }
+
+    fn webhook(url: &str) -> Webhook {
+        Webhook {
+            backoff: Duration::from_millis(10),
+            ..Webhook::new(url)
+        }
+    }
+
+    #[test]
+    fn posts_summary_and_retries_server_errors() {
+        let (_runtime, url, received) = mock_server(vec![StatusCode::BAD_GATEWAY]);
+        let summary = Summary::of(&scan_fixtures());
+        webhook(&url)
+            .send(&Payload::new("vault", Some("4f2c1e0"), &summary))
+            .unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], received[1]);
+        let body = &received[1];
+        assert_eq!(body["repo"], "vault");
+        assert_eq!(body["commit"], "4f2c1e0");
+        assert_eq!(body["risk_score"], summary.risk_score);
+        assert_eq!(body["summary"], serde_json::to_value(&summary).unwrap());
+        let keys: Vec<_> = body.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["repo", "commit", "summary", "risk_score"]);
+    }
+
+    #[test]
+    fn gives_up_on_client_errors_and_exhausted_retries() {
+        let (_runtime, url, received) = mock_server(vec![StatusCode::NOT_FOUND]);
+        let summary = Summary::default();
+        let err = webhook(&url)
+            .send(&Payload::new("vault", None, &summary))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("webhook {url}: server answered 404")
+        );
+        assert_eq!(received.lock().unwrap().len(), 1);
+        assert_eq!(received.lock().unwrap()[0]["commit"], json!(null));
+
+        let (_runtime, url, received) = mock_server(vec![StatusCode::INTERNAL_SERVER_ERROR; 3]);
+        assert!(webhook(&url)
+            .send(&Payload::new("vault", None, &summary))
+            .is_err());
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+}
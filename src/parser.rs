@@ -0,0 +1,861 @@
+//! Parsing of Anchor programs into a typed, linked representation.
+//!
+//! Each source file is parsed with [`syn::parse_file`]. The `#[program]`
+//! module, its `pub fn` instruction handlers and the `#[derive(Accounts)]`
+//! structs are pulled out of the syntax tree and linked together, so
+//! detectors work on real AST nodes instead of matching source text.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use proc_macro2::{Punct, Spacing, TokenStream, TokenTree};
+use sha2::{Digest, Sha256};
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Fields, FnArg, GenericArgument, Item, ItemFn, ItemMod, ItemStruct, LitStr, Meta,
+    PathArguments, Type, Visibility,
+};
+
+use crate::error::{Error, ParseError, Result};
+
+/// A parsed source file together with the Anchor items found in it.
+#[derive(Debug)]
+pub struct ParsedProgram {
+    pub path: PathBuf,
+    /// The program address from `declare_id!("...")`, if the file has one.
+    pub declared_id: Option<String>,
+    /// The text parsed, shared with every finding in the file.
+    pub source: Arc<str>,
+    pub file: syn::File,
+    /// The `#[program]` module, if the file declares one.
+    pub program: Option<ProgramModule>,
+    /// Every `#[derive(Accounts)]` struct in the file.
+    pub accounts: Vec<AccountsStruct>,
+    /// Every `#[account]` state struct in the file.
+    pub state: Vec<ItemStruct>,
+    /// Comments, which `syn` drops from the token stream.
+    pub comments: Vec<Comment>,
+}
+
+/// A `//` or `/* */` comment found in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// 1-based line the comment starts on.
+    pub line: usize,
+    /// Text without the comment markers, trimmed.
+    pub text: String,
+    /// Whether code precedes the comment on the same line.
+    pub trailing: bool,
+}
+
+/// The `#[program]` module of an Anchor program.
+#[derive(Debug)]
+pub struct ProgramModule {
+    pub name: String,
+    pub item: ItemMod,
+    pub handlers: Vec<Handler>,
+}
+
+/// A `pub fn` instruction handler inside the `#[program]` module.
+#[derive(Debug)]
+pub struct Handler {
+    pub name: String,
+    pub item: ItemFn,
+    /// Name of the Accounts struct from the `Context<T>` argument.
+    pub context: Option<String>,
+}
+
+impl Handler {
+    /// The 8 bytes Anchor prefixes instruction data with to select this
+    /// handler; see [`discriminator`].
+    pub fn discriminator(&self) -> [u8; 8] {
+        discriminator(&self.name)
+    }
+}
+
+/// Anchor's instruction discriminator for the handler `name`: the first 8
+/// bytes of `sha256("global:<name>")`.
+pub fn discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{name}"));
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    bytes
+}
+
+/// A `#[derive(Accounts)]` struct.
+#[derive(Debug)]
+pub struct AccountsStruct {
+    pub name: String,
+    pub item: ItemStruct,
+    pub fields: Vec<AccountField>,
+}
+
+/// One field of an Accounts struct.
+#[derive(Debug)]
+pub struct AccountField {
+    pub name: String,
+    pub kind: AccountKind,
+    /// Entries of the field's `#[account(...)]` attributes.
+    pub constraints: Vec<Constraint>,
+    pub field: syn::Field,
+}
+
+/// One entry of an `#[account(...)]` attribute, such as `mut`,
+/// `has_one = authority` or `space = 8 + 200`.
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    /// `mut`, `has_one`, `token::mint`, ...
+    pub key: String,
+    /// Tokens after the `=`, if any.
+    pub value: Option<TokenStream>,
+}
+
+impl Constraint {
+    /// The value with spacing normalised, e.g. `8+200`.
+    pub fn value_text(&self) -> Option<String> {
+        self.value
+            .as_ref()
+            .map(|value| value.to_string().split_whitespace().collect())
+    }
+}
+
+/// The Anchor wrapper type of an account field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountKind {
+    /// `Account<'info, T>`, carrying `T`.
+    Account(String),
+    AccountInfo,
+    UncheckedAccount,
+    Signer,
+    /// `Program<'info, T>`, carrying `T`.
+    Program(String),
+    SystemAccount,
+    Other(String),
+}
+
+impl ParsedProgram {
+    /// Handlers of the `#[program]` module, empty when there is none.
+    pub fn handlers(&self) -> &[Handler] {
+        self.program
+            .as_ref()
+            .map_or(&[][..], |program| &program.handlers)
+    }
+
+    pub fn accounts_struct(&self, name: &str) -> Option<&AccountsStruct> {
+        self.accounts.iter().find(|accounts| accounts.name == name)
+    }
+
+    /// The Accounts struct named by `handler`'s `Context<T>`.
+    pub fn accounts_for(&self, handler: &Handler) -> Option<&AccountsStruct> {
+        self.accounts_struct(handler.context.as_deref()?)
+    }
+
+    /// Handlers whose context is the Accounts struct `name`.
+    pub fn handlers_using<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Handler> + 'a {
+        self.handlers()
+            .iter()
+            .filter(move |handler| handler.context.as_deref() == Some(name))
+    }
+
+    /// The handler `line` belongs to: the one whose body contains it, or
+    /// else the first one using the Accounts struct that contains it.
+    pub fn handler_at(&self, line: usize) -> Option<&Handler> {
+        let contains =
+            |span: proc_macro2::Span| (span.start().line..=span.end().line).contains(&line);
+        if let Some(handler) = self.handlers().iter().find(|h| contains(h.item.span())) {
+            return Some(handler);
+        }
+        let accounts = self.accounts.iter().find(|a| contains(a.item.span()))?;
+        self.handlers_using(&accounts.name).next()
+    }
+
+    pub fn state_struct(&self, name: &str) -> Option<&ItemStruct> {
+        self.state.iter().find(|item| item.ident == name)
+    }
+
+    /// Type name of `field` on the state behind `handler`'s `account`, e.g.
+    /// `u64` for `vault.balance` where `vault: Account<'info, Vault>`.
+    pub fn state_field_type(
+        &self,
+        handler: &Handler,
+        account: &str,
+        field: &str,
+    ) -> Option<String> {
+        let AccountKind::Account(state) = &self.accounts_for(handler)?.field(account)?.kind else {
+            return None;
+        };
+        let Fields::Named(fields) = &self.state_struct(state)?.fields else {
+            return None;
+        };
+        let field = fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|ident| ident == field))?;
+        type_parts(&field.ty).map(|(name, _)| name)
+    }
+}
+
+impl Handler {
+    /// Type name of the argument `name`, e.g. `u64` for `amount: u64`.
+    pub fn arg_type(&self, name: &str) -> Option<String> {
+        self.item.sig.inputs.iter().find_map(|input| match input {
+            FnArg::Typed(arg) if crate::ast::pat_ident(&arg.pat).as_deref() == Some(name) => {
+                type_parts(&arg.ty).map(|(ty, _)| ty)
+            }
+            _ => None,
+        })
+    }
+}
+
+impl AccountField {
+    pub fn constraint(&self, key: &str) -> Option<&Constraint> {
+        self.constraints.iter().find(|c| c.key == key)
+    }
+
+    pub fn has_constraint(&self, key: &str) -> bool {
+        self.constraint(key).is_some()
+    }
+
+    /// Every value given for `key`, e.g. each `has_one = x`.
+    pub fn constraint_values<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a TokenStream> {
+        self.constraints
+            .iter()
+            .filter(move |c| c.key == key)
+            .filter_map(|c| c.value.as_ref())
+    }
+
+    pub fn is_mut(&self) -> bool {
+        self.has_constraint("mut")
+    }
+
+    /// Lines of the field's `///` doc comment, with the leading space
+    /// removed.
+    pub fn docs(&self) -> Vec<String> {
+        self.field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .filter_map(|attr| match &attr.meta {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    value:
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(text),
+                            ..
+                        }),
+                    ..
+                }) => Some(text.value().trim().to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Span of the field name, which is what findings point at.
+    pub fn span(&self) -> proc_macro2::Span {
+        self.field
+            .ident
+            .as_ref()
+            .map_or_else(|| self.field.span(), |ident| ident.span())
+    }
+}
+
+impl AccountsStruct {
+    pub fn field(&self, name: &str) -> Option<&AccountField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Reads and parses the file at `path`.
+pub fn parse_file(path: &Path) -> Result<ParsedProgram> {
+    let source = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(parse_source(path, source)?)
+}
+
+/// Parses in-memory `source`, attributing it to `path`.
+pub fn parse_source(
+    path: impl Into<PathBuf>,
+    source: String,
+) -> std::result::Result<ParsedProgram, ParseError> {
+    let path = path.into();
+    let file = syn::parse_file(&source).map_err(|err| ParseError::from_syn(path.clone(), &err))?;
+
+    let mut program = None;
+    let mut accounts = Vec::new();
+    let mut state = Vec::new();
+    collect_items(&file.items, &mut program, &mut accounts, &mut state);
+    let comments = collect_comments(&source);
+
+    Ok(ParsedProgram {
+        declared_id: declared_id(&file.items),
+        path,
+        source: source.into(),
+        file,
+        program,
+        accounts,
+        state,
+        comments,
+    })
+}
+
+/// Frees the sources parsed on the calling thread so far.
+///
+/// With span locations enabled, proc-macro2 keeps a copy of every source it
+/// tokenizes in a thread-local map, so that spans can report their line and
+/// column, and never drops it. Long-running callers, the server and watch
+/// mode, call this once the findings of their parses have been converted to
+/// [`Span`](crate::finding::Span)s. Syntax trees parsed on this thread
+/// before the call must not be used after it: their spans panic when asked
+/// for a location.
+pub fn release_spans() {
+    proc_macro2::extra::invalidate_current_thread_spans();
+}
+
+/// Finds every comment in `source`, skipping string and char literals.
+fn collect_comments(source: &str) -> Vec<Comment> {
+    let mut lexer = CommentLexer {
+        source,
+        bytes: source.as_bytes(),
+        pos: 0,
+        line: 1,
+        line_has_code: false,
+        comments: Vec::new(),
+    };
+    lexer.run();
+    lexer.comments
+}
+
+struct CommentLexer<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+    line_has_code: bool,
+    comments: Vec<Comment>,
+}
+
+impl CommentLexer<'_> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    /// Advances one byte, keeping track of lines.
+    fn bump(&mut self) {
+        if self.peek(0) == Some(b'\n') {
+            self.line += 1;
+            self.line_has_code = false;
+        }
+        self.pos += 1;
+    }
+
+    fn run(&mut self) {
+        while let Some(byte) = self.peek(0) {
+            match (byte, self.peek(1)) {
+                (b'/', Some(b'/')) => self.line_comment(),
+                (b'/', Some(b'*')) => self.block_comment(),
+                (b'"', _) => self.string(None),
+                (b'r', Some(b'"' | b'#')) if !self.continues_ident() => {
+                    let hashes = self.bytes[self.pos + 1..]
+                        .iter()
+                        .take_while(|&&b| b == b'#')
+                        .count();
+                    if self.peek(1 + hashes) == Some(b'"') {
+                        self.pos += 1 + hashes;
+                        self.string(Some(hashes));
+                    } else {
+                        self.pos += 1;
+                    }
+                }
+                (b'\'', _) => self.char_or_lifetime(),
+                _ => {
+                    self.line_has_code |= !byte.is_ascii_whitespace();
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    /// Whether the byte before the cursor is part of an identifier, so that
+    /// the `r` in `bar"` does not start a raw string. `br"..."` still does.
+    fn continues_ident(&self) -> bool {
+        let Some(&prev) = self.pos.checked_sub(1).and_then(|i| self.bytes.get(i)) else {
+            return false;
+        };
+        prev != b'b' && (prev.is_ascii_alphanumeric() || prev == b'_')
+    }
+
+    fn line_comment(&mut self) {
+        let end = self.source[self.pos..]
+            .find('\n')
+            .map_or(self.source.len(), |n| self.pos + n);
+        let text = self.source[self.pos..end].trim_start_matches(['/', '!']);
+        self.push(self.line, self.line_has_code, text);
+        self.pos = end;
+    }
+
+    fn block_comment(&mut self) {
+        let (start, line, trailing) = (self.pos, self.line, self.line_has_code);
+        let mut depth = 0;
+        while self.pos < self.bytes.len() {
+            match (self.bytes[self.pos], self.peek(1)) {
+                (b'/', Some(b'*')) => {
+                    depth += 1;
+                    self.pos += 2;
+                }
+                (b'*', Some(b'/')) => {
+                    depth -= 1;
+                    self.pos += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => self.bump(),
+            }
+        }
+        let body = &self.source[start + 2..self.pos.saturating_sub(2).max(start + 2)];
+        self.push(line, trailing, body.trim_start_matches(['*', '!']));
+    }
+
+    fn push(&mut self, line: usize, trailing: bool, text: &str) {
+        self.comments.push(Comment {
+            line,
+            text: text.trim().to_string(),
+            trailing,
+        });
+    }
+
+    /// Skips a string literal whose opening quote is at the cursor.
+    /// `raw_hashes` is the number of `#`s of a raw string, which has no escapes.
+    fn string(&mut self, raw_hashes: Option<usize>) {
+        self.line_has_code = true;
+        self.pos += 1;
+        let hashes = raw_hashes.unwrap_or(0);
+        while let Some(byte) = self.peek(0) {
+            match byte {
+                b'\\' if raw_hashes.is_none() => {
+                    self.bump();
+                    self.bump();
+                }
+                b'"' if self.pos + hashes < self.bytes.len()
+                    && self.bytes[self.pos + 1..=self.pos + hashes]
+                        .iter()
+                        .all(|&b| b == b'#') =>
+                {
+                    self.pos += 1 + hashes;
+                    return;
+                }
+                _ => self.bump(),
+            }
+        }
+    }
+
+    /// Skips `'a'` or `'\n'`, or just the quote of a lifetime like `'info`.
+    fn char_or_lifetime(&mut self) {
+        self.line_has_code = true;
+        let next = self.source[self.pos + 1..].chars().next();
+        match next {
+            Some('\\') => {
+                // Skip `'\` and the escaped character, which may itself be `'`.
+                self.pos += 3;
+                while self.peek(0).is_some_and(|b| b != b'\'') {
+                    self.pos += 1;
+                }
+                self.pos += 1;
+            }
+            Some(c) if self.peek(1 + c.len_utf8()) == Some(b'\'') => {
+                self.pos += 2 + c.len_utf8();
+            }
+            _ => self.pos += 1,
+        }
+    }
+}
+
+fn collect_items(
+    items: &[Item],
+    program: &mut Option<ProgramModule>,
+    accounts: &mut Vec<AccountsStruct>,
+    state: &mut Vec<ItemStruct>,
+) {
+    for item in items {
+        match item {
+            Item::Mod(module) if has_attr(&module.attrs, "program") => {
+                program.get_or_insert_with(|| program_module(module));
+            }
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect_items(items, program, accounts, state);
+                }
+            }
+            Item::Struct(item) if derives(&item.attrs, "Accounts") => {
+                accounts.push(accounts_struct(item));
+            }
+            Item::Struct(item) if has_attr(&item.attrs, "account") => {
+                state.push(item.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The string literal of the first `declare_id!`, looking into inline
+/// modules as [`collect_items`] does.
+fn declared_id(items: &[Item]) -> Option<String> {
+    items.iter().find_map(|item| match item {
+        Item::Macro(item) if item.mac.path.is_ident("declare_id") => {
+            item.mac.parse_body::<LitStr>().ok().map(|id| id.value())
+        }
+        Item::Mod(module) => module
+            .content
+            .as_ref()
+            .and_then(|(_, items)| declared_id(items)),
+        _ => None,
+    })
+}
+
+fn program_module(module: &ItemMod) -> ProgramModule {
+    let handlers = module
+        .content
+        .iter()
+        .flat_map(|(_, items)| items)
+        .filter_map(|item| match item {
+            Item::Fn(item) if matches!(item.vis, Visibility::Public(_)) => Some(Handler {
+                name: item.sig.ident.to_string(),
+                context: context_type(item),
+                item: item.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+
+    ProgramModule {
+        name: module.ident.to_string(),
+        item: module.clone(),
+        handlers,
+    }
+}
+
+fn accounts_struct(item: &ItemStruct) -> AccountsStruct {
+    let fields = match &item.fields {
+        Fields::Named(named) => named
+            .named
+            .iter()
+            .filter_map(|field| {
+                Some(AccountField {
+                    name: field.ident.as_ref()?.to_string(),
+                    kind: account_kind(&field.ty),
+                    constraints: account_constraints(&field.attrs),
+                    field: field.clone(),
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    AccountsStruct {
+        name: item.ident.to_string(),
+        item: item.clone(),
+        fields,
+    }
+}
+
+/// Splits every `#[account(...)]` attribute into its comma-separated entries.
+fn account_constraints(attrs: &[Attribute]) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("account")) {
+        let Meta::List(list) = &attr.meta else {
+            continue;
+        };
+        let mut entry = Vec::new();
+        for tree in list
+            .tokens
+            .clone()
+            .into_iter()
+            .chain([TokenTree::Punct(Punct::new(',', Spacing::Alone))])
+        {
+            match &tree {
+                TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                    constraints.extend(constraint(std::mem::take(&mut entry)));
+                }
+                _ => entry.push(tree),
+            }
+        }
+    }
+    constraints
+}
+
+/// Parses `key` or `key = value` from the tokens of one entry.
+fn constraint(tokens: Vec<TokenTree>) -> Option<Constraint> {
+    // A lone `=` separates key and value. In `==`, `<=` or `>=` the `=` is
+    // preceded by a joint punct and belongs to the value.
+    let alone_eq = |tree: &TokenTree| matches!(tree, TokenTree::Punct(p) if p.as_char() == '=' && p.spacing() == Spacing::Alone);
+    let joint =
+        |tree: &TokenTree| matches!(tree, TokenTree::Punct(p) if p.spacing() == Spacing::Joint);
+    let eq = (0..tokens.len()).find(|&i| alone_eq(&tokens[i]) && !(i > 0 && joint(&tokens[i - 1])));
+    let (key, value) = match eq {
+        Some(eq) => (
+            &tokens[..eq],
+            Some(tokens[eq + 1..].iter().cloned().collect()),
+        ),
+        None => (&tokens[..], None),
+    };
+    let key: String = key.iter().map(ToString::to_string).collect();
+    (!key.is_empty()).then_some(Constraint { key, value })
+}
+
+/// Extracts `T` from a leading `ctx: Context<T>` argument.
+fn context_type(item: &ItemFn) -> Option<String> {
+    item.sig.inputs.iter().find_map(|input| {
+        let FnArg::Typed(arg) = input else {
+            return None;
+        };
+        let (wrapper, inner) = type_parts(&arg.ty)?;
+        (wrapper == "Context").then_some(inner).flatten()
+    })
+}
+
+fn account_kind(ty: &Type) -> AccountKind {
+    let Some((wrapper, inner)) = type_parts(ty) else {
+        return AccountKind::Other(String::new());
+    };
+    match (wrapper.as_str(), inner) {
+        ("Account", Some(inner)) => AccountKind::Account(inner),
+        ("Program", Some(inner)) => AccountKind::Program(inner),
+        ("AccountInfo", _) => AccountKind::AccountInfo,
+        ("UncheckedAccount", _) => AccountKind::UncheckedAccount,
+        ("Signer", _) => AccountKind::Signer,
+        ("SystemAccount", _) => AccountKind::SystemAccount,
+        _ => AccountKind::Other(wrapper),
+    }
+}
+
+/// Splits `Wrapper<'a, Inner>` into its last path segment and the name of
+/// the first type argument, ignoring lifetimes.
+pub(crate) fn type_parts(ty: &Type) -> Option<(String, Option<String>)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let inner = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(Type::Path(inner)) => {
+                Some(inner.path.segments.last()?.ident.to_string())
+            }
+            _ => None,
+        }),
+        _ => None,
+    };
+    Some((segment.ident.to_string(), inner))
+}
+
+pub(crate) fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+fn derives(attrs: &[Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .any(|attr| {
+            let mut found = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= meta.path.is_ident(name);
+                Ok(())
+            });
+            found
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::visit::Visit;
+
+    use super::*;
+    use crate::test_support::{fixture, parse_fixture};
+
+    #[test]
+    fn computes_anchor_instruction_discriminators() {
+        let program = parse_fixture("vulnerable_vault.rs");
+        let withdraw = &program.handlers()[0];
+        assert_eq!(withdraw.name, "withdraw");
+        // sha256("global:withdraw"), as Anchor's `#[program]` derives it.
+        assert_eq!(
+            withdraw.discriminator(),
+            [0xb7, 0x12, 0x46, 0x9c, 0x94, 0x6d, 0xa1, 0x22]
+        );
+        assert_eq!(
+            discriminator("initialize"),
+            [0xaf, 0xaf, 0x6d, 0x1f, 0x0d, 0x98, 0x9b, 0xed]
+        );
+    }
+
+    #[test]
+    fn links_handlers_to_accounts_structs() {
+        let program = parse_fixture("vulnerable_vault.rs");
+        let module = program.program.as_ref().unwrap();
+        assert_eq!(module.name, "vulnerable_vault");
+
+        let names: Vec<_> = program.handlers().iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "withdraw",
+                "get_user_balance",
+                "update_authority",
+                "deposit",
+                "emergency_drain",
+                "calculate_rewards"
+            ]
+        );
+
+        let withdraw = &program.handlers()[0];
+        let accounts = program.accounts_for(withdraw).unwrap();
+        assert_eq!(accounts.name, "Withdraw");
+        assert_eq!(
+            accounts.field("authority").unwrap().kind,
+            AccountKind::AccountInfo
+        );
+        assert_eq!(
+            accounts.field("vault").unwrap().kind,
+            AccountKind::Account("Vault".into())
+        );
+        assert!(program.state_struct("Vault").is_some());
+
+        let query_users: Vec<_> = program.handlers_using("Query").map(|h| &h.name).collect();
+        assert_eq!(query_users, ["get_user_balance", "calculate_rewards"]);
+    }
+
+    #[test]
+    fn parses_nft_marketplace_fixture() {
+        let program = parse_fixture("insecure_nft_marketplace.rs");
+        assert_eq!(program.handlers().len(), 7);
+        assert_eq!(program.accounts.len(), 6);
+        assert_eq!(
+            program.declared_id.as_deref(),
+            Some("NFT1234567890123456789012345678901234567890")
+        );
+        let mint = program.accounts_struct("MintNFT").unwrap();
+        assert_eq!(mint.field("payer").unwrap().kind, AccountKind::Signer);
+        assert_eq!(
+            mint.field("system_program").unwrap().kind,
+            AccountKind::Program("System".into())
+        );
+    }
+
+    #[test]
+    fn ignores_code_in_comments_and_strings() {
+        let source = r#"
+            use anchor_lang::prelude::*;
+            /// Example: vault.balance - amount
+            #[program]
+            pub mod demo {
+                use super::*;
+                pub fn run(ctx: Context<Run>, amount: u64) -> Result<()> {
+                    // vault.balance - amount
+                    msg!("vault.balance - amount");
+                    let vault = &mut ctx.accounts.vault;
+                    vault.balance = vault.balance - amount;
+                    Ok(())
+                }
+            }
+        "#;
+        let program = parse_source("demo.rs", source.to_string()).unwrap();
+
+        struct Subtractions(usize);
+        impl Visit<'_> for Subtractions {
+            fn visit_expr_binary(&mut self, node: &syn::ExprBinary) {
+                self.0 += matches!(node.op, syn::BinOp::Sub(_)) as usize;
+                syn::visit::visit_expr_binary(self, node);
+            }
+        }
+        let mut visitor = Subtractions(0);
+        visitor.visit_item_fn(&program.handlers()[0].item);
+        assert_eq!(source.matches("vault.balance - amount").count(), 4);
+        assert_eq!(visitor.0, 1);
+    }
+
+    #[test]
+    fn parses_account_constraints() {
+        let program = parse_fixture("insecure_nft_marketplace.rs");
+        let mint = program.accounts_struct("MintNFT").unwrap();
+        let nft = mint.field("nft_account").unwrap();
+        let keys: Vec<_> = nft.constraints.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, ["init", "payer", "space"]);
+        assert_eq!(
+            nft.constraint("space").unwrap().value_text().unwrap(),
+            "8+200"
+        );
+        assert!(mint.field("mint").unwrap().is_mut());
+        assert!(!mint.field("authority").unwrap().is_mut());
+
+        let program = crate::test_support::parse(
+            r#"
+            #[derive(Accounts)]
+            pub struct Withdraw<'info> {
+                #[account(mut, has_one = authority, constraint = vault_token.owner == vault.key() @ E::Owner)]
+                pub vault: Account<'info, Vault>,
+                #[account(token::mint = mint)]
+                pub vault_token: Account<'info, TokenAccount>,
+            }
+            "#,
+        );
+        let accounts = program.accounts_struct("Withdraw").unwrap();
+        let vault = accounts.field("vault").unwrap();
+        let keys: Vec<_> = vault.constraints.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, ["mut", "has_one", "constraint"]);
+        assert_eq!(
+            vault
+                .constraint("constraint")
+                .unwrap()
+                .value_text()
+                .unwrap(),
+            "vault_token.owner==vault.key()@E::Owner"
+        );
+        let token = accounts.field("vault_token").unwrap();
+        assert_eq!(token.constraints[0].key, "token::mint");
+    }
+
+    #[test]
+    fn collects_comments_outside_literals() {
+        let source = r##"
+            /// doc
+            fn f<'info>(c: char) {
+                let s = "not // a comment";
+                let r = r#"nor /* this */"#; // trailing
+                let q = '"'; /* block
+                  spanning lines */
+                let e = '\''; // after escaped quote
+            }
+            // own line
+        "##;
+        let comments = collect_comments(source);
+        let found: Vec<_> = comments
+            .iter()
+            .map(|c| (c.line, c.text.as_str(), c.trailing))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, "doc", false),
+                (5, "trailing", true),
+                (6, "block\n                  spanning lines", true),
+                (8, "after escaped quote", true),
+                (10, "own line", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_parse_errors_with_location() {
+        let err = parse_source("broken.rs", "pub fn broken( {".to_string()).unwrap_err();
+        assert_eq!(err.file, Path::new("broken.rs"));
+        assert_eq!(err.line, 1);
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        let err = parse_file(&fixture("does_not_exist.rs")).unwrap_err();
+        assert!(matches!(err, Error::Io { .. }));
+    }
+}
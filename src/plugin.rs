@@ -0,0 +1,349 @@
+//! Detectors loaded from dynamic libraries.
+//!
+//! Every library (`.so`, `.dylib` or `.dll`) in the configured directory
+//! is opened and its detectors are registered after the built-in ones. The
+//! interface is defined by the `anchor-audit-plugin` crate, whose
+//! documentation explains how to write a plugin.
+//!
+//! Plugins run inside the scanner with its privileges, so only configure
+//! libraries you trust.
+
+use std::env::consts::DLL_EXTENSION;
+use std::ffi::{c_char, CStr, CString};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anchor_audit_plugin::{
+    symbols, ApiVersionFn, DetectorMeta, DetectorsFn, FreeFn, PluginFinding, RunFn,
+};
+use libloading::Library;
+
+use crate::detectors::{Detector, DetectorInfo, Registry};
+use crate::error::{Error, Result};
+use crate::finding::{Confidence, Finding, Span};
+use crate::parser::ParsedProgram;
+
+pub use anchor_audit_plugin::API_VERSION;
+
+/// A detector implemented by a loaded library.
+pub struct PluginDetector {
+    /// Keeps `run` and `free` mapped for as long as the detector lives.
+    _library: Arc<Library>,
+    index: u32,
+    info: &'static DetectorInfo,
+    run: RunFn,
+    free: FreeFn,
+}
+
+impl Detector for PluginDetector {
+    fn info(&self) -> &DetectorInfo {
+        self.info
+    }
+
+    /// Findings the plugin reports for `program`; none if it panicked or
+    /// returned something unreadable.
+    fn run(&self, program: &ParsedProgram) -> Vec<Finding> {
+        let path = program.path.to_string_lossy();
+        let (Ok(path), Ok(source)) = (
+            CString::new(path.as_bytes()),
+            CString::new(&*program.source),
+        ) else {
+            return Vec::new();
+        };
+        // SAFETY: both strings outlive the call, and the library is still
+        // loaded because `self` holds it.
+        let json = unsafe {
+            take(
+                (self.run)(self.index, path.as_ptr(), source.as_ptr()),
+                self.free,
+            )
+        };
+        let Some(findings) =
+            json.and_then(|json| serde_json::from_str::<Vec<PluginFinding>>(&json).ok())
+        else {
+            return Vec::new();
+        };
+        findings
+            .into_iter()
+            .filter_map(|found| self.finding(program, found))
+            .collect()
+    }
+}
+
+impl PluginDetector {
+    /// `found` as a finding in `program`, with a span that runs past the
+    /// end of the file cut short there. `None` when the span does not start
+    /// inside the file or ends before it starts.
+    fn finding(&self, program: &ParsedProgram, found: PluginFinding) -> Option<Finding> {
+        let lines = program.source.lines().count();
+        if found.line_start == 0 || found.line_start > lines || found.line_end < found.line_start {
+            return None;
+        }
+        let severity = found
+            .severity
+            .and_then(|severity| severity.parse().ok())
+            .unwrap_or(self.info.severity);
+        let confidence = found
+            .confidence
+            .and_then(|confidence| confidence.parse().ok())
+            .unwrap_or(Confidence::Medium);
+        let line_end = found.line_end.min(lines);
+        let col_start = found.col_start.max(1);
+        let col_end = if line_end == found.line_start {
+            found.col_end.max(col_start)
+        } else {
+            found.col_end
+        };
+        let span = Span {
+            file: program.path.clone(),
+            line_start: found.line_start,
+            col_start,
+            line_end,
+            col_end,
+        };
+        let finding = Finding::at(self.info.name, severity, program, span, found.message)
+            .with_confidence(confidence);
+        Some(match found.suggestion {
+            Some(suggestion) => finding.with_suggestion(suggestion),
+            None => finding,
+        })
+    }
+}
+
+/// Copies a string returned by a plugin and hands it back to be freed.
+///
+/// # Safety
+///
+/// `text` must be null or a string from the library `free` belongs to.
+unsafe fn take(text: *mut c_char, free: FreeFn) -> Option<String> {
+    if text.is_null() {
+        return None;
+    }
+    let copy = unsafe { CStr::from_ptr(text) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { free(text) };
+    Some(copy)
+}
+
+/// A catalog entry for `meta` that lives as long as the process, as the
+/// built-in ones do. Libraries are never unloaded once their detectors are
+/// registered, so nothing is lost.
+fn leak(meta: DetectorMeta) -> std::result::Result<&'static DetectorInfo, String> {
+    fn leak_str(text: String) -> &'static str {
+        Box::leak(text.into_boxed_str())
+    }
+    let references: Vec<&'static str> = meta.references.into_iter().map(leak_str).collect();
+    let info = DetectorInfo {
+        category: meta.category.parse()?,
+        severity: meta.severity.parse()?,
+        id: leak_str(meta.id),
+        name: leak_str(meta.name),
+        title: leak_str(meta.title),
+        cwe: meta.cwe,
+        description: leak_str(meta.description),
+        example: leak_str(meta.example),
+        fixed_example: leak_str(meta.fixed_example),
+        references: Box::leak(references.into_boxed_slice()),
+    };
+    Ok(Box::leak(Box::new(info)))
+}
+
+/// Opens the library at `path` and returns its detectors, after checking
+/// that it was built against [`API_VERSION`].
+pub fn load(path: &Path) -> Result<Vec<PluginDetector>> {
+    let error = |message: String| Error::Plugin {
+        path: path.to_path_buf(),
+        message,
+    };
+    // SAFETY: opening a library runs its initialisers; plugins are trusted
+    // by being configured. The symbols are checked against the types the
+    // plugin crate declares for them, starting with the API version.
+    unsafe {
+        let library = Arc::new(Library::new(path).map_err(|err| error(err.to_string()))?);
+        let symbol = |name: &[u8]| {
+            let name = String::from_utf8_lossy(&name[..name.len() - 1]).into_owned();
+            move |err: libloading::Error| error(format!("missing `{name}`: {err}"))
+        };
+        let version = library
+            .get::<ApiVersionFn>(symbols::API_VERSION)
+            .map_err(symbol(symbols::API_VERSION))?;
+        let version = version();
+        if version != API_VERSION {
+            return Err(error(format!(
+                "built for plugin API version {version}, but this is version {API_VERSION}"
+            )));
+        }
+        let detectors = *library
+            .get::<DetectorsFn>(symbols::DETECTORS)
+            .map_err(symbol(symbols::DETECTORS))?;
+        let run = *library
+            .get::<RunFn>(symbols::RUN)
+            .map_err(symbol(symbols::RUN))?;
+        let free = *library
+            .get::<FreeFn>(symbols::FREE)
+            .map_err(symbol(symbols::FREE))?;
+
+        let json = take(detectors(), free).ok_or_else(|| error("no detector list".into()))?;
+        let metas: Vec<DetectorMeta> = serde_json::from_str(&json)
+            .map_err(|err| error(format!("invalid detector list: {err}")))?;
+        metas
+            .into_iter()
+            .enumerate()
+            .map(|(index, meta)| {
+                Ok(PluginDetector {
+                    _library: Arc::clone(&library),
+                    index: index as u32,
+                    info: leak(meta).map_err(error)?,
+                    run,
+                    free,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Registers the detectors of every library in `dir`, in file name order,
+/// and returns how many there were.
+pub fn load_dir(dir: &Path, registry: &mut Registry) -> Result<usize> {
+    let io_error = |source| Error::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut libraries: Vec<_> = fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()
+        .map_err(io_error)?;
+    libraries.retain(|path| path.extension().is_some_and(|ext| ext == DLL_EXTENSION));
+    libraries.sort();
+
+    let mut count = 0;
+    for path in libraries {
+        for detector in load(&path)? {
+            registry.register(detector);
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use super::*;
+    use crate::finding::Severity;
+    use crate::scanner::{self, ScanOptions};
+    use crate::test_support::fixture;
+
+    /// Builds the plugin crate `anchor-audit-{name}-plugin` under `plugins`
+    /// and returns the path of its library.
+    fn build_plugin(name: &str) -> PathBuf {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let package = format!("anchor-audit-{name}-plugin");
+        let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+        let status = Command::new(cargo)
+            .args(["build", "--quiet", "-p", &package])
+            .current_dir(root)
+            .status()
+            .unwrap();
+        assert!(status.success(), "building {package} failed");
+        let target =
+            std::env::var_os("CARGO_TARGET_DIR").map_or_else(|| root.join("target"), PathBuf::from);
+        let lib = package.replace('-', "_");
+        target.join(format!("debug/{DLL_PREFIX}{lib}{DLL_SUFFIX}"))
+    }
+
+    #[test]
+    fn example_plugin_findings_appear_in_a_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins = dir.path().join("plugins");
+        fs::create_dir(&plugins).unwrap();
+        let library = build_plugin("example");
+        fs::copy(&library, plugins.join(library.file_name().unwrap())).unwrap();
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        let source = source.replace(
+            "vault.balance = vault.balance + amount;",
+            "// TODO: check the authority\n        vault.balance = vault.balance + amount;",
+        );
+        fs::write(dir.path().join("lib.rs"), source).unwrap();
+
+        let mut options = ScanOptions::default();
+        assert_eq!(load_dir(&plugins, &mut options.registry).unwrap(), 1);
+        let detector = options.registry.get("example/todo-comment").unwrap();
+        assert_eq!(detector.name(), "todo_comment");
+
+        let report = scanner::scan_with(dir.path(), &options).unwrap();
+        let todo: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|finding| finding.detector == "todo_comment")
+            .collect();
+        assert_eq!(todo.len(), 1);
+        assert_eq!(todo[0].severity, Severity::Low);
+        assert_eq!(todo[0].confidence, Confidence::High);
+        assert_eq!(
+            todo[0].message,
+            "unfinished work: `TODO: check the authority`"
+        );
+        assert_eq!(todo[0].handler.as_deref(), Some("deposit"));
+        assert!(report.findings.len() > 1);
+    }
+
+    #[test]
+    fn spans_outside_the_file_are_dropped_or_cut_short() {
+        let detector = load(&build_plugin("bad-spans")).unwrap().remove(0);
+        let program = crate::parser::parse_file(&fixture("vulnerable_vault.rs")).unwrap();
+        let lines = program.source.lines().count();
+        let findings = detector.run(&program);
+        let kept: Vec<_> = findings
+            .iter()
+            .map(|finding| {
+                let span = &finding.span;
+                let position = (span.line_start, span.col_start, span.line_end, span.col_end);
+                (finding.message.as_str(), position)
+            })
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                ("runs past the end", (2, 1, lines, 1)),
+                ("backwards columns", (3, 9, 3, 9)),
+                ("fine", (1, 1, 1, 2)),
+            ]
+        );
+        assert!(findings
+            .iter()
+            .all(|finding| finding.fingerprint().len() == 16));
+    }
+
+    #[test]
+    fn plugins_for_another_api_version_are_an_error() {
+        let path = build_plugin("old-api");
+        let err = load(&path).err().unwrap();
+        let Error::Plugin { path: p, message } = &err else {
+            panic!("{err}");
+        };
+        assert_eq!(*p, path);
+        assert_eq!(
+            *message,
+            format!("built for plugin API version 0, but this is version {API_VERSION}")
+        );
+    }
+
+    #[test]
+    fn files_that_are_not_plugins_are_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("broken.{DLL_EXTENSION}"));
+        fs::write(&path, "not a library").unwrap();
+        let err = load_dir(dir.path(), &mut Registry::empty()).unwrap_err();
+        assert!(
+            matches!(err, Error::Plugin { path: ref p, .. } if *p == path),
+            "{err}"
+        );
+    }
+}
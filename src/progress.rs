@@ -0,0 +1,113 @@
+//! Progress reporting for long interactive scans.
+//!
+//! [`Progress::stderr`] draws a bar with the files scanned so far, the
+//! total and the file just finished, but only when someone is watching:
+//! stdout and stderr must both be terminals. Piped or redirected output,
+//! and every machine-readable format, get a hidden bar that draws nothing,
+//! which is also what [`ScanOptions`](crate::scanner::ScanOptions) uses by
+//! default.
+
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// Template of the visible bar: `[=====>    ] 12/340 programs/vault/src/lib.rs`.
+const TEMPLATE: &str = "[{bar:30}] {pos}/{len} {wide_msg}";
+
+/// A file counter drawn on stderr, or nowhere.
+#[derive(Debug, Clone)]
+pub struct Progress(ProgressBar);
+
+impl Progress {
+    /// A bar that never draws.
+    pub fn hidden() -> Self {
+        Progress(ProgressBar::hidden())
+    }
+
+    /// A bar on stderr if `wanted` and both stdout and stderr are terminals;
+    /// otherwise [`Progress::hidden`].
+    pub fn stderr(wanted: bool) -> Self {
+        if !visible(
+            wanted,
+            io::stdout().is_terminal(),
+            io::stderr().is_terminal(),
+        ) {
+            return Progress::hidden();
+        }
+        let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr());
+        bar.set_style(
+            ProgressStyle::with_template(TEMPLATE)
+                .expect("progress template is valid")
+                .progress_chars("=> "),
+        );
+        Progress(bar)
+    }
+
+    /// Whether the bar draws nothing.
+    pub fn is_hidden(&self) -> bool {
+        self.0.is_hidden()
+    }
+
+    /// Starts counting towards `files`.
+    pub(crate) fn start(&self, files: usize) {
+        self.0.reset();
+        self.0.set_length(files as u64);
+    }
+
+    /// Counts `file` as scanned.
+    pub(crate) fn advance(&self, file: &Path) {
+        self.0.set_message(file.display().to_string());
+        self.0.inc(1);
+    }
+
+    /// Removes the bar, so the report starts on a clean line.
+    pub(crate) fn finish(&self) {
+        self.0.finish_and_clear();
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress::hidden()
+    }
+}
+
+/// Whether a wanted bar is drawn, given which streams are terminals. The
+/// report goes to stdout and the bar to stderr, so either one being piped
+/// means nobody is watching the bar.
+fn visible(wanted: bool, stdout_is_terminal: bool, stderr_is_terminal: bool) -> bool {
+    wanted && stdout_is_terminal && stderr_is_terminal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{self, ScanOptions};
+    use crate::test_support::fixture;
+
+    #[test]
+    fn nothing_is_drawn_when_output_is_piped() {
+        assert!(visible(true, true, true));
+        assert!(!visible(true, false, true));
+        assert!(!visible(true, true, false));
+        assert!(!visible(false, true, true));
+        assert!(Progress::stderr(false).is_hidden());
+    }
+
+    #[test]
+    fn a_scan_counts_every_file() {
+        let progress = Progress(ProgressBar::with_draw_target(
+            None,
+            ProgressDrawTarget::hidden(),
+        ));
+        let options = ScanOptions {
+            progress: progress.clone(),
+            ..ScanOptions::default()
+        };
+        let report = scanner::scan_with(&fixture(""), &options).unwrap();
+        assert_eq!(progress.0.length(), Some(report.files.len() as u64));
+        assert_eq!(progress.0.position(), report.files.len() as u64);
+        assert!(progress.0.is_finished());
+    }
+}
@@ -0,0 +1,130 @@
+//! Report files written next to the main output.
+//!
+//! A CI job usually wants several formats from one scan: SARIF for code
+//! scanning, JSON to archive, Markdown for the pull request. Each
+//! [`Artifact`] renders the same findings and summary, so the files always
+//! agree with each other and with what was printed.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::{Error, Result};
+use crate::finding::Finding;
+use crate::report::workspace::ProgramEntry;
+use crate::report::{self, Summary};
+
+/// A report format that can be written to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Sarif,
+    Markdown,
+    Junit,
+}
+
+impl Format {
+    /// Renders `findings` in this format, ending in a newline. Only JSON
+    /// includes the call graph of `programs`.
+    pub fn render(
+        self,
+        findings: &[Finding],
+        summary: &Summary,
+        programs: &[ProgramEntry],
+    ) -> String {
+        match self {
+            Format::Json => format!("{:#}\n", report::json::to_json(findings, summary, programs)),
+            Format::Sarif => format!("{:#}\n", report::sarif::to_sarif(findings)),
+            Format::Markdown => report::markdown::to_markdown(findings),
+            Format::Junit => report::junit::to_junit(findings),
+        }
+    }
+}
+
+/// One report file to write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub format: Format,
+    pub path: PathBuf,
+}
+
+impl Artifact {
+    pub fn new(format: Format, path: impl Into<PathBuf>) -> Self {
+        Artifact {
+            format,
+            path: path.into(),
+        }
+    }
+
+    /// Renders the report and writes it, creating missing parent
+    /// directories.
+    pub fn write(
+        &self,
+        findings: &[Finding],
+        summary: &Summary,
+        programs: &[ProgramEntry],
+    ) -> Result<()> {
+        let error = |source| Error::Write {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(error)?;
+        }
+        fs::write(&self.path, self.format.render(findings, summary, programs)).map_err(error)
+    }
+}
+
+/// Writes every artifact, stopping at the first that fails.
+pub fn write_all(
+    artifacts: &[Artifact],
+    findings: &[Finding],
+    summary: &Summary,
+    programs: &[ProgramEntry],
+) -> Result<()> {
+    artifacts
+        .iter()
+        .try_for_each(|artifact| artifact.write(findings, summary, programs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture_programs, scan_fixtures};
+
+    #[test]
+    fn one_scan_writes_consistent_artifacts() {
+        let findings = scan_fixtures();
+        let summary = Summary::of(&findings);
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("reports/ci");
+        let artifacts = [
+            Artifact::new(Format::Sarif, out.join("audit.sarif")),
+            Artifact::new(Format::Json, out.join("json/audit.json")),
+            Artifact::new(Format::Markdown, out.join("audit.md")),
+        ];
+        write_all(&artifacts, &findings, &summary, &fixture_programs()).unwrap();
+
+        let read = |artifact: &Artifact| fs::read_to_string(&artifact.path).unwrap();
+        let sarif: serde_json::Value = serde_json::from_str(&read(&artifacts[0])).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&read(&artifacts[1])).unwrap();
+        let markdown = read(&artifacts[2]);
+
+        let sarif_results = sarif["runs"][0]["results"].as_array().unwrap().len();
+        let json_findings = json["findings"].as_array().unwrap().len();
+        let markdown_entries = markdown.lines().filter(|l| l.starts_with("- [")).count();
+        assert_eq!(sarif_results, findings.len());
+        assert_eq!(json_findings, findings.len());
+        assert_eq!(markdown_entries, findings.len());
+        assert_eq!(json["summary"]["total"], findings.len());
+    }
+
+    #[test]
+    fn unwritable_paths_are_write_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("file");
+        fs::write(&blocker, "").unwrap();
+        let artifact = Artifact::new(Format::Json, blocker.join("audit.json"));
+        let err = artifact.write(&[], &Summary::default(), &[]).unwrap_err();
+        assert!(matches!(err, Error::Write { path, .. } if path == artifact.path));
+    }
+}
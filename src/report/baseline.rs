@@ -0,0 +1,255 @@
+//! Baseline files for reporting only newly introduced findings.
+//!
+//! A baseline records the [`Finding::fingerprint`] of every accepted
+//! finding. Later runs are compared against it, so CI on a legacy program
+//! fails only on issues that were not there before.
+//!
+//! Findings are [normalized](crate::normalize) against the directory that
+//! holds the baseline: entries name files relative to it and fingerprint
+//! those relative paths. A baseline committed to a repository therefore
+//! matches however the repository was checked out and whichever directory
+//! the scan runs from.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::finding::Finding;
+use crate::normalize::NormalizedFinding;
+
+pub const BASELINE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Baseline {
+    version: u32,
+    findings: Vec<BaselineEntry>,
+}
+
+/// One accepted finding. Only `file` and `fingerprint` are used for
+/// matching; the rest helps humans reading the file. `file` is relative to
+/// the directory holding the baseline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub detector: String,
+    pub file: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl BaselineEntry {
+    /// The entry for `finding` in a baseline kept in `root`.
+    pub fn new(finding: &Finding, root: &Path) -> Self {
+        let normalized = NormalizedFinding::new(finding, root);
+        BaselineEntry {
+            fingerprint: normalized.fingerprint,
+            detector: finding.detector.to_string(),
+            file: PathBuf::from(normalized.file),
+            line: finding.span.line_start,
+            message: finding.message.clone(),
+        }
+    }
+
+    /// What a baseline entry for `finding` in `root` is matched on.
+    fn key(finding: &Finding, root: &Path) -> (PathBuf, String) {
+        let entry = BaselineEntry::new(finding, root);
+        (entry.file, entry.fingerprint)
+    }
+}
+
+/// The directory entries of the baseline at `path` are relative to.
+fn root(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// How the current findings compare with a baseline.
+#[derive(Debug, Default)]
+pub struct BaselineDiff {
+    /// Findings missing from the baseline.
+    pub new: Vec<Finding>,
+    /// Baseline entries no longer found.
+    pub fixed: Vec<BaselineEntry>,
+    /// Findings already in the baseline.
+    pub unchanged: Vec<Finding>,
+}
+
+/// What [`gate`] did with the findings.
+#[derive(Debug)]
+pub enum Gate {
+    /// There was no baseline yet, so one was written accepting this many
+    /// findings.
+    Created { accepted: usize },
+    /// The findings were compared with the existing baseline.
+    Compared(BaselineDiff),
+}
+
+/// The "don't regress" CI workflow behind `--fail-on-new`: compares
+/// `findings` with the baseline at `path`, or, on the first run, records
+/// them as that baseline.
+pub fn gate(findings: Vec<Finding>, path: &Path) -> Result<Gate> {
+    if path.exists() {
+        return diff_against_baseline(findings, path).map(Gate::Compared);
+    }
+    write_baseline(&findings, path)?;
+    Ok(Gate::Created {
+        accepted: findings.len(),
+    })
+}
+
+/// Records `findings` as the accepted baseline at `path`.
+pub fn write_baseline(findings: &[Finding], path: &Path) -> Result<()> {
+    let root = root(path);
+    let baseline = Baseline {
+        version: BASELINE_VERSION,
+        findings: findings
+            .iter()
+            .map(|finding| BaselineEntry::new(finding, &root))
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&baseline).expect("baseline is always serializable");
+    fs::write(path, json + "\n").map_err(|source| Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Splits `findings` into new and unchanged relative to the baseline at
+/// `path`, and lists the baseline entries that have disappeared.
+///
+/// Identical findings in one file, such as two copies of the same line,
+/// are matched by count.
+pub fn diff_against_baseline(findings: Vec<Finding>, path: &Path) -> Result<BaselineDiff> {
+    let text = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let baseline: Baseline = serde_json::from_str(&text).map_err(|source| Error::Baseline {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let mut remaining: HashMap<(PathBuf, String), Vec<BaselineEntry>> = HashMap::new();
+    for entry in baseline.findings {
+        remaining
+            .entry((entry.file.clone(), entry.fingerprint.clone()))
+            .or_default()
+            .push(entry);
+    }
+
+    let root = root(path);
+    let mut diff = BaselineDiff::default();
+    for finding in findings {
+        match remaining
+            .get_mut(&BaselineEntry::key(&finding, &root))
+            .and_then(Vec::pop)
+        {
+            Some(_) => diff.unchanged.push(finding),
+            None => diff.new.push(finding),
+        }
+    }
+    diff.fixed = remaining.into_values().flatten().collect();
+    diff.fixed
+        .sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner;
+    use crate::test_support::fixture;
+
+    #[test]
+    fn only_introduced_findings_are_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("vault.rs");
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        fs::write(&program, &source).unwrap();
+        let baseline = dir.path().join("baseline.json");
+
+        let before = scanner::scan(dir.path()).unwrap().findings;
+        write_baseline(&before, &baseline).unwrap();
+        let diff = diff_against_baseline(before.clone(), &baseline).unwrap();
+        assert!(diff.new.is_empty() && diff.fixed.is_empty());
+        assert_eq!(diff.unchanged.len(), before.len());
+
+        // Shift every line down and add one unchecked subtraction to
+        // `update_authority`.
+        let edited = format!("// header\n\n{source}").replace(
+            "vault.authority = new_authority;",
+            "vault.authority = new_authority;\n        vault.balance -= 1;",
+        );
+        fs::write(&program, edited).unwrap();
+        let after = scanner::scan(dir.path()).unwrap().findings;
+        let diff = diff_against_baseline(after, &baseline).unwrap();
+        assert_eq!(diff.new.len(), 1, "{:#?}", diff.new);
+        assert!(diff.new[0].message.contains("in `update_authority`"));
+        assert!(diff.fixed.is_empty());
+        assert_eq!(diff.unchanged.len(), before.len());
+    }
+
+    #[test]
+    fn vanished_findings_are_fixed() {
+        let dir = tempfile::tempdir().unwrap();
+        let baseline = dir.path().join("baseline.json");
+        let findings = crate::scan_file(&fixture("vulnerable_vault.rs")).unwrap();
+        write_baseline(&findings, &baseline).unwrap();
+
+        let diff = diff_against_baseline(findings[1..].to_vec(), &baseline).unwrap();
+        assert!(diff.new.is_empty());
+        assert_eq!(diff.fixed, [BaselineEntry::new(&findings[0], dir.path())]);
+    }
+
+    #[test]
+    fn gate_creates_the_baseline_then_reports_regressions() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("vault.rs");
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        fs::write(&program, &source).unwrap();
+        let baseline = dir.path().join("audit-baseline.json");
+
+        let first = scanner::scan(dir.path()).unwrap();
+        let count = first.findings.len();
+        assert!(matches!(
+            gate(first.findings, &baseline).unwrap(),
+            Gate::Created { accepted } if accepted == count
+        ));
+        assert!(baseline.exists());
+
+        fs::write(
+            &program,
+            source.replace(
+                "vault.authority = new_authority;",
+                "vault.authority = new_authority;\n        vault.balance -= 1;",
+            ),
+        )
+        .unwrap();
+        let mut second = scanner::scan(dir.path()).unwrap();
+        let Gate::Compared(diff) = gate(std::mem::take(&mut second.findings), &baseline).unwrap()
+        else {
+            panic!("the baseline already exists");
+        };
+        assert_eq!(diff.new.len(), 1, "{:#?}", diff.new);
+        second.keep_only(diff.new);
+        assert_eq!(second.exit_code(), scanner::EXIT_FINDINGS);
+        assert_eq!(second.summary.total, 1);
+        let printed = crate::report::text::render(&second.findings, &second.summary, false);
+        assert_eq!(printed.matches("vault.rs:").count(), 1, "{printed}");
+        assert!(printed.ends_with("1 finding(s): 0 critical, 1 high, 0 medium, 0 low\n"));
+    }
+
+    #[test]
+    fn malformed_baseline_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let baseline = dir.path().join("baseline.json");
+        fs::write(&baseline, "not json").unwrap();
+        assert!(matches!(
+            diff_against_baseline(Vec::new(), &baseline),
+            Err(Error::Baseline { .. })
+        ));
+    }
+}
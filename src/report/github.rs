@@ -0,0 +1,68 @@
+//! GitHub Actions workflow commands.
+//!
+//! Printing `::error file=...,line=...,col=...::message` from a step makes
+//! Actions show the finding inline on the pull request diff. Critical and
+//! high findings become errors, the rest warnings.
+
+use std::fmt::Write;
+
+use crate::finding::{Finding, Severity};
+
+/// Renders one workflow command per finding.
+pub fn to_annotations(findings: &[Finding]) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        let level = match finding.severity {
+            Severity::Critical | Severity::High => "error",
+            Severity::Medium | Severity::Low => "warning",
+        };
+        let span = &finding.span;
+        let _ = writeln!(
+            out,
+            "::{level} file={},line={},col={}::{}",
+            escape_property(&span.file.display().to_string()),
+            span.line_start,
+            span.col_start,
+            escape_data(&finding.message)
+        );
+    }
+    out
+}
+
+/// Escapes a command's message so it stays on one line.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a `key=value` property, where `:` and `,` are separators.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::scan_fixtures;
+
+    #[test]
+    fn annotates_withdraw_finding() {
+        let findings = scan_fixtures();
+        let out = to_annotations(&findings);
+        assert_eq!(out.lines().count(), findings.len());
+        assert!(out.lines().any(|line| {
+            line == "::error file=contracts/sources/vulnerable_vault.rs,line=141,col=9::\
+                `Withdraw::authority` is used as CPI authority in `withdraw` but is not a `Signer`"
+        }));
+        assert!(out
+            .lines()
+            .any(|line| line.starts_with("::warning file=contracts/sources/")));
+    }
+
+    #[test]
+    fn escapes_separators() {
+        assert_eq!(escape_data("50% done\nnext"), "50%25 done%0Anext");
+        assert_eq!(escape_property("C:\\a,b.rs"), "C%3A\\a%2Cb.rs");
+    }
+}
@@ -0,0 +1,130 @@
+//! Machine-readable JSON report.
+//!
+//! The layout is versioned by [`SCHEMA_VERSION`]; bump it whenever a field is
+//! renamed or removed so consumers can detect the change.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::finding::{Confidence, Finding, Severity, Span};
+use crate::report::workspace::ProgramEntry;
+use crate::report::Summary;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Report<'a> {
+    schema_version: u32,
+    summary: &'a Summary,
+    findings: Vec<JsonFinding<'a>>,
+    /// Program module, then handler in source order, then its CPIs.
+    call_graph: Map<String, Value>,
+}
+
+/// One finding as it appears in JSON output.
+#[derive(Serialize)]
+pub(crate) struct JsonFinding<'a> {
+    id: &'a str,
+    detector: &'a str,
+    severity: Severity,
+    confidence: Confidence,
+    span: &'a Span,
+    message: &'a str,
+    suggestion: Option<&'a str>,
+    /// See [`Finding::fingerprint`].
+    fingerprint: &'a str,
+    handler: Option<&'a str>,
+    /// Hex instruction discriminator of `handler`.
+    discriminator: Option<String>,
+    also_matched: &'a [&'static str],
+    references: &'static [&'static str],
+}
+
+impl<'a> From<&'a Finding> for JsonFinding<'a> {
+    fn from(finding: &'a Finding) -> Self {
+        JsonFinding {
+            id: finding.rule_id(),
+            detector: finding.detector,
+            severity: finding.severity,
+            confidence: finding.confidence,
+            span: &finding.span,
+            message: &finding.message,
+            suggestion: finding.suggestion.as_deref(),
+            fingerprint: finding.fingerprint(),
+            handler: finding.handler.as_deref(),
+            discriminator: finding.discriminator(),
+            also_matched: &finding.also_matched,
+            references: finding.references(),
+        }
+    }
+}
+
+/// Builds the versioned JSON report for `findings`, with the call graph of
+/// every `#[program]` module in `programs`.
+pub fn to_json(findings: &[Finding], summary: &Summary, programs: &[ProgramEntry]) -> Value {
+    let report = Report {
+        schema_version: SCHEMA_VERSION,
+        summary,
+        findings: findings.iter().map(JsonFinding::from).collect(),
+        call_graph: call_graph(programs),
+    };
+    serde_json::to_value(report).expect("report is always serializable")
+}
+
+fn call_graph(programs: &[ProgramEntry]) -> Map<String, Value> {
+    programs
+        .iter()
+        .filter_map(|entry| {
+            let handlers = entry
+                .handlers
+                .iter()
+                .map(|handler| {
+                    let edges = entry.call_graph.get(handler).map_or(&[][..], Vec::as_slice);
+                    let edges = serde_json::to_value(edges).expect("edges are serializable");
+                    (handler.clone(), edges)
+                })
+                .collect();
+            Some((entry.module.clone()?, Value::Object(handlers)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_golden, fixture_programs, scan_fixtures};
+
+    #[test]
+    fn matches_golden_report() {
+        let findings = scan_fixtures();
+        let summary = Summary::of(&findings);
+        let report = to_json(&findings, &summary, &fixture_programs());
+
+        assert_eq!(report["schema_version"], 1);
+        let listed = report["findings"].as_array().unwrap();
+        assert_eq!(report["summary"]["total"], listed.len());
+        for severity in Severity::ALL {
+            let counted = listed
+                .iter()
+                .filter(|f| f["severity"] == severity.as_str())
+                .count();
+            assert_eq!(report["summary"][severity.as_str()], counted);
+        }
+
+        let withdraw = listed.iter().find(|f| f["handler"] == "withdraw").unwrap();
+        assert_eq!(withdraw["discriminator"], "b712469c946da122");
+        assert!(listed
+            .iter()
+            .zip(&findings)
+            .all(|(listed, finding)| listed["fingerprint"] == finding.fingerprint()));
+
+        let vault = &report["call_graph"]["vulnerable_vault"];
+        for handler in ["withdraw", "deposit"] {
+            assert_eq!(vault[handler][0]["program"], "token", "{handler}");
+        }
+        assert_eq!(vault["get_user_balance"], Value::Array(Vec::new()));
+
+        let rendered = serde_json::to_string_pretty(&report).unwrap() + "\n";
+        assert_golden("report.json", &rendered);
+    }
+}
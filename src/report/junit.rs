@@ -0,0 +1,151 @@
+//! JUnit XML, the test report format most CI systems can display.
+//!
+//! Every catalog detector becomes a `<testsuite>` named by its namespaced
+//! id. Each finding is a failing `<testcase>` carrying the severity and
+//! message; a detector with no findings gets one passing case, so a clean
+//! scan shows up as a green run rather than an empty one.
+
+use std::fmt::Write;
+
+use crate::detectors;
+use crate::finding::Finding;
+
+/// Renders `findings` as a `<testsuites>` document.
+pub fn to_junit(findings: &[Finding]) -> String {
+    let mut suites: Vec<(&str, Vec<&Finding>)> = detectors::catalog()
+        .map(|info| (info.id, Vec::new()))
+        .collect();
+    for finding in findings {
+        let rule = finding.rule_id();
+        match suites.iter_mut().find(|(id, _)| *id == rule) {
+            Some((_, members)) => members.push(finding),
+            None => suites.push((rule, vec![finding])),
+        }
+    }
+
+    let tests: usize = suites.iter().map(|(_, members)| members.len().max(1)).sum();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"{}\" tests=\"{tests}\" failures=\"{}\">",
+        env!("CARGO_PKG_NAME"),
+        findings.len()
+    );
+    for (id, members) in &suites {
+        let id = escape(id);
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{id}\" tests=\"{}\" failures=\"{}\">",
+            members.len().max(1),
+            members.len()
+        );
+        if members.is_empty() {
+            let _ = writeln!(
+                out,
+                "    <testcase name=\"no findings\" classname=\"{id}\"/>"
+            );
+        }
+        for finding in members {
+            write_case(&mut out, &id, finding);
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn write_case(out: &mut String, suite: &str, finding: &Finding) {
+    let span = &finding.span;
+    let file = escape(&span.file.display().to_string());
+    let _ = writeln!(
+        out,
+        "    <testcase name=\"{file}:{}:{}\" classname=\"{suite}\" file=\"{file}\" line=\"{}\">",
+        span.line_start, span.col_start, span.line_start
+    );
+    let mut body = format!(
+        "severity: {}, confidence: {}\n{}",
+        finding.severity, finding.confidence, finding.message
+    );
+    if let Some(suggestion) = &finding.suggestion {
+        let _ = write!(body, "\nhelp: {suggestion}");
+    }
+    let _ = writeln!(
+        out,
+        "      <failure type=\"{}\" message=\"{}\">{}</failure>",
+        finding.severity,
+        escape(&finding.message),
+        escape(&body)
+    );
+    out.push_str("    </testcase>\n");
+}
+
+/// Escapes text for use in an attribute value or element content.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_golden, scan_fixtures};
+
+    #[test]
+    fn matches_golden_report() {
+        let findings = scan_fixtures();
+        let xml = to_junit(&findings);
+        let doc = roxmltree::Document::parse(&xml).unwrap();
+        let root = doc.root_element();
+        assert_eq!(root.tag_name().name(), "testsuites");
+        let failures: Vec<_> = doc
+            .descendants()
+            .filter(|node| node.has_tag_name("failure"))
+            .collect();
+        assert_eq!(failures.len(), findings.len());
+        assert_eq!(
+            root.attribute("failures"),
+            Some(findings.len().to_string().as_str())
+        );
+        let suites = root.children().filter(|node| node.is_element()).count();
+        assert_eq!(suites, detectors::catalog().count());
+
+        let withdraw = failures
+            .iter()
+            .find(|failure| {
+                failure.parent().unwrap().attribute("name")
+                    == Some("contracts/sources/vulnerable_vault.rs:141:9")
+            })
+            .unwrap();
+        assert_eq!(withdraw.attribute("type"), Some("critical"));
+        assert!(withdraw
+            .text()
+            .unwrap()
+            .contains("`Withdraw::authority` is used as CPI authority in `withdraw`"));
+
+        assert_golden("report.junit.xml", &xml);
+    }
+
+    #[test]
+    fn clean_scan_passes_every_suite() {
+        let xml = to_junit(&[]);
+        let doc = roxmltree::Document::parse(&xml).unwrap();
+        assert!(!doc.descendants().any(|node| node.has_tag_name("failure")));
+        let passing = doc
+            .descendants()
+            .filter(|node| node.attribute("name") == Some("no findings"))
+            .count();
+        assert_eq!(passing, detectors::catalog().count());
+        assert_eq!(escape("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+}
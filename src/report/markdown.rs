@@ -0,0 +1,156 @@
+//! Markdown audit report for human-readable deliverables.
+//!
+//! Findings are grouped under one `##` section per severity, most severe
+//! first, each with the offending source lines and [`SNIPPET_CONTEXT`]
+//! lines either side, quoted from the source the finding was made in.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::finding::{Finding, Severity};
+use crate::report::Summary;
+
+/// Lines of context quoted around each finding's own lines.
+pub const SNIPPET_CONTEXT: usize = 2;
+
+/// Renders `findings` as a Markdown report.
+pub fn to_markdown(findings: &[Finding]) -> String {
+    let mut out = String::from("# Anchor audit report\n\n");
+    write_summary(&mut out, findings);
+
+    for severity in Severity::ALL {
+        let group: Vec<_> = findings.iter().filter(|f| f.severity == severity).collect();
+        if group.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "## {}\n", title(severity));
+        for finding in group {
+            let _ = writeln!(
+                out,
+                "- [{}] {}:{} — {}",
+                finding.rule_id(),
+                finding.span.file.display(),
+                finding.span.line_start,
+                finding.message
+            );
+            let snippet = finding.snippet(SNIPPET_CONTEXT);
+            if !snippet.is_empty() {
+                out.push_str("\n  ```text\n");
+                for line in snippet.lines() {
+                    let _ = writeln!(out, "  {line}");
+                }
+                out.push_str("  ```\n");
+            }
+            if let Some(impact) = &finding.impact {
+                let _ = write!(out, "\n  Impact: {impact}\n");
+            }
+            if let Some(suggestion) = &finding.suggestion {
+                let _ = write!(out, "\n  Fix: {suggestion}\n");
+            }
+            let references = finding.references();
+            if !references.is_empty() {
+                let links: Vec<_> = references.iter().map(|url| format!("<{url}>")).collect();
+                let _ = write!(out, "\n  References: {}\n", links.join(", "));
+            }
+            out.push('\n');
+        }
+    }
+    if findings.is_empty() {
+        out.push_str("No findings.\n");
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// Severity and per-file count tables.
+fn write_summary(out: &mut String, findings: &[Finding]) {
+    let summary = Summary::of(findings);
+    out.push_str("| Severity | Findings |\n|---|---|\n");
+    for severity in Severity::ALL {
+        let _ = writeln!(out, "| {} | {} |", title(severity), summary.count(severity));
+    }
+    let _ = writeln!(out, "| **Total** | **{}** |", summary.total);
+
+    let mut files: BTreeMap<&Path, Vec<Finding>> = BTreeMap::new();
+    for finding in findings {
+        files
+            .entry(&finding.span.file)
+            .or_default()
+            .push(finding.clone());
+    }
+    out.push('\n');
+    if files.is_empty() {
+        return;
+    }
+    out.push_str(
+        "\n| File | Critical | High | Medium | Low | Total |\n|---|---|---|---|---|---|\n",
+    );
+    for (file, findings) in &files {
+        let summary = Summary::of(findings);
+        let _ = writeln!(
+            out,
+            "| `{}` | {} | {} | {} | {} | {} |",
+            file.display(),
+            summary.critical,
+            summary.high,
+            summary.medium,
+            summary.low,
+            summary.total
+        );
+    }
+    out.push('\n');
+}
+
+fn title(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "Critical",
+        Severity::High => "High",
+        Severity::Medium => "Medium",
+        Severity::Low => "Low",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_golden, fixture, scan_fixtures};
+
+    #[test]
+    fn matches_snapshot() {
+        let findings = scan_fixtures();
+        let report = to_markdown(&findings);
+
+        let critical = report.split("\n## High").next().unwrap();
+        let critical = &critical[critical.find("## Critical").unwrap()..];
+        assert!(critical.contains("`withdraw`"));
+        assert!(critical.contains("`emergency_drain`"));
+        assert!(critical.contains("\n  Impact: Any wallet can call `withdraw`"));
+        let total = format!("| **Total** | **{}** |", findings.len());
+        assert!(report.contains(&total));
+
+        assert_golden("report.md", &report);
+    }
+
+    #[test]
+    fn quotes_sources_that_exist_only_in_memory() {
+        let source = std::fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        let findings = crate::scan_source("posted/vault.rs", &source).unwrap();
+        assert!(!Path::new("posted/vault.rs").exists());
+        let report = to_markdown(&findings);
+        let marked = report
+            .lines()
+            .find(|line| line.contains("std::ptr::write_bytes"))
+            .unwrap();
+        assert!(marked.starts_with("  > "), "{marked}");
+        assert!(report.contains("\n  ```text\n"));
+    }
+
+    #[test]
+    fn empty_report_says_so() {
+        let report = to_markdown(&[]);
+        assert!(report.contains("| **Total** | **0** |"));
+        assert!(report.ends_with("No findings.\n"));
+    }
+}
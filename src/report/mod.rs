@@ -0,0 +1,148 @@
+//! Rendering of findings for humans and machines.
+
+pub mod artifact;
+pub mod baseline;
+pub mod github;
+pub mod json;
+pub mod junit;
+pub mod markdown;
+pub mod ndjson;
+pub mod prometheus;
+pub mod sarif;
+pub mod table;
+pub mod text;
+pub mod workspace;
+
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::finding::{Confidence, Finding, Severity};
+
+/// Finding counts per severity, and the [`risk_score`] overall and per file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub critical: usize,
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+    pub total: usize,
+    /// Sum of the per-file scores, so summaries of separate scans add up.
+    pub risk_score: u32,
+    /// Risk score of every file with at least one finding.
+    pub files: BTreeMap<PathBuf, u32>,
+}
+
+impl Summary {
+    pub fn of(findings: &[Finding]) -> Self {
+        let mut summary = Summary::default();
+        let mut by_file: BTreeMap<PathBuf, Vec<Finding>> = BTreeMap::new();
+        for finding in findings {
+            *summary.count_mut(finding.severity) += 1;
+            summary.total += 1;
+            by_file
+                .entry(finding.span.file.clone())
+                .or_default()
+                .push(finding.clone());
+        }
+        summary.files = by_file
+            .into_iter()
+            .map(|(file, findings)| (file, risk_score(&findings)))
+            .collect();
+        summary.risk_score = summary.files.values().sum();
+        summary
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        match severity {
+            Severity::Critical => self.critical,
+            Severity::High => self.high,
+            Severity::Medium => self.medium,
+            Severity::Low => self.low,
+        }
+    }
+
+    fn count_mut(&mut self, severity: Severity) -> &mut usize {
+        match severity {
+            Severity::Critical => &mut self.critical,
+            Severity::High => &mut self.high,
+            Severity::Medium => &mut self.medium,
+            Severity::Low => &mut self.low,
+        }
+    }
+}
+
+impl AddAssign for Summary {
+    fn add_assign(&mut self, other: Summary) {
+        self.critical += other.critical;
+        self.high += other.high;
+        self.medium += other.medium;
+        self.low += other.low;
+        self.total += other.total;
+        self.risk_score += other.risk_score;
+        for (file, score) in other.files {
+            *self.files.entry(file).or_default() += score;
+        }
+    }
+}
+
+/// A single number for comparing contracts: the sum of every finding's
+/// severity weight (critical 10, high 5, medium 2, low 1), scaled by how
+/// confident its detector is, rounded to the nearest integer.
+pub fn risk_score(findings: &[Finding]) -> u32 {
+    let percent: u32 = findings
+        .iter()
+        .map(|finding| weight(finding.severity) * confidence_percent(finding.confidence))
+        .sum();
+    (percent + 50) / 100
+}
+
+fn weight(severity: Severity) -> u32 {
+    match severity {
+        Severity::Critical => 10,
+        Severity::High => 5,
+        Severity::Medium => 2,
+        Severity::Low => 1,
+    }
+}
+
+fn confidence_percent(confidence: Confidence) -> u32 {
+    match confidence {
+        Confidence::High => 100,
+        Confidence::Medium => 60,
+        Confidence::Low => 30,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+
+    fn score(name: &str) -> u32 {
+        risk_score(&crate::scan_file(&fixture(name)).unwrap())
+    }
+
+    #[test]
+    fn vulnerable_fixtures_score_high() {
+        assert!(score("insecure_nft_marketplace.rs") > 50);
+        let clean = crate::test_support::parse(
+            "pub fn add(a: u64, b: u64) -> Option<u64> { a.checked_add(b) }",
+        );
+        let clean = crate::scanner::analyze(&clean, &Default::default());
+        assert_eq!(risk_score(&clean), 0);
+        assert!(score("vulnerable_vault.rs") > risk_score(&clean));
+    }
+
+    #[test]
+    fn summary_scores_each_file() {
+        let findings = crate::test_support::scan_fixtures();
+        let summary = Summary::of(&findings);
+        assert_eq!(summary.files.len(), 2);
+        assert_eq!(summary.risk_score, summary.files.values().sum::<u32>());
+        let vault = summary.files[&PathBuf::from("contracts/sources/vulnerable_vault.rs")];
+        assert_eq!(vault, score("vulnerable_vault.rs"));
+    }
+}
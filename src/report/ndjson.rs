@@ -0,0 +1,90 @@
+//! Newline-delimited JSON, one object per line, for streaming consumers.
+//!
+//! Every finding is written as the same object the JSON report lists, plus
+//! `"type": "finding"`. The last line is the summary, tagged
+//! `"type": "summary"`.
+
+use serde::Serialize;
+
+use crate::finding::Finding;
+use crate::report::json::JsonFinding;
+use crate::report::Summary;
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Line<'a> {
+    Finding(JsonFinding<'a>),
+    Summary(&'a Summary),
+}
+
+fn line(line: Line<'_>) -> String {
+    serde_json::to_string(&line).expect("report is always serializable")
+}
+
+/// The line for one finding, without the trailing newline.
+pub fn finding_line(finding: &Finding) -> String {
+    line(Line::Finding(JsonFinding::from(finding)))
+}
+
+/// The closing summary line, without the trailing newline.
+pub fn summary_line(summary: &Summary) -> String {
+    line(Line::Summary(summary))
+}
+
+/// Renders already collected findings followed by the summary.
+pub fn to_ndjson(findings: &[Finding], summary: &Summary) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        out += &finding_line(finding);
+        out.push('\n');
+    }
+    out += &summary_line(summary);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::report::json;
+    use crate::scanner::{self, ScanOptions};
+    use crate::test_support::fixture;
+
+    #[test]
+    fn streamed_lines_parse_back_into_the_report() {
+        let root = fixture("");
+        let options = ScanOptions {
+            jobs: 4,
+            ..ScanOptions::default()
+        };
+        let mut out = String::new();
+        let report = scanner::scan_streaming(&root, &options, |finding| {
+            out += &finding_line(finding);
+            out.push('\n');
+        })
+        .unwrap();
+        out += &summary_line(&report.summary);
+        out.push('\n');
+        assert!(report.findings.is_empty());
+
+        let mut lines: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let summary = lines.pop().unwrap();
+        assert_eq!(summary["type"], "summary");
+        assert_eq!(summary["total"], report.streamed);
+        for line in &mut lines {
+            assert_eq!(line["type"], "finding");
+            line.as_object_mut().unwrap().remove("type");
+        }
+
+        // Same findings, in the same order, as the buffered JSON report.
+        let buffered = scanner::scan_with(&root, &options).unwrap();
+        let expected = json::to_json(&buffered.findings, &buffered.summary, &buffered.programs);
+        assert_eq!(Value::Array(lines), expected["findings"]);
+        assert_eq!(to_ndjson(&buffered.findings, &buffered.summary), out);
+    }
+}
@@ -0,0 +1,104 @@
+//! Prometheus text exposition of the scan history, served at `GET /metrics`.
+//!
+//! Every repository contributes the finding counts of its most recent scan,
+//! one `audit_findings_total` sample per severity, zeros included so that a
+//! fixed finding brings its series down instead of making it disappear.
+//! `audit_last_scan_timestamp_seconds` tells a dashboard how stale each
+//! repository's numbers are.
+
+use std::fmt::Write;
+
+use crate::finding::Severity;
+use crate::storage::StoredScan;
+
+/// `Content-Type` of the text format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Renders the gauges for `scans`, the latest scan of each repository.
+pub fn to_prometheus(scans: &[StoredScan]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# HELP audit_findings_total Findings in the most recent scan of a repository."
+    );
+    let _ = writeln!(out, "# TYPE audit_findings_total gauge");
+    for scan in scans {
+        let repo = escape(&scan.repo);
+        for severity in Severity::ALL {
+            let count = scan
+                .findings
+                .iter()
+                .filter(|finding| finding.severity == severity)
+                .count();
+            let _ = writeln!(
+                out,
+                "audit_findings_total{{repo=\"{repo}\",severity=\"{severity}\"}} {count}",
+                severity = severity.as_str()
+            );
+        }
+    }
+    let _ = writeln!(
+        out,
+        "# HELP audit_last_scan_timestamp_seconds When the most recent scan of a repository \
+         was saved."
+    );
+    let _ = writeln!(out, "# TYPE audit_last_scan_timestamp_seconds gauge");
+    for scan in scans {
+        let _ = writeln!(
+            out,
+            "audit_last_scan_timestamp_seconds{{repo=\"{}\"}} {}",
+            escape(&scan.repo),
+            scan.created_at
+        );
+    }
+    out
+}
+
+/// `value` as a label value: backslashes, quotes and newlines escaped.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use crate::test_support::scan_fixtures;
+
+    #[test]
+    fn exposes_counts_of_the_latest_scan_per_repo() {
+        let findings = scan_fixtures();
+        let mut storage = Storage::open_in_memory().unwrap();
+        storage
+            .save_scan("deploybackend", "abc", &findings)
+            .unwrap();
+        let text = to_prometheus(&storage.latest_scans().unwrap());
+
+        assert!(text.contains("# TYPE audit_findings_total gauge\n"));
+        for severity in Severity::ALL {
+            let expected = findings.iter().filter(|f| f.severity == severity).count();
+            let sample = format!(
+                "audit_findings_total{{repo=\"deploybackend\",severity=\"{}\"}} {expected}\n",
+                severity.as_str()
+            );
+            assert!(text.contains(&sample), "{sample}");
+        }
+        assert!(text.contains("audit_last_scan_timestamp_seconds{repo=\"deploybackend\"} "));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+        assert_eq!(
+            to_prometheus(&[]),
+            "# HELP audit_findings_total Findings in the most recent scan of a repository.\n\
+             # TYPE audit_findings_total gauge\n\
+             # HELP audit_last_scan_timestamp_seconds When the most recent scan of a repository \
+             was saved.\n\
+             # TYPE audit_last_scan_timestamp_seconds gauge\n"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! SARIF 2.1.0 output for code scanning dashboards such as GitHub's.
+//!
+//! Every catalog detector becomes a rule keyed by its namespaced id, and
+//! each finding a result referencing that rule.
+
+use serde_json::{json, Value};
+
+use crate::detectors::{self, DetectorInfo};
+use crate::finding::{Finding, Severity};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Builds a SARIF log with a single run covering `findings`.
+pub fn to_sarif(findings: &[Finding]) -> Value {
+    let catalog: Vec<_> = detectors::catalog().collect();
+    let rules: Vec<_> = catalog.iter().map(|info| rule(info)).collect();
+    let results: Vec<_> = findings
+        .iter()
+        .map(|finding| {
+            let index = catalog.iter().position(|info| info.id == finding.rule_id());
+            result(finding, index)
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn rule(info: &DetectorInfo) -> Value {
+    json!({
+        "id": info.id,
+        "name": info.name,
+        "shortDescription": { "text": info.title },
+        "fullDescription": { "text": info.description },
+        "helpUri": info.references.first(),
+        "help": {
+            "text": info.references.join("\n"),
+            "markdown": info
+                .references
+                .iter()
+                .map(|url| format!("- <{url}>"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        "defaultConfiguration": { "level": level(info.severity) },
+        "properties": {
+            "category": info.category,
+            "security-severity": security_severity(info.severity),
+            "tags": ["security", format!("external/cwe/cwe-{}", info.cwe)],
+        },
+    })
+}
+
+fn result(finding: &Finding, rule_index: Option<usize>) -> Value {
+    let span = &finding.span;
+    let mut message = finding.message.clone();
+    if let Some(suggestion) = &finding.suggestion {
+        message = format!("{message}. Fix: {suggestion}");
+    }
+    let mut result = json!({
+        "ruleId": finding.rule_id(),
+        "level": level(finding.severity),
+        "message": { "text": message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri(&span.file) },
+                "region": {
+                    "startLine": span.line_start,
+                    "startColumn": span.col_start,
+                    "endLine": span.line_end,
+                    "endColumn": span.col_end,
+                },
+            },
+        }],
+        "partialFingerprints": { "anchorAudit/v1": finding.fingerprint() },
+        "properties": {
+            "severity": finding.severity,
+            "confidence": finding.confidence,
+        },
+    });
+    if let Some(index) = rule_index {
+        result["ruleIndex"] = index.into();
+    }
+    if let (Some(handler), Some(discriminator)) = (&finding.handler, finding.discriminator()) {
+        result["properties"]["handler"] = handler.as_str().into();
+        result["properties"]["discriminator"] = discriminator.into();
+    }
+    result
+}
+
+/// SARIF has three result levels; critical and high both map to `error`.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low => "note",
+    }
+}
+
+/// The CVSS-like score GitHub uses to bucket security alerts.
+fn security_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "9.5",
+        Severity::High => "8.0",
+        Severity::Medium => "5.5",
+        Severity::Low => "2.0",
+    }
+}
+
+/// Forward-slash relative URI, as SARIF viewers expect on every platform.
+fn uri(path: &std::path::Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_golden, scan_fixtures};
+
+    #[test]
+    fn matches_golden_log() {
+        let findings = scan_fixtures();
+        let log = to_sarif(&findings);
+        let run = &log["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), findings.len());
+        for result in results {
+            let index = result["ruleIndex"].as_u64().unwrap() as usize;
+            assert_eq!(rules[index]["id"], result["ruleId"]);
+        }
+        for (result, finding) in results.iter().zip(&findings) {
+            assert_eq!(
+                result["partialFingerprints"]["anchorAudit/v1"],
+                finding.fingerprint()
+            );
+        }
+
+        let rendered = serde_json::to_string_pretty(&log).unwrap() + "\n";
+        assert_golden("report.sarif", &rendered);
+    }
+}
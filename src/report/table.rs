@@ -0,0 +1,183 @@
+//! Compact fixed-width table for `--format table`.
+//!
+//! One row per finding, most severe first and then by location, with the
+//! columns sized to fit a terminal of the given width: severity and
+//! location keep their full text where they can, the detector is capped and
+//! the message takes what is left, cut short with `…`.
+
+use std::cmp::Reverse;
+use std::fmt::Write;
+
+use crate::finding::Finding;
+use crate::report::text::write_totals;
+use crate::report::Summary;
+
+/// Width used when the terminal's is unknown.
+pub const DEFAULT_WIDTH: usize = 120;
+
+const HEADERS: [&str; 4] = ["Severity", "Detector", "Location", "Message"];
+const SEPARATOR: &str = " | ";
+const MAX_DETECTOR: usize = 32;
+const MAX_LOCATION: usize = 48;
+/// How far the location and detector columns shrink on a narrow terminal
+/// before the message gives up its minimum.
+const MIN_DETECTOR: usize = 16;
+const MIN_LOCATION: usize = 24;
+/// The message column never gets narrower than this, even if the table
+/// then overflows `width`.
+const MIN_MESSAGE: usize = 20;
+
+/// Renders `findings` as a table at most `width` columns wide, followed by
+/// the same totals line as the text report.
+pub fn to_table(findings: &[Finding], summary: &Summary, width: usize) -> String {
+    let mut rows: Vec<[String; 4]> = findings
+        .iter()
+        .map(|finding| {
+            [
+                finding.severity.as_str().to_string(),
+                finding.detector.to_string(),
+                format!(
+                    "{}:{}",
+                    finding.span.file.display(),
+                    finding.span.line_start
+                ),
+                finding.message.clone(),
+            ]
+        })
+        .collect();
+    let mut order: Vec<_> = (0..findings.len()).collect();
+    order.sort_by_key(|&i| {
+        let finding = &findings[i];
+        (
+            Reverse(finding.severity),
+            &finding.span.file,
+            finding.span.line_start,
+            finding.span.col_start,
+        )
+    });
+    let rows: Vec<_> = order
+        .into_iter()
+        .map(|i| std::mem::take(&mut rows[i]))
+        .collect();
+
+    let column = |index: usize, max: usize| {
+        rows.iter()
+            .map(|row| row[index].chars().count())
+            .chain([HEADERS[index].len()])
+            .max()
+            .unwrap_or(0)
+            .min(max)
+    };
+    let severity = column(0, usize::MAX);
+    let mut detector = column(1, MAX_DETECTOR);
+    let mut location = column(2, MAX_LOCATION);
+    // On a narrow terminal, give up location then detector characters
+    // before squeezing the message below its minimum.
+    let mut excess =
+        (severity + detector + location + 3 * SEPARATOR.len() + MIN_MESSAGE).saturating_sub(width);
+    shrink(&mut location, MIN_LOCATION, &mut excess);
+    shrink(&mut detector, MIN_DETECTOR, &mut excess);
+    let fixed = severity + detector + location + 3 * SEPARATOR.len();
+    let message = width.saturating_sub(fixed).max(MIN_MESSAGE);
+    let widths = [severity, detector, location, message];
+
+    let mut out = String::new();
+    write_row(&mut out, &HEADERS.map(String::from), &widths);
+    let rule: Vec<_> = widths.iter().map(|&w| "-".repeat(w)).collect();
+    let _ = writeln!(out, "{}", rule.join("-+-"));
+    for row in &rows {
+        write_row(&mut out, row, &widths);
+    }
+    write_totals(&mut out, findings.len(), summary);
+    out
+}
+
+/// Takes up to `excess` characters off `column`, leaving at least `floor`.
+fn shrink(column: &mut usize, floor: usize, excess: &mut usize) {
+    let cut = (*excess).min(column.saturating_sub(floor));
+    *column -= cut;
+    *excess -= cut;
+}
+
+fn write_row(out: &mut String, cells: &[String; 4], widths: &[usize; 4]) {
+    let cells: Vec<_> = cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(index, (cell, &width))| {
+            // Paths lose their start rather than the line number.
+            let cell = if index == 2 {
+                fit_start(cell, width)
+            } else {
+                fit(cell, width)
+            };
+            format!("{cell:width$}")
+        })
+        .collect();
+    let _ = writeln!(out, "{}", cells.join(SEPARATOR).trim_end());
+}
+
+/// `text` cut to `width` characters, ending in `…` if anything was dropped.
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(width.saturating_sub(1)).collect();
+    kept + "…"
+}
+
+/// Like [`fit`], but drops characters from the start.
+fn fit_start(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+    if count <= width {
+        return text.to_string();
+    }
+    let kept: String = text.chars().skip(count + 1 - width.max(1)).collect();
+    "…".to_string() + &kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::Severity;
+    use crate::test_support::scan_fixtures;
+
+    #[test]
+    fn critical_findings_lead_the_table() {
+        let findings = scan_fixtures();
+        let summary = Summary::of(&findings);
+        let table = to_table(&findings, &summary, 160);
+        let lines: Vec<_> = table.lines().collect();
+        assert!(lines[0].starts_with("Severity | Detector"));
+        assert!(lines[0].contains("| Location "));
+        assert!(lines[0].ends_with("| Message"));
+        assert!(lines[1].starts_with("---------+-"));
+        assert!(lines.iter().all(|line| line.chars().count() <= 160));
+
+        let critical = &lines[2..2 + summary.critical];
+        assert!(critical.iter().all(|row| row.starts_with("critical |")));
+        assert!(
+            critical
+                .iter()
+                .any(|row| row.contains("vulnerable_vault.rs:90")
+                    && row.contains("`emergency_drain`"))
+        );
+        assert!(lines[2 + summary.critical].starts_with("high     |"));
+        assert_eq!(lines.len(), 2 + findings.len() + 1);
+    }
+
+    #[test]
+    fn messages_are_cut_to_the_width() {
+        let findings: Vec<_> = scan_fixtures()
+            .into_iter()
+            .filter(|f| f.severity == Severity::Low)
+            .collect();
+        let table = to_table(&findings, &Summary::of(&findings), 80);
+        let rows: Vec<_> = table.lines().skip(2).take(findings.len()).collect();
+        assert!(rows.iter().all(|row| row.chars().count() <= 80));
+        assert!(rows.iter().any(|row| row.ends_with('…')));
+        assert_eq!(fit("abcdef", 4), "abc…");
+        assert_eq!(fit("abc", 4), "abc");
+        assert_eq!(fit_start("src/lib.rs:9", 8), "…ib.rs:9");
+    }
+}
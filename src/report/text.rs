@@ -0,0 +1,300 @@
+//! Plain-text terminal report.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::detectors::DetectorInfo;
+use crate::finding::{Finding, Severity};
+use crate::report::workspace::WorkspaceReport;
+use crate::report::Summary;
+use crate::watch::Delta;
+
+/// Renders one line per finding followed by its suggestion, if any, and a
+/// closing line with the per-severity counts from `summary`, which may
+/// include findings that were filtered out.
+///
+/// With `color`, each severity label is wrapped in the ANSI color of its
+/// severity.
+pub fn render(findings: &[Finding], summary: &Summary, color: bool) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        write_finding(&mut out, finding, "", color);
+    }
+    write_totals(&mut out, findings.len(), summary);
+    out
+}
+
+/// Like [`render`], but with the findings grouped under the handler they
+/// belong to, each group headed by its own severity subtotals. Groups are
+/// in order of their first finding; findings outside any handler form a
+/// group per file.
+pub fn render_by_handler(findings: &[Finding], summary: &Summary, color: bool) -> String {
+    let mut groups: Vec<(GroupKey, Vec<&Finding>)> = Vec::new();
+    for finding in findings {
+        let key = (finding.span.file.as_path(), finding.handler.as_deref());
+        match groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, members)) => members.push(finding),
+            None => groups.push((key, vec![finding])),
+        }
+    }
+    let mut out = String::new();
+    for ((file, handler), members) in &groups {
+        let counts =
+            severity_counts(|severity| members.iter().filter(|f| f.severity == severity).count());
+        let _ = match handler {
+            Some(handler) => writeln!(out, "{handler} ({}): {counts}", file.display()),
+            None => writeln!(out, "outside any handler ({}): {counts}", file.display()),
+        };
+        for finding in members {
+            write_finding(&mut out, finding, "  ", color);
+        }
+        out.push('\n');
+    }
+    write_totals(&mut out, findings.len(), summary);
+    out
+}
+
+/// Like [`render`], but with a section per declared program listing its
+/// handlers and then its findings, followed by findings no program claims.
+pub fn render_workspace(workspace: &WorkspaceReport, summary: &Summary, color: bool) -> String {
+    let mut out = String::new();
+    for (id, program) in &workspace.programs {
+        let files: Vec<_> = program
+            .files
+            .iter()
+            .map(|f| f.display().to_string())
+            .collect();
+        let _ = match &program.module {
+            Some(module) => writeln!(out, "program {id} `{module}` ({})", files.join(", ")),
+            None => writeln!(out, "program {id} ({})", files.join(", ")),
+        };
+        let _ = writeln!(out, "  handlers: {}", program.handlers.join(", "));
+        let _ = writeln!(
+            out,
+            "  {} finding(s): {}",
+            program.summary.total,
+            severity_counts(|severity| program.summary.count(severity))
+        );
+        for finding in &program.findings {
+            write_finding(&mut out, finding, "  ", color);
+        }
+        out.push('\n');
+    }
+    if !workspace.unattributed.is_empty() {
+        let _ = writeln!(out, "outside any declared program:");
+        for finding in &workspace.unattributed {
+            write_finding(&mut out, finding, "  ", color);
+        }
+        out.push('\n');
+    }
+    let shown = workspace
+        .programs
+        .values()
+        .map(|program| program.findings.len())
+        .sum::<usize>()
+        + workspace.unattributed.len();
+    write_totals(&mut out, shown, summary);
+    out
+}
+
+/// The findings a watch-mode rescan added, marked `+`, and removed,
+/// marked `-`, with a closing line counting both.
+pub fn render_delta(delta: &Delta, color: bool) -> String {
+    let mut out = String::new();
+    for finding in &delta.new {
+        write_finding(&mut out, finding, "+ ", color);
+    }
+    for finding in &delta.fixed {
+        write_finding(&mut out, finding, "- ", color);
+    }
+    let _ = writeln!(
+        out,
+        "{} new, {} fixed in {} file(s)",
+        delta.new.len(),
+        delta.fixed.len(),
+        delta.files.len()
+    );
+    out
+}
+
+/// SGR code of the foreground color `severity` is shown in.
+fn ansi_color(severity: Severity) -> u8 {
+    match severity {
+        Severity::Critical => 31,
+        Severity::High => 35,
+        Severity::Medium => 33,
+        Severity::Low => 34,
+    }
+}
+
+/// File and handler of a [`render_by_handler`] group.
+type GroupKey<'a> = (&'a Path, Option<&'a str>);
+
+fn write_finding(out: &mut String, finding: &Finding, indent: &str, color: bool) {
+    let label = finding.severity.as_str().to_uppercase();
+    let label = if color {
+        format!("\x1b[{}m{label}\x1b[0m", ansi_color(finding.severity))
+    } else {
+        label
+    };
+    let _ = writeln!(
+        out,
+        "{indent}{}: {label} ({} confidence) [{}] {}",
+        finding.span,
+        finding.confidence,
+        finding.rule_id(),
+        finding.message
+    );
+    if let Some(suggestion) = &finding.suggestion {
+        let _ = writeln!(out, "{indent}    help: {suggestion}");
+    }
+    if !finding.also_matched.is_empty() {
+        let _ = writeln!(
+            out,
+            "{indent}    also matched: {}",
+            finding.also_matched.join(", ")
+        );
+    }
+}
+
+pub(crate) fn write_totals(out: &mut String, shown: usize, summary: &Summary) {
+    if shown == summary.total {
+        let _ = write!(out, "{} finding(s)", summary.total);
+    } else {
+        let _ = write!(out, "{} of {} finding(s) shown", shown, summary.total);
+    }
+    let _ = writeln!(
+        out,
+        ": {}",
+        severity_counts(|severity| summary.count(severity))
+    );
+}
+
+/// `4 critical, 10 high, 5 medium, 0 low`
+fn severity_counts(count: impl Fn(Severity) -> usize) -> String {
+    let counts: Vec<_> = Severity::ALL
+        .iter()
+        .map(|&severity| format!("{} {severity}", count(severity)))
+        .collect();
+    counts.join(", ")
+}
+
+/// One line per severity, then the total and risk score; the whole report
+/// for `--format summary`.
+pub fn summary(summary: &Summary) -> String {
+    let mut out = String::new();
+    for severity in Severity::ALL {
+        let _ = writeln!(out, "{severity}: {}", summary.count(severity));
+    }
+    let _ = writeln!(out, "total: {}", summary.total);
+    let _ = writeln!(out, "risk score: {}", summary.risk_score);
+    out
+}
+
+/// Documentation of one detector, as printed by `anchor-audit explain`.
+pub fn explain(info: &DetectorInfo) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{} ({}): {}", info.id, info.name, info.title);
+    let _ = writeln!(
+        out,
+        "category: {}, default severity: {}, CWE-{}",
+        info.category, info.severity, info.cwe
+    );
+    let _ = writeln!(out, "\n{}", info.description);
+    for (heading, code) in [("Vulnerable", info.example), ("Fixed", info.fixed_example)] {
+        let _ = writeln!(out, "\n{heading}:");
+        for line in code.lines() {
+            let _ = writeln!(out, "    {line}");
+        }
+    }
+    let _ = writeln!(out, "\nReferences:");
+    for url in info.references {
+        let _ = writeln!(out, "    {url}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture, scan_fixtures};
+
+    #[test]
+    fn summary_counts_fixture_findings() {
+        let summary = summary(&Summary::of(&scan_fixtures()));
+        let lines: Vec<_> = summary.lines().collect();
+        assert_eq!(lines[..3], ["critical: 8", "high: 24", "medium: 28"]);
+        assert_eq!(lines[3..5], ["low: 12", "total: 72"]);
+        assert!(lines[5].starts_with("risk score: "));
+        assert_eq!(lines.len(), 6);
+    }
+
+    #[test]
+    fn colors_severity_labels_only_when_asked() {
+        let findings = scan_fixtures();
+        let summary = Summary::of(&findings);
+        let plain = render(&findings, &summary, false);
+        assert!(!plain.contains('\x1b'));
+
+        let colored = render(&findings, &summary, true);
+        for label in [
+            "\x1b[31mCRITICAL\x1b[0m",
+            "\x1b[35mHIGH\x1b[0m",
+            "\x1b[33mMEDIUM\x1b[0m",
+            "\x1b[34mLOW\x1b[0m",
+        ] {
+            assert!(colored.contains(label), "{label:?} missing");
+        }
+        let stripped = colored.replace("\x1b[0m", "");
+        let stripped = ["31", "35", "33", "34"]
+            .iter()
+            .fold(stripped, |text, code| {
+                text.replace(&format!("\x1b[{code}m"), "")
+            });
+        assert_eq!(stripped, plain);
+    }
+
+    #[test]
+    fn workspace_report_has_a_section_per_program() {
+        let scan = crate::scanner::scan(Path::new("contracts/sources")).unwrap();
+        let workspace = WorkspaceReport::new(&scan.programs, &scan.findings);
+        let text = render_workspace(&workspace, &scan.summary, false);
+        let headers: Vec<_> = text.lines().filter(|l| l.starts_with("program ")).collect();
+        assert_eq!(
+            headers,
+            [
+                "program NFT1234567890123456789012345678901234567890 \
+                 `insecure_nft_marketplace` (contracts/sources/insecure_nft_marketplace.rs)",
+                "program VuLn1234567890123456789012345678901234567890 `vulnerable_vault` \
+                 (contracts/sources/vulnerable_vault.rs)"
+            ]
+        );
+        assert!(text.contains("\n  handlers: withdraw, get_user_balance, update_authority,"));
+        assert!(text.ends_with("72 finding(s): 8 critical, 24 high, 28 medium, 12 low\n"));
+    }
+
+    #[test]
+    fn groups_findings_by_handler() {
+        let findings = crate::scan_file(&fixture("vulnerable_vault.rs")).unwrap();
+        let text = render_by_handler(&findings, &Summary::of(&findings), false);
+        let group = text
+            .split("\n\n")
+            .find(|group| group.starts_with("emergency_drain ("))
+            .unwrap();
+        let header = group.lines().next().unwrap();
+        assert!(
+            header.ends_with("): 2 critical, 4 high, 7 medium, 0 low"),
+            "{header}"
+        );
+        for rule in [
+            "[rust/unsafe-deref] `write_bytes`",
+            "[rust/panic-prone-unwrap]",
+            "[solana/missing-signer] `Emergency::authority`",
+        ] {
+            assert!(group.contains(rule), "{rule} missing from\n{group}");
+        }
+        assert!(group.contains("also matched: incomplete_account_zeroing"));
+        assert!(!group.contains("`withdraw`"));
+        assert!(text.ends_with("\n\n35 finding(s): 4 critical, 14 high, 12 medium, 5 low\n"));
+    }
+}
@@ -0,0 +1,216 @@
+//! One report for a tree holding several programs.
+//!
+//! Programs are indexed by the address in their `declare_id!`. A file
+//! without one belongs to the program declared nearest above it in the
+//! directory tree, since Anchor keeps `lib.rs` at the root of a crate's
+//! `src/` and puts state and instructions in modules below it.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::call_graph::{self, CpiTarget};
+use crate::finding::Finding;
+use crate::parser::ParsedProgram;
+use crate::report::Summary;
+
+/// What a scan records about a file that declares a program or holds its
+/// `#[program]` module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramEntry {
+    pub file: PathBuf,
+    /// Address from `declare_id!`, if the file has one.
+    pub id: Option<String>,
+    /// Name of the `#[program]` module, if the file has one.
+    pub module: Option<String>,
+    pub handlers: Vec<String>,
+    /// CPIs of each handler; see [`call_graph::call_graph`].
+    pub call_graph: HashMap<String, Vec<CpiTarget>>,
+}
+
+impl ProgramEntry {
+    /// The entry for `program`; `None` for files with neither a
+    /// `declare_id!` nor a `#[program]` module.
+    pub fn of(program: &ParsedProgram) -> Option<Self> {
+        if program.declared_id.is_none() && program.program.is_none() {
+            return None;
+        }
+        Some(ProgramEntry {
+            file: program.path.clone(),
+            id: program.declared_id.clone(),
+            module: program.program.as_ref().map(|module| module.name.clone()),
+            handlers: program.handlers().iter().map(|h| h.name.clone()).collect(),
+            call_graph: call_graph::call_graph(program),
+        })
+    }
+}
+
+/// One declared program and the findings in its files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgramReport {
+    /// Name of the `#[program]` module, if one was found.
+    pub module: Option<String>,
+    /// Files declaring the program's id, usually just one.
+    pub files: Vec<PathBuf>,
+    /// Every handler of the program, whether or not it has findings.
+    pub handlers: Vec<String>,
+    pub findings: Vec<Finding>,
+    pub summary: Summary,
+}
+
+/// Findings of a scan nested under the program they belong to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkspaceReport {
+    /// Programs by declared id.
+    pub programs: BTreeMap<String, ProgramReport>,
+    /// Findings in files no declared program claims.
+    pub unattributed: Vec<Finding>,
+}
+
+impl WorkspaceReport {
+    /// Indexes `entries` by their declared id and sorts `findings` into
+    /// the program owning each finding's file.
+    pub fn new(entries: &[ProgramEntry], findings: &[Finding]) -> Self {
+        let declared: Vec<(&Path, &str)> = entries
+            .iter()
+            .filter_map(|entry| Some((entry.file.as_path(), entry.id.as_deref()?)))
+            .collect();
+        let owner = |file: &Path| owner(&declared, file);
+
+        let mut report = WorkspaceReport::default();
+        for &(file, id) in &declared {
+            report
+                .programs
+                .entry(id.to_string())
+                .or_default()
+                .files
+                .push(file.to_path_buf());
+        }
+        for entry in entries {
+            let Some(program) = owner(&entry.file).and_then(|id| report.programs.get_mut(id))
+            else {
+                continue;
+            };
+            if program.module.is_none() {
+                program.module.clone_from(&entry.module);
+            }
+            program.handlers.extend(entry.handlers.iter().cloned());
+        }
+        for finding in findings {
+            match owner(&finding.span.file).and_then(|id| report.programs.get_mut(id)) {
+                Some(program) => program.findings.push(finding.clone()),
+                None => report.unattributed.push(finding.clone()),
+            }
+        }
+        for program in report.programs.values_mut() {
+            program.summary = Summary::of(&program.findings);
+        }
+        report
+    }
+
+    pub fn program(&self, id: &str) -> Option<&ProgramReport> {
+        self.programs.get(id)
+    }
+}
+
+/// The id declared in `file` itself, or else in the file whose directory
+/// is the closest ancestor of `file`.
+fn owner<'a>(declared: &[(&Path, &'a str)], file: &Path) -> Option<&'a str> {
+    if let Some(&(_, id)) = declared.iter().find(|(path, _)| *path == file) {
+        return Some(id);
+    }
+    declared
+        .iter()
+        .filter_map(|&(path, id)| {
+            let dir = path.parent()?;
+            file.starts_with(dir)
+                .then_some((dir.components().count(), id))
+        })
+        .max_by_key(|&(depth, _)| depth)
+        .map(|(_, id)| id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::Severity;
+    use crate::scanner;
+    use crate::test_support::fixture;
+
+    const NFT: &str = "NFT1234567890123456789012345678901234567890";
+    const VAULT: &str = "VuLn1234567890123456789012345678901234567890";
+
+    fn critical_rules(program: &ProgramReport) -> Vec<&str> {
+        program
+            .findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Critical)
+            .map(|finding| finding.rule_id())
+            .collect()
+    }
+
+    #[test]
+    fn nests_fixture_findings_under_their_program() {
+        let scan = scanner::scan(&fixture("")).unwrap();
+        let report = WorkspaceReport::new(&scan.programs, &scan.findings);
+        let ids: Vec<_> = report.programs.keys().collect();
+        assert_eq!(ids, [NFT, VAULT]);
+        assert!(report.unattributed.is_empty());
+
+        let nft = report.program(NFT).unwrap();
+        assert_eq!(nft.module.as_deref(), Some("insecure_nft_marketplace"));
+        assert_eq!(nft.handlers.len(), 7);
+        assert!(nft
+            .findings
+            .iter()
+            .all(|finding| finding.span.file.ends_with("insecure_nft_marketplace.rs")));
+        assert_eq!(
+            critical_rules(nft),
+            [
+                "solana/direct-lamport-mutation",
+                "solana/tainted-owner-assignment",
+                "solana/missing-signer",
+                "solana/missing-signer"
+            ]
+        );
+
+        let vault = report.program(VAULT).unwrap();
+        assert_eq!(vault.module.as_deref(), Some("vulnerable_vault"));
+        assert_eq!(
+            critical_rules(vault),
+            [
+                "solana/tainted-owner-assignment",
+                "solana/unprotected-privileged-fn",
+                "solana/missing-signer",
+                "solana/missing-signer"
+            ]
+        );
+        assert_eq!(vault.summary.critical, 4);
+        assert_eq!(
+            nft.findings.len() + vault.findings.len(),
+            scan.findings.len()
+        );
+    }
+
+    #[test]
+    fn files_without_an_id_join_the_program_above_them() {
+        let entry = |file: &str, id: Option<&str>, handlers: &[&str]| ProgramEntry {
+            file: file.into(),
+            id: id.map(String::from),
+            module: None,
+            handlers: handlers.iter().map(|h| h.to_string()).collect(),
+            call_graph: HashMap::new(),
+        };
+        let entries = [
+            entry("a/src/lib.rs", Some("AAA"), &[]),
+            entry("a/src/instructions/mod.rs", None, &["pay"]),
+            entry("b/src/lib.rs", Some("BBB"), &["mint"]),
+        ];
+        let report = WorkspaceReport::new(&entries, &[]);
+        assert_eq!(report.program("AAA").unwrap().handlers, ["pay"]);
+        assert_eq!(report.program("BBB").unwrap().handlers, ["mint"]);
+        assert_eq!(
+            owner(&[(Path::new("a/src/lib.rs"), "AAA")], Path::new("c/x.rs")),
+            None
+        );
+    }
+}
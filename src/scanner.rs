@@ -0,0 +1,927 @@
+//! Scanning of whole directory trees.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use rayon::prelude::*;
+use walkdir::{DirEntry, WalkDir};
+
+use crate::cache::ParseCache;
+use crate::detectors::{invalid_program_id, Registry};
+use crate::diff::ChangedLines;
+use crate::error::{Error, ParseError, Result};
+use crate::finding::{self, Finding, Severity};
+use crate::parser::{self, ParsedProgram};
+use crate::progress::Progress;
+use crate::report::workspace::ProgramEntry;
+use crate::report::Summary;
+use crate::suppress;
+
+/// Directories never descended into.
+const SKIPPED_DIRS: &[&str] = &["target"];
+
+/// Process exit code when findings at or above the threshold remain.
+pub const EXIT_FINDINGS: u8 = 1;
+
+/// Process exit code when a file could not be read or parsed.
+pub const EXIT_ERROR: u8 = 2;
+
+/// Knobs controlling what a scan reports.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Findings below this severity are counted in the summary but not
+    /// reported.
+    pub min_severity: Severity,
+    /// Number of files analysed at once; `0` uses one thread per core and
+    /// `1` scans serially.
+    pub jobs: usize,
+    /// Which detectors run.
+    pub registry: Registry,
+    /// Advanced once per file as results come in.
+    pub progress: Progress,
+    /// Refuse to scan a tree with more `.rs` files than this; `None` scans
+    /// any number.
+    pub max_files: Option<usize>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            min_severity: Severity::Low,
+            jobs: 0,
+            registry: Registry::default(),
+            progress: Progress::hidden(),
+            max_files: None,
+        }
+    }
+}
+
+/// Everything a scan produced.
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    /// Every `.rs` file that was read, parsed or not.
+    pub files: Vec<PathBuf>,
+    /// Findings at or above [`ScanOptions::min_severity`].
+    pub findings: Vec<Finding>,
+    /// Counts of every finding, including those filtered out.
+    pub summary: Summary,
+    /// Every parsed file that declares a program or holds its
+    /// `#[program]` module, in file order.
+    pub programs: Vec<ProgramEntry>,
+    /// Files that could not be parsed; the scan carries on without them.
+    pub parse_errors: Vec<ParseError>,
+    /// Findings handed out by [`scan_streaming`] instead of being kept in
+    /// `findings`.
+    pub streamed: usize,
+    /// How many of the streamed findings were warning-only.
+    pub streamed_warnings: usize,
+}
+
+impl ScanReport {
+    /// Narrows the report to `findings`, such as the new ones after a
+    /// baseline comparison, with the summary counting only those.
+    pub fn keep_only(&mut self, findings: Vec<Finding>) {
+        self.summary = Summary::of(&findings);
+        self.findings = findings;
+    }
+
+    /// Appends the results of scanning another root.
+    pub fn merge(&mut self, other: ScanReport) {
+        self.files.extend(other.files);
+        self.findings.extend(other.findings);
+        self.programs.extend(other.programs);
+        self.summary += other.summary;
+        self.parse_errors.extend(other.parse_errors);
+        self.streamed += other.streamed;
+        self.streamed_warnings += other.streamed_warnings;
+    }
+
+    /// [`EXIT_ERROR`] if any file failed to parse, [`EXIT_FINDINGS`] if any
+    /// finding was reported that is not [`Finding::warning_only`], `0`
+    /// otherwise.
+    pub fn exit_code(&self) -> u8 {
+        let blocking = self.findings.iter().any(|finding| !finding.warning_only)
+            || self.streamed > self.streamed_warnings;
+        if !self.parse_errors.is_empty() {
+            EXIT_ERROR
+        } else if blocking {
+            EXIT_FINDINGS
+        } else {
+            0
+        }
+    }
+}
+
+/// Scans every `*.rs` file under `root`, or `root` itself if it is a file,
+/// with default options.
+///
+/// `target/` and hidden directories are skipped. Files that fail to parse
+/// are recorded in [`ScanReport::parse_errors`] instead of aborting.
+pub fn scan(root: &Path) -> Result<ScanReport> {
+    scan_with(root, &ScanOptions::default())
+}
+
+/// Like [`scan`], reporting only what `options` asks for.
+///
+/// Files are analysed in parallel. Findings are sorted by file, position
+/// and detector afterwards, so the output does not depend on scheduling.
+pub fn scan_with(root: &Path, options: &ScanOptions) -> Result<ScanReport> {
+    scan_matching(root, options, |_| true)
+}
+
+/// Scans `root` but reports only findings whose span touches a line added
+/// or changed by `diff`, a unified diff with paths relative to `root`.
+///
+/// The whole tree is still analysed, so findings that depend on unchanged
+/// code are found; they are just not reported unless they reach into a
+/// hunk. The summary counts only findings in the diff.
+pub fn scan_diff(root: &Path, diff: &str) -> Result<ScanReport> {
+    scan_diff_with(root, diff, &ScanOptions::default())
+}
+
+/// Like [`scan_diff`], reporting only what `options` asks for.
+pub fn scan_diff_with(root: &Path, diff: &str, options: &ScanOptions) -> Result<ScanReport> {
+    let changed = ChangedLines::parse(diff);
+    scan_matching(root, options, |finding| {
+        changed.touches(root, &finding.span)
+    })
+}
+
+/// Scans `root`, keeping only findings `keep` accepts.
+fn scan_matching(
+    root: &Path,
+    options: &ScanOptions,
+    keep: impl Fn(&Finding) -> bool,
+) -> Result<ScanReport> {
+    let mut findings = Vec::new();
+    let mut report = scan_each(root, options, None, keep, |file| findings.extend(file))?;
+    report.findings = findings;
+    Ok(report)
+}
+
+/// Like [`scan_with`], but serially and parsing only files whose contents
+/// changed since `cache` last saw them.
+pub fn scan_cached(
+    root: &Path,
+    options: &ScanOptions,
+    cache: &mut ParseCache,
+) -> Result<ScanReport> {
+    let mut findings = Vec::new();
+    let mut report = scan_each(
+        root,
+        options,
+        Some(cache),
+        |_| true,
+        |file| findings.extend(file),
+    )?;
+    report.findings = findings;
+    Ok(report)
+}
+
+/// Like [`scan_with`], but hands findings to `emit` as the scan goes
+/// instead of collecting them, so memory stays bounded on huge trees.
+///
+/// Each file's findings are buffered until every file before it has been
+/// emitted, so `emit` sees the same order [`scan_with`] reports.
+/// [`ScanReport::findings`] stays empty; [`ScanReport::streamed`] counts
+/// what was emitted.
+pub fn scan_streaming(
+    root: &Path,
+    options: &ScanOptions,
+    mut emit: impl FnMut(&Finding),
+) -> Result<ScanReport> {
+    let (mut streamed, mut warnings) = (0, 0);
+    let mut report = scan_each(
+        root,
+        options,
+        None,
+        |_| true,
+        |file| {
+            streamed += file.len();
+            warnings += file.iter().filter(|finding| finding.warning_only).count();
+            file.iter().for_each(&mut emit);
+        },
+    )?;
+    report.streamed = streamed;
+    report.streamed_warnings = warnings;
+    Ok(report)
+}
+
+/// Analyses every file under `root` and passes the findings `keep` accepts
+/// and `options` reports to `sink`, one file at a time in file order.
+fn scan_each(
+    root: &Path,
+    options: &ScanOptions,
+    cache: Option<&mut ParseCache>,
+    keep: impl Fn(&Finding) -> bool,
+    mut sink: impl FnMut(Vec<Finding>),
+) -> Result<ScanReport> {
+    let files = rust_files(root)?;
+    if let Some(limit) = options.max_files.filter(|&limit| files.len() > limit) {
+        return Err(Error::TooManyFiles {
+            path: root.to_path_buf(),
+            found: files.len(),
+            limit,
+        });
+    }
+    // Fingerprints name files relative to the tree being scanned.
+    let base = match root.parent() {
+        Some(parent) if root.is_file() => parent,
+        _ => root,
+    };
+    let mut report = ScanReport::default();
+    let mut failure = None;
+    let mut declared = HashSet::new();
+    let mut done = files.iter();
+    options.progress.start(files.len());
+    let analyzed = analyze_in_order(&files, options, cache, |result| {
+        if let Some(file) = done.next() {
+            options.progress.advance(file);
+        }
+        match result {
+            Ok(FileScan {
+                program,
+                mut findings,
+                redeclaration,
+            }) => {
+                let id = program.as_ref().and_then(|program| program.id.clone());
+                if let Some(id) = id {
+                    if !declared.insert(id) {
+                        findings.extend(redeclaration);
+                    }
+                }
+                report.programs.extend(program);
+                let mut findings: Vec<_> = findings.into_iter().filter(&keep).collect();
+                for finding in &mut findings {
+                    finding.rebase_fingerprint(base);
+                }
+                findings.sort_by(|a, b| order(a).cmp(&order(b)));
+                report.summary += Summary::of(&findings);
+                findings.retain(|finding| finding.severity >= options.min_severity);
+                sink(findings);
+            }
+            Err(Error::Parse(err)) => report.parse_errors.push(err),
+            Err(err) => {
+                failure.get_or_insert(err);
+            }
+        }
+    });
+    options.progress.finish();
+    analyzed?;
+    report.files = files;
+    match failure {
+        Some(err) => Err(err),
+        None => Ok(report),
+    }
+}
+
+/// Analyses `files` with the parallelism `options` asks for, calling
+/// `sink` on the calling thread with each result in file order. With a
+/// `cache` the files are analysed serially, reusing unchanged programs.
+/// Fails only if the thread pool `options.jobs` asks for cannot start.
+fn analyze_in_order(
+    files: &[PathBuf],
+    options: &ScanOptions,
+    cache: Option<&mut ParseCache>,
+    mut sink: impl FnMut(Result<FileScan>),
+) -> Result<()> {
+    let registry = &options.registry;
+    if let Some(cache) = cache {
+        for file in files {
+            sink(
+                cache
+                    .parse_file(file)
+                    .map(|program| FileScan::of(&program, registry)),
+            );
+        }
+        return Ok(());
+    }
+    if options.jobs == 1 {
+        for file in files {
+            sink(scan_one(file, registry));
+        }
+        return Ok(());
+    }
+    // Zero runs on rayon's global pool.
+    let pool = match options.jobs {
+        0 => None,
+        jobs => Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build()
+                .map_err(|source| Error::Threads { jobs, source })?,
+        ),
+    };
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let analyze = || {
+                files
+                    .par_iter()
+                    .enumerate()
+                    .for_each_with(sender, |sender, (index, file)| {
+                        // The receiver only hangs up once every result arrived.
+                        let _ = sender.send((index, scan_one(file, registry)));
+                    })
+            };
+            match &pool {
+                None => analyze(),
+                Some(pool) => pool.install(analyze),
+            }
+        });
+        // Results finishing early wait here until their predecessors are in.
+        let mut waiting = BTreeMap::new();
+        let mut next = 0;
+        for (index, result) in receiver {
+            waiting.insert(index, result);
+            while let Some(result) = waiting.remove(&next) {
+                sink(result);
+                next += 1;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Deterministic report order: file, position, then detector.
+pub(crate) fn order(finding: &Finding) -> (&Path, usize, usize, &str) {
+    let span = &finding.span;
+    (
+        &span.file,
+        span.line_start,
+        span.col_start,
+        finding.detector,
+    )
+}
+
+/// What analysing one file produced.
+struct FileScan {
+    program: Option<ProgramEntry>,
+    findings: Vec<Finding>,
+    /// Reported if an earlier file declared the same program id.
+    redeclaration: Option<Finding>,
+}
+
+impl FileScan {
+    fn of(program: &ParsedProgram, registry: &Registry) -> Self {
+        let span = tracing::info_span!(
+            "scan_file",
+            file = %program.path.display(),
+            finding_count = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+        let findings = analyze(program, registry);
+        span.record("finding_count", findings.len());
+        let redeclaration = invalid_program_id::redeclaration(program)
+            .filter(|_| registry.is_enabled(invalid_program_id::ID))
+            .and_then(|finding| suppress::apply(program, vec![finding]).pop())
+            .map(|finding| registry.rerate(finding));
+        FileScan {
+            program: ProgramEntry::of(program),
+            findings,
+            redeclaration,
+        }
+    }
+}
+
+/// What scanning one in-memory source produced.
+#[derive(Debug, Clone)]
+pub struct SourceScan {
+    pub findings: Vec<Finding>,
+    /// The program the source declares, if any.
+    pub program: Option<ProgramEntry>,
+}
+
+/// Parses `source`, attributing it to `name`, and runs the enabled
+/// detectors of `registry` over it. Nothing is read from disk and no
+/// state is kept between calls, so cross-file checks such as program id
+/// redeclarations are left to the tree scans. Fingerprints are those a
+/// [`scan`] of the file `name` would compute.
+pub fn scan_source_with(
+    name: impl Into<PathBuf>,
+    source: &str,
+    registry: &Registry,
+) -> std::result::Result<SourceScan, ParseError> {
+    let program = parser::parse_source(name, source.to_string())?;
+    let mut findings = analyze(&program, registry);
+    let dir = program.path.parent().unwrap_or(Path::new(""));
+    for finding in &mut findings {
+        finding.rebase_fingerprint(dir);
+    }
+    Ok(SourceScan {
+        findings,
+        program: ProgramEntry::of(&program),
+    })
+}
+
+/// Parses and analyses one file. The parsed program never leaves the
+/// calling thread; `syn` trees are not `Send`.
+fn scan_one(file: &Path, registry: &Registry) -> Result<FileScan> {
+    let program = parser::parse_file(file)?;
+    Ok(FileScan::of(&program, registry))
+}
+
+/// Runs the enabled detectors over `program`, applies inline suppressions
+/// and merges findings several detectors made on the same span.
+pub fn analyze(program: &ParsedProgram, registry: &Registry) -> Vec<Finding> {
+    finding::dedupe(suppress::apply(program, registry.run_all(program)))
+}
+
+/// The `.rs` files under `root` in a stable, sorted order.
+pub fn rust_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !skipped(entry));
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|err| Error::Io {
+            path: err.path().unwrap_or(root).to_path_buf(),
+            source: err.into(),
+        })?;
+        let is_rust = entry.path().extension().is_some_and(|ext| ext == "rs");
+        if entry.file_type().is_file() && is_rust {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+fn skipped(entry: &DirEntry) -> bool {
+    entry.file_type().is_dir() && skipped_dir(&entry.file_name().to_string_lossy())
+}
+
+/// Whether a directory named `name` is left out of scans.
+pub(crate) fn skipped_dir(name: &str) -> bool {
+    name.starts_with('.') || SKIPPED_DIRS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    use super::*;
+    use crate::detectors::{Detector, DetectorInfo};
+    use crate::test_support::fixture;
+
+    #[test]
+    fn scans_both_fixture_programs() {
+        let report = scan(&fixture("")).unwrap();
+        let names: Vec<_> = report
+            .files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["insecure_nft_marketplace.rs", "vulnerable_vault.rs"]
+        );
+        assert!(report.parse_errors.is_empty());
+        for name in names {
+            assert!(report.findings.iter().any(|f| f.span.file.ends_with(name)));
+        }
+    }
+
+    #[test]
+    fn scanning_source_matches_scanning_the_file() {
+        for name in ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"] {
+            let path = fixture(name);
+            let source = fs::read_to_string(&path).unwrap();
+            let from_source = crate::scan_source(path.to_str().unwrap(), &source).unwrap();
+            let from_file = crate::scan_file(&path).unwrap();
+            assert!(!from_source.is_empty(), "{name}");
+            assert_eq!(from_source.len(), from_file.len(), "{name}");
+            assert_eq!(from_source, from_file, "{name}");
+        }
+
+        let scan = scan_source_with(
+            "vault.rs",
+            &fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap(),
+            &Registry::default(),
+        )
+        .unwrap();
+        assert!(scan
+            .findings
+            .iter()
+            .all(|f| f.span.file == Path::new("vault.rs")));
+        assert!(scan.program.is_some());
+        let err = crate::scan_source("broken.rs", "pub fn broken( {").unwrap_err();
+        assert_eq!(err.file, Path::new("broken.rs"));
+    }
+
+    #[test]
+    fn walks_nested_modules_and_skips_ignored_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        for path in [
+            "programs/vault/src/lib.rs",
+            "programs/vault/src/instructions/mod.rs",
+            "target/debug/build/generated.rs",
+            ".anchor/program.rs",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, &vault).unwrap();
+        }
+        fs::write(dir.path().join("programs/vault/src/broken.rs"), "fn (").unwrap();
+        fs::write(dir.path().join("programs/vault/README.md"), "docs").unwrap();
+
+        let report = scan(dir.path()).unwrap();
+        let relative: Vec<_> = report
+            .files
+            .iter()
+            .map(|file| file.strip_prefix(dir.path()).unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            relative,
+            [
+                "programs/vault/src/broken.rs",
+                "programs/vault/src/instructions/mod.rs",
+                "programs/vault/src/lib.rs"
+            ]
+        );
+        assert_eq!(report.parse_errors.len(), 1);
+        assert!(report.parse_errors[0].file.ends_with("broken.rs"));
+
+        // Both copies declare the same id; the second one is reported.
+        let single = crate::scan_file(&fixture("vulnerable_vault.rs")).unwrap();
+        assert_eq!(report.findings.len(), 2 * single.len() + 1);
+        let redeclared: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.message.contains("also declared by another file"))
+            .collect();
+        assert_eq!(redeclared.len(), 1);
+        assert!(redeclared[0]
+            .span
+            .file
+            .ends_with("programs/vault/src/lib.rs"));
+    }
+
+    #[test]
+    fn parallel_scan_matches_serial_order() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"] {
+            let source = fs::read_to_string(fixture(name)).unwrap();
+            for copy in 0..50 {
+                fs::write(dir.path().join(format!("{copy:02}_{name}")), &source).unwrap();
+            }
+        }
+
+        let serial = ScanOptions {
+            jobs: 1,
+            ..ScanOptions::default()
+        };
+        let expected = scan_with(dir.path(), &serial).unwrap();
+        assert_eq!(expected.files.len(), 100);
+        for jobs in [0, 4] {
+            let options = ScanOptions {
+                jobs,
+                ..ScanOptions::default()
+            };
+            let report = scan_with(dir.path(), &options).unwrap();
+            assert_eq!(report.files, expected.files);
+            assert_eq!(report.findings, expected.findings);
+        }
+
+        let positions: Vec<_> = expected
+            .findings
+            .iter()
+            .map(|f| (&f.span.file, f.span.line_start, f.span.col_start))
+            .collect();
+        assert!(positions.is_sorted());
+    }
+
+    #[test]
+    fn exit_code_reflects_severity_threshold() {
+        let critical = ScanOptions {
+            min_severity: Severity::Critical,
+            ..ScanOptions::default()
+        };
+        let report = scan_with(&fixture(""), &critical).unwrap();
+        assert_eq!(report.exit_code(), EXIT_FINDINGS);
+        assert!(report
+            .findings
+            .iter()
+            .all(|f| f.severity == Severity::Critical));
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| f.message.contains("`emergency_drain`")));
+        assert!(report.summary.total > report.findings.len());
+        assert_eq!(report.summary.critical, report.findings.len());
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("clean.rs"),
+            "pub fn add(a: u64, b: u64) -> Option<u64> { a.checked_add(b) }",
+        )
+        .unwrap();
+        let report = scan_with(dir.path(), &critical).unwrap();
+        assert_eq!(report.exit_code(), 0);
+        assert_eq!(scan(dir.path()).unwrap().exit_code(), 0);
+    }
+
+    /// Reports every handler's name at the given severity.
+    struct OnHandlers(DetectorInfo);
+
+    impl Detector for OnHandlers {
+        fn info(&self) -> &DetectorInfo {
+            &self.0
+        }
+
+        fn run(&self, program: &ParsedProgram) -> Vec<Finding> {
+            program
+                .handlers()
+                .iter()
+                .map(|handler| {
+                    let span = handler.item.sig.ident.span();
+                    Finding::new(self.name(), self.0.severity, program, span, self.name())
+                })
+                .collect()
+        }
+    }
+
+    fn on_handlers(name: &'static str, severity: Severity) -> OnHandlers {
+        OnHandlers(DetectorInfo {
+            id: name,
+            name,
+            severity,
+            ..crate::detectors::missing_signer_authority::INFO
+        })
+    }
+
+    #[test]
+    fn overlapping_detectors_are_merged() {
+        let program = parser::parse_file(&fixture("vulnerable_vault.rs")).unwrap();
+        let analyze_in = |order: [(&'static str, Severity); 3]| {
+            let mut registry = Registry::empty();
+            for (name, severity) in order {
+                registry.register(on_handlers(name, severity));
+            }
+            analyze(&program, &registry)
+        };
+
+        let findings = analyze_in([
+            ("memory_check", Severity::Medium),
+            ("panic_check", Severity::High),
+            ("style_check", Severity::Medium),
+        ]);
+        assert_eq!(findings.len(), program.handlers().len());
+        let withdraw = &findings[0];
+        assert_eq!(withdraw.detector, "panic_check");
+        assert_eq!(withdraw.also_matched, ["memory_check", "style_check"]);
+
+        let reversed = analyze_in([
+            ("style_check", Severity::Medium),
+            ("panic_check", Severity::High),
+            ("memory_check", Severity::Medium),
+        ]);
+        assert_eq!(reversed, findings);
+    }
+
+    #[test]
+    fn diff_scan_reports_only_changed_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("programs/vault/src");
+        fs::create_dir_all(&src).unwrap();
+        let vault = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        fs::write(src.join("lib.rs"), &vault).unwrap();
+
+        let lines: Vec<&str> = vault.lines().collect();
+        assert_eq!(
+            lines[121].trim(),
+            "let rewards = vault.balance * multiplier;"
+        );
+        let mut diff = String::from(
+            "--- a/programs/vault/src/lib.rs\n+++ b/programs/vault/src/lib.rs\n@@ -119,7 +119,7 @@\n",
+        );
+        for line in &lines[118..121] {
+            diff += &format!(" {line}\n");
+        }
+        diff += "-        let rewards = vault.balance;\n";
+        diff += &format!("+{}\n", lines[121]);
+        for line in &lines[122..125] {
+            diff += &format!(" {line}\n");
+        }
+
+        let full = scan(dir.path()).unwrap();
+        let report = scan_diff(dir.path(), &diff).unwrap();
+        assert!(report.findings.len() < full.findings.len());
+        let detectors: Vec<_> = report.findings.iter().map(|f| f.detector).collect();
+        assert_eq!(detectors, ["mul_overflow"]);
+        assert_eq!(report.findings[0].span.line_start, 122);
+        assert_eq!(report.summary.total, 1);
+
+        assert!(scan_diff(dir.path(), "").unwrap().findings.is_empty());
+    }
+
+    #[test]
+    fn missing_root_is_an_error() {
+        assert!(matches!(
+            scan(Path::new("does/not/exist")),
+            Err(Error::Io { .. })
+        ));
+    }
+
+    /// Statements built from the constructs the detectors look for.
+    const STATEMENTS: &[&str] = &[
+        "let vault = &mut ctx.accounts.vault;",
+        "vault.balance = vault.balance + amount;",
+        "vault.balance -= amount * 2;",
+        "let share = amount * 3 / 100;",
+        "vault.authority = ctx.accounts.user.key();",
+        "vault.name = name;",
+        "let data = ctx.accounts.user.to_account_info().try_borrow_data().unwrap();",
+        "let info = ctx.accounts.vault.to_account_info();",
+        "let bytes = info.data.borrow();",
+        "**ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= share;",
+        "let cpi_accounts = Transfer { from: ctx.accounts.vault.to_account_info(), \
+         to: ctx.accounts.user.to_account_info(), authority: ctx.accounts.authority.to_account_info() };",
+        "let cpi_accounts = MintTo { mint: ctx.accounts.vault.to_account_info(), \
+         to: ctx.accounts.user.to_account_info(), authority: ctx.accounts.authority.to_account_info() };",
+        "token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), amount)?;",
+        "token::mint_to(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), 1)?;",
+        "require_keys_eq!(ctx.accounts.vault.authority, ctx.accounts.authority.key());",
+        "ctx.accounts.vault.reload()?;",
+        "unsafe { std::ptr::write_bytes(data.as_mut_ptr(), 0, 8); }",
+        "let name = String::from_utf8(data.to_vec()).unwrap();",
+        "if amount > vault.balance { return err!(ErrorCode::Insufficient); }",
+        "return Ok(amount / 2);",
+        "// audit-ignore",
+        "vault.balance = vault.balance + amount; // audit-ignore: unchecked_arithmetic",
+    ];
+
+    /// Accounts struct fields, several sharing a name with different types.
+    const FIELDS: &[&str] = &[
+        "#[account(mut)] pub vault: Account<'info, Vault>,",
+        "#[account(init, payer = user, space = 8 + 8)] pub vault: Account<'info, Vault>,",
+        "#[account(mut, has_one = authority)] pub vault: Account<'info, Vault>,",
+        "/// CHECK: none\npub user: AccountInfo<'info>,",
+        "#[account(mut)] pub user: Signer<'info>,",
+        "#[account(owner = crate::ID)] pub user: AccountInfo<'info>,",
+        "pub authority: UncheckedAccount<'info>,",
+        "pub authority: Signer<'info>,",
+        "pub token_program: Program<'info, Token>,",
+    ];
+
+    /// Fragments spliced into the fixtures to break them in varied ways.
+    const FRAGMENTS: &[&str] = &[
+        "{",
+        "}",
+        "(",
+        ")",
+        ";",
+        "'info",
+        "#[account(",
+        "ctx.accounts.",
+        "::",
+        "\"",
+        "/*",
+        "*/",
+        "// audit-ignore\n",
+        "// audit-ignore: unchecked_arithmetic\n",
+        "unsafe {",
+        "?",
+        "as u8",
+        "\n",
+    ];
+
+    /// An Anchor program with one handler per body, all sharing one
+    /// Accounts struct.
+    fn program(bodies: &[Vec<usize>], fields: &[usize]) -> String {
+        let mut source =
+            String::from("use anchor_lang::prelude::*;\n\n#[program]\npub mod fuzz {\n");
+        for (i, body) in bodies.iter().enumerate() {
+            source += &format!(
+                "    pub fn handler_{i}(ctx: Context<Shared>, amount: u64, name: String) -> Result<u64> {{\n"
+            );
+            for &statement in body {
+                source += &format!("        {}\n", STATEMENTS[statement]);
+            }
+            source += "        Ok(amount)\n    }\n";
+        }
+        source += "}\n\n#[derive(Accounts)]\npub struct Shared<'info> {\n";
+        for &field in fields {
+            source += &format!("    {}\n", FIELDS[field]);
+        }
+        source += "}\n\n#[account]\npub struct Vault {\n    pub balance: u64,\n    pub authority: Pubkey,\n    pub name: String,\n}\n";
+        source
+    }
+
+    /// Parses and analyses `source`, checking that every finding points
+    /// into it.
+    fn check(source: String) {
+        let lines = source.lines().count().max(1);
+        match parser::parse_source("fuzz.rs", source) {
+            Ok(program) => {
+                for finding in analyze(&program, &Registry::default()) {
+                    let span = &finding.span;
+                    assert!(
+                        1 <= span.line_start
+                            && span.line_start <= span.line_end
+                            && span.line_end <= lines,
+                        "{finding:?} is outside the {lines} line(s) of input"
+                    );
+                }
+            }
+            Err(err) => assert!(!err.message.is_empty()),
+        }
+    }
+
+    /// The source of each fixture, read once.
+    fn seeds() -> &'static [String] {
+        static SEEDS: std::sync::OnceLock<Vec<String>> = std::sync::OnceLock::new();
+        SEEDS.get_or_init(|| {
+            ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"]
+                .iter()
+                .map(|name| fs::read_to_string(fixture(name)).unwrap())
+                .collect()
+        })
+    }
+
+    /// One way of breaking a seed.
+    #[derive(Debug, Clone)]
+    enum Mutation {
+        Truncate(Index),
+        Delete(Index, Index),
+        Insert(Index, usize),
+        DuplicateLine(Index),
+        SwapLines(Index, Index),
+    }
+
+    fn mutation() -> impl Strategy<Value = Mutation> {
+        prop_oneof![
+            any::<Index>().prop_map(Mutation::Truncate),
+            (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Mutation::Delete(a, b)),
+            (any::<Index>(), 0..FRAGMENTS.len()).prop_map(|(at, f)| Mutation::Insert(at, f)),
+            any::<Index>().prop_map(Mutation::DuplicateLine),
+            (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Mutation::SwapLines(a, b)),
+        ]
+    }
+
+    fn mutate(source: &str, mutation: &Mutation) -> String {
+        let boundaries: Vec<usize> = source
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([source.len()])
+            .collect();
+        let at = |index: &Index| boundaries[index.index(boundaries.len())];
+        let mut lines: Vec<&str> = source.lines().collect();
+        match mutation {
+            Mutation::Truncate(end) => source[..at(end)].to_string(),
+            Mutation::Delete(a, b) => {
+                let (start, end) = (at(a).min(at(b)), at(a).max(at(b)));
+                format!("{}{}", &source[..start], &source[end..])
+            }
+            Mutation::Insert(index, fragment) => {
+                let at = at(index);
+                format!("{}{}{}", &source[..at], FRAGMENTS[*fragment], &source[at..])
+            }
+            Mutation::DuplicateLine(line) => {
+                let line = line.index(lines.len());
+                lines.insert(line, lines[line]);
+                lines.join("\n")
+            }
+            Mutation::SwapLines(a, b) => {
+                let (a, b) = (a.index(lines.len()), b.index(lines.len()));
+                lines.swap(a, b);
+                lines.join("\n")
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn generated_programs_never_panic(
+            bodies in prop::collection::vec(
+                prop::collection::vec(0..STATEMENTS.len(), 0..12),
+                1..4,
+            ),
+            fields in prop::collection::vec(0..FIELDS.len(), 0..8),
+        ) {
+            let source = program(&bodies, &fields);
+            prop_assert!(parser::parse_source("fuzz.rs", source.clone()).is_ok(), "{source}");
+            check(source);
+        }
+
+        #[test]
+        fn mutated_fixtures_never_panic(
+            seed in 0..2usize,
+            mutations in prop::collection::vec(mutation(), 1..4),
+        ) {
+            let source = mutations
+                .iter()
+                .fold(seeds()[seed].clone(), |source, mutation| mutate(&source, mutation));
+            check(source);
+        }
+    }
+}
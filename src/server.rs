@@ -0,0 +1,598 @@
+//! HTTP front end for the scanner.
+//!
+//! `POST /scan` takes `{ "filename": "x.rs", "source": "..." }` and answers
+//! with the same versioned document as `--format json`. The optional query
+//! parameters `min_severity=high` and `categories=Arithmetic,AccessControl`
+//! narrow the findings returned; values that name no severity or category
+//! are rejected with `400 Bad Request`. Source that does not parse is
+//! rejected with `422 Unprocessable Entity` and an error body pointing at
+//! the offending line. `GET /detectors` lists the detector
+//! catalog so clients can offer checks to enable or disable.
+//!
+//! Sources over [`ServerOptions::max_source_bytes`], 1 MiB by default, are
+//! rejected with `413 Payload Too Large`.
+//!
+//! With a scan history database, a `POST /scan` body that also names a
+//! `repo` (and optionally a `commit`) is saved to it, and `GET /metrics`
+//! exposes the finding counts of each repository's latest scan in the
+//! Prometheus text format; see [`report::prometheus`].
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{DefaultBodyLimit, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::detectors::{self, Category, DetectorInfo, Registry};
+use crate::error::ParseError;
+use crate::finding::{Finding, Severity};
+use crate::parser;
+use crate::report::{self, Summary};
+use crate::scanner::{self, SourceScan};
+use crate::storage::Storage;
+
+/// Body of a `POST /scan` request.
+#[derive(Debug, Deserialize)]
+pub struct ScanRequest {
+    pub filename: String,
+    pub source: String,
+    /// Repository the source belongs to; the scan is saved to the history
+    /// under this name when the server has one.
+    #[serde(default)]
+    pub repo: Option<String>,
+    /// Commit the source was taken from, saved along with `repo`.
+    #[serde(default)]
+    pub commit: Option<String>,
+}
+
+/// Query parameters of a `POST /scan` request, as sent.
+#[derive(Debug, Default, Deserialize)]
+pub struct ScanQuery {
+    /// Least severity to return, e.g. `high`.
+    pub min_severity: Option<String>,
+    /// Comma-separated categories to return, e.g. `Arithmetic,Validation`.
+    pub categories: Option<String>,
+}
+
+/// The findings a [`ScanQuery`] asks for.
+#[derive(Debug)]
+struct Filter {
+    min_severity: Option<Severity>,
+    /// Empty returns every category.
+    categories: Vec<Category>,
+}
+
+impl TryFrom<&ScanQuery> for Filter {
+    type Error = ApiError;
+
+    fn try_from(query: &ScanQuery) -> Result<Self, ApiError> {
+        let min_severity = query
+            .min_severity
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(ApiError::Query)?;
+        let categories = query
+            .categories
+            .iter()
+            .flat_map(|list| list.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(ApiError::Query)?;
+        Ok(Filter {
+            min_severity,
+            categories,
+        })
+    }
+}
+
+impl Filter {
+    /// Findings of detectors outside the catalog have no category, so they
+    /// are dropped whenever categories are asked for.
+    fn keeps(&self, finding: &Finding) -> bool {
+        let category = detectors::lookup(finding.detector).map(|info| info.category);
+        self.min_severity
+            .is_none_or(|min_severity| finding.severity >= min_severity)
+            && (self.categories.is_empty()
+                || category.is_some_and(|category| self.categories.contains(&category)))
+    }
+}
+
+/// One detector in the `GET /detectors` response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub id: String,
+    pub name: String,
+    pub title: String,
+    pub category: Category,
+    pub default_severity: Severity,
+    pub cwe: u32,
+    pub description: String,
+    pub example: String,
+    pub fixed_example: String,
+    pub references: Vec<String>,
+}
+
+impl From<&DetectorInfo> for CatalogEntry {
+    fn from(info: &DetectorInfo) -> Self {
+        CatalogEntry {
+            id: info.id.to_string(),
+            name: info.name.to_string(),
+            title: info.title.to_string(),
+            category: info.category,
+            default_severity: info.severity,
+            cwe: info.cwe,
+            description: info.description.to_string(),
+            example: info.example.to_string(),
+            fixed_example: info.fixed_example.to_string(),
+            references: info.references.iter().map(|url| url.to_string()).collect(),
+        }
+    }
+}
+
+/// Largest `source` a `POST /scan` accepts unless configured otherwise.
+pub const DEFAULT_MAX_SOURCE_BYTES: usize = 1 << 20;
+
+/// JSON escapes can take up to six bytes per source byte (`\u0000`), so
+/// request bodies may be this many times larger than the source they carry.
+const JSON_ESCAPE_OVERHEAD: usize = 6;
+
+/// How the server is set up.
+pub struct ServerOptions {
+    /// Which detectors run on posted sources.
+    pub registry: Registry,
+    /// Where scans posted with a `repo` are saved, and what `GET /metrics`
+    /// reports; `None` disables both.
+    pub history: Option<Storage>,
+    /// Sources longer than this are rejected with `413 Payload Too Large`.
+    pub max_source_bytes: usize,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            registry: Registry::default(),
+            history: None,
+            max_source_bytes: DEFAULT_MAX_SOURCE_BYTES,
+        }
+    }
+}
+
+/// What every request handler shares.
+struct AppState {
+    registry: Registry,
+    history: Option<Mutex<Storage>>,
+    max_source_bytes: usize,
+}
+
+/// Builds the application routes, running the detectors in `registry`,
+/// with the other [`ServerOptions`] at their defaults.
+pub fn router(registry: Registry) -> Router {
+    router_with(ServerOptions {
+        registry,
+        ..ServerOptions::default()
+    })
+}
+
+/// Builds the application routes as `options` asks.
+pub fn router_with(options: ServerOptions) -> Router {
+    let body_limit = options
+        .max_source_bytes
+        .saturating_mul(JSON_ESCAPE_OVERHEAD);
+    Router::new()
+        .route("/detectors", get(list_detectors))
+        .route("/scan", post(scan))
+        .route("/metrics", get(metrics))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .with_state(Arc::new(AppState {
+            registry: options.registry,
+            history: options.history.map(Mutex::new),
+            max_source_bytes: options.max_source_bytes,
+        }))
+}
+
+/// Serves [`router_with`] on `addr` until the process is stopped.
+pub async fn serve(addr: SocketAddr, options: ServerOptions) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router_with(options)).await
+}
+
+async fn list_detectors() -> Json<Vec<CatalogEntry>> {
+    Json(detectors::catalog().map(CatalogEntry::from).collect())
+}
+
+async fn scan(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ScanQuery>,
+    Json(request): Json<ScanRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let filter = Filter::try_from(&query)?;
+    if request.source.len() > state.max_source_bytes {
+        return Err(ApiError::TooLarge {
+            size: request.source.len(),
+            limit: state.max_source_bytes,
+        });
+    }
+    // Parsing and detection are CPU-bound and `syn` trees are not `Send`,
+    // so the whole scan, and saving it, runs on the blocking pool.
+    let scan = tokio::task::spawn_blocking(move || scan_and_save(&state, &request))
+        .await
+        .map_err(|_| ApiError::Internal)??;
+    let programs: Vec<_> = scan.program.into_iter().collect();
+    let findings: Vec<_> = scan
+        .findings
+        .into_iter()
+        .filter(|finding| filter.keeps(finding))
+        .collect();
+    let summary = Summary::of(&findings);
+    Ok(Json(report::json::to_json(&findings, &summary, &programs)))
+}
+
+/// Scans one posted source and saves the scan to the history when the
+/// request names a repository. Runs on a blocking-pool thread, whose parsed
+/// sources are released before it returns; see [`parser::release_spans`].
+fn scan_and_save(state: &AppState, request: &ScanRequest) -> Result<SourceScan, ApiError> {
+    let scan = scanner::scan_source_with(&request.filename, &request.source, &state.registry);
+    // Findings and parse errors carry line and column spans by now.
+    parser::release_spans();
+    let scan = scan?;
+    if let (Some(repo), Some(history)) = (&request.repo, &state.history) {
+        let commit = request.commit.as_deref().unwrap_or_default();
+        history
+            .lock()
+            .map_err(|_| ApiError::Internal)?
+            .save_scan(repo, commit, &scan.findings)
+            .map_err(|err| ApiError::History(err.to_string()))?;
+    }
+    Ok(scan)
+}
+
+async fn metrics(State(state): State<Arc<AppState>>) -> Result<Response, ApiError> {
+    let text = tokio::task::spawn_blocking(move || {
+        let history = state.history.as_ref().ok_or(ApiError::NoHistory)?;
+        let scans = history
+            .lock()
+            .map_err(|_| ApiError::Internal)?
+            .latest_scans()
+            .map_err(|err| ApiError::History(err.to_string()))?;
+        Ok::<_, ApiError>(report::prometheus::to_prometheus(&scans))
+    })
+    .await
+    .map_err(|_| ApiError::Internal)??;
+    Ok((
+        [(header::CONTENT_TYPE, report::prometheus::CONTENT_TYPE)],
+        text,
+    )
+        .into_response())
+}
+
+/// Failures reported to API clients as a JSON `error` object.
+#[derive(Debug)]
+enum ApiError {
+    /// A query parameter that names no severity or category.
+    Query(String),
+    Parse(ParseError),
+    /// A source over [`ServerOptions::max_source_bytes`].
+    TooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// `GET /metrics` on a server started without a history database.
+    NoHistory,
+    /// The history database could not be read or written.
+    History(String),
+    Internal,
+}
+
+impl From<ParseError> for ApiError {
+    fn from(err: ParseError) -> Self {
+        ApiError::Parse(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, body) = match self {
+            ApiError::Query(message) => (
+                StatusCode::BAD_REQUEST,
+                json!({ "error": { "kind": "query", "message": message } }),
+            ),
+            ApiError::Parse(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                json!({
+                    "error": {
+                        "kind": "parse",
+                        "message": err.message,
+                        "file": err.file,
+                        "line": err.line,
+                        "column": err.column,
+                    }
+                }),
+            ),
+            ApiError::TooLarge { size, limit } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                json!({
+                    "error": {
+                        "kind": "too_large",
+                        "message": format!("source is {size} bytes, more than the limit of {limit}"),
+                        "limit": limit,
+                    }
+                }),
+            ),
+            ApiError::NoHistory => (
+                StatusCode::NOT_FOUND,
+                json!({
+                    "error": {
+                        "kind": "history",
+                        "message": "no scan history is configured",
+                    }
+                }),
+            ),
+            ApiError::History(message) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({ "error": { "kind": "history", "message": message } }),
+            ),
+            ApiError::Internal => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({ "error": { "kind": "internal", "message": "scan failed" } }),
+            ),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::test_support::{fixture, is_released, parsed_span};
+
+    async fn send(request: Request<Body>) -> (StatusCode, Vec<u8>) {
+        let response = router(Registry::default()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, bytes.to_vec())
+    }
+
+    async fn post_scan(body: Value) -> (StatusCode, Value) {
+        post_scan_to("/scan", body).await
+    }
+
+    async fn post_scan_to(uri: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let (status, bytes) = send(request).await;
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn lists_every_registered_detector() {
+        let request = Request::get("/detectors").body(Body::empty()).unwrap();
+        let (status, bytes) = send(request).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let catalog: Vec<CatalogEntry> = serde_json::from_slice(&bytes).unwrap();
+        let listed: Vec<_> = catalog.iter().map(|entry| entry.id.as_str()).collect();
+        let registered: Vec<_> = Registry::default()
+            .detectors()
+            .map(|detector| detector.id())
+            .collect();
+        assert_eq!(listed, registered);
+
+        let arithmetic = &catalog[listed
+            .iter()
+            .position(|id| *id == "rust/unchecked-arithmetic")
+            .unwrap()];
+        assert_eq!(arithmetic.category, Category::Arithmetic);
+        assert_eq!(arithmetic.default_severity, Severity::High);
+        assert!(catalog
+            .iter()
+            .all(|entry| !entry.example.is_empty() && entry.fixed_example != entry.example));
+    }
+
+    #[tokio::test]
+    async fn scans_posted_source() {
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        let (status, report) =
+            post_scan(json!({ "filename": "vulnerable_vault.rs", "source": source })).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["schema_version"], report::json::SCHEMA_VERSION);
+        let expected = crate::scan_file(&fixture("vulnerable_vault.rs")).unwrap();
+        let critical = Summary::of(&expected).critical;
+        assert!(critical > 0);
+        assert_eq!(report["summary"]["critical"], critical);
+        let findings = report["findings"].as_array().unwrap();
+        assert_eq!(report["summary"]["total"], findings.len());
+        assert!(findings
+            .iter()
+            .all(|f| f["span"]["file"] == "vulnerable_vault.rs"));
+    }
+
+    #[tokio::test]
+    async fn filters_findings_by_query() {
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        let body = json!({ "filename": "vulnerable_vault.rs", "source": source });
+        let (status, report) = post_scan_to("/scan?min_severity=critical", body.clone()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let findings = report["findings"].as_array().unwrap();
+        assert!(findings.iter().all(|f| f["severity"] == "critical"));
+        let expected = crate::scan_file(&fixture("vulnerable_vault.rs")).unwrap();
+        assert_eq!(findings.len(), Summary::of(&expected).critical);
+        assert_eq!(report["summary"]["total"], findings.len());
+        let mut handlers: Vec<_> = findings
+            .iter()
+            .map(|f| f["handler"].as_str().unwrap())
+            .collect();
+        handlers.sort_unstable();
+        handlers.dedup();
+        assert_eq!(
+            handlers,
+            ["emergency_drain", "update_authority", "withdraw"]
+        );
+
+        let (status, report) =
+            post_scan_to("/scan?categories=Arithmetic,memorysafety", body.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        let categories: Vec<_> = report["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| {
+                detectors::lookup(f["detector"].as_str().unwrap())
+                    .unwrap()
+                    .category
+            })
+            .collect();
+        assert!(categories.contains(&Category::Arithmetic));
+        assert!(categories
+            .iter()
+            .all(|c| matches!(c, Category::Arithmetic | Category::MemorySafety)));
+
+        for uri in [
+            "/scan?min_severity=severe",
+            "/scan?categories=Arithmetic,Speed",
+        ] {
+            let (status, error) = post_scan_to(uri, body.clone()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(error["error"]["kind"], "query");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unparsable_source() {
+        let (status, body) =
+            post_scan(json!({ "filename": "broken.rs", "source": "pub fn broken( {" })).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["kind"], "parse");
+        assert_eq!(body["error"]["file"], "broken.rs");
+        assert_eq!(body["error"]["line"], 1);
+    }
+
+    #[tokio::test]
+    async fn metrics_report_the_latest_saved_scan() {
+        let app = router_with(ServerOptions {
+            history: Some(Storage::open_in_memory().unwrap()),
+            ..ServerOptions::default()
+        });
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        let scan = Request::post("/scan?min_severity=critical")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({
+                    "filename": "vault.rs",
+                    "source": source,
+                    "repo": "deploybackend",
+                    "commit": "abc123",
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        let response = app.clone().oneshot(scan).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let metrics = Request::get("/metrics").body(Body::empty()).unwrap();
+        let response = app.oneshot(metrics).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            report::prometheus::CONTENT_TYPE
+        );
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+
+        // The history keeps the whole scan, not just what the query returned.
+        let expected = Summary::of(&crate::scan_file(&fixture("vulnerable_vault.rs")).unwrap());
+        assert!(text.contains("# TYPE audit_findings_total gauge"));
+        for severity in Severity::ALL {
+            let sample = format!(
+                "audit_findings_total{{repo=\"deploybackend\",severity=\"{}\"}} {}\n",
+                severity.as_str(),
+                expected.count(severity)
+            );
+            assert!(text.contains(&sample), "{sample}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_source() {
+        let source = format!("// {}\n", "x".repeat(DEFAULT_MAX_SOURCE_BYTES));
+        let (status, body) = post_scan(json!({ "filename": "huge.rs", "source": source })).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["error"]["kind"], "too_large");
+        assert_eq!(body["error"]["limit"], DEFAULT_MAX_SOURCE_BYTES);
+
+        let app = router_with(ServerOptions {
+            max_source_bytes: 16,
+            ..ServerOptions::default()
+        });
+        let request = |source: &str| {
+            Request::post("/scan")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({ "filename": "a.rs", "source": source }).to_string(),
+                ))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(request("fn a() {}")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(request("fn abcdefgh() {}\n")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn repeated_scans_release_their_sources() {
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        let body = json!({ "filename": "vulnerable_vault.rs", "source": source });
+        let (_, first) = post_scan(body.clone()).await;
+        for _ in 0..100 {
+            let (status, report) = post_scan(body.clone()).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(report["findings"], first["findings"]);
+        }
+
+        // Which pool thread runs a request is up to tokio, so check the
+        // release on this one.
+        let state = AppState {
+            registry: Registry::default(),
+            history: None,
+            max_source_bytes: DEFAULT_MAX_SOURCE_BYTES,
+        };
+        let request = ScanRequest {
+            filename: "vulnerable_vault.rs".into(),
+            source,
+            repo: None,
+            commit: None,
+        };
+        let before = parsed_span();
+        let scan = scan_and_save(&state, &request).unwrap();
+        assert!(!scan.findings.is_empty());
+        assert!(is_released(before));
+    }
+
+    #[tokio::test]
+    async fn metrics_need_a_history() {
+        let request = Request::get("/metrics").body(Body::empty()).unwrap();
+        let (status, bytes) = send(request).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["kind"], "history");
+    }
+}
@@ -0,0 +1,238 @@
+//! SQLite history of scans.
+//!
+//! Each scan of a repository at some commit is a row in `scans`; its
+//! findings go to `findings`, keyed by the scan id. Keeping the fingerprint
+//! lets callers follow a finding from one scan to the next, and the simple
+//! layout keeps trend queries (findings per scan over time) plain SQL.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::Result;
+use crate::finding::{Finding, Severity};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS scans (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        repo TEXT NOT NULL,
+        \"commit\" TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS findings (
+        scan_id INTEGER NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+        detector TEXT NOT NULL,
+        severity TEXT NOT NULL,
+        file TEXT NOT NULL,
+        line INTEGER NOT NULL,
+        message TEXT NOT NULL,
+        fingerprint TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS scans_by_repo ON scans(repo, created_at);
+    CREATE INDEX IF NOT EXISTS findings_by_scan ON findings(scan_id);
+";
+
+/// A scan read back from the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredScan {
+    pub id: i64,
+    pub repo: String,
+    pub commit: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: i64,
+    pub findings: Vec<StoredFinding>,
+}
+
+/// A finding as saved in the `findings` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredFinding {
+    pub detector: String,
+    pub severity: Severity,
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+    pub fingerprint: String,
+}
+
+/// Connection to a scan history database.
+pub struct Storage {
+    conn: Connection,
+}
+
+impl Storage {
+    /// Opens (creating if needed) the database at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// A throwaway database, mostly useful in tests.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Storage { conn })
+    }
+
+    /// Records a scan of `repo` at `commit` and returns its id.
+    pub fn save_scan(&mut self, repo: &str, commit: &str, findings: &[Finding]) -> Result<i64> {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO scans (repo, \"commit\", created_at) VALUES (?1, ?2, ?3)",
+            params![repo, commit, created_at],
+        )?;
+        let scan_id = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO findings (scan_id, detector, severity, file, line, message, fingerprint)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for finding in findings {
+                insert.execute(params![
+                    scan_id,
+                    finding.detector,
+                    finding.severity.as_str(),
+                    finding.span.file.display().to_string(),
+                    finding.span.line_start as i64,
+                    finding.message,
+                    finding.fingerprint(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(scan_id)
+    }
+
+    /// The most recently saved scan of `repo`, if any.
+    pub fn latest_scan(&self, repo: &str) -> Result<Option<StoredScan>> {
+        let scan = self
+            .conn
+            .query_row(
+                "SELECT id, repo, \"commit\", created_at FROM scans WHERE repo = ?1
+                 ORDER BY created_at DESC, id DESC LIMIT 1",
+                params![repo],
+                |row| {
+                    Ok(StoredScan {
+                        id: row.get(0)?,
+                        repo: row.get(1)?,
+                        commit: row.get(2)?,
+                        created_at: row.get(3)?,
+                        findings: Vec::new(),
+                    })
+                },
+            )
+            .optional()?;
+        let Some(mut scan) = scan else {
+            return Ok(None);
+        };
+        scan.findings = self.findings(scan.id)?;
+        Ok(Some(scan))
+    }
+
+    /// The most recent scan of every repository, ordered by repository.
+    pub fn latest_scans(&self) -> Result<Vec<StoredScan>> {
+        let repos: Vec<String> = self
+            .conn
+            .prepare("SELECT DISTINCT repo FROM scans ORDER BY repo")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        let mut scans = Vec::with_capacity(repos.len());
+        for repo in repos {
+            scans.extend(self.latest_scan(&repo)?);
+        }
+        Ok(scans)
+    }
+
+    fn findings(&self, scan_id: i64) -> Result<Vec<StoredFinding>> {
+        let mut select = self.conn.prepare(
+            "SELECT detector, severity, file, line, message, fingerprint FROM findings
+             WHERE scan_id = ?1 ORDER BY rowid",
+        )?;
+        let rows = select.query_map(params![scan_id], |row| {
+            let severity: String = row.get(1)?;
+            Ok(StoredFinding {
+                detector: row.get(0)?,
+                severity: severity.parse().map_err(|err: String| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        1,
+                        rusqlite::types::Type::Text,
+                        err.into(),
+                    )
+                })?,
+                file: row.get(2)?,
+                line: row.get::<_, i64>(3)? as usize,
+                message: row.get(4)?,
+                fingerprint: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::scan_fixtures;
+
+    #[test]
+    fn latest_scan_is_the_most_recent() {
+        let findings = scan_fixtures();
+        let mut storage = Storage::open_in_memory().unwrap();
+        assert_eq!(storage.latest_scan("deploybackend").unwrap(), None);
+
+        let first = storage
+            .save_scan("deploybackend", "abc123", &findings)
+            .unwrap();
+        let second = storage
+            .save_scan("deploybackend", "def456", &findings[..3])
+            .unwrap();
+        storage.save_scan("other", "999", &findings).unwrap();
+        assert!(second > first);
+
+        let latest = storage.latest_scan("deploybackend").unwrap().unwrap();
+        assert_eq!(latest.id, second);
+        assert_eq!(latest.commit, "def456");
+        assert_eq!(latest.findings.len(), 3);
+        let stored = &latest.findings[0];
+        assert_eq!(stored.detector, findings[0].detector);
+        assert_eq!(stored.severity, findings[0].severity);
+        assert_eq!(stored.line, findings[0].span.line_start);
+        assert_eq!(stored.fingerprint, findings[0].fingerprint());
+    }
+
+    #[test]
+    fn latest_scans_has_one_per_repo() {
+        let findings = scan_fixtures();
+        let mut storage = Storage::open_in_memory().unwrap();
+        assert!(storage.latest_scans().unwrap().is_empty());
+        storage.save_scan("vault", "abc", &findings).unwrap();
+        storage.save_scan("market", "123", &findings).unwrap();
+        storage.save_scan("vault", "def", &findings[..1]).unwrap();
+
+        let latest = storage.latest_scans().unwrap();
+        let repos: Vec<_> = latest
+            .iter()
+            .map(|scan| (scan.repo.as_str(), scan.commit.as_str()))
+            .collect();
+        assert_eq!(repos, [("market", "123"), ("vault", "def")]);
+        assert_eq!(latest[1].findings.len(), 1);
+    }
+
+    #[test]
+    fn history_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        Storage::open(&path)
+            .unwrap()
+            .save_scan("repo", "abc", &scan_fixtures())
+            .unwrap();
+        let latest = Storage::open(&path).unwrap().latest_scan("repo").unwrap();
+        assert_eq!(latest.unwrap().findings.len(), scan_fixtures().len());
+    }
+}
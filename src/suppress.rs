@@ -0,0 +1,146 @@
+//! Inline suppression of findings with `// audit-ignore` comments.
+//!
+//! A comment `// audit-ignore: unchecked_arithmetic` either trailing the
+//! offending line or on its own line directly above it silences that
+//! detector there. Several ids may be separated by commas; without any id
+//! every detector on that line is silenced. Detectors may be named by short
+//! name or by namespaced id (`rust/unchecked-arithmetic`).
+
+use crate::finding::Finding;
+use crate::parser::ParsedProgram;
+
+const MARKER: &str = "audit-ignore";
+
+/// A parsed `audit-ignore` comment.
+#[derive(Debug, PartialEq, Eq)]
+struct Directive {
+    /// Line whose findings are suppressed.
+    line: usize,
+    /// Suppressed detector ids; empty means all detectors.
+    detectors: Vec<String>,
+}
+
+impl Directive {
+    fn suppresses(&self, finding: &Finding) -> bool {
+        finding.span.line_start == self.line
+            && (self.detectors.is_empty()
+                || self
+                    .detectors
+                    .iter()
+                    .any(|id| id == finding.detector || id == finding.rule_id()))
+    }
+}
+
+/// Drops findings silenced by an `audit-ignore` comment in `program`.
+pub fn apply(program: &ParsedProgram, mut findings: Vec<Finding>) -> Vec<Finding> {
+    let directives = directives(program);
+    if !directives.is_empty() {
+        findings.retain(|finding| !directives.iter().any(|d| d.suppresses(finding)));
+    }
+    findings
+}
+
+fn directives(program: &ParsedProgram) -> Vec<Directive> {
+    let lines: Vec<&str> = program.source.lines().collect();
+    program
+        .comments
+        .iter()
+        .filter_map(|comment| {
+            let rest = comment.text.strip_prefix(MARKER)?;
+            let detectors = match rest.trim_start().strip_prefix(':') {
+                Some(ids) => ids
+                    .split([',', ' '])
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                None if rest.trim().is_empty() => Vec::new(),
+                // Something like `audit-ignored`, not a directive.
+                None => return None,
+            };
+            let line = if comment.trailing {
+                comment.line
+            } else {
+                next_code_line(&lines, comment.line)
+            };
+            Some(Directive { line, detectors })
+        })
+        .collect()
+}
+
+/// The first line after `line` that is neither blank nor a comment.
+fn next_code_line(lines: &[&str], line: usize) -> usize {
+    lines
+        .iter()
+        .enumerate()
+        .skip(line)
+        .find(|(_, text)| {
+            let text = text.trim();
+            !text.is_empty() && !text.starts_with("//")
+        })
+        .map_or(line + 1, |(index, _)| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::detectors::Registry;
+    use crate::scanner;
+    use crate::test_support::fixture;
+
+    fn scan_with(replace: &str, with: &str) -> Vec<Finding> {
+        let source = fs::read_to_string(fixture("vulnerable_vault.rs")).unwrap();
+        assert!(source.contains(replace));
+        let program =
+            crate::parser::parse_source("vault.rs", source.replacen(replace, with, 1)).unwrap();
+        scanner::analyze(&program, &Registry::default())
+    }
+
+    fn deposit_arithmetic(findings: &[Finding]) -> usize {
+        findings
+            .iter()
+            .filter(|f| f.detector == "unchecked_arithmetic" && f.message.contains("`deposit`"))
+            .count()
+    }
+
+    #[test]
+    fn preceding_comment_suppresses_one_detector() {
+        let line = "        vault.balance = vault.balance + amount;";
+        let baseline = scan_with(line, line);
+        let suppressed = scan_with(
+            line,
+            &format!("        // audit-ignore: unchecked_arithmetic\n{line}"),
+        );
+        assert_eq!(deposit_arithmetic(&baseline), 2);
+        assert_eq!(deposit_arithmetic(&suppressed), 1);
+        assert_eq!(suppressed.len(), baseline.len() - 1);
+    }
+
+    #[test]
+    fn namespaced_id_suppresses() {
+        let line = "        vault.balance = vault.balance + amount;";
+        let suppressed = scan_with(
+            line,
+            &format!("{line} // audit-ignore: rust/unchecked-arithmetic"),
+        );
+        assert_eq!(deposit_arithmetic(&suppressed), 1);
+    }
+
+    #[test]
+    fn trailing_comment_without_id_suppresses_all_detectors() {
+        let line = "        vault.balance = vault.balance + amount;";
+        let suppressed = scan_with(line, &format!("{line} // audit-ignore"));
+        assert_eq!(deposit_arithmetic(&suppressed), 1);
+    }
+
+    #[test]
+    fn other_detector_ids_do_not_suppress() {
+        let line = "        vault.balance = vault.balance + amount;";
+        let suppressed = scan_with(
+            line,
+            &format!("{line} // audit-ignore: missing_signer_authority, mul_overflow"),
+        );
+        assert_eq!(deposit_arithmetic(&suppressed), 2);
+    }
+}
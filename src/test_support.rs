@@ -0,0 +1,76 @@
+//! Helpers shared by unit tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::finding::Finding;
+use crate::parser::{self, ParsedProgram};
+
+/// Path of a fixture under `contracts/sources/`.
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("contracts/sources")
+        .join(name)
+}
+
+pub fn parse_fixture(name: &str) -> ParsedProgram {
+    parser::parse_file(&fixture(name)).expect("fixture should parse")
+}
+
+pub fn parse(source: &str) -> ParsedProgram {
+    parser::parse_source("test.rs", source.to_string()).expect("source should parse")
+}
+
+/// A span of freshly parsed tokens, for checking with [`is_released`]
+/// that a later call released this thread's sources.
+pub fn parsed_span() -> proc_macro2::Span {
+    let tokens: proc_macro2::TokenStream = "released".parse().unwrap();
+    tokens.into_iter().next().unwrap().span()
+}
+
+/// Whether `span` no longer has a source to point into, because
+/// [`parser::release_spans`] ran on this thread since it was parsed.
+pub fn is_released(span: proc_macro2::Span) -> bool {
+    std::panic::catch_unwind(|| span.start()).is_err()
+}
+
+/// Scans both Rust fixtures using repo-relative paths, so output is
+/// identical on every machine.
+pub fn scan_fixtures() -> Vec<Finding> {
+    ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"]
+        .iter()
+        .flat_map(|name| {
+            let path = Path::new("contracts/sources").join(name);
+            crate::scan_file(&path).expect("fixture should scan")
+        })
+        .collect()
+}
+
+/// [`ProgramEntry`](crate::report::workspace::ProgramEntry) of both Rust
+/// fixtures, with the same repo-relative paths as [`scan_fixtures`].
+pub fn fixture_programs() -> Vec<crate::report::workspace::ProgramEntry> {
+    ["vulnerable_vault.rs", "insecure_nft_marketplace.rs"]
+        .iter()
+        .filter_map(|name| {
+            let program = parser::parse_file(&Path::new("contracts/sources").join(name))
+                .expect("fixture should parse");
+            crate::report::workspace::ProgramEntry::of(&program)
+        })
+        .collect()
+}
+
+/// Compares `actual` with `tests/golden/<name>`. Run with
+/// `UPDATE_GOLDEN=1` to rewrite the golden file instead.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing golden file {}", path.display()));
+    assert_eq!(actual, expected, "output differs from {}", path.display());
+}
@@ -0,0 +1,397 @@
+//! Rescanning a tree whenever its files change.
+//!
+//! [`watch`] scans the tree once and then waits for file system events.
+//! Events arriving within [`Watch::debounce`] of each other are handled as
+//! one batch, so an editor that saves in several writes triggers a single
+//! rescan. Only the files named in the batch are analysed again, through a
+//! [`ParseCache`], and the caller is handed the findings that appeared and
+//! disappeared. A deleted file takes its findings with it.
+//!
+//! Every parse keeps a copy of its source on the watching thread until the
+//! spans are [released](parser::release_spans), which also makes the
+//! cached programs unusable. Once the sources parsed since the last release
+//! pass [`Watch::release_after`], the cache is emptied and the sources are
+//! released, so memory stays bounded however long the watch runs while
+//! unchanged files keep coming from the cache in between.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use ::notify::event::{Event, EventKind};
+use ::notify::{RecursiveMode, Watcher};
+
+use crate::cache::ParseCache;
+use crate::error::{Error, ParseError, Result};
+use crate::finding::Finding;
+use crate::parser;
+use crate::scanner::{self, ScanOptions};
+
+/// Quiet period that ends a batch of events.
+pub const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Bytes of parsed source kept before the cache is emptied and the sources
+/// are released.
+pub const RELEASE_AFTER: usize = 64 << 20;
+
+/// How the findings of a watched tree changed in one rescan.
+#[derive(Debug, Default)]
+pub struct Delta {
+    /// Files analysed again, in order, deleted ones included.
+    pub files: Vec<PathBuf>,
+    /// Findings that were not reported before the rescan.
+    pub new: Vec<Finding>,
+    /// Findings that were reported before and are gone now.
+    pub fixed: Vec<Finding>,
+    /// Files that no longer parse; their previous findings are kept.
+    pub parse_errors: Vec<ParseError>,
+}
+
+impl Delta {
+    /// Whether the rescan changed nothing worth reporting.
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.fixed.is_empty() && self.parse_errors.is_empty()
+    }
+}
+
+/// The findings of a tree, kept current one batch of changes at a time.
+pub struct Watch {
+    root: PathBuf,
+    options: ScanOptions,
+    cache: ParseCache,
+    findings: BTreeMap<PathBuf, Vec<Finding>>,
+    pub debounce: Duration,
+    /// See [`RELEASE_AFTER`].
+    pub release_after: usize,
+}
+
+impl Watch {
+    /// Watches `root` with no findings known yet; see [`Watch::scan_all`].
+    pub fn new(root: impl Into<PathBuf>, options: ScanOptions) -> Self {
+        Watch {
+            root: root.into(),
+            options,
+            cache: ParseCache::new(),
+            findings: BTreeMap::new(),
+            debounce: DEBOUNCE,
+            release_after: RELEASE_AFTER,
+        }
+    }
+
+    /// Scans every file under the root, reporting all findings as new the
+    /// first time.
+    pub fn scan_all(&mut self) -> Result<Delta> {
+        let files = scanner::rust_files(&self.root)?;
+        let known: Vec<_> = self.findings.keys().cloned().collect();
+        Ok(self.rescan(files.into_iter().chain(known)))
+    }
+
+    /// Analyses the watched files among `paths` again and returns what
+    /// changed. Paths that no longer exist lose their findings.
+    pub fn rescan(&mut self, paths: impl IntoIterator<Item = PathBuf>) -> Delta {
+        let paths: BTreeSet<_> = paths
+            .into_iter()
+            .filter(|path| self.is_watched(path))
+            .collect();
+        let mut delta = Delta::default();
+        for path in paths {
+            let current = if path.is_file() {
+                match self.cache.parse_file(&path) {
+                    Ok(program) => self.analyze(&program),
+                    Err(Error::Parse(err)) => {
+                        delta.parse_errors.push(err);
+                        delta.files.push(path);
+                        continue;
+                    }
+                    // Deleted between the event and the read.
+                    Err(_) => Vec::new(),
+                }
+            } else {
+                self.cache.forget(&path);
+                Vec::new()
+            };
+            let previous = self.findings.remove(&path).unwrap_or_default();
+            let fingerprints = |findings: &[Finding]| -> HashSet<String> {
+                findings
+                    .iter()
+                    .map(|f| f.fingerprint().to_string())
+                    .collect()
+            };
+            let (before, after) = (fingerprints(&previous), fingerprints(&current));
+            delta.fixed.extend(
+                previous
+                    .into_iter()
+                    .filter(|finding| !after.contains(finding.fingerprint())),
+            );
+            delta.new.extend(
+                current
+                    .iter()
+                    .filter(|finding| !before.contains(finding.fingerprint()))
+                    .cloned(),
+            );
+            if !current.is_empty() {
+                self.findings.insert(path.clone(), current);
+            }
+            delta.files.push(path);
+        }
+        // Findings carry line and column spans, so only the cached trees
+        // have to go.
+        if self.cache.parsed_bytes() > self.release_after {
+            self.cache.clear();
+            parser::release_spans();
+        }
+        delta
+    }
+
+    /// Every finding currently reported, by file.
+    pub fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.values().flatten()
+    }
+
+    /// Lookups the parse cache answered without parsing, and those that
+    /// parsed.
+    pub fn cache_stats(&self) -> (usize, usize) {
+        (self.cache.hits(), self.cache.misses())
+    }
+
+    /// Handles `events` until the sender hangs up, calling `on_delta` after
+    /// every batch that changed something.
+    pub fn run(
+        &mut self,
+        events: &Receiver<::notify::Result<Event>>,
+        mut on_delta: impl FnMut(&Delta),
+    ) -> Result<()> {
+        let error = |err: ::notify::Error| Error::Watch {
+            path: err.paths.first().cloned().unwrap_or_default(),
+            message: err.to_string(),
+        };
+        while let Ok(event) = events.recv() {
+            let mut paths = changed_paths(event.map_err(error)?);
+            let mut open = true;
+            while open {
+                match events.recv_timeout(self.debounce) {
+                    Ok(event) => paths.extend(changed_paths(event.map_err(error)?)),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => open = false,
+                }
+            }
+            let delta = self.rescan(paths);
+            if !delta.is_empty() {
+                on_delta(&delta);
+            }
+            if !open {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn analyze(&self, program: &crate::ParsedProgram) -> Vec<Finding> {
+        let mut findings = scanner::analyze(program, &self.options.registry);
+        findings.retain(|finding| finding.severity >= self.options.min_severity);
+        for finding in &mut findings {
+            finding.rebase_fingerprint(&self.root);
+        }
+        findings.sort_by(|a, b| scanner::order(a).cmp(&scanner::order(b)));
+        findings
+    }
+
+    /// Whether a full scan of the root would include `path`.
+    fn is_watched(&self, path: &Path) -> bool {
+        if path.extension().is_none_or(|ext| ext != "rs") {
+            return false;
+        }
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut dirs = relative.parent().into_iter().flat_map(Path::components);
+        !dirs.any(|dir| match dir {
+            Component::Normal(name) => scanner::skipped_dir(&name.to_string_lossy()),
+            _ => false,
+        })
+    }
+}
+
+/// Paths a file system event says were written, created or removed.
+fn changed_paths(event: Event) -> Vec<PathBuf> {
+    match event.kind {
+        // Reads, including the scanner's own, change nothing.
+        EventKind::Access(_) => Vec::new(),
+        _ => event.paths,
+    }
+}
+
+/// Scans `root`, then rescans on every change until the watcher fails.
+/// `on_delta` sees the initial scan first, every finding in it new.
+pub fn watch(root: &Path, options: ScanOptions, mut on_delta: impl FnMut(&Delta)) -> Result<()> {
+    let root = root.canonicalize().map_err(|source| Error::Io {
+        path: root.to_path_buf(),
+        source,
+    })?;
+    let error = |err: ::notify::Error| Error::Watch {
+        path: root.clone(),
+        message: err.to_string(),
+    };
+    let (sender, events) = mpsc::channel();
+    let mut watcher = ::notify::recommended_watcher(sender).map_err(error)?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(error)?;
+
+    let mut state = Watch::new(root.clone(), options);
+    on_delta(&state.scan_all()?);
+    state.run(&events, on_delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ::notify::event::{AccessKind, DataChange, ModifyKind};
+
+    use super::*;
+    use crate::test_support::{fixture, is_released, parsed_span};
+
+    fn tree() -> (tempfile::TempDir, Watch) {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["insecure_nft_marketplace.rs", "vulnerable_vault.rs"] {
+            fs::copy(fixture(name), dir.path().join(name)).unwrap();
+        }
+        let mut watch = Watch::new(dir.path(), ScanOptions::default());
+        watch.debounce = Duration::from_millis(20);
+        let initial = watch.scan_all().unwrap();
+        assert_eq!(initial.files.len(), 2);
+        assert!(initial.fixed.is_empty());
+        assert_eq!(initial.new.len(), watch.findings().count());
+        (dir, watch)
+    }
+
+    fn modified(path: &Path) -> ::notify::Result<Event> {
+        Ok(
+            Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+                .add_path(path.to_path_buf()),
+        )
+    }
+
+    #[test]
+    fn rescans_only_the_modified_file() {
+        let (dir, mut watch) = tree();
+        let vault = dir.path().join("vulnerable_vault.rs");
+        let source = fs::read_to_string(&vault).unwrap();
+        fs::write(
+            &vault,
+            source.replace("vault.balance - amount", "vault.balance"),
+        )
+        .unwrap();
+
+        // Two writes of one save, plus a read that must not count.
+        let (sender, events) = mpsc::channel();
+        sender.send(modified(&vault)).unwrap();
+        sender.send(modified(&vault)).unwrap();
+        sender
+            .send(Ok(Event::new(EventKind::Access(AccessKind::Any))
+                .add_path(dir.path().join("insecure_nft_marketplace.rs"))))
+            .unwrap();
+        drop(sender);
+
+        let mut deltas = Vec::new();
+        watch
+            .run(&events, |delta| deltas.push(delta.files.clone()))
+            .unwrap();
+        assert_eq!(deltas, [vec![vault.clone()]]);
+        assert_eq!(watch.cache_stats(), (0, 3));
+    }
+
+    #[test]
+    fn edits_report_new_and_fixed_findings() {
+        let (dir, mut watch) = tree();
+        let vault = dir.path().join("vulnerable_vault.rs");
+        let source = fs::read_to_string(&vault).unwrap();
+        fs::write(
+            &vault,
+            source.replace(
+                "vault.balance = vault.balance - amount;",
+                "vault.balance = vault.balance.checked_sub(amount).unwrap();",
+            ),
+        )
+        .unwrap();
+        let delta = watch.rescan([vault]);
+        let rules =
+            |findings: &[Finding]| -> Vec<&str> { findings.iter().map(|f| f.rule_id()).collect() };
+        assert!(rules(&delta.fixed).contains(&"rust/unchecked-arithmetic"));
+        assert_eq!(rules(&delta.new), ["rust/checked-then-unwrap"]);
+    }
+
+    #[test]
+    fn deleting_a_file_fixes_its_findings() {
+        let (dir, mut watch) = tree();
+        let vault = dir.path().join("vulnerable_vault.rs");
+        let before = watch.findings().count();
+        fs::remove_file(&vault).unwrap();
+
+        let delta = watch.rescan([vault.clone(), dir.path().join("README.md")]);
+        assert_eq!(delta.files, [vault]);
+        assert!(delta.new.is_empty());
+        assert!(delta
+            .fixed
+            .iter()
+            .all(|finding| finding.span.file.ends_with("vulnerable_vault.rs")));
+        assert_eq!(watch.findings().count(), before - delta.fixed.len());
+        assert!(watch
+            .findings()
+            .all(|finding| finding.span.file.ends_with("insecure_nft_marketplace.rs")));
+    }
+
+    #[test]
+    fn broken_saves_keep_the_previous_findings() {
+        let (dir, mut watch) = tree();
+        let before = watch.findings().count();
+        let vault = dir.path().join("vulnerable_vault.rs");
+        fs::write(&vault, "pub fn broken( {").unwrap();
+        let delta = watch.rescan([vault]);
+        assert_eq!(delta.parse_errors.len(), 1);
+        assert!(delta.new.is_empty() && delta.fixed.is_empty());
+        assert_eq!(watch.findings().count(), before);
+        assert!(!watch.is_watched(&dir.path().join("target/gen.rs")));
+    }
+
+    #[test]
+    fn unchanged_files_come_from_the_cache() {
+        let (dir, mut watch) = tree();
+        let vault = dir.path().join("vulnerable_vault.rs");
+        let span = parsed_span();
+        assert!(watch.rescan([vault.clone()]).is_empty());
+        assert_eq!(watch.cache_stats(), (1, 2));
+
+        // A save that leaves the contents alone.
+        fs::write(&vault, fs::read_to_string(&vault).unwrap()).unwrap();
+        assert!(watch.rescan([vault]).is_empty());
+        assert_eq!(watch.cache_stats(), (2, 2));
+        assert!(!is_released(span));
+    }
+
+    #[test]
+    fn rescans_release_the_parsed_sources() {
+        let (dir, mut watch) = tree();
+        let vault = dir.path().join("vulnerable_vault.rs");
+        let nft = dir.path().join("insecure_nft_marketplace.rs");
+        let source = fs::read_to_string(&vault).unwrap();
+        watch.release_after = 4 * source.len();
+        let before = watch.findings().count();
+        let span = parsed_span();
+        for round in 0..50 {
+            fs::write(&vault, format!("{source}\n// save {round}\n")).unwrap();
+            let delta = watch.rescan([vault.clone(), nft.clone()]);
+            assert!(delta.new.is_empty() && delta.fixed.is_empty());
+            assert!(watch.cache.parsed_bytes() <= watch.release_after);
+        }
+        assert!(is_released(span));
+        assert_eq!(watch.findings().count(), before);
+        let (hits, _) = watch.cache_stats();
+        assert!(
+            hits > 25,
+            "the untouched file should mostly come from the cache"
+        );
+    }
+}